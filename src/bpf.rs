@@ -0,0 +1,1719 @@
+// Copyright 2015 The Rust Project Developers.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Classic Berkeley Packet Filter (cBPF) programs.
+//!
+//! Classic BPF programs are used by [`Socket::attach_filter`],
+//! [`Socket::attach_reuseport_filter`] and
+//! [`Socket::attach_packet_fanout_filter`]. A program is a slice of
+//! [`SockFilter`] instructions, which this module allows to be created
+//! without encoding the opcodes by hand.
+//!
+//! [`ProgramBuilder`] assembles a program from typed [`Instruction`]s,
+//! resolving jumps to labels. [`validate`] checks a program using the same
+//! rules as the kernel (`bpf_check_classic`), so that an invalid program is
+//! reported before attaching it fails with `EINVAL`. [`disassemble`] formats
+//! a program in a syntax similar to the kernel's `bpf_asm`. [`Interpreter`]
+//! runs a program in userspace, allowing it to be tested without a socket.
+//!
+//! The [`pcap`] module compiles `tcpdump`-style filter expressions.
+//!
+//! See the [kernel documentation] for the instruction set.
+//!
+//! [`Socket::attach_filter`]: crate::Socket::attach_filter
+//! [`Socket::attach_reuseport_filter`]: crate::Socket::attach_reuseport_filter
+//! [`Socket::attach_packet_fanout_filter`]: crate::Socket::attach_packet_fanout_filter
+//! [kernel documentation]: https://www.kernel.org/doc/html/latest/networking/filter.html
+//!
+//! # Examples
+//!
+//! Only accept IPv4 UDP packets on a packet socket.
+//!
+//! ```
+//! # fn main() -> Result<(), socket2::bpf::ProgramError> {
+//! use socket2::bpf::{Condition, Operand, ProgramBuilder, ReturnValue, Size, Source};
+//!
+//! let program = ProgramBuilder::new()
+//!     .load(Source::Absolute(Size::Half, 12))
+//!     .jump_if(Condition::Eq, Operand::Constant(0x0800), "next", "drop")
+//!     .label("next")
+//!     .load(Source::Absolute(Size::Byte, 23))
+//!     .jump_if(Condition::Eq, Operand::Constant(17), "accept", "drop")
+//!     .label("accept")
+//!     .ret(ReturnValue::Constant(u32::MAX))
+//!     .label("drop")
+//!     .ret(ReturnValue::Constant(0))
+//!     .build()?;
+//!
+//! assert_eq!(
+//!     socket2::bpf::disassemble(&program).to_string(),
+//!     "(000) ldh      [12]\n\
+//!      (001) jeq      #0x800           jt 2    jf 5\n\
+//!      (002) ldb      [23]\n\
+//!      (003) jeq      #0x11            jt 4    jf 5\n\
+//!      (004) ret      #4294967295\n\
+//!      (005) ret      #0"
+//! );
+//! # Ok(()) }
+//! ```
+
+use std::{error, fmt, io};
+
+use crate::SockFilter;
+
+pub mod pcap;
+
+const LD: u16 = libc::BPF_LD as u16;
+const LDX: u16 = libc::BPF_LDX as u16;
+const ST: u16 = libc::BPF_ST as u16;
+const STX: u16 = libc::BPF_STX as u16;
+const ALU: u16 = libc::BPF_ALU as u16;
+const JMP: u16 = libc::BPF_JMP as u16;
+const RET: u16 = libc::BPF_RET as u16;
+const MISC: u16 = libc::BPF_MISC as u16;
+
+const W: u16 = libc::BPF_W as u16;
+const H: u16 = libc::BPF_H as u16;
+const B: u16 = libc::BPF_B as u16;
+
+const IMM: u16 = libc::BPF_IMM as u16;
+const ABS: u16 = libc::BPF_ABS as u16;
+const IND: u16 = libc::BPF_IND as u16;
+const MEM: u16 = libc::BPF_MEM as u16;
+const LEN: u16 = libc::BPF_LEN as u16;
+const MSH: u16 = libc::BPF_MSH as u16;
+
+const K: u16 = libc::BPF_K as u16;
+const X: u16 = libc::BPF_X as u16;
+const A: u16 = libc::BPF_A as u16;
+
+const JA: u16 = libc::BPF_JA as u16;
+const NEG: u16 = libc::BPF_NEG as u16;
+const TAX: u16 = libc::BPF_TAX as u16;
+const TXA: u16 = libc::BPF_TXA as u16;
+
+const AD_OFF: u32 = libc::SKF_AD_OFF as u32;
+const NET_OFF: u32 = libc::SKF_NET_OFF as u32;
+const LL_OFF: u32 = libc::SKF_LL_OFF as u32;
+
+/// Maximum number of instructions in a program, `BPF_MAXINSNS`.
+pub const MAX_INSTRUCTIONS: usize = libc::BPF_MAXINSNS as usize;
+/// Number of scratch memory words (`M[0]` to `M[15]`), `BPF_MEMWORDS`.
+pub const MEMORY_WORDS: u32 = libc::BPF_MEMWORDS as u32;
+
+/// Classic BPF instruction.
+///
+/// Converts into a [`SockFilter`] using `From`, use
+/// [`Instruction::decode`] for the reverse. The `Display` implementation uses
+/// the same syntax as [`disassemble`], with jump offsets relative to the next
+/// instruction.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Instruction {
+    /// Load a value into the accumulator, `ld`.
+    Load(Source),
+    /// Load a value into the index register, `ldx`.
+    LoadX(SourceX),
+    /// Store the accumulator in scratch memory `M[k]`, `st`.
+    Store(u32),
+    /// Store the index register in scratch memory `M[k]`, `stx`.
+    StoreX(u32),
+    /// Arithmetic operation on the accumulator.
+    Alu(AluOp, Operand),
+    /// Negate the accumulator, `neg`.
+    Neg,
+    /// Unconditionally skip `k` instructions, `ja`.
+    Jump(u32),
+    /// Compare the accumulator with `operand`, skipping `jt` instructions if
+    /// the condition holds or `jf` instructions if it doesn't.
+    JumpIf {
+        /// Comparison.
+        cond: Condition,
+        /// Value compared against the accumulator.
+        operand: Operand,
+        /// Number of instructions to skip if true.
+        jt: u8,
+        /// Number of instructions to skip if false.
+        jf: u8,
+    },
+    /// Stop and accept the returned number of bytes of the packet, `ret`.
+    /// Returning zero drops the packet.
+    Return(ReturnValue),
+    /// Copy the accumulator to the index register, `tax`.
+    Tax,
+    /// Copy the index register to the accumulator, `txa`.
+    Txa,
+}
+
+/// Size of a packet load.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Size {
+    /// 32 bits, `BPF_W`.
+    Word,
+    /// 16 bits, `BPF_H`.
+    Half,
+    /// 8 bits, `BPF_B`.
+    Byte,
+}
+
+/// Source of [`Instruction::Load`].
+///
+/// Packet data is loaded in network byte order.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Source {
+    /// Constant, `#k`.
+    Immediate(u32),
+    /// Packet data at offset `k`, `[k]`.
+    Absolute(Size, u32),
+    /// Packet data at offset `X + k`, `[x + k]`.
+    Indirect(Size, u32),
+    /// Packet data at offset `k` from the network header, `SKF_NET_OFF`.
+    Network(Size, u32),
+    /// Packet data at offset `k` from the link layer header, `SKF_LL_OFF`.
+    LinkLayer(Size, u32),
+    /// Scratch memory `M[k]`.
+    Memory(u32),
+    /// Length of the packet.
+    Length,
+    /// Packet metadata, `SKF_AD_*`.
+    ///
+    /// The kernel accepts the metadata loads with any size, but always loads
+    /// the complete value.
+    Ancillary(Size, Ancillary),
+}
+
+/// Source of [`Instruction::LoadX`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SourceX {
+    /// Constant, `#k`.
+    Immediate(u32),
+    /// Scratch memory `M[k]`.
+    Memory(u32),
+    /// Length of the packet.
+    Length,
+    /// Length of the IPv4 header starting at offset `k`,
+    /// `4 * ([k] & 0xf)` (`BPF_MSH`).
+    IpHeaderLength(u32),
+}
+
+/// Arithmetic operation of [`Instruction::Alu`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AluOp {
+    /// `add`.
+    Add,
+    /// `sub`.
+    Sub,
+    /// `mul`.
+    Mul,
+    /// `div`, dividing by zero returns zero (drops the packet).
+    Div,
+    /// `mod`.
+    Mod,
+    /// `and`.
+    And,
+    /// `or`.
+    Or,
+    /// `xor`.
+    Xor,
+    /// `lsh`.
+    Lsh,
+    /// `rsh`.
+    Rsh,
+}
+
+/// Comparison of [`Instruction::JumpIf`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Condition {
+    /// `A == operand`, `jeq`.
+    Eq,
+    /// `A > operand`, `jgt`.
+    Gt,
+    /// `A >= operand`, `jge`.
+    Ge,
+    /// `A & operand != 0`, `jset`.
+    Set,
+}
+
+/// Operand of an arithmetic or jump instruction.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Operand {
+    /// Constant, `BPF_K`.
+    Constant(u32),
+    /// The index register, `BPF_X`.
+    X,
+}
+
+/// Value of [`Instruction::Return`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ReturnValue {
+    /// Constant, `BPF_K`.
+    Constant(u32),
+    /// The accumulator, `BPF_A`.
+    A,
+}
+
+/// Packet metadata loaded by [`Source::Ancillary`], `SKF_AD_*`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Ancillary {
+    /// Protocol (ethertype) of the packet, `SKF_AD_PROTOCOL`.
+    Protocol,
+    /// Packet type (`PACKET_HOST`, etc.), `SKF_AD_PKTTYPE`.
+    PacketType,
+    /// Index of the interface, `SKF_AD_IFINDEX`.
+    Interface,
+    /// Offset of the netlink attribute with type `X` starting at offset `A`,
+    /// `SKF_AD_NLATTR`.
+    NetlinkAttr,
+    /// Offset of the nested netlink attribute with type `X` in the attribute
+    /// at offset `A`, `SKF_AD_NLATTR_NEST`.
+    NetlinkAttrNested,
+    /// Packet mark, `SKF_AD_MARK`.
+    Mark,
+    /// Receive queue mapping, `SKF_AD_QUEUE`.
+    Queue,
+    /// Hardware type of the interface (`ARPHRD_*`), `SKF_AD_HATYPE`.
+    HardwareType,
+    /// Flow hash of the packet, `SKF_AD_RXHASH`.
+    RxHash,
+    /// Current CPU, `SKF_AD_CPU`.
+    Cpu,
+    /// `A ^ X`, `SKF_AD_ALU_XOR_X`.
+    AluXorX,
+    /// VLAN TCI, `SKF_AD_VLAN_TAG`.
+    VlanTag,
+    /// Whether a VLAN tag is present, `SKF_AD_VLAN_TAG_PRESENT`.
+    VlanTagPresent,
+    /// Offset of the payload (after the transport header),
+    /// `SKF_AD_PAY_OFFSET`.
+    PayloadOffset,
+    /// Random number, `SKF_AD_RANDOM`.
+    Random,
+    /// VLAN protocol, `SKF_AD_VLAN_TPID`.
+    VlanTpid,
+}
+
+impl Ancillary {
+    const fn to_raw(self) -> u32 {
+        (match self {
+            Ancillary::Protocol => libc::SKF_AD_PROTOCOL,
+            Ancillary::PacketType => libc::SKF_AD_PKTTYPE,
+            Ancillary::Interface => libc::SKF_AD_IFINDEX,
+            Ancillary::NetlinkAttr => libc::SKF_AD_NLATTR,
+            Ancillary::NetlinkAttrNested => libc::SKF_AD_NLATTR_NEST,
+            Ancillary::Mark => libc::SKF_AD_MARK,
+            Ancillary::Queue => libc::SKF_AD_QUEUE,
+            Ancillary::HardwareType => libc::SKF_AD_HATYPE,
+            Ancillary::RxHash => libc::SKF_AD_RXHASH,
+            Ancillary::Cpu => libc::SKF_AD_CPU,
+            Ancillary::AluXorX => libc::SKF_AD_ALU_XOR_X,
+            Ancillary::VlanTag => libc::SKF_AD_VLAN_TAG,
+            Ancillary::VlanTagPresent => libc::SKF_AD_VLAN_TAG_PRESENT,
+            Ancillary::PayloadOffset => libc::SKF_AD_PAY_OFFSET,
+            Ancillary::Random => libc::SKF_AD_RANDOM,
+            Ancillary::VlanTpid => libc::SKF_AD_VLAN_TPID,
+        } as u32)
+            .wrapping_add(AD_OFF)
+    }
+
+    const fn from_raw(k: u32) -> Option<Ancillary> {
+        if k < AD_OFF {
+            return None;
+        }
+        Some(match (k - AD_OFF) as libc::c_int {
+            libc::SKF_AD_PROTOCOL => Ancillary::Protocol,
+            libc::SKF_AD_PKTTYPE => Ancillary::PacketType,
+            libc::SKF_AD_IFINDEX => Ancillary::Interface,
+            libc::SKF_AD_NLATTR => Ancillary::NetlinkAttr,
+            libc::SKF_AD_NLATTR_NEST => Ancillary::NetlinkAttrNested,
+            libc::SKF_AD_MARK => Ancillary::Mark,
+            libc::SKF_AD_QUEUE => Ancillary::Queue,
+            libc::SKF_AD_HATYPE => Ancillary::HardwareType,
+            libc::SKF_AD_RXHASH => Ancillary::RxHash,
+            libc::SKF_AD_CPU => Ancillary::Cpu,
+            libc::SKF_AD_ALU_XOR_X => Ancillary::AluXorX,
+            libc::SKF_AD_VLAN_TAG => Ancillary::VlanTag,
+            libc::SKF_AD_VLAN_TAG_PRESENT => Ancillary::VlanTagPresent,
+            libc::SKF_AD_PAY_OFFSET => Ancillary::PayloadOffset,
+            libc::SKF_AD_RANDOM => Ancillary::Random,
+            libc::SKF_AD_VLAN_TPID => Ancillary::VlanTpid,
+            _ => return None,
+        })
+    }
+
+    /// Name used by the kernel's `bpf_asm`.
+    const fn name(self) -> &'static str {
+        match self {
+            Ancillary::Protocol => "proto",
+            Ancillary::PacketType => "type",
+            Ancillary::Interface => "ifidx",
+            Ancillary::NetlinkAttr => "nla",
+            Ancillary::NetlinkAttrNested => "nlan",
+            Ancillary::Mark => "mark",
+            Ancillary::Queue => "queue",
+            Ancillary::HardwareType => "hatype",
+            Ancillary::RxHash => "rxhash",
+            Ancillary::Cpu => "cpu",
+            Ancillary::AluXorX => "xor_x",
+            Ancillary::VlanTag => "vlan_tci",
+            Ancillary::VlanTagPresent => "vlan_avail",
+            Ancillary::PayloadOffset => "poff",
+            Ancillary::Random => "rand",
+            Ancillary::VlanTpid => "vlan_tpid",
+        }
+    }
+}
+
+impl Size {
+    const fn to_raw(self) -> u16 {
+        match self {
+            Size::Word => W,
+            Size::Half => H,
+            Size::Byte => B,
+        }
+    }
+}
+
+impl AluOp {
+    const fn to_raw(self) -> u16 {
+        (match self {
+            AluOp::Add => libc::BPF_ADD,
+            AluOp::Sub => libc::BPF_SUB,
+            AluOp::Mul => libc::BPF_MUL,
+            AluOp::Div => libc::BPF_DIV,
+            AluOp::Mod => libc::BPF_MOD,
+            AluOp::And => libc::BPF_AND,
+            AluOp::Or => libc::BPF_OR,
+            AluOp::Xor => libc::BPF_XOR,
+            AluOp::Lsh => libc::BPF_LSH,
+            AluOp::Rsh => libc::BPF_RSH,
+        }) as u16
+    }
+
+    const fn name(self) -> &'static str {
+        match self {
+            AluOp::Add => "add",
+            AluOp::Sub => "sub",
+            AluOp::Mul => "mul",
+            AluOp::Div => "div",
+            AluOp::Mod => "mod",
+            AluOp::And => "and",
+            AluOp::Or => "or",
+            AluOp::Xor => "xor",
+            AluOp::Lsh => "lsh",
+            AluOp::Rsh => "rsh",
+        }
+    }
+}
+
+impl Condition {
+    const fn to_raw(self) -> u16 {
+        (match self {
+            Condition::Eq => libc::BPF_JEQ,
+            Condition::Gt => libc::BPF_JGT,
+            Condition::Ge => libc::BPF_JGE,
+            Condition::Set => libc::BPF_JSET,
+        }) as u16
+    }
+
+    const fn name(self) -> &'static str {
+        match self {
+            Condition::Eq => "jeq",
+            Condition::Gt => "jgt",
+            Condition::Ge => "jge",
+            Condition::Set => "jset",
+        }
+    }
+}
+
+impl Operand {
+    /// Returns the `BPF_K`/`BPF_X` bit and `k`.
+    const fn to_raw(self) -> (u16, u32) {
+        match self {
+            Operand::Constant(k) => (K, k),
+            Operand::X => (X, 0),
+        }
+    }
+}
+
+impl Instruction {
+    /// Decode `filter`, returns `None` if the opcode is unknown.
+    pub fn decode(filter: &SockFilter) -> Option<Instruction> {
+        let (code, jt, jf, k) = (filter.code(), filter.jt(), filter.jf(), filter.k());
+        if code > 0xff {
+            return None;
+        }
+        let size = || match code & 0x18 {
+            W => Some(Size::Word),
+            H => Some(Size::Half),
+            B => Some(Size::Byte),
+            _ => None,
+        };
+        let operand = || match code & 0x08 {
+            K => Operand::Constant(k),
+            _ => Operand::X,
+        };
+        let insn = match code & 0x07 {
+            LD => Instruction::Load(match code & 0xe0 {
+                ABS => {
+                    let size = size()?;
+                    match k {
+                        k if k >= AD_OFF => match Ancillary::from_raw(k) {
+                            Some(anc) => Source::Ancillary(size, anc),
+                            None => Source::Absolute(size, k),
+                        },
+                        k if k >= NET_OFF => Source::Network(size, k - NET_OFF),
+                        k if k >= LL_OFF => Source::LinkLayer(size, k - LL_OFF),
+                        k => Source::Absolute(size, k),
+                    }
+                }
+                IND => Source::Indirect(size()?, k),
+                IMM if code == LD | W | IMM => Source::Immediate(k),
+                MEM if code == LD | W | MEM => Source::Memory(k),
+                LEN if code == LD | W | LEN => Source::Length,
+                _ => return None,
+            }),
+            LDX => Instruction::LoadX(match code {
+                c if c == LDX | W | IMM => SourceX::Immediate(k),
+                c if c == LDX | W | MEM => SourceX::Memory(k),
+                c if c == LDX | W | LEN => SourceX::Length,
+                c if c == LDX | B | MSH => SourceX::IpHeaderLength(k),
+                _ => return None,
+            }),
+            ST if code == ST => Instruction::Store(k),
+            STX if code == STX => Instruction::StoreX(k),
+            ALU => {
+                if code == ALU | NEG {
+                    return Some(Instruction::Neg);
+                }
+                let op = match code & !0x08 {
+                    c if c == ALU | AluOp::Add.to_raw() => AluOp::Add,
+                    c if c == ALU | AluOp::Sub.to_raw() => AluOp::Sub,
+                    c if c == ALU | AluOp::Mul.to_raw() => AluOp::Mul,
+                    c if c == ALU | AluOp::Div.to_raw() => AluOp::Div,
+                    c if c == ALU | AluOp::Mod.to_raw() => AluOp::Mod,
+                    c if c == ALU | AluOp::And.to_raw() => AluOp::And,
+                    c if c == ALU | AluOp::Or.to_raw() => AluOp::Or,
+                    c if c == ALU | AluOp::Xor.to_raw() => AluOp::Xor,
+                    c if c == ALU | AluOp::Lsh.to_raw() => AluOp::Lsh,
+                    c if c == ALU | AluOp::Rsh.to_raw() => AluOp::Rsh,
+                    _ => return None,
+                };
+                Instruction::Alu(op, operand())
+            }
+            JMP => {
+                if code == JMP | JA {
+                    return Some(Instruction::Jump(k));
+                }
+                let cond = match code & !0x08 {
+                    c if c == JMP | Condition::Eq.to_raw() => Condition::Eq,
+                    c if c == JMP | Condition::Gt.to_raw() => Condition::Gt,
+                    c if c == JMP | Condition::Ge.to_raw() => Condition::Ge,
+                    c if c == JMP | Condition::Set.to_raw() => Condition::Set,
+                    _ => return None,
+                };
+                Instruction::JumpIf {
+                    cond,
+                    operand: operand(),
+                    jt,
+                    jf,
+                }
+            }
+            RET => match code {
+                c if c == RET | K => Instruction::Return(ReturnValue::Constant(k)),
+                c if c == RET | A => Instruction::Return(ReturnValue::A),
+                _ => return None,
+            },
+            MISC => match code {
+                c if c == MISC | TAX => Instruction::Tax,
+                c if c == MISC | TXA => Instruction::Txa,
+                _ => return None,
+            },
+            _ => return None,
+        };
+        Some(insn)
+    }
+
+    /// Mnemonic, shared by `bpf_asm` and `tcpdump -d`.
+    fn mnemonic(&self) -> &'static str {
+        match self {
+            Instruction::Load(source) => match source {
+                Source::Absolute(size, _)
+                | Source::Indirect(size, _)
+                | Source::Network(size, _)
+                | Source::LinkLayer(size, _)
+                | Source::Ancillary(size, _) => match size {
+                    Size::Word => "ld",
+                    Size::Half => "ldh",
+                    Size::Byte => "ldb",
+                },
+                _ => "ld",
+            },
+            Instruction::LoadX(SourceX::IpHeaderLength(_)) => "ldxb",
+            Instruction::LoadX(_) => "ldx",
+            Instruction::Store(_) => "st",
+            Instruction::StoreX(_) => "stx",
+            Instruction::Alu(op, _) => op.name(),
+            Instruction::Neg => "neg",
+            Instruction::Jump(_) => "ja",
+            Instruction::JumpIf { cond, .. } => cond.name(),
+            Instruction::Return(_) => "ret",
+            Instruction::Tax => "tax",
+            Instruction::Txa => "txa",
+        }
+    }
+
+    /// Write the operand, excluding jump targets, using the `bpf_asm` syntax.
+    fn fmt_operand(&self, f: &mut dyn fmt::Write) -> fmt::Result {
+        match *self {
+            Instruction::Load(source) => match source {
+                Source::Immediate(k) => write!(f, "#0x{k:x}"),
+                Source::Absolute(_, k) => write!(f, "[{k}]"),
+                Source::Indirect(_, k) => write!(f, "[x + {k}]"),
+                Source::Network(_, k) => write!(f, "[net + {k}]"),
+                Source::LinkLayer(_, k) => write!(f, "[ll + {k}]"),
+                Source::Memory(k) => write!(f, "M[{k}]"),
+                Source::Length => f.write_str("#len"),
+                Source::Ancillary(_, anc) => write!(f, "#{}", anc.name()),
+            },
+            Instruction::LoadX(source) => match source {
+                SourceX::Immediate(k) => write!(f, "#0x{k:x}"),
+                SourceX::Memory(k) => write!(f, "M[{k}]"),
+                SourceX::Length => f.write_str("#len"),
+                SourceX::IpHeaderLength(k) => write!(f, "4*([{k}]&0xf)"),
+            },
+            Instruction::Store(k) | Instruction::StoreX(k) => write!(f, "M[{k}]"),
+            Instruction::Alu(_, operand) | Instruction::JumpIf { operand, .. } => match operand {
+                Operand::Constant(k) => write!(f, "#0x{k:x}"),
+                Operand::X => f.write_str("x"),
+            },
+            Instruction::Return(ReturnValue::Constant(k)) => write!(f, "#{k}"),
+            Instruction::Return(ReturnValue::A) => f.write_str("a"),
+            Instruction::Neg | Instruction::Jump(_) | Instruction::Tax | Instruction::Txa => Ok(()),
+        }
+    }
+}
+
+impl From<Instruction> for SockFilter {
+    fn from(insn: Instruction) -> SockFilter {
+        let (code, jt, jf, k) = match insn {
+            Instruction::Load(source) => match source {
+                Source::Immediate(k) => (LD | W | IMM, 0, 0, k),
+                Source::Absolute(size, k) => (LD | size.to_raw() | ABS, 0, 0, k),
+                Source::Indirect(size, k) => (LD | size.to_raw() | IND, 0, 0, k),
+                Source::Network(size, k) => {
+                    (LD | size.to_raw() | ABS, 0, 0, NET_OFF.wrapping_add(k))
+                }
+                Source::LinkLayer(size, k) => {
+                    (LD | size.to_raw() | ABS, 0, 0, LL_OFF.wrapping_add(k))
+                }
+                Source::Memory(k) => (LD | W | MEM, 0, 0, k),
+                Source::Length => (LD | W | LEN, 0, 0, 0),
+                Source::Ancillary(size, anc) => (LD | size.to_raw() | ABS, 0, 0, anc.to_raw()),
+            },
+            Instruction::LoadX(source) => match source {
+                SourceX::Immediate(k) => (LDX | W | IMM, 0, 0, k),
+                SourceX::Memory(k) => (LDX | W | MEM, 0, 0, k),
+                SourceX::Length => (LDX | W | LEN, 0, 0, 0),
+                SourceX::IpHeaderLength(k) => (LDX | B | MSH, 0, 0, k),
+            },
+            Instruction::Store(k) => (ST, 0, 0, k),
+            Instruction::StoreX(k) => (STX, 0, 0, k),
+            Instruction::Alu(op, operand) => {
+                let (src, k) = operand.to_raw();
+                (ALU | op.to_raw() | src, 0, 0, k)
+            }
+            Instruction::Neg => (ALU | NEG, 0, 0, 0),
+            Instruction::Jump(k) => (JMP | JA, 0, 0, k),
+            Instruction::JumpIf {
+                cond,
+                operand,
+                jt,
+                jf,
+            } => {
+                let (src, k) = operand.to_raw();
+                (JMP | cond.to_raw() | src, jt, jf, k)
+            }
+            Instruction::Return(ReturnValue::Constant(k)) => (RET | K, 0, 0, k),
+            Instruction::Return(ReturnValue::A) => (RET | A, 0, 0, 0),
+            Instruction::Tax => (MISC | TAX, 0, 0, 0),
+            Instruction::Txa => (MISC | TXA, 0, 0, 0),
+        };
+        SockFilter::new(code, jt, jf, k)
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.mnemonic())?;
+        match *self {
+            Instruction::Neg | Instruction::Tax | Instruction::Txa => Ok(()),
+            Instruction::Jump(k) => write!(f, " +{k}"),
+            Instruction::JumpIf { jt, jf, .. } => {
+                f.write_str(" ")?;
+                self.fmt_operand(f)?;
+                write!(f, " jt +{jt} jf +{jf}")
+            }
+            _ => {
+                f.write_str(" ")?;
+                self.fmt_operand(f)
+            }
+        }
+    }
+}
+
+/// Target of a jump in [`ProgramBuilder`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Target<'a> {
+    /// The next instruction, i.e. don't jump.
+    Next,
+    /// The instruction following [`ProgramBuilder::label`] with this name.
+    Label(&'a str),
+}
+
+impl<'a> From<&'a str> for Target<'a> {
+    fn from(label: &'a str) -> Target<'a> {
+        Target::Label(label)
+    }
+}
+
+#[derive(Debug)]
+enum PendingInstruction<'a> {
+    Resolved(Instruction),
+    Jump(Target<'a>),
+    JumpIf(Condition, Operand, Target<'a>, Target<'a>),
+}
+
+/// Assembler for classic BPF programs.
+///
+/// Jumps refer to labels, which are resolved into instruction offsets by
+/// [`ProgramBuilder::build`]. As classic BPF only allows jumping forward a
+/// label must be defined after the jumps that refer to it.
+///
+/// See the [module documentation] for an example.
+///
+/// [module documentation]: crate::bpf
+#[derive(Debug, Default)]
+pub struct ProgramBuilder<'a> {
+    instructions: Vec<PendingInstruction<'a>>,
+    labels: Vec<(&'a str, usize)>,
+}
+
+impl<'a> ProgramBuilder<'a> {
+    /// Create an empty program.
+    pub fn new() -> ProgramBuilder<'a> {
+        ProgramBuilder::default()
+    }
+
+    /// Add `insn`.
+    ///
+    /// Jumps added this way use the offsets in `insn` as is.
+    pub fn instruction(mut self, insn: Instruction) -> Self {
+        self.instructions.push(PendingInstruction::Resolved(insn));
+        self
+    }
+
+    /// Load a value into the accumulator, `ld`.
+    pub fn load(self, source: Source) -> Self {
+        self.instruction(Instruction::Load(source))
+    }
+
+    /// Load a value into the index register, `ldx`.
+    pub fn load_x(self, source: SourceX) -> Self {
+        self.instruction(Instruction::LoadX(source))
+    }
+
+    /// Store the accumulator in scratch memory `M[index]`, `st`.
+    pub fn store(self, index: u32) -> Self {
+        self.instruction(Instruction::Store(index))
+    }
+
+    /// Store the index register in scratch memory `M[index]`, `stx`.
+    pub fn store_x(self, index: u32) -> Self {
+        self.instruction(Instruction::StoreX(index))
+    }
+
+    /// Arithmetic operation on the accumulator.
+    pub fn alu(self, op: AluOp, operand: Operand) -> Self {
+        self.instruction(Instruction::Alu(op, operand))
+    }
+
+    /// Negate the accumulator, `neg`.
+    pub fn negate(self) -> Self {
+        self.instruction(Instruction::Neg)
+    }
+
+    /// Copy the accumulator to the index register, `tax`.
+    pub fn tax(self) -> Self {
+        self.instruction(Instruction::Tax)
+    }
+
+    /// Copy the index register to the accumulator, `txa`.
+    pub fn txa(self) -> Self {
+        self.instruction(Instruction::Txa)
+    }
+
+    /// Unconditionally jump to `target`, `ja`.
+    pub fn jump<T: Into<Target<'a>>>(mut self, target: T) -> Self {
+        let target = target.into();
+        self.instructions.push(PendingInstruction::Jump(target));
+        self
+    }
+
+    /// Compare the accumulator with `operand`, jumping to `jt` if the
+    /// condition holds or to `jf` if it doesn't.
+    ///
+    /// Conditional jumps can skip at most 255 instructions.
+    pub fn jump_if<T, F>(mut self, cond: Condition, operand: Operand, jt: T, jf: F) -> Self
+    where
+        T: Into<Target<'a>>,
+        F: Into<Target<'a>>,
+    {
+        let pending = PendingInstruction::JumpIf(cond, operand, jt.into(), jf.into());
+        self.instructions.push(pending);
+        self
+    }
+
+    /// Stop and accept `value` bytes of the packet, `ret`.
+    pub fn ret(self, value: ReturnValue) -> Self {
+        self.instruction(Instruction::Return(value))
+    }
+
+    /// Define label `name` for the next instruction.
+    pub fn label(mut self, name: &'a str) -> Self {
+        self.labels.push((name, self.instructions.len()));
+        self
+    }
+
+    /// Resolve the labels and [`validate`] the program.
+    pub fn build(self) -> Result<Vec<SockFilter>, ProgramError> {
+        for (i, (name, _)) in self.labels.iter().enumerate() {
+            if self.labels[..i].iter().any(|(n, _)| n == name) {
+                return Err(ProgramError::new(None, ProgramErrorKind::DuplicateLabel));
+            }
+        }
+
+        let mut program = Vec::with_capacity(self.instructions.len());
+        for (pc, pending) in self.instructions.iter().enumerate() {
+            let insn = match *pending {
+                PendingInstruction::Resolved(insn) => insn,
+                PendingInstruction::Jump(target) => Instruction::Jump(self.offset(pc, target)?),
+                PendingInstruction::JumpIf(cond, operand, jt, jf) => {
+                    let offset = |target| {
+                        u8::try_from(self.offset(pc, target)?)
+                            .map_err(|_| ProgramError::new(Some(pc), ProgramErrorKind::JumpTooFar))
+                    };
+                    Instruction::JumpIf {
+                        cond,
+                        operand,
+                        jt: offset(jt)?,
+                        jf: offset(jf)?,
+                    }
+                }
+            };
+            program.push(SockFilter::from(insn));
+        }
+        validate(&program)?;
+        Ok(program)
+    }
+
+    /// Returns the offset of `target` relative to the instruction after
+    /// `pc`.
+    fn offset(&self, pc: usize, target: Target<'_>) -> Result<u32, ProgramError> {
+        let name = match target {
+            Target::Next => return Ok(0),
+            Target::Label(name) => name,
+        };
+        let target = match self.labels.iter().find(|(n, _)| *n == name) {
+            Some((_, target)) => *target,
+            None => {
+                return Err(ProgramError::new(
+                    Some(pc),
+                    ProgramErrorKind::UndefinedLabel,
+                ))
+            }
+        };
+        if target <= pc {
+            return Err(ProgramError::new(Some(pc), ProgramErrorKind::BackwardJump));
+        }
+        Ok((target - pc - 1) as u32)
+    }
+}
+
+/// Check `program` using the rules of the kernel (`bpf_check_classic`).
+///
+/// A program that passes validation is accepted by
+/// [`Socket::attach_filter`], barring resource limits such as
+/// `net.core.optmem_max`.
+///
+/// [`Socket::attach_filter`]: crate::Socket::attach_filter
+pub fn validate(program: &[SockFilter]) -> Result<(), ProgramError> {
+    if program.is_empty() {
+        return Err(ProgramError::new(None, ProgramErrorKind::Empty));
+    } else if program.len() > MAX_INSTRUCTIONS {
+        return Err(ProgramError::new(None, ProgramErrorKind::TooLong));
+    }
+
+    let len = program.len();
+    for (pc, filter) in program.iter().enumerate() {
+        let err = |kind| Err(ProgramError::new(Some(pc), kind));
+        let insn = match Instruction::decode(filter) {
+            Some(insn) => insn,
+            None => return err(ProgramErrorKind::InvalidOpcode),
+        };
+        match insn {
+            Instruction::Alu(AluOp::Div | AluOp::Mod, Operand::Constant(0)) => {
+                return err(ProgramErrorKind::DivisionByZero)
+            }
+            Instruction::Alu(AluOp::Lsh | AluOp::Rsh, Operand::Constant(k)) if k >= 32 => {
+                return err(ProgramErrorKind::InvalidShift)
+            }
+            Instruction::Load(Source::Memory(k))
+            | Instruction::LoadX(SourceX::Memory(k))
+            | Instruction::Store(k)
+            | Instruction::StoreX(k)
+                if k >= MEMORY_WORDS =>
+            {
+                return err(ProgramErrorKind::InvalidMemoryIndex)
+            }
+            Instruction::Jump(k) if k as usize >= len - pc - 1 => {
+                return err(ProgramErrorKind::JumpOutOfRange)
+            }
+            Instruction::JumpIf { jt, jf, .. }
+                if pc + jt as usize + 1 >= len || pc + jf as usize + 1 >= len =>
+            {
+                return err(ProgramErrorKind::JumpOutOfRange)
+            }
+            Instruction::Load(Source::Absolute(_, k)) if k >= AD_OFF => {
+                return err(ProgramErrorKind::UnknownAncillary)
+            }
+            _ => {}
+        }
+    }
+
+    if !matches!(
+        Instruction::decode(&program[len - 1]),
+        Some(Instruction::Return(_))
+    ) {
+        return Err(ProgramError::new(
+            Some(len - 1),
+            ProgramErrorKind::MissingReturn,
+        ));
+    }
+
+    check_load_and_stores(program)
+}
+
+/// Check that scratch memory is written before it's read on all paths,
+/// `check_load_and_stores` in the kernel.
+fn check_load_and_stores(program: &[SockFilter]) -> Result<(), ProgramError> {
+    // One bit per memory word, set if the word is initialised on all paths
+    // to the instruction.
+    let mut masks = vec![u16::MAX; program.len()];
+    let mut valid = 0u16;
+    for (pc, filter) in program.iter().enumerate() {
+        valid &= masks[pc];
+        // `validate` already checked the opcodes, indices and jump targets.
+        match Instruction::decode(filter) {
+            Some(Instruction::Store(k) | Instruction::StoreX(k)) => valid |= 1 << k,
+            Some(Instruction::Load(Source::Memory(k)) | Instruction::LoadX(SourceX::Memory(k)))
+                if valid & (1 << k) == 0 =>
+            {
+                return Err(ProgramError::new(
+                    Some(pc),
+                    ProgramErrorKind::UninitializedMemory,
+                ));
+            }
+            Some(Instruction::Jump(k)) => {
+                masks[pc + 1 + k as usize] &= valid;
+                valid = u16::MAX;
+            }
+            Some(Instruction::JumpIf { jt, jf, .. }) => {
+                masks[pc + 1 + jt as usize] &= valid;
+                masks[pc + 1 + jf as usize] &= valid;
+                valid = u16::MAX;
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Format `program` one instruction per line with absolute jump targets.
+///
+/// The layout follows `tcpdump -d`, but the operands use the syntax of the
+/// kernel's `bpf_asm` (`tools/bpf`) rather than libpcap's: constants are
+/// hexadecimal (`#0x800`), the packet length is `#len`, ancillary data is
+/// named (`#proto`) and loads relative to the network or link layer header
+/// are `[net + k]` and `[ll + k]`. The output is meant for reading, it
+/// isn't guaranteed to be identical to either tool.
+///
+/// Invalid opcodes are formatted as `.invalid` followed by the raw
+/// instruction.
+pub fn disassemble(program: &[SockFilter]) -> Disassembly<'_> {
+    Disassembly { program }
+}
+
+/// Disassembled program, see [`disassemble`].
+#[derive(Debug)]
+pub struct Disassembly<'a> {
+    program: &'a [SockFilter],
+}
+
+impl<'a> fmt::Display for Disassembly<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (pc, filter) in self.program.iter().enumerate() {
+            if pc != 0 {
+                f.write_str("\n")?;
+            }
+            let insn = match Instruction::decode(filter) {
+                Some(insn) => insn,
+                None => {
+                    write!(
+                        f,
+                        "({pc:03}) .invalid code 0x{:02x} jt {} jf {} k 0x{:x}",
+                        filter.code(),
+                        filter.jt(),
+                        filter.jf(),
+                        filter.k()
+                    )?;
+                    continue;
+                }
+            };
+            let mut operand = String::new();
+            insn.fmt_operand(&mut operand)?;
+            let target = |offset: u32| pc as u64 + 1 + offset as u64;
+            match insn {
+                Instruction::Neg | Instruction::Tax | Instruction::Txa => {
+                    write!(f, "({pc:03}) {}", insn.mnemonic())?
+                }
+                Instruction::Jump(k) => write!(f, "({pc:03}) {:<8} {}", "ja", target(k))?,
+                Instruction::JumpIf { jt, jf, .. } => write!(
+                    f,
+                    "({pc:03}) {:<8} {operand:<16} jt {:<4} jf {}",
+                    insn.mnemonic(),
+                    target(jt.into()),
+                    target(jf.into())
+                )?,
+                _ => write!(f, "({pc:03}) {:<8} {operand}", insn.mnemonic())?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Packet for [`Interpreter::run`].
+///
+/// The program sees the packet starting at the data offset (`skb->data`),
+/// which depends on the socket: packet sockets start at the link layer
+/// header, UDP sockets at the UDP header, etc. Loads relative to the
+/// network (`SKF_NET_OFF`) and link layer (`SKF_LL_OFF`) headers can access
+/// the entire frame.
+#[derive(Clone, Debug)]
+pub struct Packet<'a> {
+    frame: &'a [u8],
+    data_offset: usize,
+    network_offset: usize,
+    link_layer_offset: Option<usize>,
+    protocol: u16,
+    packet_type: u8,
+    interface: u32,
+    hardware_type: u16,
+    mark: u32,
+    queue: u16,
+    rxhash: u32,
+    cpu: u32,
+    vlan: Option<(u16, u16)>,
+    payload_offset: u32,
+    random: u32,
+}
+
+impl<'a> Packet<'a> {
+    /// Create a packet for `frame`, starting at offset 0.
+    ///
+    /// All ancillary data is zero, the network header starts at offset 0 and
+    /// the link layer header isn't set.
+    pub const fn new(frame: &'a [u8]) -> Packet<'a> {
+        Packet {
+            frame,
+            data_offset: 0,
+            network_offset: 0,
+            link_layer_offset: None,
+            protocol: 0,
+            packet_type: 0,
+            interface: 0,
+            hardware_type: 0,
+            mark: 0,
+            queue: 0,
+            rxhash: 0,
+            cpu: 0,
+            vlan: None,
+            payload_offset: 0,
+            random: 0,
+        }
+    }
+
+    /// Set the offset in the frame of the data seen by the program.
+    pub const fn with_data_offset(mut self, offset: usize) -> Self {
+        self.data_offset = offset;
+        self
+    }
+
+    /// Set the offset in the frame of the network header.
+    pub const fn with_network_offset(mut self, offset: usize) -> Self {
+        self.network_offset = offset;
+        self
+    }
+
+    /// Set the offset in the frame of the link layer header.
+    pub const fn with_link_layer_offset(mut self, offset: usize) -> Self {
+        self.link_layer_offset = Some(offset);
+        self
+    }
+
+    /// Set the protocol (ethertype), [`Ancillary::Protocol`].
+    pub const fn with_protocol(mut self, protocol: u16) -> Self {
+        self.protocol = protocol;
+        self
+    }
+
+    /// Set the packet type (`PACKET_HOST`, etc.), [`Ancillary::PacketType`].
+    pub const fn with_packet_type(mut self, packet_type: u8) -> Self {
+        self.packet_type = packet_type;
+        self
+    }
+
+    /// Set the interface index, [`Ancillary::Interface`].
+    pub const fn with_interface(mut self, index: u32) -> Self {
+        self.interface = index;
+        self
+    }
+
+    /// Set the hardware type of the interface, [`Ancillary::HardwareType`].
+    pub const fn with_hardware_type(mut self, hardware_type: u16) -> Self {
+        self.hardware_type = hardware_type;
+        self
+    }
+
+    /// Set the mark, [`Ancillary::Mark`].
+    pub const fn with_mark(mut self, mark: u32) -> Self {
+        self.mark = mark;
+        self
+    }
+
+    /// Set the receive queue, [`Ancillary::Queue`].
+    pub const fn with_queue(mut self, queue: u16) -> Self {
+        self.queue = queue;
+        self
+    }
+
+    /// Set the flow hash, [`Ancillary::RxHash`].
+    pub const fn with_rxhash(mut self, hash: u32) -> Self {
+        self.rxhash = hash;
+        self
+    }
+
+    /// Set the CPU, [`Ancillary::Cpu`].
+    pub const fn with_cpu(mut self, cpu: u32) -> Self {
+        self.cpu = cpu;
+        self
+    }
+
+    /// Set the VLAN tag, [`Ancillary::VlanTag`], and protocol,
+    /// [`Ancillary::VlanTpid`].
+    pub const fn with_vlan(mut self, tci: u16, tpid: u16) -> Self {
+        self.vlan = Some((tci, tpid));
+        self
+    }
+
+    /// Set the payload offset, [`Ancillary::PayloadOffset`].
+    pub const fn with_payload_offset(mut self, offset: u32) -> Self {
+        self.payload_offset = offset;
+        self
+    }
+
+    /// Set the value returned by [`Ancillary::Random`].
+    pub const fn with_random(mut self, random: u32) -> Self {
+        self.random = random;
+        self
+    }
+
+    /// Returns the data seen by the program.
+    fn data(&self) -> &'a [u8] {
+        self.frame.get(self.data_offset..).unwrap_or(&[])
+    }
+
+    /// Load `size` bytes at `offset` (`k`, or `X + k`), in network byte
+    /// order. Negative offsets are relative to the network or link layer
+    /// header, `bpf_internal_load_pointer_neg_helper` in the kernel.
+    fn load(&self, offset: u32, size: Size) -> Option<u32> {
+        let len = match size {
+            Size::Word => 4,
+            Size::Half => 2,
+            Size::Byte => 1,
+        };
+        let bytes = if (offset as i32) >= 0 {
+            self.data().get(offset as usize..)?
+        } else if offset >= NET_OFF {
+            let start = self
+                .network_offset
+                .checked_add((offset - NET_OFF) as usize)?;
+            self.frame.get(start..)?
+        } else if offset >= LL_OFF {
+            let start = self
+                .link_layer_offset?
+                .checked_add((offset - LL_OFF) as usize)?;
+            self.frame.get(start..)?
+        } else {
+            return None;
+        };
+        let bytes = bytes.get(..len)?;
+        Some(bytes.iter().fold(0, |value, b| value << 8 | u32::from(*b)))
+    }
+
+    /// `__skb_get_nlattr` and `__skb_get_nlattr_nest` in the kernel.
+    fn netlink_attr(&self, offset: u32, kind: u32, nested: bool) -> u32 {
+        const NLA_HDRLEN: usize = 4;
+        let data = self.data();
+        let offset = offset as usize;
+        if data.len() < NLA_HDRLEN || offset > data.len() - NLA_HDRLEN {
+            return 0;
+        }
+        let nla_len = |at: usize| usize::from(u16::from_ne_bytes([data[at], data[at + 1]]));
+        let (mut pos, mut rem) = (offset, data.len() - offset);
+        if nested {
+            if nla_len(offset) > rem {
+                return 0;
+            }
+            // Search the attributes in the payload of the attribute at
+            // `offset`.
+            pos += NLA_HDRLEN;
+            rem = nla_len(offset).saturating_sub(NLA_HDRLEN);
+        }
+        while rem >= NLA_HDRLEN && nla_len(pos) >= NLA_HDRLEN && nla_len(pos) <= rem {
+            let nla_type = u16::from_ne_bytes([data[pos + 2], data[pos + 3]]);
+            if u32::from(nla_type & 0x3fff) == kind {
+                return pos as u32;
+            }
+            let total = (nla_len(pos) + 3) & !3;
+            if total >= rem {
+                break;
+            }
+            pos += total;
+            rem -= total;
+        }
+        0
+    }
+
+    fn ancillary(&self, anc: Ancillary, a: u32, x: u32) -> u32 {
+        match anc {
+            Ancillary::Protocol => self.protocol.into(),
+            Ancillary::PacketType => self.packet_type.into(),
+            Ancillary::Interface => self.interface,
+            Ancillary::NetlinkAttr => self.netlink_attr(a, x, false),
+            Ancillary::NetlinkAttrNested => self.netlink_attr(a, x, true),
+            Ancillary::Mark => self.mark,
+            Ancillary::Queue => self.queue.into(),
+            Ancillary::HardwareType => self.hardware_type.into(),
+            Ancillary::RxHash => self.rxhash,
+            Ancillary::Cpu => self.cpu,
+            Ancillary::AluXorX => a ^ x,
+            Ancillary::VlanTag => self.vlan.map_or(0, |(tci, _)| tci.into()),
+            Ancillary::VlanTagPresent => self.vlan.is_some().into(),
+            Ancillary::PayloadOffset => self.payload_offset,
+            Ancillary::Random => self.random,
+            Ancillary::VlanTpid => self.vlan.map_or(0, |(_, tpid)| tpid.into()),
+        }
+    }
+}
+
+/// Userspace interpreter for classic BPF programs.
+///
+/// This allows testing programs without attaching them to a socket.
+///
+/// # Examples
+///
+/// ```
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// use socket2::bpf::{Interpreter, Packet, Size, Source, Condition, Operand, ProgramBuilder, ReturnValue};
+///
+/// // Accept the first 64 bytes of IPv4 packets.
+/// let program = ProgramBuilder::new()
+///     .load(Source::Absolute(Size::Half, 12))
+///     .jump_if(Condition::Eq, Operand::Constant(0x0800), "accept", "drop")
+///     .label("accept")
+///     .ret(ReturnValue::Constant(64))
+///     .label("drop")
+///     .ret(ReturnValue::Constant(0))
+///     .build()?;
+/// let interpreter = Interpreter::new(&program)?;
+///
+/// let mut frame = [0; 14];
+/// frame[12..].copy_from_slice(&[0x08, 0x00]);
+/// assert_eq!(interpreter.run(&Packet::new(&frame))?, 64);
+/// frame[12..].copy_from_slice(&[0x86, 0xdd]);
+/// assert_eq!(interpreter.run(&Packet::new(&frame))?, 0);
+/// // Loading past the end of the packet faults, dropping it.
+/// assert!(interpreter.run(&Packet::new(&frame[..12])).is_err());
+/// # Ok(()) }
+/// ```
+#[derive(Debug)]
+pub struct Interpreter<'a> {
+    program: &'a [SockFilter],
+}
+
+impl<'a> Interpreter<'a> {
+    /// Create an interpreter for `program`, which is [validated] first.
+    ///
+    /// [validated]: validate
+    pub fn new(program: &'a [SockFilter]) -> Result<Interpreter<'a>, ProgramError> {
+        validate(program)?;
+        Ok(Interpreter { program })
+    }
+
+    /// Run the program on `packet`.
+    ///
+    /// Returns the value of the `ret` instruction, for socket filters this
+    /// is the number of bytes of the packet to accept, truncating it, with
+    /// zero dropping the packet.
+    ///
+    /// On a fault, such as loading data outside of the packet or dividing by
+    /// zero, the kernel stops the program and returns zero. Use
+    /// `unwrap_or(0)` to get the same result.
+    pub fn run(&self, packet: &Packet<'_>) -> Result<u32, Fault> {
+        let mut a = 0u32;
+        let mut x = 0u32;
+        let mut mem = [0u32; MEMORY_WORDS as usize];
+        let mut pc = 0;
+        loop {
+            let index = pc;
+            let fault = |kind| Err(Fault { index, kind });
+            // `new` validated the program, so all opcodes are valid, jumps
+            // are in range and it ends with a `ret`.
+            let insn = match Instruction::decode(&self.program[index]) {
+                Some(insn) => insn,
+                None => unreachable!("invalid BPF instruction"),
+            };
+            pc += 1;
+            match insn {
+                Instruction::Load(source) => {
+                    let value = match source {
+                        Source::Immediate(k) => Some(k),
+                        Source::Absolute(size, k) => packet.load(k, size),
+                        Source::Indirect(size, k) => packet.load(x.wrapping_add(k), size),
+                        Source::Network(size, k) => packet.load(NET_OFF.wrapping_add(k), size),
+                        Source::LinkLayer(size, k) => packet.load(LL_OFF.wrapping_add(k), size),
+                        Source::Memory(k) => Some(mem[k as usize]),
+                        Source::Length => Some(packet.data().len() as u32),
+                        Source::Ancillary(_, anc) => Some(packet.ancillary(anc, a, x)),
+                    };
+                    match value {
+                        Some(value) => a = value,
+                        None => return fault(FaultKind::OutOfBounds),
+                    }
+                }
+                Instruction::LoadX(source) => {
+                    x = match source {
+                        SourceX::Immediate(k) => k,
+                        SourceX::Memory(k) => mem[k as usize],
+                        SourceX::Length => packet.data().len() as u32,
+                        SourceX::IpHeaderLength(k) => match packet.load(k, Size::Byte) {
+                            Some(value) => 4 * (value & 0xf),
+                            None => return fault(FaultKind::OutOfBounds),
+                        },
+                    }
+                }
+                Instruction::Store(k) => mem[k as usize] = a,
+                Instruction::StoreX(k) => mem[k as usize] = x,
+                Instruction::Alu(op, operand) => {
+                    let value = match operand {
+                        Operand::Constant(k) => k,
+                        Operand::X => x,
+                    };
+                    a = match op {
+                        AluOp::Add => a.wrapping_add(value),
+                        AluOp::Sub => a.wrapping_sub(value),
+                        AluOp::Mul => a.wrapping_mul(value),
+                        AluOp::Div | AluOp::Mod if value == 0 => {
+                            return fault(FaultKind::DivisionByZero)
+                        }
+                        AluOp::Div => a / value,
+                        AluOp::Mod => a % value,
+                        AluOp::And => a & value,
+                        AluOp::Or => a | value,
+                        AluOp::Xor => a ^ value,
+                        AluOp::Lsh => a.wrapping_shl(value),
+                        AluOp::Rsh => a.wrapping_shr(value),
+                    };
+                }
+                Instruction::Neg => a = a.wrapping_neg(),
+                Instruction::Jump(k) => pc += k as usize,
+                Instruction::JumpIf {
+                    cond,
+                    operand,
+                    jt,
+                    jf,
+                } => {
+                    let value = match operand {
+                        Operand::Constant(k) => k,
+                        Operand::X => x,
+                    };
+                    let taken = match cond {
+                        Condition::Eq => a == value,
+                        Condition::Gt => a > value,
+                        Condition::Ge => a >= value,
+                        Condition::Set => a & value != 0,
+                    };
+                    pc += usize::from(if taken { jt } else { jf });
+                }
+                Instruction::Return(ReturnValue::Constant(k)) => return Ok(k),
+                Instruction::Return(ReturnValue::A) => return Ok(a),
+                Instruction::Tax => x = a,
+                Instruction::Txa => a = x,
+            }
+        }
+    }
+}
+
+/// Fault while running a program, returned by [`Interpreter::run`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Fault {
+    index: usize,
+    kind: FaultKind,
+}
+
+/// Kind of [`Fault`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FaultKind {
+    /// Load outside of the packet, or relative to a header that isn't set.
+    OutOfBounds,
+    /// Division or modulo by zero (`X`).
+    DivisionByZero,
+}
+
+impl Fault {
+    /// Returns the index of the faulting instruction.
+    pub const fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns the kind of fault.
+    pub const fn kind(&self) -> FaultKind {
+        self.kind
+    }
+}
+
+impl fmt::Display for Fault {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self.kind {
+            FaultKind::OutOfBounds => "BPF load out of bounds",
+            FaultKind::DivisionByZero => "BPF division by zero",
+        })?;
+        write!(f, " at instruction {}", self.index)
+    }
+}
+
+impl error::Error for Fault {}
+
+/// Error returned by [`validate`] and [`ProgramBuilder::build`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ProgramError {
+    index: Option<usize>,
+    kind: ProgramErrorKind,
+}
+
+/// Kind of [`ProgramError`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ProgramErrorKind {
+    /// Program has no instructions.
+    Empty,
+    /// Program has more than [`MAX_INSTRUCTIONS`] instructions.
+    TooLong,
+    /// Unknown or unsupported opcode.
+    InvalidOpcode,
+    /// Division or modulo by constant zero.
+    DivisionByZero,
+    /// Shift by a constant of 32 or more.
+    InvalidShift,
+    /// Scratch memory index of [`MEMORY_WORDS`] or more.
+    InvalidMemoryIndex,
+    /// Jump past the end of the program.
+    JumpOutOfRange,
+    /// Unknown ancillary (`SKF_AD_*`) load.
+    UnknownAncillary,
+    /// Last instruction isn't a `ret`.
+    MissingReturn,
+    /// Scratch memory is read before it's written.
+    UninitializedMemory,
+    /// Jump to a label that isn't defined.
+    UndefinedLabel,
+    /// Label is defined more than once.
+    DuplicateLabel,
+    /// Jump to a label defined before the jump.
+    BackwardJump,
+    /// Conditional jump skipping more than 255 instructions.
+    JumpTooFar,
+}
+
+impl ProgramError {
+    const fn new(index: Option<usize>, kind: ProgramErrorKind) -> ProgramError {
+        ProgramError { index, kind }
+    }
+
+    /// Returns the index of the invalid instruction, if any.
+    pub const fn index(&self) -> Option<usize> {
+        self.index
+    }
+
+    /// Returns the kind of error.
+    pub const fn kind(&self) -> ProgramErrorKind {
+        self.kind
+    }
+}
+
+impl fmt::Display for ProgramError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self.kind {
+            ProgramErrorKind::Empty => "empty BPF program",
+            ProgramErrorKind::TooLong => "BPF program too long",
+            ProgramErrorKind::InvalidOpcode => "invalid BPF opcode",
+            ProgramErrorKind::DivisionByZero => "BPF division by zero",
+            ProgramErrorKind::InvalidShift => "invalid BPF shift",
+            ProgramErrorKind::InvalidMemoryIndex => "invalid BPF scratch memory index",
+            ProgramErrorKind::JumpOutOfRange => "BPF jump out of range",
+            ProgramErrorKind::UnknownAncillary => "unknown BPF ancillary load",
+            ProgramErrorKind::MissingReturn => "BPF program doesn't end with ret",
+            ProgramErrorKind::UninitializedMemory => "BPF scratch memory read before written",
+            ProgramErrorKind::UndefinedLabel => "undefined BPF label",
+            ProgramErrorKind::DuplicateLabel => "duplicate BPF label",
+            ProgramErrorKind::BackwardJump => "backward BPF jump",
+            ProgramErrorKind::JumpTooFar => "BPF jump too far",
+        })?;
+        if let Some(index) = self.index {
+            write!(f, " at instruction {index}")?;
+        }
+        Ok(())
+    }
+}
+
+impl error::Error for ProgramError {}
+
+impl From<ProgramError> for io::Error {
+    fn from(err: ProgramError) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidInput, err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn disassemble_program() {
+        // Only accept UDP datagrams with more than 2 bytes of payload.
+        let program = ProgramBuilder::new()
+            .load(Source::Network(Size::Byte, 9))
+            .jump_if(Condition::Eq, Operand::Constant(17), "udp", "drop")
+            .label("udp")
+            .load(Source::Length)
+            .store(0)
+            .load_x(SourceX::Memory(0))
+            .txa()
+            .jump_if(Condition::Gt, Operand::Constant(8 + 2), "accept", "drop")
+            .label("accept")
+            .ret(ReturnValue::Constant(u32::MAX))
+            .label("drop")
+            .ret(ReturnValue::Constant(0))
+            .build()
+            .unwrap();
+        assert_eq!(
+            disassemble(&program).to_string(),
+            "(000) ldb      [net + 9]\n\
+             (001) jeq      #0x11            jt 2    jf 8\n\
+             (002) ld       #len\n\
+             (003) st       M[0]\n\
+             (004) ldx      M[0]\n\
+             (005) txa\n\
+             (006) jgt      #0xa             jt 7    jf 8\n\
+             (007) ret      #4294967295\n\
+             (008) ret      #0"
+        );
+        assert_eq!(
+            format!("{:?}", program[1]),
+            "SockFilter(jeq #0x11 jt +0 jf +6)"
+        );
+    }
+
+    #[test]
+    fn decode() {
+        // Existing programs decode and pass validation.
+        for program in [
+            &SockFilter::reuseport_cpu(2)[..],
+            &SockFilter::reuseport_hash(2),
+        ] {
+            validate(program).unwrap();
+            for filter in program {
+                let insn = Instruction::decode(filter).unwrap();
+                assert_eq!(SockFilter::from(insn).code(), filter.code());
+            }
+        }
+        assert_eq!(
+            Instruction::decode(&SockFilter::reuseport_cpu(2)[0]),
+            Some(Instruction::Load(Source::Ancillary(
+                Size::Word,
+                Ancillary::Cpu
+            )))
+        );
+
+        // Metadata loads of any size.
+        for (code, size, name) in [
+            (0x20, Size::Word, "ld"),
+            (0x28, Size::Half, "ldh"),
+            (0x30, Size::Byte, "ldb"),
+        ] {
+            let program = [
+                SockFilter::new(code, 0, 0, 0xfffff000),
+                Instruction::Return(ReturnValue::A).into(),
+            ];
+            validate(&program).unwrap();
+            let insn = Instruction::decode(&program[0]).unwrap();
+            assert_eq!(
+                insn,
+                Instruction::Load(Source::Ancillary(size, Ancillary::Protocol))
+            );
+            assert_eq!(SockFilter::from(insn).code(), code);
+            assert_eq!(
+                format!("{:?}", program[0]),
+                format!("SockFilter({name} #proto)")
+            );
+        }
+    }
+
+    #[test]
+    fn validate_invalid() {
+        let ret = || SockFilter::from(Instruction::Return(ReturnValue::A));
+        let invalid = [
+            (vec![], ProgramErrorKind::Empty, None),
+            (
+                vec![SockFilter::new(0xff, 0, 0, 0), ret()],
+                ProgramErrorKind::InvalidOpcode,
+                Some(0),
+            ),
+            (
+                vec![
+                    Instruction::Alu(AluOp::Div, Operand::Constant(0)).into(),
+                    ret(),
+                ],
+                ProgramErrorKind::DivisionByZero,
+                Some(0),
+            ),
+            (
+                vec![
+                    Instruction::Alu(AluOp::Lsh, Operand::Constant(32)).into(),
+                    ret(),
+                ],
+                ProgramErrorKind::InvalidShift,
+                Some(0),
+            ),
+            (
+                vec![Instruction::Store(16).into(), ret()],
+                ProgramErrorKind::InvalidMemoryIndex,
+                Some(0),
+            ),
+            (
+                vec![Instruction::Jump(1).into(), ret()],
+                ProgramErrorKind::JumpOutOfRange,
+                Some(0),
+            ),
+            (
+                vec![
+                    Instruction::Load(Source::Absolute(Size::Word, 0xfffff040)).into(),
+                    ret(),
+                ],
+                ProgramErrorKind::UnknownAncillary,
+                Some(0),
+            ),
+            (
+                vec![
+                    Instruction::Load(Source::Absolute(Size::Half, 0xfffff040)).into(),
+                    ret(),
+                ],
+                ProgramErrorKind::UnknownAncillary,
+                Some(0),
+            ),
+            (
+                vec![ret(), Instruction::Tax.into()],
+                ProgramErrorKind::MissingReturn,
+                Some(1),
+            ),
+            (
+                vec![Instruction::Load(Source::Memory(1)).into(), ret()],
+                ProgramErrorKind::UninitializedMemory,
+                Some(0),
+            ),
+            (
+                vec![
+                    Instruction::Load(Source::Length).into(),
+                    Instruction::JumpIf {
+                        cond: Condition::Set,
+                        operand: Operand::Constant(1),
+                        jt: 1,
+                        jf: 0,
+                    }
+                    .into(),
+                    Instruction::Store(1).into(),
+                    Instruction::Load(Source::Memory(1)).into(),
+                    ret(),
+                ],
+                ProgramErrorKind::UninitializedMemory,
+                Some(3),
+            ),
+        ];
+        for (program, kind, index) in invalid {
+            let err = validate(&program).unwrap_err();
+            assert_eq!((err.kind(), err.index()), (kind, index), "{err}");
+        }
+    }
+
+    #[test]
+    fn program_builder_errors() {
+        let err = ProgramBuilder::new()
+            .jump("missing")
+            .ret(ReturnValue::A)
+            .build()
+            .unwrap_err();
+        assert_eq!(err.kind(), ProgramErrorKind::UndefinedLabel);
+        let err = ProgramBuilder::new()
+            .label("start")
+            .load(Source::Length)
+            .jump("start")
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            (err.kind(), err.index()),
+            (ProgramErrorKind::BackwardJump, Some(1))
+        );
+        let mut builder = ProgramBuilder::new().jump_if(Condition::Eq, Operand::X, "end", "end");
+        for _ in 0..256 {
+            builder = builder.negate();
+        }
+        let err = builder
+            .label("end")
+            .ret(ReturnValue::A)
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            (err.kind(), err.index()),
+            (ProgramErrorKind::JumpTooFar, Some(0))
+        );
+    }
+}
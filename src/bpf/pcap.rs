@@ -0,0 +1,793 @@
+// Copyright 2015 The Rust Project Developers.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Compiler for `tcpdump`-style filter expressions, see `pcap-filter(7)`.
+//!
+//! [`compile`] supports the following subset of the syntax:
+//!
+//!  * `ip`, `ip6`, `tcp`, `udp` and `icmp`.
+//!  * `[ip|ip6] [src|dst] host <address>`, the address can be IPv4 or IPv6.
+//!  * `[ip|ip6] [src|dst] net <address>[/<prefix length>]`.
+//!  * `[tcp|udp] [src|dst] port <port>` and
+//!    `[tcp|udp] [src|dst] portrange <port>-<port>`.
+//!  * `vlan [<vlan id>]`, matching the VLAN tag stripped by the kernel.
+//!  * `len <op> <length>` with `<op>` one of `<`, `<=`, `>`, `>=`, `=` or
+//!    `!=`, `less <length>` and `greater <length>`.
+//!  * `and` (`&&`), `or` (`||`), `not` (`!`) and parentheses.
+//!
+//! The direction can be `src`, `dst`, `src or dst` (the default) or
+//! `src and dst`. `src <address>` is short for `src host <address>`.
+//!
+//! # Examples
+//!
+//! ```
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! use socket2::bpf::pcap::{compile, LinkType};
+//! use socket2::bpf::{Interpreter, Packet};
+//!
+//! let program = compile("ip and not (udp port 53 or src net 10.0.0.0/8)", LinkType::Ip)?;
+//!
+//! let mut packet = [0; 28];
+//! packet[..4].copy_from_slice(&[0x45, 0, 0, 28]);
+//! packet[9] = 17; // UDP.
+//! packet[12..16].copy_from_slice(&[192, 0, 2, 1]);
+//! packet[22..24].copy_from_slice(&53u16.to_be_bytes());
+//! let packet = Packet::new(&packet).with_protocol(libc::ETH_P_IP as u16);
+//! assert_eq!(Interpreter::new(&program)?.run(&packet)?, 0);
+//! # Ok(()) }
+//! ```
+
+use std::net::IpAddr;
+use std::{error, fmt, io};
+
+use super::{
+    AluOp, Ancillary, Condition, Instruction, Operand, ProgramBuilder, ReturnValue, Size, Source,
+    SourceX, NET_OFF,
+};
+use crate::SockFilter;
+
+const ETH_P_IP: u32 = libc::ETH_P_IP as u32;
+const ETH_P_IPV6: u32 = libc::ETH_P_IPV6 as u32;
+const IPPROTO_ICMP: u32 = libc::IPPROTO_ICMP as u32;
+const IPPROTO_TCP: u32 = libc::IPPROTO_TCP as u32;
+const IPPROTO_UDP: u32 = libc::IPPROTO_UDP as u32;
+
+/// Link layer of the packets seen by the program.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum LinkType {
+    /// Packets start with an Ethernet header, e.g. `Domain::PACKET` sockets
+    /// using `Type::RAW` on an Ethernet (or loopback) interface.
+    Ethernet,
+    /// Only the network header is used, the protocol is determined using
+    /// [`Ancillary::Protocol`]. The network header is loaded relative to
+    /// `SKF_NET_OFF`, so this works regardless of where the packet data
+    /// starts, e.g. `Domain::PACKET` sockets using `Type::DGRAM` ("cooked"
+    /// packets) or `Domain::IPV4` sockets using `Type::RAW`. Note that `len`
+    /// is still the length of the packet data.
+    Ip,
+}
+
+impl LinkType {
+    /// Offset of the network header, for `Ip` relative to `SKF_NET_OFF`.
+    const fn network_offset(self) -> u32 {
+        match self {
+            LinkType::Ethernet => 14,
+            LinkType::Ip => NET_OFF,
+        }
+    }
+}
+
+/// Compile `expression` into a program accepting the entire packet if it
+/// matches the expression, or dropping it if it doesn't.
+///
+/// An empty expression accepts all packets.
+pub fn compile(expression: &str, link: LinkType) -> Result<Vec<SockFilter>, CompileError> {
+    let tokens = tokenize(expression)?;
+    let mut parser = Parser {
+        tokens,
+        pos: 0,
+        end: expression.len(),
+        link,
+    };
+    let node = if parser.tokens.is_empty() {
+        None
+    } else {
+        let node = parser.parse_or()?;
+        if let Some(token) = parser.peek() {
+            return Err(CompileError::new(
+                Some(token.offset),
+                CompileErrorKind::UnexpectedToken,
+            ));
+        }
+        Some(node)
+    };
+
+    let mut gen = CodeGen {
+        ops: Vec::new(),
+        labels: 0,
+    };
+    let accept = gen.new_label();
+    let reject = gen.new_label();
+    if let Some(node) = &node {
+        gen.gen(node, accept, reject);
+    }
+    for (label, value) in [(accept, u32::MAX), (reject, 0)] {
+        let ret = Instruction::Return(ReturnValue::Constant(value));
+        gen.ops.extend([Op::Label(label), Op::Instruction(ret)]);
+    }
+    gen.add_trampolines();
+
+    let names: Vec<String> = (0..gen.labels).map(|label| label.to_string()).collect();
+    let mut builder = ProgramBuilder::new();
+    for op in &gen.ops {
+        builder = match *op {
+            Op::Instruction(insn) => builder.instruction(insn),
+            Op::JumpIf(cond, k, jt, jf) => builder.jump_if(
+                cond,
+                Operand::Constant(k),
+                names[jt].as_str(),
+                names[jf].as_str(),
+            ),
+            Op::Jump(target) => builder.jump(names[target].as_str()),
+            Op::Label(label) => builder.label(&names[label]),
+        };
+    }
+    builder
+        .build()
+        .map_err(|_| CompileError::new(None, CompileErrorKind::TooComplex))
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Token<'a> {
+    text: &'a str,
+    offset: usize,
+}
+
+fn tokenize(expression: &str) -> Result<Vec<Token<'_>>, CompileError> {
+    let is_word = |c: char| c.is_ascii_alphanumeric() || matches!(c, '.' | ':' | '/' | '-' | '_');
+    let mut tokens = Vec::new();
+    let mut rest = expression;
+    while let Some(c) = rest.chars().next() {
+        let offset = expression.len() - rest.len();
+        let len = if c.is_whitespace() {
+            rest = &rest[c.len_utf8()..];
+            continue;
+        } else if is_word(c) {
+            rest.find(|c| !is_word(c)).unwrap_or(rest.len())
+        } else if ["!=", "&&", "||", "<=", ">=", "=="]
+            .iter()
+            .any(|op| rest.starts_with(op))
+        {
+            2
+        } else if matches!(c, '(' | ')' | '!' | '<' | '>' | '=') {
+            1
+        } else {
+            return Err(CompileError::new(
+                Some(offset),
+                CompileErrorKind::UnexpectedToken,
+            ));
+        };
+        tokens.push(Token {
+            text: &rest[..len],
+            offset,
+        });
+        rest = &rest[len..];
+    }
+    Ok(tokens)
+}
+
+/// Expression, lowered to checks on the packet.
+#[derive(Clone, Debug)]
+enum Node {
+    /// Run `loads` and compare the accumulator with `k`.
+    Check {
+        loads: Vec<Instruction>,
+        cond: Condition,
+        k: u32,
+    },
+    And(Box<Node>, Box<Node>),
+    Or(Box<Node>, Box<Node>),
+    Not(Box<Node>),
+}
+
+fn check(loads: &[Instruction], cond: Condition, k: u32) -> Node {
+    Node::Check {
+        loads: loads.to_vec(),
+        cond,
+        k,
+    }
+}
+
+fn and(a: Node, b: Node) -> Node {
+    Node::And(Box::new(a), Box::new(b))
+}
+
+fn or(a: Node, b: Node) -> Node {
+    Node::Or(Box::new(a), Box::new(b))
+}
+
+fn not(a: Node) -> Node {
+    Node::Not(Box::new(a))
+}
+
+/// Direction qualifier.
+#[derive(Copy, Clone, Debug)]
+enum Direction {
+    Src,
+    Dst,
+    SrcOrDst,
+    SrcAndDst,
+}
+
+impl Direction {
+    /// Combine the checks of the source and destination fields, `check` is
+    /// called with the offset of the field.
+    fn apply<F: Fn(u32) -> Node>(self, src: u32, dst: u32, check: F) -> Node {
+        match self {
+            Direction::Src => check(src),
+            Direction::Dst => check(dst),
+            Direction::SrcOrDst => or(check(src), check(dst)),
+            Direction::SrcAndDst => and(check(src), check(dst)),
+        }
+    }
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token<'a>>,
+    pos: usize,
+    /// Offset of the end of the expression, for errors.
+    end: usize,
+    link: LinkType,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<Token<'a>> {
+        self.tokens.get(self.pos).copied()
+    }
+
+    fn peek_is(&self, texts: &[&str]) -> bool {
+        self.peek().is_some_and(|token| texts.contains(&token.text))
+    }
+
+    fn next(&mut self) -> Result<Token<'a>, CompileError> {
+        let token = self.peek().ok_or(CompileError::new(
+            Some(self.end),
+            CompileErrorKind::UnexpectedEnd,
+        ))?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn parse_or(&mut self) -> Result<Node, CompileError> {
+        let mut node = self.parse_and()?;
+        while self.peek_is(&["or", "||"]) {
+            self.pos += 1;
+            node = or(node, self.parse_and()?);
+        }
+        Ok(node)
+    }
+
+    fn parse_and(&mut self) -> Result<Node, CompileError> {
+        let mut node = self.parse_unary()?;
+        while self.peek_is(&["and", "&&"]) {
+            self.pos += 1;
+            node = and(node, self.parse_unary()?);
+        }
+        Ok(node)
+    }
+
+    fn parse_unary(&mut self) -> Result<Node, CompileError> {
+        if self.peek_is(&["not", "!"]) {
+            self.pos += 1;
+            Ok(not(self.parse_unary()?))
+        } else if self.peek_is(&["("]) {
+            self.pos += 1;
+            let node = self.parse_or()?;
+            let token = self.next()?;
+            if token.text != ")" {
+                return Err(CompileError::new(
+                    Some(token.offset),
+                    CompileErrorKind::UnexpectedToken,
+                ));
+            }
+            Ok(node)
+        } else {
+            self.parse_primitive()
+        }
+    }
+
+    fn parse_primitive(&mut self) -> Result<Node, CompileError> {
+        let token = self.next()?;
+        match token.text {
+            "ip" | "ip6" | "tcp" | "udp" | "icmp" => {
+                if self.peek_is(&["src", "dst", "host", "net", "port", "portrange"]) {
+                    self.parse_qualified(Some(token))
+                } else {
+                    Ok(match token.text {
+                        "ip" => self.ethertype(ETH_P_IP),
+                        "ip6" => self.ethertype(ETH_P_IPV6),
+                        "tcp" => self.transport(IPPROTO_TCP),
+                        "udp" => self.transport(IPPROTO_UDP),
+                        _ => self.ipv4_protocol(IPPROTO_ICMP),
+                    })
+                }
+            }
+            "src" | "dst" | "host" | "net" | "port" | "portrange" => {
+                self.pos -= 1;
+                self.parse_qualified(None)
+            }
+            "vlan" => {
+                let present = check(
+                    &[Instruction::Load(Source::Ancillary(
+                        Size::Word,
+                        Ancillary::VlanTagPresent,
+                    ))],
+                    Condition::Eq,
+                    1,
+                );
+                let is_number = self
+                    .peek()
+                    .is_some_and(|token| token.text.starts_with(|c: char| c.is_ascii_digit()));
+                if !is_number {
+                    return Ok(present);
+                }
+                let id = self.parse_number(0xfff)?;
+                let loads = [
+                    Instruction::Load(Source::Ancillary(Size::Word, Ancillary::VlanTag)),
+                    Instruction::Alu(AluOp::And, Operand::Constant(0xfff)),
+                ];
+                Ok(and(present, check(&loads, Condition::Eq, id)))
+            }
+            "len" => {
+                let op = self.next()?;
+                let (cond, negate) = match op.text {
+                    "=" | "==" => (Condition::Eq, false),
+                    "!=" => (Condition::Eq, true),
+                    ">" => (Condition::Gt, false),
+                    ">=" => (Condition::Ge, false),
+                    "<" => (Condition::Ge, true),
+                    "<=" => (Condition::Gt, true),
+                    _ => {
+                        return Err(CompileError::new(
+                            Some(op.offset),
+                            CompileErrorKind::UnexpectedToken,
+                        ))
+                    }
+                };
+                let length = self.parse_number(u32::MAX)?;
+                let node = self.length(cond, length);
+                Ok(if negate { not(node) } else { node })
+            }
+            "less" => {
+                let length = self.parse_number(u32::MAX)?;
+                Ok(not(self.length(Condition::Gt, length)))
+            }
+            "greater" => {
+                let length = self.parse_number(u32::MAX)?;
+                Ok(self.length(Condition::Ge, length))
+            }
+            _ => Err(CompileError::new(
+                Some(token.offset),
+                CompileErrorKind::UnexpectedToken,
+            )),
+        }
+    }
+
+    /// Parse `[src|dst] [host|net|port|portrange] <id>`, after an optional
+    /// protocol qualifier.
+    fn parse_qualified(&mut self, protocol: Option<Token<'a>>) -> Result<Node, CompileError> {
+        let dir = match self.peek().map(|token| token.text) {
+            Some(first @ ("src" | "dst")) => {
+                self.pos += 1;
+                let other = if first == "src" { "dst" } else { "src" };
+                let combined = self.peek_is(&["or", "and"])
+                    && self.tokens.get(self.pos + 1).map(|t| t.text) == Some(other);
+                if combined {
+                    let op = self.next()?;
+                    self.pos += 1;
+                    if op.text == "or" {
+                        Direction::SrcOrDst
+                    } else {
+                        Direction::SrcAndDst
+                    }
+                } else if first == "src" {
+                    Direction::Src
+                } else {
+                    Direction::Dst
+                }
+            }
+            _ => Direction::SrcOrDst,
+        };
+        let kind = if self.peek_is(&["host", "net", "port", "portrange"]) {
+            self.next()?.text
+        } else {
+            "host"
+        };
+
+        let invalid_qualifier = |token: Token<'_>| {
+            Err(CompileError::new(
+                Some(token.offset),
+                CompileErrorKind::InvalidQualifier,
+            ))
+        };
+        match kind {
+            "host" | "net" => {
+                let version = match protocol {
+                    Some(token) if token.text == "ip" => Some(4),
+                    Some(token) if token.text == "ip6" => Some(6),
+                    Some(token) => return invalid_qualifier(token),
+                    None => None,
+                };
+                let token = self.next()?;
+                let (addr, prefix) = match (kind, token.text.split_once('/')) {
+                    ("net", Some((addr, prefix))) => (addr, Some(prefix)),
+                    _ => (token.text, None),
+                };
+                let invalid_address =
+                    CompileError::new(Some(token.offset), CompileErrorKind::InvalidAddress);
+                let addr: IpAddr = addr.parse().map_err(|_| invalid_address)?;
+                let bits = if addr.is_ipv4() { 32 } else { 128 };
+                let prefix = match prefix {
+                    Some(prefix) => match prefix.parse::<u32>() {
+                        Ok(prefix) if prefix <= bits => prefix,
+                        _ => return Err(invalid_address),
+                    },
+                    None => bits,
+                };
+                match (addr, version) {
+                    (IpAddr::V4(_), Some(6)) | (IpAddr::V6(_), Some(4)) => {
+                        return Err(invalid_address)
+                    }
+                    _ => {}
+                }
+                self.address(dir, addr, prefix).ok_or(invalid_address)
+            }
+            _ => {
+                let protocols: &[u32] = match protocol {
+                    Some(token) if token.text == "tcp" => &[IPPROTO_TCP],
+                    Some(token) if token.text == "udp" => &[IPPROTO_UDP],
+                    Some(token) => return invalid_qualifier(token),
+                    None => &[IPPROTO_TCP, IPPROTO_UDP],
+                };
+                let (low, high) = if kind == "port" {
+                    let port = self.parse_number(u16::MAX.into())?;
+                    (port, port)
+                } else {
+                    let token = self.next()?;
+                    let invalid_number =
+                        CompileError::new(Some(token.offset), CompileErrorKind::InvalidNumber);
+                    let (low, high) = token.text.split_once('-').ok_or(invalid_number)?;
+                    let low = parse_number(low, u16::MAX.into()).ok_or(invalid_number)?;
+                    let high = parse_number(high, u16::MAX.into()).ok_or(invalid_number)?;
+                    if low > high {
+                        return Err(invalid_number);
+                    }
+                    (low, high)
+                };
+                Ok(self.port(dir, protocols, low, high))
+            }
+        }
+    }
+
+    fn parse_number(&mut self, max: u32) -> Result<u32, CompileError> {
+        let token = self.next()?;
+        parse_number(token.text, max).ok_or(CompileError::new(
+            Some(token.offset),
+            CompileErrorKind::InvalidNumber,
+        ))
+    }
+
+    /// Check the ethertype of the packet.
+    fn ethertype(&self, ethertype: u32) -> Node {
+        let load = match self.link {
+            LinkType::Ethernet => Source::Absolute(Size::Half, 12),
+            LinkType::Ip => Source::Ancillary(Size::Word, Ancillary::Protocol),
+        };
+        check(&[Instruction::Load(load)], Condition::Eq, ethertype)
+    }
+
+    /// Load `size` bytes at `offset` in the network header.
+    fn network(&self, size: Size, offset: u32) -> Instruction {
+        match self.link {
+            LinkType::Ethernet => Instruction::Load(Source::Absolute(size, 14 + offset)),
+            LinkType::Ip => Instruction::Load(Source::Network(size, offset)),
+        }
+    }
+
+    fn ipv4_protocol(&self, protocol: u32) -> Node {
+        let load = self.network(Size::Byte, 9);
+        and(
+            self.ethertype(ETH_P_IP),
+            check(&[load], Condition::Eq, protocol),
+        )
+    }
+
+    fn ipv6_protocol(&self, protocol: u32) -> Node {
+        let load = self.network(Size::Byte, 6);
+        and(
+            self.ethertype(ETH_P_IPV6),
+            check(&[load], Condition::Eq, protocol),
+        )
+    }
+
+    fn transport(&self, protocol: u32) -> Node {
+        or(self.ipv4_protocol(protocol), self.ipv6_protocol(protocol))
+    }
+
+    fn length(&self, cond: Condition, length: u32) -> Node {
+        check(&[Instruction::Load(Source::Length)], cond, length)
+    }
+
+    /// Match the `prefix` bits of the source and/or destination address
+    /// against `addr`. Returns `None` if host bits are set in `addr`.
+    fn address(&self, dir: Direction, addr: IpAddr, prefix: u32) -> Option<Node> {
+        let (ethertype, src, dst, words) = match addr {
+            IpAddr::V4(addr) => (ETH_P_IP, 12, 16, vec![u32::from(addr)]),
+            IpAddr::V6(addr) => {
+                let words = addr
+                    .octets()
+                    .chunks(4)
+                    .map(|word| u32::from_be_bytes(word.try_into().unwrap()))
+                    .collect();
+                (ETH_P_IPV6, 8, 24, words)
+            }
+        };
+        let mut checks = Vec::new();
+        for (i, word) in (0..).zip(words) {
+            let mask = match prefix.saturating_sub(32 * i).min(32) {
+                0 => 0,
+                bits => u32::MAX << (32 - bits),
+            };
+            if word & !mask != 0 {
+                return None;
+            } else if mask != 0 {
+                checks.push((4 * i, mask, word));
+            }
+        }
+        let ethertype = self.ethertype(ethertype);
+        if checks.is_empty() {
+            return Some(ethertype);
+        }
+        let field = |offset: u32| {
+            let mut node = None;
+            for &(i, mask, word) in &checks {
+                let mut loads = vec![self.network(Size::Word, offset + i)];
+                if mask != u32::MAX {
+                    loads.push(Instruction::Alu(AluOp::And, Operand::Constant(mask)));
+                }
+                let check = check(&loads, Condition::Eq, word);
+                node = Some(match node {
+                    Some(node) => and(node, check),
+                    None => check,
+                });
+            }
+            node.unwrap()
+        };
+        Some(and(ethertype, dir.apply(src, dst, field)))
+    }
+
+    /// Match the source and/or destination port of TCP or UDP packets
+    /// against `low..=high`.
+    fn port(&self, dir: Direction, protocols: &[u32], low: u32, high: u32) -> Node {
+        let in_range = |loads: &[Instruction]| {
+            if low == high {
+                check(loads, Condition::Eq, low)
+            } else {
+                and(
+                    check(loads, Condition::Ge, low),
+                    not(check(loads, Condition::Gt, high)),
+                )
+            }
+        };
+        let any_protocol = |offset: u32| {
+            let load = [self.network(Size::Byte, offset)];
+            let mut protocols = protocols.iter();
+            let first = check(&load, Condition::Eq, *protocols.next().unwrap());
+            protocols.fold(first, |node, protocol| {
+                or(node, check(&load, Condition::Eq, *protocol))
+            })
+        };
+        let nh = self.link.network_offset();
+
+        // Skip the IPv4 header, which has a variable length, and only check
+        // the first fragment.
+        let fragment = check(&[self.network(Size::Half, 6)], Condition::Set, 0x1fff);
+        let ipv4 = dir.apply(0, 2, |offset| {
+            in_range(&[
+                Instruction::LoadX(SourceX::IpHeaderLength(nh)),
+                Instruction::Load(Source::Indirect(Size::Half, nh + offset)),
+            ])
+        });
+        let ipv4 = and(
+            and(self.ethertype(ETH_P_IP), any_protocol(9)),
+            and(not(fragment), ipv4),
+        );
+
+        // Extension headers are not supported.
+        let ipv6 = dir.apply(40, 42, |offset| {
+            in_range(&[self.network(Size::Half, offset)])
+        });
+        let ipv6 = and(and(self.ethertype(ETH_P_IPV6), any_protocol(6)), ipv6);
+        or(ipv4, ipv6)
+    }
+}
+
+fn parse_number(text: &str, max: u32) -> Option<u32> {
+    let n = match text.strip_prefix("0x") {
+        Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+        None => text.parse().ok()?,
+    };
+    (n <= max).then_some(n)
+}
+
+#[derive(Copy, Clone, Debug)]
+enum Op {
+    Instruction(Instruction),
+    JumpIf(Condition, u32, usize, usize),
+    Jump(usize),
+    Label(usize),
+}
+
+struct CodeGen {
+    ops: Vec<Op>,
+    labels: usize,
+}
+
+impl CodeGen {
+    fn new_label(&mut self) -> usize {
+        self.labels += 1;
+        self.labels - 1
+    }
+
+    /// Generate code for `node` jumping to label `t` if it matches or to `f`
+    /// if it doesn't.
+    fn gen(&mut self, node: &Node, t: usize, f: usize) {
+        match node {
+            Node::Check { loads, cond, k } => {
+                let loads = loads.iter().map(|insn| Op::Instruction(*insn));
+                self.ops.extend(loads);
+                self.ops.push(Op::JumpIf(*cond, *k, t, f));
+            }
+            Node::And(a, b) => {
+                let next = self.new_label();
+                self.gen(a, next, f);
+                self.ops.push(Op::Label(next));
+                self.gen(b, t, f);
+            }
+            Node::Or(a, b) => {
+                let next = self.new_label();
+                self.gen(a, t, next);
+                self.ops.push(Op::Label(next));
+                self.gen(b, t, f);
+            }
+            Node::Not(a) => self.gen(a, f, t),
+        }
+    }
+
+    /// The targets of conditional jumps are limited to 255 instructions
+    /// ahead, redirect jumps to labels that are further away (e.g. the
+    /// shared accept and reject labels) using a `ja` placed directly after
+    /// the jump.
+    ///
+    /// Code following a conditional jump is only reached using a label, so
+    /// the `ja` is never executed by falling through.
+    fn add_trampolines(&mut self) {
+        loop {
+            let mut positions = vec![0; self.labels];
+            let mut pos = 0;
+            for op in &self.ops {
+                match *op {
+                    Op::Label(label) => positions[label] = pos,
+                    Op::Instruction(_) | Op::JumpIf(..) | Op::Jump(_) => pos += 1,
+                }
+            }
+            let too_far = |pos: usize, label: usize| positions[label] - pos - 1 > 255;
+
+            let mut far = Vec::new();
+            let mut pos = 0;
+            for (i, op) in self.ops.iter().enumerate() {
+                match *op {
+                    Op::Label(_) => continue,
+                    Op::JumpIf(_, _, t, f) if too_far(pos, t) || too_far(pos, f) => {
+                        far.push((i, too_far(pos, t), too_far(pos, f)));
+                    }
+                    _ => {}
+                }
+                pos += 1;
+            }
+            if far.is_empty() {
+                return;
+            }
+
+            // Insert from the back so the indices remain valid.
+            for (i, t_far, f_far) in far.into_iter().rev() {
+                let Op::JumpIf(cond, k, mut t, mut f) = self.ops[i] else {
+                    unreachable!()
+                };
+                let mut trampolines = Vec::new();
+                for (far, target) in [(t_far, &mut t), (f_far, &mut f)] {
+                    if far {
+                        let label = self.new_label();
+                        trampolines.extend([Op::Label(label), Op::Jump(*target)]);
+                        *target = label;
+                    }
+                }
+                self.ops[i] = Op::JumpIf(cond, k, t, f);
+                self.ops.splice(i + 1..i + 1, trampolines);
+            }
+        }
+    }
+}
+
+/// Error returned by [`compile`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct CompileError {
+    offset: Option<usize>,
+    kind: CompileErrorKind,
+}
+
+/// Kind of [`CompileError`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CompileErrorKind {
+    /// Expression ends unexpectedly.
+    UnexpectedEnd,
+    /// Unexpected or unsupported token.
+    UnexpectedToken,
+    /// Invalid or out of range number.
+    InvalidNumber,
+    /// Invalid address or prefix length.
+    InvalidAddress,
+    /// Protocol qualifier that can't be combined with the primitive, e.g.
+    /// `tcp host`.
+    InvalidQualifier,
+    /// Program is too long.
+    TooComplex,
+}
+
+impl CompileError {
+    const fn new(offset: Option<usize>, kind: CompileErrorKind) -> CompileError {
+        CompileError { offset, kind }
+    }
+
+    /// Returns the byte offset in the expression of the error, if any.
+    pub const fn offset(&self) -> Option<usize> {
+        self.offset
+    }
+
+    /// Returns the kind of error.
+    pub const fn kind(&self) -> CompileErrorKind {
+        self.kind
+    }
+}
+
+impl fmt::Display for CompileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self.kind {
+            CompileErrorKind::UnexpectedEnd => "unexpected end of filter expression",
+            CompileErrorKind::UnexpectedToken => "unexpected token in filter expression",
+            CompileErrorKind::InvalidNumber => "invalid number in filter expression",
+            CompileErrorKind::InvalidAddress => "invalid address in filter expression",
+            CompileErrorKind::InvalidQualifier => "invalid qualifier in filter expression",
+            CompileErrorKind::TooComplex => "filter expression too complex",
+        })?;
+        if let Some(offset) = self.offset {
+            write!(f, " at offset {offset}")?;
+        }
+        Ok(())
+    }
+}
+
+impl error::Error for CompileError {}
+
+impl From<CompileError> for io::Error {
+    fn from(err: CompileError) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidInput, err)
+    }
+}
@@ -0,0 +1,980 @@
+// Copyright 2015 The Rust Project Developers.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::cmp::min;
+use std::mem::{self, size_of, MaybeUninit};
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddrV6};
+use std::os::fd::BorrowedFd;
+use std::time::{Duration, SystemTime};
+use std::{fmt, io, ptr, slice};
+
+use crate::sys::{c_int, from_in6_addr, from_in_addr, msghdr_control_len, to_in6_addr, to_in_addr};
+use crate::{MsgHdrMut, SockAddr, SockAddrStorage, UnixCredentials};
+
+/// Returns `CMSG_SPACE(len)`, the number of bytes a control message with a
+/// payload of `len` bytes takes up in the control buffer, including padding.
+///
+/// Unlike libc's version this can be used to size arrays.
+pub(crate) const fn cmsg_space(len: usize) -> usize {
+    cmsg_align(size_of::<libc::cmsghdr>()) + cmsg_align(len)
+}
+
+/// Returns `CMSG_ALIGN(len)`, aligning `len` to `size_t`.
+const fn cmsg_align(len: usize) -> usize {
+    (len + size_of::<usize>() - 1) & !(size_of::<usize>() - 1)
+}
+
+/// Returns `CMSG_LEN(len)`, the value of `cmsg_len` for a control message with
+/// a payload of `len` bytes.
+pub(crate) fn cmsg_len(len: usize) -> usize {
+    // SAFETY: `CMSG_LEN` only does arithmetic.
+    unsafe { libc::CMSG_LEN(len as libc::c_uint) as usize }
+}
+
+/// Builder for the control (ancillary) data of a [`MsgHdr`].
+///
+/// The builder only collects the control messages, [`space`] can be used to
+/// determine the size of the buffer needed and [`encode`] writes the messages
+/// into a buffer, correctly aligning every message.
+///
+/// [`MsgHdr`]: crate::MsgHdr
+/// [`space`]: ControlMessageBuilder::space
+/// [`encode`]: ControlMessageBuilder::encode
+///
+/// # Examples
+///
+/// ```
+/// # fn main() -> std::io::Result<()> {
+/// use std::io::IoSlice;
+/// use std::mem::MaybeUninit;
+/// use std::os::fd::AsFd;
+///
+/// use socket2::{ControlMessageBuilder, Domain, MsgHdr, Socket, Type};
+///
+/// let (a, b) = Socket::pair(Domain::UNIX, Type::STREAM, None)?;
+///
+/// // Send the file descriptor of `b` to the other side.
+/// let fds = [b.as_fd()];
+/// let control = ControlMessageBuilder::new().with_rights(&fds);
+/// let mut buf = vec![MaybeUninit::uninit(); control.space()];
+/// let control = control.encode(&mut buf)?;
+///
+/// let bufs = [IoSlice::new(b"hello")];
+/// let msg = MsgHdr::new().with_buffers(&bufs).with_control(control);
+/// a.sendmsg(&msg, 0)?;
+/// # Ok(()) }
+/// ```
+pub struct ControlMessageBuilder<'a> {
+    entries: Vec<Entry<'a>>,
+}
+
+/// A single control message stored in [`ControlMessageBuilder`].
+enum Entry<'a> {
+    Rights(&'a [BorrowedFd<'a>]),
+    Credentials(libc::ucred),
+    PktInfoV4(libc::in_pktinfo),
+    PktInfoV6(libc::in6_pktinfo),
+    Int(c_int, c_int, c_int),
+    U16(c_int, c_int, u16),
+    Other(c_int, c_int, &'a [u8]),
+}
+
+impl<'a> Entry<'a> {
+    /// Returns the `cmsg_level` and `cmsg_type` for this message.
+    fn level_type(&self) -> (c_int, c_int) {
+        match self {
+            Entry::Rights(..) => (libc::SOL_SOCKET, libc::SCM_RIGHTS),
+            Entry::Credentials(..) => (libc::SOL_SOCKET, libc::SCM_CREDENTIALS),
+            Entry::PktInfoV4(..) => (libc::IPPROTO_IP, libc::IP_PKTINFO),
+            Entry::PktInfoV6(..) => (libc::IPPROTO_IPV6, libc::IPV6_PKTINFO),
+            Entry::Int(level, ty, _) | Entry::U16(level, ty, _) | Entry::Other(level, ty, _) => {
+                (*level, *ty)
+            }
+        }
+    }
+
+    /// Returns the payload of the message.
+    fn data(&self) -> &[u8] {
+        match self {
+            // SAFETY: `BorrowedFd` is `repr(transparent)` over `RawFd`.
+            Entry::Rights(fds) => unsafe { as_bytes_slice(fds) },
+            Entry::Credentials(creds) => unsafe { as_bytes(creds) },
+            Entry::PktInfoV4(info) => unsafe { as_bytes(info) },
+            Entry::PktInfoV6(info) => unsafe { as_bytes(info) },
+            Entry::Int(_, _, value) => unsafe { as_bytes(value) },
+            Entry::U16(_, _, value) => unsafe { as_bytes(value) },
+            Entry::Other(_, _, data) => data,
+        }
+    }
+}
+
+/// Returns the bytes of `value`.
+///
+/// # Safety
+///
+/// `T` must not contain any padding bytes.
+unsafe fn as_bytes<T>(value: &T) -> &[u8] {
+    slice::from_raw_parts((value as *const T).cast(), size_of::<T>())
+}
+
+/// Returns the bytes of `values`.
+///
+/// # Safety
+///
+/// `T` must not contain any padding bytes.
+unsafe fn as_bytes_slice<T>(values: &[T]) -> &[u8] {
+    slice::from_raw_parts(values.as_ptr().cast(), mem::size_of_val(values))
+}
+
+impl<'a> ControlMessageBuilder<'a> {
+    /// Create a new, empty, `ControlMessageBuilder`.
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> ControlMessageBuilder<'a> {
+        ControlMessageBuilder {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Add a `SCM_RIGHTS` message, passing the file descriptors `fds` to the
+    /// receiving process.
+    ///
+    /// Only supported on Unix sockets.
+    pub fn with_rights(mut self, fds: &'a [BorrowedFd<'a>]) -> Self {
+        self.entries.push(Entry::Rights(fds));
+        self
+    }
+
+    /// Add a `SCM_CREDENTIALS` message, passing the `credentials` to the
+    /// receiving process.
+    ///
+    /// Only supported on Unix sockets. Unless the process has the required
+    /// privileges the credentials must match those of the sending process.
+    pub fn with_credentials(mut self, credentials: UnixCredentials) -> Self {
+        self.entries.push(Entry::Credentials(libc::ucred {
+            pid: credentials.pid() as libc::pid_t,
+            uid: credentials.uid(),
+            gid: credentials.gid(),
+        }));
+        self
+    }
+
+    /// Add an `IP_PKTINFO` message, setting the `source` address and outgoing
+    /// `interface` (index) of the packet.
+    ///
+    /// An unspecified `source` address or an `interface` index of zero leaves
+    /// the choice to the routing table.
+    pub fn with_pktinfo_v4(mut self, interface: u32, source: Ipv4Addr) -> Self {
+        self.entries.push(Entry::PktInfoV4(libc::in_pktinfo {
+            ipi_ifindex: interface as c_int,
+            ipi_spec_dst: to_in_addr(&source),
+            ipi_addr: to_in_addr(&Ipv4Addr::UNSPECIFIED),
+        }));
+        self
+    }
+
+    /// Add an `IPV6_PKTINFO` message, setting the `source` address and
+    /// outgoing `interface` (index) of the packet.
+    ///
+    /// An unspecified `source` address or an `interface` index of zero leaves
+    /// the choice to the routing table.
+    pub fn with_pktinfo_v6(mut self, interface: u32, source: Ipv6Addr) -> Self {
+        self.entries.push(Entry::PktInfoV6(libc::in6_pktinfo {
+            ipi6_addr: to_in6_addr(&source),
+            ipi6_ifindex: interface,
+        }));
+        self
+    }
+
+    /// Add an `IP_TOS` message, setting the type-of-service field of the
+    /// packet.
+    ///
+    /// Also see [`Socket::set_tos_v4`].
+    ///
+    /// [`Socket::set_tos_v4`]: crate::Socket::set_tos_v4
+    pub fn with_tos_v4(mut self, tos: u32) -> Self {
+        let entry = Entry::Int(libc::IPPROTO_IP, libc::IP_TOS, tos as c_int);
+        self.entries.push(entry);
+        self
+    }
+
+    /// Add an `IPV6_TCLASS` message, setting the traffic class field of the
+    /// packet.
+    ///
+    /// Also see [`Socket::set_tclass_v6`].
+    ///
+    /// [`Socket::set_tclass_v6`]: crate::Socket::set_tclass_v6
+    pub fn with_tclass_v6(mut self, tclass: u32) -> Self {
+        let entry = Entry::Int(libc::IPPROTO_IPV6, libc::IPV6_TCLASS, tclass as c_int);
+        self.entries.push(entry);
+        self
+    }
+
+    /// Add an `IPV6_HOPLIMIT` message, setting the hop limit field of the
+    /// packet.
+    ///
+    /// Also see [`Socket::set_unicast_hops_v6`].
+    ///
+    /// [`Socket::set_unicast_hops_v6`]: crate::Socket::set_unicast_hops_v6
+    pub fn with_hoplimit_v6(mut self, hoplimit: u32) -> Self {
+        let entry = Entry::Int(libc::IPPROTO_IPV6, libc::IPV6_HOPLIMIT, hoplimit as c_int);
+        self.entries.push(entry);
+        self
+    }
+
+    /// Add an `UDP_SEGMENT` message, splitting the data of the message into
+    /// multiple datagrams of `size` bytes each (only the last one can be
+    /// smaller).
+    ///
+    /// Also see [`Socket::set_udp_segment`].
+    ///
+    /// [`Socket::set_udp_segment`]: crate::Socket::set_udp_segment
+    pub fn with_udp_segment(mut self, size: u16) -> Self {
+        let entry = Entry::U16(libc::SOL_UDP, libc::UDP_SEGMENT, size);
+        self.entries.push(entry);
+        self
+    }
+
+    /// Add a control message with an arbitrary `level`, `ty`pe and `data`.
+    ///
+    /// This can be used for control messages not (yet) supported by this
+    /// builder.
+    pub fn with_other(mut self, level: c_int, ty: c_int, data: &'a [u8]) -> Self {
+        self.entries.push(Entry::Other(level, ty, data));
+        self
+    }
+
+    /// Returns the exact size of the buffer, in bytes, required to encode all
+    /// control messages using [`encode`].
+    ///
+    /// [`encode`]: ControlMessageBuilder::encode
+    pub fn space(&self) -> usize {
+        self.entries
+            .iter()
+            .map(|entry| cmsg_space(entry.data().len()))
+            .sum()
+    }
+
+    /// Encode the control messages into `buf`.
+    ///
+    /// Returns the initialised part of the buffer, which can be passed to
+    /// [`MsgHdr::with_control`]. Returns an error if `buf` is smaller than
+    /// [`space`], in which case nothing is written.
+    ///
+    /// [`MsgHdr::with_control`]: crate::MsgHdr::with_control
+    /// [`space`]: ControlMessageBuilder::space
+    pub fn encode<'buf>(&self, buf: &'buf mut [MaybeUninit<u8>]) -> io::Result<&'buf [u8]> {
+        let space = self.space();
+        if buf.len() < space {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "control message buffer too small",
+            ));
+        }
+
+        // Zero the buffer first to initialise all padding bytes.
+        let buf = &mut buf[..space];
+        for byte in buf.iter_mut() {
+            *byte = MaybeUninit::new(0);
+        }
+
+        let mut offset = 0;
+        for entry in &self.entries {
+            let (level, ty) = entry.level_type();
+            let data = entry.data();
+            // SAFETY: all zero is valid for `cmsghdr`.
+            let mut header: libc::cmsghdr = unsafe { mem::zeroed() };
+            header.cmsg_len = cmsg_len(data.len()) as _;
+            header.cmsg_level = level;
+            header.cmsg_type = ty;
+            // SAFETY: `space` ensures the buffer is large enough to hold the
+            // header and data. We use unaligned writes as the buffer is not
+            // required to be aligned.
+            unsafe {
+                let ptr = buf.as_mut_ptr().add(offset).cast::<u8>();
+                ptr::write_unaligned(ptr.cast::<libc::cmsghdr>(), header);
+                let data_ptr = ptr.add(cmsg_len(0));
+                ptr::copy_nonoverlapping(data.as_ptr(), data_ptr, data.len());
+            }
+            offset += cmsg_space(data.len());
+        }
+        debug_assert_eq!(offset, space);
+
+        // SAFETY: we've initialised all bytes above.
+        Ok(unsafe { slice::from_raw_parts(buf.as_ptr().cast(), space) })
+    }
+}
+
+impl<'a> fmt::Debug for ControlMessageBuilder<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ControlMessageBuilder")
+            .field("space", &self.space())
+            .finish_non_exhaustive()
+    }
+}
+
+/// A control (ancillary) message received using `recvmsg(2)`.
+///
+/// See [`ControlMessages`] and [`MsgHdrMut::control_messages`].
+///
+/// [`MsgHdrMut::control_messages`]: crate::MsgHdrMut::control_messages
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ControlMessage<'a> {
+    /// `SCM_CREDENTIALS` message, the credentials of the sending process.
+    ///
+    /// See [`Socket::set_passcred`].
+    ///
+    /// [`Socket::set_passcred`]: crate::Socket::set_passcred
+    Credentials(UnixCredentials),
+    /// `IP_TOS` message, the type-of-service field of the packet.
+    ///
+    /// See [`Socket::set_recv_tos_v4`].
+    ///
+    /// [`Socket::set_recv_tos_v4`]: crate::Socket::set_recv_tos_v4
+    TosV4(u32),
+    /// `IPV6_TCLASS` message, the traffic class field of the packet.
+    ///
+    /// See [`Socket::set_recv_tclass_v6`].
+    ///
+    /// [`Socket::set_recv_tclass_v6`]: crate::Socket::set_recv_tclass_v6
+    TclassV6(u32),
+    /// `IPV6_HOPLIMIT` message, the hop limit field of the packet.
+    ///
+    /// See [`Socket::set_recv_hoplimit_v6`].
+    ///
+    /// [`Socket::set_recv_hoplimit_v6`]: crate::Socket::set_recv_hoplimit_v6
+    HopLimitV6(u32),
+    /// `IP_PKTINFO` message, information about the incoming packet.
+    PktInfoV4 {
+        /// Index of the interface the packet was received on.
+        interface: u32,
+        /// Local address of the packet, i.e. the address that would be used
+        /// as source address when replying.
+        local: Ipv4Addr,
+        /// Destination address from the header of the packet.
+        destination: Ipv4Addr,
+    },
+    /// `IPV6_PKTINFO` message, information about the incoming packet.
+    PktInfoV6 {
+        /// Index of the interface the packet was received on.
+        interface: u32,
+        /// Destination address from the header of the packet.
+        destination: Ipv6Addr,
+    },
+    /// `UDP_GRO` message, the size of the segments of a datagram coalesced by
+    /// generic receive offload.
+    ///
+    /// See [`Socket::set_udp_gro`] and [`GroSegments`].
+    ///
+    /// [`Socket::set_udp_gro`]: crate::Socket::set_udp_gro
+    /// [`GroSegments`]: crate::GroSegments
+    UdpGro(u16),
+    /// `SCM_TIMESTAMP` message, the time the packet was received.
+    ///
+    /// See [`Socket::set_timestamp`].
+    ///
+    /// [`Socket::set_timestamp`]: crate::Socket::set_timestamp
+    Timestamp(SystemTime),
+    /// `SCM_TIMESTAMPNS` message, the time the packet was received.
+    ///
+    /// See [`Socket::set_timestamp_ns`].
+    ///
+    /// [`Socket::set_timestamp_ns`]: crate::Socket::set_timestamp_ns
+    TimestampNs(SystemTime),
+    /// `SCM_TIMESTAMPING` message, the software and/or hardware timestamps
+    /// of the packet.
+    ///
+    /// See [`Socket::set_timestamping`].
+    ///
+    /// [`Socket::set_timestamping`]: crate::Socket::set_timestamping
+    Timestamping(Timestamps),
+    /// `IP_RECVERR` or `IPV6_RECVERR` message, an error received from the
+    /// error queue.
+    ///
+    /// See [`Socket::recv_error_queue`].
+    ///
+    /// [`Socket::recv_error_queue`]: crate::Socket::recv_error_queue
+    ExtendedError(ExtendedError),
+    /// `PACKET_AUXDATA` message, auxiliary data of a packet received on a
+    /// packet socket.
+    ///
+    /// See [`Socket::set_packet_auxdata`].
+    ///
+    /// [`Socket::set_packet_auxdata`]: crate::Socket::set_packet_auxdata
+    PacketAuxData(PacketAuxData),
+    /// `IPV6_PATHMTU` message, the path MTU of a packet that was too large
+    /// to send.
+    ///
+    /// See [`Socket::set_recv_path_mtu_v6`].
+    ///
+    /// [`Socket::set_recv_path_mtu_v6`]: crate::Socket::set_recv_path_mtu_v6
+    PathMtuV6 {
+        /// Destination address of the packet.
+        destination: SocketAddrV6,
+        /// The path MTU to the destination.
+        mtu: u32,
+    },
+    /// Any other control message, not (yet) decoded by this crate.
+    Other {
+        /// The originating protocol (`cmsg_level`).
+        level: c_int,
+        /// The protocol-specific type (`cmsg_type`).
+        ty: c_int,
+        /// The data of the message.
+        data: &'a [u8],
+    },
+}
+
+/// `struct ip6_mtuinfo`, not defined in libc.
+#[repr(C)]
+#[derive(Copy, Clone)]
+struct Ip6MtuInfo {
+    addr: libc::sockaddr_in6,
+    mtu: u32,
+}
+
+impl<'a> ControlMessage<'a> {
+    /// Decode a single control message.
+    fn decode(level: c_int, ty: c_int, data: &'a [u8]) -> ControlMessage<'a> {
+        // SAFETY: all types read below are valid for all bit patterns.
+        let decoded = match (level, ty) {
+            (libc::SOL_SOCKET, libc::SCM_CREDENTIALS) => {
+                unsafe { read::<libc::ucred>(data) }.map(|creds| {
+                    let creds = UnixCredentials::new(creds.pid as u32, creds.uid, creds.gid);
+                    ControlMessage::Credentials(creds)
+                })
+            }
+            // NOTE: the kernel sends `IP_TOS` as a single byte, but it's an
+            // integer when we send it.
+            (libc::IPPROTO_IP, libc::IP_TOS) => unsafe { read::<c_int>(data) }
+                .map(|tos| tos as u32)
+                .or_else(|| unsafe { read::<u8>(data) }.map(u32::from))
+                .map(ControlMessage::TosV4),
+            (libc::IPPROTO_IPV6, libc::IPV6_TCLASS) => {
+                unsafe { read::<c_int>(data) }.map(|tclass| ControlMessage::TclassV6(tclass as u32))
+            }
+            (libc::IPPROTO_IPV6, libc::IPV6_HOPLIMIT) => unsafe { read::<c_int>(data) }
+                .map(|hoplimit| ControlMessage::HopLimitV6(hoplimit as u32)),
+            (libc::IPPROTO_IP, libc::IP_PKTINFO) => {
+                unsafe { read::<libc::in_pktinfo>(data) }.map(|info| ControlMessage::PktInfoV4 {
+                    interface: info.ipi_ifindex as u32,
+                    local: from_in_addr(info.ipi_spec_dst),
+                    destination: from_in_addr(info.ipi_addr),
+                })
+            }
+            (libc::IPPROTO_IPV6, libc::IPV6_PKTINFO) => unsafe { read::<libc::in6_pktinfo>(data) }
+                .map(|info| ControlMessage::PktInfoV6 {
+                    interface: info.ipi6_ifindex,
+                    destination: from_in6_addr(info.ipi6_addr),
+                }),
+            (libc::SOL_UDP, libc::UDP_GRO) => {
+                unsafe { read::<c_int>(data) }.map(|size| ControlMessage::UdpGro(size as u16))
+            }
+            (libc::SOL_SOCKET, libc::SCM_TIMESTAMP) => read_times::<1>(data, 1000)
+                .map(|[time]| ControlMessage::Timestamp(SystemTime::UNIX_EPOCH + time)),
+            (libc::SOL_SOCKET, libc::SCM_TIMESTAMPNS) => read_times::<1>(data, 1)
+                .map(|[time]| ControlMessage::TimestampNs(SystemTime::UNIX_EPOCH + time)),
+            (libc::SOL_SOCKET, libc::SCM_TIMESTAMPING) => {
+                read_times::<3>(data, 1).map(|[software, _, hardware]| {
+                    ControlMessage::Timestamping(Timestamps { software, hardware })
+                })
+            }
+            (libc::IPPROTO_IP, libc::IP_RECVERR) | (libc::IPPROTO_IPV6, libc::IPV6_RECVERR) => {
+                ExtendedError::decode(data).map(ControlMessage::ExtendedError)
+            }
+            (libc::IPPROTO_IPV6, libc::IPV6_PATHMTU) => decode_path_mtu_v6(data),
+            (libc::SOL_PACKET, libc::PACKET_AUXDATA) => {
+                unsafe { read::<libc::tpacket_auxdata>(data) }.map(|aux| {
+                    ControlMessage::PacketAuxData(PacketAuxData {
+                        status: aux.tp_status,
+                        len: aux.tp_len,
+                        snaplen: aux.tp_snaplen,
+                        mac: aux.tp_mac,
+                        net: aux.tp_net,
+                        vlan_tci: aux.tp_vlan_tci,
+                        vlan_tpid: aux.tp_vlan_tpid,
+                    })
+                })
+            }
+            _ => None,
+        };
+        decoded.unwrap_or(ControlMessage::Other { level, ty, data })
+    }
+}
+
+/// Read a `T` from the start of `data`, returns `None` if `data` is too short.
+///
+/// # Safety
+///
+/// `T` must be valid for all bit patterns.
+unsafe fn read<T>(data: &[u8]) -> Option<T> {
+    (data.len() >= size_of::<T>()).then(|| ptr::read_unaligned(data.as_ptr().cast()))
+}
+
+/// Read `N` times, either `timeval` or `timespec` (`frac_mul` of 1000 or 1
+/// respectively), from `data`.
+///
+/// Depending on the architecture and the `_NEW`/`_OLD` variant of the option
+/// the fields are either 32 or 64 bits, which we determine using the size of
+/// `data`.
+fn read_times<const N: usize>(data: &[u8], frac_mul: u32) -> Option<[Duration; N]> {
+    let size = data.len() / N;
+    if size != 8 && size != 16 {
+        return None;
+    }
+    let mut times = [Duration::ZERO; N];
+    for (time, data) in times.iter_mut().zip(data.chunks_exact(size)) {
+        // SAFETY: all bit patterns are valid for integers.
+        let (secs, frac) = match size {
+            16 => unsafe { (read::<i64>(data)?, read::<i64>(&data[8..])?) },
+            8 => unsafe {
+                let (secs, frac) = (read::<i32>(data)?, read::<i32>(&data[4..])?);
+                (secs.into(), frac.into())
+            },
+            _ => return None,
+        };
+        let nanos = (frac as u32).checked_mul(frac_mul)?;
+        if secs < 0 || nanos >= 1_000_000_000 {
+            return None;
+        }
+        *time = Duration::new(secs as u64, nanos);
+    }
+    Some(times)
+}
+
+/// Decode a `IPV6_PATHMTU` message.
+fn decode_path_mtu_v6<'a>(data: &[u8]) -> Option<ControlMessage<'a>> {
+    // SAFETY: all bit patterns are valid for `ip6_mtuinfo`.
+    let info = unsafe { read::<Ip6MtuInfo>(data)? };
+    Some(ControlMessage::PathMtuV6 {
+        destination: SocketAddrV6::new(
+            from_in6_addr(info.addr.sin6_addr),
+            u16::from_be(info.addr.sin6_port),
+            info.addr.sin6_flowinfo,
+            info.addr.sin6_scope_id,
+        ),
+        mtu: info.mtu,
+    })
+}
+
+/// Timestamps of a packet, see [`ControlMessage::Timestamping`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timestamps {
+    software: Duration,
+    hardware: Duration,
+}
+
+impl Timestamps {
+    /// Returns the software timestamp, if any.
+    ///
+    /// Requires [`TimestampingFlags::SOFTWARE`].
+    ///
+    /// [`TimestampingFlags::SOFTWARE`]: crate::TimestampingFlags::SOFTWARE
+    pub fn software(&self) -> Option<SystemTime> {
+        (!self.software.is_zero()).then(|| SystemTime::UNIX_EPOCH + self.software)
+    }
+
+    /// Returns the hardware timestamp, if any.
+    ///
+    /// Hardware timestamps are taken from the clock of the network adapter,
+    /// which is not necessarily synchronised with the system time. Hence it's
+    /// returned as time since the epoch of the adapter's clock.
+    ///
+    /// Requires [`TimestampingFlags::RAW_HARDWARE`].
+    ///
+    /// [`TimestampingFlags::RAW_HARDWARE`]: crate::TimestampingFlags::RAW_HARDWARE
+    pub fn hardware(&self) -> Option<Duration> {
+        (!self.hardware.is_zero()).then_some(self.hardware)
+    }
+}
+
+/// A transmit timestamp, see [`Socket::recv_tx_timestamp`].
+///
+/// [`Socket::recv_tx_timestamp`]: crate::Socket::recv_tx_timestamp
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TxTimestamp {
+    kind: TimestampKind,
+    id: u32,
+    timestamps: Timestamps,
+}
+
+// Not defined in libc.
+const SCM_TSTAMP_SND: u32 = 0;
+const SCM_TSTAMP_SCHED: u32 = 1;
+const SCM_TSTAMP_ACK: u32 = 2;
+
+impl TxTimestamp {
+    /// Create a new timestamp from the `ee_info` and `ee_data` fields of the
+    /// `sock_extended_err` structure.
+    pub(crate) fn new(info: u32, data: u32, timestamps: Timestamps) -> TxTimestamp {
+        let kind = match info {
+            SCM_TSTAMP_SND => TimestampKind::Sent,
+            SCM_TSTAMP_SCHED => TimestampKind::Scheduled,
+            SCM_TSTAMP_ACK => TimestampKind::Acknowledged,
+            kind => TimestampKind::Other(kind),
+        };
+        TxTimestamp {
+            kind,
+            id: data,
+            timestamps,
+        }
+    }
+
+    /// Returns the point in the transmit path at which the timestamp was
+    /// taken.
+    pub const fn kind(&self) -> TimestampKind {
+        self.kind
+    }
+
+    /// Returns the identifier of the timestamp.
+    ///
+    /// Only set if [`TimestampingFlags::OPT_ID`] is used. For datagram sockets
+    /// this is a counter of the messages sent, for stream sockets it's the
+    /// byte offset in the stream.
+    ///
+    /// [`TimestampingFlags::OPT_ID`]: crate::TimestampingFlags::OPT_ID
+    pub const fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Returns the timestamps.
+    pub const fn timestamps(&self) -> Timestamps {
+        self.timestamps
+    }
+}
+
+/// The point in the transmit path at which a [`TxTimestamp`] was taken.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TimestampKind {
+    /// Before the packet entered the packet scheduler, `SCM_TSTAMP_SCHED`.
+    Scheduled,
+    /// When the packet left the kernel or network adapter, `SCM_TSTAMP_SND`.
+    Sent,
+    /// When all data was acknowledged by the peer, `SCM_TSTAMP_ACK`.
+    Acknowledged,
+    /// Unknown kind of timestamp.
+    Other(u32),
+}
+
+/// An extended error received from the error queue, see
+/// [`Socket::recv_error_queue`].
+///
+/// This wraps `sock_extended_err` and the address returned by
+/// `SO_EE_OFFENDER`, see `ip(7)`.
+///
+/// [`Socket::recv_error_queue`]: crate::Socket::recv_error_queue
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtendedError {
+    errno: i32,
+    origin: ErrorOrigin,
+    ty: u8,
+    code: u8,
+    info: u32,
+    data: u32,
+    offender: Option<SockAddr>,
+}
+
+// Not defined in libc.
+const SO_EE_ORIGIN_ZEROCOPY: u8 = 5;
+
+impl ExtendedError {
+    /// Decode a `sock_extended_err` structure, followed by the offender
+    /// address.
+    fn decode(data: &[u8]) -> Option<ExtendedError> {
+        // SAFETY: all bit patterns are valid for `sock_extended_err`.
+        let err = unsafe { read::<libc::sock_extended_err>(data)? };
+        let origin = match err.ee_origin {
+            libc::SO_EE_ORIGIN_NONE => ErrorOrigin::None,
+            libc::SO_EE_ORIGIN_LOCAL => ErrorOrigin::Local,
+            libc::SO_EE_ORIGIN_ICMP => ErrorOrigin::Icmp,
+            libc::SO_EE_ORIGIN_ICMP6 => ErrorOrigin::Icmp6,
+            libc::SO_EE_ORIGIN_TIMESTAMPING => ErrorOrigin::Timestamping,
+            SO_EE_ORIGIN_ZEROCOPY => ErrorOrigin::Zerocopy,
+            origin => ErrorOrigin::Other(origin),
+        };
+        let offender = decode_offender(&data[size_of::<libc::sock_extended_err>()..]);
+        Some(ExtendedError {
+            errno: err.ee_errno as i32,
+            origin,
+            ty: err.ee_type,
+            code: err.ee_code,
+            info: err.ee_info,
+            data: err.ee_data,
+            offender,
+        })
+    }
+
+    /// Returns the error number (`ee_errno`).
+    pub const fn errno(&self) -> i32 {
+        self.errno
+    }
+
+    /// Returns the error as [`io::Error`], created from the error number.
+    pub fn error(&self) -> io::Error {
+        io::Error::from_raw_os_error(self.errno)
+    }
+
+    /// Returns the origin of the error (`ee_origin`).
+    pub const fn origin(&self) -> ErrorOrigin {
+        self.origin
+    }
+
+    /// Returns the type of the error (`ee_type`), e.g. the ICMP type for
+    /// errors originating from ICMP.
+    pub const fn r#type(&self) -> u8 {
+        self.ty
+    }
+
+    /// Returns the code of the error (`ee_code`), e.g. the ICMP code for
+    /// errors originating from ICMP.
+    pub const fn code(&self) -> u8 {
+        self.code
+    }
+
+    /// Returns additional information (`ee_info`), e.g. the discovered path
+    /// MTU for "fragmentation needed" errors.
+    pub const fn info(&self) -> u32 {
+        self.info
+    }
+
+    /// Returns additional data (`ee_data`).
+    pub const fn data(&self) -> u32 {
+        self.data
+    }
+
+    /// Returns the address of the node that caused the error
+    /// (`SO_EE_OFFENDER`), e.g. the router that sent the ICMP message.
+    pub const fn offender(&self) -> Option<&SockAddr> {
+        self.offender.as_ref()
+    }
+}
+
+/// Decode the offender address, `data` is the data following the
+/// `sock_extended_err` structure.
+fn decode_offender(data: &[u8]) -> Option<SockAddr> {
+    // SAFETY: all bit patterns are valid for integers.
+    let family = unsafe { read::<libc::sa_family_t>(data)? };
+    let len = match family as c_int {
+        libc::AF_INET => size_of::<libc::sockaddr_in>(),
+        libc::AF_INET6 => size_of::<libc::sockaddr_in6>(),
+        _ => return None,
+    };
+    if data.len() < len {
+        return None;
+    }
+    let mut storage = SockAddrStorage::zeroed();
+    // SAFETY: checked the length above, the storage is large enough to hold
+    // any address.
+    unsafe {
+        let dst = (&mut storage as *mut SockAddrStorage).cast::<u8>();
+        ptr::copy_nonoverlapping(data.as_ptr(), dst, len);
+        Some(SockAddr::new(storage, len as libc::socklen_t))
+    }
+}
+
+/// Origin of an [`ExtendedError`] (`ee_origin`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ErrorOrigin {
+    /// No origin, `SO_EE_ORIGIN_NONE`.
+    None,
+    /// Generated locally, e.g. when exceeding the path MTU,
+    /// `SO_EE_ORIGIN_LOCAL`.
+    Local,
+    /// Received ICMP message, `SO_EE_ORIGIN_ICMP`.
+    Icmp,
+    /// Received ICMPv6 message, `SO_EE_ORIGIN_ICMP6`.
+    Icmp6,
+    /// Transmit timestamp, `SO_EE_ORIGIN_TIMESTAMPING`, see
+    /// [`Socket::recv_tx_timestamp`].
+    ///
+    /// [`Socket::recv_tx_timestamp`]: crate::Socket::recv_tx_timestamp
+    Timestamping,
+    /// Zerocopy completion notification, `SO_EE_ORIGIN_ZEROCOPY`, see
+    /// [`Socket::recv_zerocopy_completion`].
+    ///
+    /// [`Socket::recv_zerocopy_completion`]: crate::Socket::recv_zerocopy_completion
+    Zerocopy,
+    /// Unknown origin.
+    Other(u8),
+}
+
+/// Auxiliary data of a packet received on a packet socket, see
+/// [`ControlMessage::PacketAuxData`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PacketAuxData {
+    status: u32,
+    len: u32,
+    snaplen: u32,
+    mac: u16,
+    net: u16,
+    vlan_tci: u16,
+    vlan_tpid: u16,
+}
+
+impl PacketAuxData {
+    /// Returns the status flags (`TP_STATUS_*`).
+    pub const fn status(&self) -> u32 {
+        self.status
+    }
+
+    /// Returns the original length of the packet.
+    pub const fn len(&self) -> u32 {
+        self.len
+    }
+
+    /// Returns `true` if the original packet was empty.
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the captured length of the packet.
+    pub const fn snaplen(&self) -> u32 {
+        self.snaplen
+    }
+
+    /// Returns the offset of the link-layer header.
+    pub const fn mac_offset(&self) -> u16 {
+        self.mac
+    }
+
+    /// Returns the offset of the network-layer header.
+    pub const fn net_offset(&self) -> u16 {
+        self.net
+    }
+
+    /// Returns the VLAN tag control information (TCI), if the packet was
+    /// VLAN tagged (`TP_STATUS_VLAN_VALID`).
+    ///
+    /// The VLAN tag is removed from the packet data by most drivers, so this
+    /// is the only way to retrieve it.
+    pub const fn vlan_tci(&self) -> Option<u16> {
+        if self.status & libc::TP_STATUS_VLAN_VALID != 0 {
+            Some(self.vlan_tci)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the VLAN tag protocol identifier (TPID), e.g. `ETH_P_8021Q`,
+    /// if known (`TP_STATUS_VLAN_TPID_VALID`).
+    pub const fn vlan_tpid(&self) -> Option<u16> {
+        if self.status & libc::TP_STATUS_VLAN_TPID_VALID != 0 {
+            Some(self.vlan_tpid)
+        } else {
+            None
+        }
+    }
+
+    /// Returns `true` if the checksum of the packet isn't computed yet
+    /// (`TP_STATUS_CSUMNOTREADY`), e.g. for outgoing packets with checksum
+    /// offloading.
+    pub const fn is_checksum_not_ready(&self) -> bool {
+        self.status & libc::TP_STATUS_CSUMNOTREADY != 0
+    }
+
+    /// Returns `true` if the checksum of the packet was validated by the
+    /// kernel or the hardware (`TP_STATUS_CSUM_VALID`).
+    pub const fn is_checksum_valid(&self) -> bool {
+        self.status & libc::TP_STATUS_CSUM_VALID != 0
+    }
+}
+
+/// Iterator over the control messages in a control buffer.
+///
+/// Created by [`MsgHdrMut::control_messages`] or [`ControlMessages::new`].
+///
+/// [`MsgHdrMut::control_messages`]: crate::MsgHdrMut::control_messages
+#[derive(Clone)]
+pub struct ControlMessages<'a> {
+    /// The OS doesn't initialise the padding between the control messages,
+    /// so only the headers and data of the messages can be read.
+    buf: &'a [MaybeUninit<u8>],
+}
+
+impl<'a> ControlMessages<'a> {
+    /// Create a new iterator over the control messages in `buf`.
+    ///
+    /// `buf` should be the part of the control buffer filled in by the OS, see
+    /// [`MsgHdrMut::control_len`].
+    ///
+    /// [`MsgHdrMut::control_len`]: crate::MsgHdrMut::control_len
+    pub const fn new(buf: &'a [u8]) -> ControlMessages<'a> {
+        // SAFETY: `u8` and `MaybeUninit<u8>` have the same layout.
+        let buf = unsafe { slice::from_raw_parts(buf.as_ptr().cast(), buf.len()) };
+        ControlMessages { buf }
+    }
+}
+
+impl<'a> Iterator for ControlMessages<'a> {
+    type Item = ControlMessage<'a>;
+
+    fn next(&mut self) -> Option<ControlMessage<'a>> {
+        let header_len = cmsg_len(0);
+        if self.buf.len() < header_len {
+            return None;
+        }
+        // SAFETY: checked the length above, the buffer is not required to be
+        // aligned so we need an unaligned read.
+        let header = unsafe { ptr::read_unaligned(self.buf.as_ptr().cast::<libc::cmsghdr>()) };
+        let len = header.cmsg_len as usize;
+        if len < header_len || len > self.buf.len() {
+            // Invalid or truncated message.
+            self.buf = &[];
+            return None;
+        }
+        let data = &self.buf[header_len..len];
+        // SAFETY: the OS initialises the header and the data of the messages.
+        let data = unsafe { &*(data as *const [MaybeUninit<u8>] as *const [u8]) };
+        let space = min(cmsg_space(data.len()), self.buf.len());
+        self.buf = &self.buf[space..];
+        Some(ControlMessage::decode(
+            header.cmsg_level,
+            header.cmsg_type,
+            data,
+        ))
+    }
+}
+
+impl<'a> fmt::Debug for ControlMessages<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
+
+impl<'addr, 'bufs, 'control> MsgHdrMut<'addr, 'bufs, 'control> {
+    /// Returns an iterator over the control messages received.
+    ///
+    /// Only returns the part of the control buffer filled in by the OS, see
+    /// [`control_len`]. If [`RecvFlags::is_control_truncated`] is set not all
+    /// control messages fitted in the buffer.
+    ///
+    /// [`control_len`]: MsgHdrMut::control_len
+    /// [`RecvFlags::is_control_truncated`]: crate::RecvFlags::is_control_truncated
+    pub fn control_messages(&self) -> ControlMessages<'_> {
+        // SAFETY: `msg_controllen` is set to the length of the control buffer
+        // in `with_control` and only lowered by the OS.
+        unsafe { control_messages(&self.inner) }
+    }
+}
+
+/// Returns the control messages in the control buffer of `msg`.
+///
+/// # Safety
+///
+/// `msg_controllen` must not exceed the length of the control buffer of
+/// `msg`, and the control messages in it must be filled in by the OS.
+pub(crate) unsafe fn control_messages(msg: &libc::msghdr) -> ControlMessages<'_> {
+    let ptr = msg.msg_control as *const MaybeUninit<u8>;
+    if ptr.is_null() {
+        return ControlMessages::new(&[]);
+    }
+    let buf = slice::from_raw_parts(ptr, msghdr_control_len(msg));
+    ControlMessages { buf }
+}
@@ -172,6 +172,14 @@ macro_rules! man_links {
     };
 }
 
+#[cfg(all(feature = "all", target_os = "linux"))]
+pub mod bpf;
+#[cfg(all(feature = "all", target_os = "linux"))]
+mod cmsg;
+#[cfg(all(feature = "all", target_os = "linux"))]
+pub mod netlink;
+#[cfg(all(feature = "all", target_os = "linux"))]
+mod packet_ring;
 mod sockaddr;
 mod socket;
 mod sockref;
@@ -185,6 +193,16 @@ compile_error!("Socket2 doesn't support the compile target");
 
 use sys::c_int;
 
+#[cfg(all(feature = "all", target_os = "linux"))]
+pub use cmsg::{
+    ControlMessage, ControlMessageBuilder, ControlMessages, ErrorOrigin, ExtendedError,
+    PacketAuxData, TimestampKind, Timestamps, TxTimestamp,
+};
+#[cfg(all(feature = "all", target_os = "linux"))]
+pub use packet_ring::{
+    PacketBlock, PacketFrame, PacketFrames, PacketRing, PacketRingConfig, PacketTxFrame,
+    PacketTxRing,
+};
 pub use sockaddr::{sa_family_t, socklen_t, SockAddr, SockAddrStorage};
 #[cfg(not(any(
     target_os = "haiku",
@@ -200,6 +218,21 @@ pub use sockref::SockRef;
 pub use sys::CcidEndpoints;
 #[cfg(all(feature = "all", any(target_os = "linux", target_os = "android")))]
 pub use sys::SockFilter;
+#[cfg(all(feature = "all", target_os = "linux"))]
+pub use sys::TimestampingFlags;
+#[cfg(all(feature = "all", target_os = "linux"))]
+pub use sys::{
+    FanoutMode, LinkLayerAddr, PacketFanout, PacketMembership, PacketStatistics, PktInfo,
+    PmtuDiscovery,
+};
+#[cfg(all(feature = "all", target_os = "linux"))]
+pub use sys::{GroSegments, MmsgHdr, MmsgHdrMut};
+#[cfg(all(feature = "all", target_os = "linux"))]
+pub use sys::{TcpAoInfo, TcpAoKey, TcpAoKeyInfo, TcpMd5Key};
+#[cfg(all(feature = "all", target_os = "linux"))]
+pub use sys::{TcpInfo, TcpState};
+#[cfg(all(feature = "all", target_os = "linux"))]
+pub use sys::{UnixCredentials, ZerocopyCompletion};
 
 /// Specification of the communication domain for a socket.
 ///
@@ -344,6 +377,30 @@ impl Protocol {
     /// Protocol corresponding to `DIVERT`.
     #[cfg(all(feature = "all", any(target_os = "freebsd", target_os = "openbsd")))]
     pub const DIVERT: Protocol = Protocol(sys::IPPROTO_DIVERT);
+
+    /// Netlink protocol corresponding to `NETLINK_ROUTE`, for routing and
+    /// link updates.
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub const NETLINK_ROUTE: Protocol = Protocol(sys::NETLINK_ROUTE);
+
+    /// Netlink protocol corresponding to `NETLINK_GENERIC`, generic netlink.
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub const NETLINK_GENERIC: Protocol = Protocol(sys::NETLINK_GENERIC);
+
+    /// Netlink protocol corresponding to `NETLINK_SOCK_DIAG`, for querying
+    /// information about sockets.
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub const NETLINK_SOCK_DIAG: Protocol = Protocol(sys::NETLINK_SOCK_DIAG);
+
+    /// Netlink protocol corresponding to `NETLINK_NETFILTER`, the netfilter
+    /// subsystem.
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub const NETLINK_NETFILTER: Protocol = Protocol(sys::NETLINK_NETFILTER);
+
+    /// Netlink protocol corresponding to `NETLINK_KOBJECT_UEVENT`, kernel
+    /// messages to user space (uevents).
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub const NETLINK_KOBJECT_UEVENT: Protocol = Protocol(sys::NETLINK_KOBJECT_UEVENT);
 }
 
 impl From<c_int> for Protocol {
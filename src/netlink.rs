@@ -0,0 +1,723 @@
+// Copyright 2015 The Rust Project Developers.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Netlink message framing and attribute encoding.
+//!
+//! Netlink is used to communicate with the Linux kernel, see `netlink(7)`. A
+//! netlink message consists of a `nlmsghdr` header, followed by a protocol
+//! specific header and attributes in type-length-value format. A single
+//! datagram can hold multiple messages.
+//!
+//! [`MessageBuilder`] builds a single request, which can be sent using
+//! [`request`]. This also receives the response, handling multipart messages
+//! and acknowledgements. Received messages can be parsed using [`Messages`]
+//! and [`Attributes`].
+//!
+//! The [`route`] module provides helpers on top of this to list network
+//! interfaces, addresses and routes, the [`sock_diag`] module to list the
+//! sockets of the system.
+//!
+//! # Examples
+//!
+//! Resolve the id of a generic netlink family.
+//!
+//! ```
+//! # fn main() -> std::io::Result<()> {
+//! use socket2::{netlink, Domain, Protocol, Socket, Type};
+//!
+//! let socket = Socket::new(Domain::NETLINK, Type::RAW, Some(Protocol::NETLINK_GENERIC))?;
+//! let family = netlink::resolve_family(&socket, "nlctrl")?;
+//! assert_eq!(family.id(), libc::GENL_ID_CTRL as u16);
+//! # Ok(()) }
+//! ```
+
+use std::mem::MaybeUninit;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::{error, fmt, io, str};
+
+use crate::{SockAddr, Socket};
+
+pub mod route;
+pub mod sock_diag;
+
+/// Size of `nlmsghdr`.
+const NLMSG_HDRLEN: usize = 16;
+/// Size of `nlattr`.
+const NLA_HDRLEN: usize = 4;
+
+// Not defined in libc.
+const NLMSGERR_ATTR_MSG: u16 = 1;
+const NLMSGERR_ATTR_OFFS: u16 = 2;
+
+/// Size of the generic netlink header (`genlmsghdr`), which precedes the
+/// attributes in generic netlink messages.
+///
+/// See [`MessageBuilder::with_genl_header`] and [`Message::attributes`].
+pub const GENL_HEADER_LEN: usize = 4;
+
+/// Round `len` up to the netlink alignment of 4 bytes.
+const fn align(len: usize) -> usize {
+    (len + 3) & !3
+}
+
+/// Sequence number of the next message created by [`MessageBuilder::new`].
+static NEXT_SEQ: AtomicU32 = AtomicU32::new(1);
+
+/// Builder for a single netlink message.
+///
+/// A message consists of the `nlmsghdr` header, optionally followed by a
+/// protocol specific header (see [`with_header`]) and attributes. All parts
+/// are correctly aligned and the length fields are kept up to date, so
+/// [`as_bytes`] can be used at any time to get the encoded message.
+///
+/// Every message is assigned a unique sequence number, which is used by
+/// [`request`] to match the response to the request.
+///
+/// [`with_header`]: MessageBuilder::with_header
+/// [`as_bytes`]: MessageBuilder::as_bytes
+///
+/// # Panics
+///
+/// Adding an attribute with data larger than `u16::MAX` (minus the attribute
+/// header) panics, as it can't be represented.
+#[derive(Clone)]
+pub struct MessageBuilder {
+    buf: Vec<u8>,
+}
+
+impl MessageBuilder {
+    /// Create a new message of type `ty` with the `flags` (`NLM_F_*`).
+    ///
+    /// `NLM_F_REQUEST` is always set.
+    pub fn new(ty: u16, flags: u16) -> MessageBuilder {
+        let seq = NEXT_SEQ.fetch_add(1, Ordering::Relaxed);
+        let mut buf = Vec::with_capacity(64);
+        buf.extend_from_slice(&(NLMSG_HDRLEN as u32).to_ne_bytes());
+        buf.extend_from_slice(&ty.to_ne_bytes());
+        buf.extend_from_slice(&(flags | libc::NLM_F_REQUEST as u16).to_ne_bytes());
+        buf.extend_from_slice(&seq.to_ne_bytes());
+        // Port id, zero lets the kernel fill it in.
+        buf.extend_from_slice(&0u32.to_ne_bytes());
+        MessageBuilder { buf }
+    }
+
+    /// Add the protocol specific header, e.g. `ifinfomsg` for
+    /// `RTM_GETLINK`.
+    ///
+    /// This must be called before adding any attributes.
+    pub fn with_header(mut self, header: &[u8]) -> Self {
+        debug_assert_eq!(
+            self.buf.len(),
+            NLMSG_HDRLEN,
+            "header added after attributes"
+        );
+        self.extend_aligned(header);
+        self
+    }
+
+    /// Add the generic netlink header (`genlmsghdr`) with command `cmd` and
+    /// `version`.
+    pub fn with_genl_header(self, cmd: u8, version: u8) -> Self {
+        self.with_header(&[cmd, version, 0, 0])
+    }
+
+    /// Add an attribute of type `ty` with `data`.
+    pub fn with_attr(mut self, ty: u16, data: &[u8]) -> Self {
+        let len = NLA_HDRLEN + data.len();
+        let len = u16::try_from(len).expect("netlink attribute too large");
+        self.buf.extend_from_slice(&len.to_ne_bytes());
+        self.buf.extend_from_slice(&ty.to_ne_bytes());
+        self.extend_aligned(data);
+        self
+    }
+
+    /// Add an attribute of type `ty` with an `u8` value.
+    pub fn with_attr_u8(self, ty: u16, value: u8) -> Self {
+        self.with_attr(ty, &[value])
+    }
+
+    /// Add an attribute of type `ty` with an `u16` value in native byte
+    /// order.
+    pub fn with_attr_u16(self, ty: u16, value: u16) -> Self {
+        self.with_attr(ty, &value.to_ne_bytes())
+    }
+
+    /// Add an attribute of type `ty` with an `u32` value in native byte
+    /// order.
+    pub fn with_attr_u32(self, ty: u16, value: u32) -> Self {
+        self.with_attr(ty, &value.to_ne_bytes())
+    }
+
+    /// Add an attribute of type `ty` with an `u64` value in native byte
+    /// order.
+    pub fn with_attr_u64(self, ty: u16, value: u64) -> Self {
+        self.with_attr(ty, &value.to_ne_bytes())
+    }
+
+    /// Add an attribute of type `ty` with a null terminated string.
+    pub fn with_attr_str(self, ty: u16, value: &str) -> Self {
+        let mut data = Vec::with_capacity(value.len() + 1);
+        data.extend_from_slice(value.as_bytes());
+        data.push(0);
+        self.with_attr(ty, &data)
+    }
+
+    /// Add a nested attribute of type `ty`, the attributes added by `f` are
+    /// nested inside of it.
+    ///
+    /// The `NLA_F_NESTED` flag is set on the attribute type.
+    pub fn with_nested<F>(mut self, ty: u16, f: F) -> Self
+    where
+        F: FnOnce(Self) -> Self,
+    {
+        let start = self.buf.len();
+        self.buf.extend_from_slice(&[0; NLA_HDRLEN]);
+        let mut this = f(self);
+        let len = this.buf.len() - start;
+        let len = u16::try_from(len).expect("netlink attribute too large");
+        let ty = ty | libc::NLA_F_NESTED as u16;
+        this.buf[start..start + 2].copy_from_slice(&len.to_ne_bytes());
+        this.buf[start + 2..start + 4].copy_from_slice(&ty.to_ne_bytes());
+        this.update_len();
+        this
+    }
+
+    /// Returns the type of the message.
+    pub fn ty(&self) -> u16 {
+        u16::from_ne_bytes([self.buf[4], self.buf[5]])
+    }
+
+    /// Returns the flags of the message.
+    pub fn flags(&self) -> u16 {
+        u16::from_ne_bytes([self.buf[6], self.buf[7]])
+    }
+
+    /// Returns the sequence number of the message.
+    pub fn seq(&self) -> u32 {
+        u32::from_ne_bytes([self.buf[8], self.buf[9], self.buf[10], self.buf[11]])
+    }
+
+    /// Returns the encoded message.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buf
+    }
+
+    /// Extend the buffer with `data`, padded to the netlink alignment.
+    fn extend_aligned(&mut self, data: &[u8]) {
+        self.buf.extend_from_slice(data);
+        self.buf.resize(align(self.buf.len()), 0);
+        self.update_len();
+    }
+
+    /// Update the length in the message header.
+    fn update_len(&mut self) {
+        let len = self.buf.len() as u32;
+        self.buf[..4].copy_from_slice(&len.to_ne_bytes());
+    }
+}
+
+impl fmt::Debug for MessageBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MessageBuilder")
+            .field("ty", &self.ty())
+            .field("flags", &self.flags())
+            .field("seq", &self.seq())
+            .field("len", &self.buf.len())
+            .finish()
+    }
+}
+
+/// Iterator over the netlink messages in a buffer, e.g. a received
+/// datagram.
+///
+/// Returns an error if the buffer holds a truncated or invalid message, after
+/// which the iterator is exhausted.
+#[derive(Clone)]
+pub struct Messages<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Messages<'a> {
+    /// Create a new iterator over the messages in `buf`.
+    pub const fn new(buf: &'a [u8]) -> Messages<'a> {
+        Messages { buf }
+    }
+}
+
+impl<'a> Iterator for Messages<'a> {
+    type Item = io::Result<Message<'a>>;
+
+    fn next(&mut self) -> Option<io::Result<Message<'a>>> {
+        if self.buf.is_empty() {
+            return None;
+        }
+        let len = read_u32(self.buf, 0).map_or(0, |len| len as usize);
+        if len < NLMSG_HDRLEN || len > self.buf.len() {
+            self.buf = &[];
+            return Some(Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "truncated netlink message",
+            )));
+        }
+        let buf = self.buf;
+        self.buf = &buf[align(len).min(buf.len())..];
+        Some(Ok(Message {
+            ty: read_u16(buf, 4).unwrap(),
+            flags: read_u16(buf, 6).unwrap(),
+            seq: read_u32(buf, 8).unwrap(),
+            port_id: read_u32(buf, 12).unwrap(),
+            payload: &buf[NLMSG_HDRLEN..len],
+        }))
+    }
+}
+
+impl<'a> fmt::Debug for Messages<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
+
+/// A single netlink message, see [`Messages`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Message<'a> {
+    ty: u16,
+    flags: u16,
+    seq: u32,
+    port_id: u32,
+    payload: &'a [u8],
+}
+
+impl<'a> Message<'a> {
+    /// Returns the type of the message (`nlmsg_type`).
+    pub const fn ty(&self) -> u16 {
+        self.ty
+    }
+
+    /// Returns the flags of the message (`nlmsg_flags`).
+    pub const fn flags(&self) -> u16 {
+        self.flags
+    }
+
+    /// Returns the sequence number of the message (`nlmsg_seq`).
+    pub const fn seq(&self) -> u32 {
+        self.seq
+    }
+
+    /// Returns the port id of the message (`nlmsg_pid`).
+    pub const fn port_id(&self) -> u32 {
+        self.port_id
+    }
+
+    /// Returns the payload of the message, i.e. everything after the
+    /// `nlmsghdr` header.
+    pub const fn payload(&self) -> &'a [u8] {
+        self.payload
+    }
+
+    /// Returns true if the message is part of a multipart message
+    /// (`NLM_F_MULTI`), which is terminated by a `NLMSG_DONE` message.
+    pub const fn is_multipart(&self) -> bool {
+        self.flags & libc::NLM_F_MULTI as u16 != 0
+    }
+
+    /// Returns the attributes of the message, which start after the protocol
+    /// specific header of `header_len` bytes.
+    pub fn attributes(&self, header_len: usize) -> Attributes<'a> {
+        let start = align(header_len).min(self.payload.len());
+        Attributes::new(&self.payload[start..])
+    }
+
+    /// Returns the result of the request if this message completes it.
+    ///
+    /// For `NLMSG_ERROR` messages this returns `Ok` for an acknowledgement,
+    /// or the error. For `NLMSG_DONE` messages, which terminate multipart
+    /// messages, this returns `Ok`, or the error if the dump failed. Returns
+    /// `None` for all other messages.
+    ///
+    /// If the error includes an extended acknowledgement (see
+    /// [`Socket::set_netlink_ext_ack`]) the returned error wraps a
+    /// [`NetlinkError`], otherwise it's created from the raw OS error.
+    ///
+    /// [`Socket::set_netlink_ext_ack`]: crate::Socket::set_netlink_ext_ack
+    pub fn result(&self) -> Option<io::Result<()>> {
+        let tlvs_offset = match self.ty as libc::c_int {
+            libc::NLMSG_ERROR => {
+                // The error code is followed by the header of the original
+                // message and, unless capped, its payload.
+                if self.flags & libc::NLM_F_CAPPED as u16 != 0 {
+                    4 + NLMSG_HDRLEN
+                } else {
+                    4 + align(read_u32(self.payload, 4).unwrap_or(0) as usize)
+                }
+            }
+            libc::NLMSG_DONE => 4,
+            _ => return None,
+        };
+        let errno = match read_u32(self.payload, 0) {
+            Some(0) | None => return Some(Ok(())),
+            Some(error) => (error as i32).wrapping_neg(),
+        };
+        let mut err = NetlinkError {
+            errno,
+            message: None,
+            offset: None,
+        };
+        if self.flags & libc::NLM_F_ACK_TLVS as u16 != 0 {
+            let tlvs = self.payload.get(tlvs_offset..).unwrap_or_default();
+            for attr in Attributes::new(tlvs) {
+                match attr.ty() {
+                    NLMSGERR_ATTR_MSG => err.message = attr.as_str().map(String::from),
+                    NLMSGERR_ATTR_OFFS => err.offset = attr.as_u32(),
+                    _ => {}
+                }
+            }
+        }
+        Some(Err(err.into()))
+    }
+}
+
+/// Error returned by the kernel in a `NLMSG_ERROR` or `NLMSG_DONE` message,
+/// including the extended acknowledgement.
+///
+/// See [`Message::result`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetlinkError {
+    errno: i32,
+    message: Option<String>,
+    offset: Option<u32>,
+}
+
+impl NetlinkError {
+    /// Returns the error number.
+    pub const fn errno(&self) -> i32 {
+        self.errno
+    }
+
+    /// Returns the human readable error message
+    /// (`NLMSGERR_ATTR_MSG`), if any.
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_deref()
+    }
+
+    /// Returns the offset of the invalid attribute in the request
+    /// (`NLMSGERR_ATTR_OFFS`), if any.
+    pub const fn offset(&self) -> Option<u32> {
+        self.offset
+    }
+}
+
+impl fmt::Display for NetlinkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        io::Error::from_raw_os_error(self.errno).fmt(f)?;
+        if let Some(message) = &self.message {
+            write!(f, ": {message}")?;
+        }
+        Ok(())
+    }
+}
+
+impl error::Error for NetlinkError {}
+
+impl From<NetlinkError> for io::Error {
+    fn from(err: NetlinkError) -> io::Error {
+        if err.message.is_none() && err.offset.is_none() {
+            io::Error::from_raw_os_error(err.errno)
+        } else {
+            io::Error::new(io::Error::from_raw_os_error(err.errno).kind(), err)
+        }
+    }
+}
+
+/// Iterator over netlink attributes.
+///
+/// Stops at the first truncated or invalid attribute.
+#[derive(Clone)]
+pub struct Attributes<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Attributes<'a> {
+    /// Create a new iterator over the attributes in `buf`.
+    pub const fn new(buf: &'a [u8]) -> Attributes<'a> {
+        Attributes { buf }
+    }
+
+    /// Returns the first attribute of type `ty`, if any.
+    pub fn get(&self, ty: u16) -> Option<Attribute<'a>> {
+        self.clone().find(|attr| attr.ty() == ty)
+    }
+}
+
+impl<'a> Iterator for Attributes<'a> {
+    type Item = Attribute<'a>;
+
+    fn next(&mut self) -> Option<Attribute<'a>> {
+        let len = read_u16(self.buf, 0)? as usize;
+        if len < NLA_HDRLEN || len > self.buf.len() {
+            self.buf = &[];
+            return None;
+        }
+        let buf = self.buf;
+        self.buf = &buf[align(len).min(buf.len())..];
+        Some(Attribute {
+            ty: read_u16(buf, 2).unwrap(),
+            data: &buf[NLA_HDRLEN..len],
+        })
+    }
+}
+
+impl<'a> fmt::Debug for Attributes<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
+
+/// A single netlink attribute, see [`Attributes`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Attribute<'a> {
+    ty: u16,
+    data: &'a [u8],
+}
+
+impl<'a> Attribute<'a> {
+    /// Returns the type of the attribute, without the `NLA_F_NESTED` and
+    /// `NLA_F_NET_BYTEORDER` flags.
+    pub const fn ty(&self) -> u16 {
+        self.ty & libc::NLA_TYPE_MASK as u16
+    }
+
+    /// Returns true if the `NLA_F_NESTED` flag is set.
+    ///
+    /// Note that not all nested attributes have this flag set.
+    pub const fn is_nested(&self) -> bool {
+        self.ty & libc::NLA_F_NESTED as u16 != 0
+    }
+
+    /// Returns true if the `NLA_F_NET_BYTEORDER` flag is set, i.e. the data
+    /// is in network (big endian) byte order.
+    pub const fn is_net_byte_order(&self) -> bool {
+        self.ty & libc::NLA_F_NET_BYTEORDER as u16 != 0
+    }
+
+    /// Returns the data of the attribute.
+    pub const fn data(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// Returns the data as `u8`, or `None` if the size doesn't match.
+    pub fn as_u8(&self) -> Option<u8> {
+        match self.data {
+            [value] => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Returns the data as `u16` in native byte order, or `None` if the size
+    /// doesn't match.
+    pub fn as_u16(&self) -> Option<u16> {
+        self.data.try_into().ok().map(u16::from_ne_bytes)
+    }
+
+    /// Returns the data as `u32` in native byte order, or `None` if the size
+    /// doesn't match.
+    pub fn as_u32(&self) -> Option<u32> {
+        self.data.try_into().ok().map(u32::from_ne_bytes)
+    }
+
+    /// Returns the data as `u64` in native byte order, or `None` if the size
+    /// doesn't match.
+    pub fn as_u64(&self) -> Option<u64> {
+        self.data.try_into().ok().map(u64::from_ne_bytes)
+    }
+
+    /// Returns the data as string, without the null terminator, or `None` if
+    /// it's not valid UTF-8.
+    pub fn as_str(&self) -> Option<&'a str> {
+        let data = self.data.split(|b| *b == 0).next().unwrap_or_default();
+        str::from_utf8(data).ok()
+    }
+
+    /// Returns the nested attributes.
+    pub const fn nested(&self) -> Attributes<'a> {
+        Attributes::new(self.data)
+    }
+}
+
+/// Read an `u16` in native byte order at `offset`.
+fn read_u16(buf: &[u8], offset: usize) -> Option<u16> {
+    let bytes = buf.get(offset..offset + 2)?;
+    Some(u16::from_ne_bytes(bytes.try_into().unwrap()))
+}
+
+/// Read an `u32` in native byte order at `offset`.
+fn read_u32(buf: &[u8], offset: usize) -> Option<u32> {
+    let bytes = buf.get(offset..offset + 4)?;
+    Some(u32::from_ne_bytes(bytes.try_into().unwrap()))
+}
+
+/// Send `request` to the kernel and receive the response, calling `f` for
+/// every response message.
+///
+/// This handles multipart responses, e.g. to dump requests (`NLM_F_DUMP`),
+/// by receiving messages until the terminating `NLMSG_DONE`. If the request
+/// has the `NLM_F_ACK` flag set this waits for the acknowledgement. Errors
+/// returned by the kernel are returned, see [`Message::result`]. Neither
+/// `NLMSG_DONE` nor `NLMSG_ERROR` messages are passed to `f`.
+///
+/// Messages that are not a response to `request`, e.g. multicast
+/// notifications, or not sent by the kernel are ignored. If `f` returns an
+/// error receiving stops and the error is returned.
+pub fn request<F>(socket: &Socket, request: &MessageBuilder, mut f: F) -> io::Result<()>
+where
+    F: FnMut(Message<'_>) -> io::Result<()>,
+{
+    let kernel = SockAddr::netlink(0, 0);
+    let _ = socket.send_to(request.as_bytes(), &kernel)?;
+    let wants_ack = request.flags() & libc::NLM_F_ACK as u16 != 0;
+    let mut buf: Vec<MaybeUninit<u8>> = vec![MaybeUninit::uninit(); 32 * 1024];
+    loop {
+        // Determine the size of the datagram first to avoid truncating it.
+        let size = socket.recv_with_flags(&mut buf, libc::MSG_PEEK | libc::MSG_TRUNC)?;
+        if size > buf.len() {
+            buf.resize(size, MaybeUninit::uninit());
+        }
+        let (n, addr) = socket.recv_from(&mut buf)?;
+        if addr.as_netlink().map_or(true, |(port_id, _)| port_id != 0) {
+            continue;
+        }
+        // SAFETY: `recv_from` initialised the first `n` bytes.
+        // TODO: use `MaybeUninit::slice_assume_init_ref` once stable.
+        let data = unsafe { &*(&buf[..n] as *const [_] as *const [u8]) };
+        let mut done = false;
+        for msg in Messages::new(data) {
+            let msg = msg?;
+            if msg.seq() != request.seq() {
+                continue;
+            }
+            if let Some(result) = msg.result() {
+                return result;
+            }
+            f(msg)?;
+            if !msg.is_multipart() && !wants_ack {
+                done = true;
+            }
+        }
+        if done {
+            return Ok(());
+        }
+    }
+}
+
+/// A generic netlink family, see [`resolve_family`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GenericFamily {
+    id: u16,
+    name: String,
+    version: u32,
+    header_size: u32,
+    max_attr: u32,
+    multicast_groups: Vec<(String, u32)>,
+}
+
+impl GenericFamily {
+    /// Returns the id of the family, used as message type.
+    pub const fn id(&self) -> u16 {
+        self.id
+    }
+
+    /// Returns the name of the family.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the version of the family.
+    pub const fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Returns the size of the family specific header, which follows the
+    /// generic netlink header.
+    pub const fn header_size(&self) -> u32 {
+        self.header_size
+    }
+
+    /// Returns the maximum attribute type supported by the family.
+    pub const fn max_attr(&self) -> u32 {
+        self.max_attr
+    }
+
+    /// Returns the multicast groups of the family, as name and group id.
+    pub fn multicast_groups(&self) -> &[(String, u32)] {
+        &self.multicast_groups
+    }
+
+    /// Returns the id of the multicast group `name`, if any.
+    ///
+    /// The id can be used in [`Socket::netlink_add_membership`].
+    ///
+    /// [`Socket::netlink_add_membership`]: crate::Socket::netlink_add_membership
+    pub fn multicast_group(&self, name: &str) -> Option<u32> {
+        self.multicast_groups
+            .iter()
+            .find_map(|(n, id)| (n == name).then_some(*id))
+    }
+}
+
+/// Resolve the generic netlink family `name` using `CTRL_CMD_GETFAMILY`.
+///
+/// `socket` must be a `NETLINK_GENERIC` socket. Returns an error with kind
+/// [`io::ErrorKind::NotFound`] if the family doesn't exist, e.g. because the
+/// kernel module providing it isn't loaded.
+pub fn resolve_family(socket: &Socket, name: &str) -> io::Result<GenericFamily> {
+    let req = MessageBuilder::new(libc::GENL_ID_CTRL as u16, 0)
+        .with_genl_header(libc::CTRL_CMD_GETFAMILY as u8, 1)
+        .with_attr_str(libc::CTRL_ATTR_FAMILY_NAME as u16, name);
+    let mut family = None;
+    request(socket, &req, |msg| {
+        let mut f = GenericFamily {
+            id: 0,
+            name: String::new(),
+            version: 0,
+            header_size: 0,
+            max_attr: 0,
+            multicast_groups: Vec::new(),
+        };
+        for attr in msg.attributes(GENL_HEADER_LEN) {
+            match attr.ty() as libc::c_int {
+                libc::CTRL_ATTR_FAMILY_ID => f.id = attr.as_u16().unwrap_or(0),
+                libc::CTRL_ATTR_FAMILY_NAME => f.name = attr.as_str().unwrap_or("").into(),
+                libc::CTRL_ATTR_VERSION => f.version = attr.as_u32().unwrap_or(0),
+                libc::CTRL_ATTR_HDRSIZE => f.header_size = attr.as_u32().unwrap_or(0),
+                libc::CTRL_ATTR_MAXATTR => f.max_attr = attr.as_u32().unwrap_or(0),
+                libc::CTRL_ATTR_MCAST_GROUPS => {
+                    for group in attr.nested() {
+                        let group = group.nested();
+                        let name = group
+                            .get(libc::CTRL_ATTR_MCAST_GRP_NAME as u16)
+                            .and_then(|attr| attr.as_str());
+                        let id = group
+                            .get(libc::CTRL_ATTR_MCAST_GRP_ID as u16)
+                            .and_then(|attr| attr.as_u32());
+                        if let (Some(name), Some(id)) = (name, id) {
+                            f.multicast_groups.push((name.into(), id));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        family = Some(f);
+        Ok(())
+    })?;
+    family.ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            "missing generic netlink family in response",
+        )
+    })
+}
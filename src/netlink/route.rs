@@ -0,0 +1,442 @@
+// Copyright 2015 The Rust Project Developers.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Routing netlink (`NETLINK_ROUTE`) helpers to list the network interfaces,
+//! addresses and routes, see `rtnetlink(7)`.
+//!
+//! All functions expect a socket created using [`Protocol::NETLINK_ROUTE`].
+//!
+//! [`Protocol::NETLINK_ROUTE`]: crate::Protocol::NETLINK_ROUTE
+//!
+//! # Examples
+//!
+//! ```
+//! # fn main() -> std::io::Result<()> {
+//! use socket2::netlink::route;
+//! use socket2::{Domain, Protocol, Socket, Type};
+//!
+//! let socket = Socket::new(Domain::NETLINK, Type::RAW, Some(Protocol::NETLINK_ROUTE))?;
+//! for link in route::links(&socket)? {
+//!     println!("{}: {} (mtu {:?})", link.index(), link.name(), link.mtu());
+//! }
+//! # Ok(()) }
+//! ```
+
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use super::{align, read_u16, read_u32, request, Attribute, Attributes, MessageBuilder};
+use crate::Socket;
+
+/// Size of `ifinfomsg`.
+const IFINFOMSG_LEN: usize = 16;
+/// Size of `ifaddrmsg`.
+const IFADDRMSG_LEN: usize = 8;
+/// Size of `rtmsg`.
+const RTMSG_LEN: usize = 12;
+/// Size of `rtnexthop`.
+const RTNEXTHOP_LEN: usize = 8;
+
+/// A network interface (link), see [`links`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Link {
+    index: u32,
+    name: String,
+    link_type: u16,
+    flags: u32,
+    mtu: Option<u32>,
+    address: Option<Vec<u8>>,
+}
+
+impl Link {
+    /// Returns the index of the interface.
+    ///
+    /// This can be used in, for example, [`Socket::bind_device_by_index_v4`]
+    /// and [`Socket::set_multicast_if_v6`].
+    ///
+    /// [`Socket::bind_device_by_index_v4`]: crate::Socket::bind_device_by_index_v4
+    /// [`Socket::set_multicast_if_v6`]: crate::Socket::set_multicast_if_v6
+    pub const fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// Returns the name of the interface, e.g. `lo`.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the hardware type of the interface (`ARPHRD_*`).
+    pub const fn link_type(&self) -> u16 {
+        self.link_type
+    }
+
+    /// Returns the flags of the interface (`IFF_*`).
+    pub const fn flags(&self) -> u32 {
+        self.flags
+    }
+
+    /// Returns true if the interface is up (`IFF_UP`).
+    pub const fn is_up(&self) -> bool {
+        self.flags & libc::IFF_UP as u32 != 0
+    }
+
+    /// Returns true if the interface is a loopback interface
+    /// (`IFF_LOOPBACK`).
+    pub const fn is_loopback(&self) -> bool {
+        self.flags & libc::IFF_LOOPBACK as u32 != 0
+    }
+
+    /// Returns true if the interface supports multicast (`IFF_MULTICAST`).
+    pub const fn is_multicast(&self) -> bool {
+        self.flags & libc::IFF_MULTICAST as u32 != 0
+    }
+
+    /// Returns the MTU of the interface.
+    pub const fn mtu(&self) -> Option<u32> {
+        self.mtu
+    }
+
+    /// Returns the hardware address of the interface, e.g. the MAC address.
+    pub fn hardware_address(&self) -> Option<&[u8]> {
+        self.address.as_deref()
+    }
+}
+
+/// An address assigned to a network interface, see [`addresses`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Address {
+    index: u32,
+    address: IpAddr,
+    prefix_len: u8,
+    scope: Scope,
+    flags: u32,
+    label: Option<String>,
+}
+
+impl Address {
+    /// Returns the index of the interface the address is assigned to.
+    pub const fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// Returns the address.
+    pub const fn address(&self) -> IpAddr {
+        self.address
+    }
+
+    /// Returns the length of the network prefix, in bits.
+    pub const fn prefix_len(&self) -> u8 {
+        self.prefix_len
+    }
+
+    /// Returns the scope of the address.
+    pub const fn scope(&self) -> Scope {
+        self.scope
+    }
+
+    /// Returns the flags of the address (`IFA_F_*`).
+    pub const fn flags(&self) -> u32 {
+        self.flags
+    }
+
+    /// Returns the label of the address, only set for IPv4 addresses.
+    pub fn label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+}
+
+/// A route, see [`routes`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Route {
+    destination: IpAddr,
+    prefix_len: u8,
+    gateway: Option<IpAddr>,
+    source: Option<IpAddr>,
+    interface: Option<u32>,
+    priority: Option<u32>,
+    table: u32,
+    protocol: u8,
+    scope: Scope,
+    kind: u8,
+    next_hops: Vec<NextHop>,
+}
+
+impl Route {
+    /// Returns the destination network of the route.
+    ///
+    /// This is the unspecified address for default routes.
+    pub const fn destination(&self) -> IpAddr {
+        self.destination
+    }
+
+    /// Returns the length of the destination network prefix, in bits.
+    pub const fn prefix_len(&self) -> u8 {
+        self.prefix_len
+    }
+
+    /// Returns the gateway (next hop) of the route, if any.
+    pub const fn gateway(&self) -> Option<IpAddr> {
+        self.gateway
+    }
+
+    /// Returns the preferred source address of the route, if any.
+    pub const fn preferred_source(&self) -> Option<IpAddr> {
+        self.source
+    }
+
+    /// Returns the index of the output interface, if any.
+    pub const fn interface(&self) -> Option<u32> {
+        self.interface
+    }
+
+    /// Returns the priority (metric) of the route, if any.
+    pub const fn priority(&self) -> Option<u32> {
+        self.priority
+    }
+
+    /// Returns the routing table of the route, e.g. `RT_TABLE_MAIN`.
+    pub const fn table(&self) -> u32 {
+        self.table
+    }
+
+    /// Returns the protocol that installed the route (`RTPROT_*`).
+    pub const fn protocol(&self) -> u8 {
+        self.protocol
+    }
+
+    /// Returns the scope of the route.
+    pub const fn scope(&self) -> Scope {
+        self.scope
+    }
+
+    /// Returns the type of the route (`RTN_*`), e.g. `RTN_UNICAST`.
+    pub const fn kind(&self) -> u8 {
+        self.kind
+    }
+
+    /// Returns the next hops of a multipath route (`RTA_MULTIPATH`).
+    ///
+    /// This is empty for routes with a single next hop, which is described
+    /// by [`Route::gateway`] and [`Route::interface`] instead.
+    pub fn next_hops(&self) -> &[NextHop] {
+        &self.next_hops
+    }
+}
+
+/// A next hop of a multipath route, see [`Route::next_hops`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NextHop {
+    interface: u32,
+    gateway: Option<IpAddr>,
+    weight: u16,
+    flags: u8,
+}
+
+impl NextHop {
+    /// Returns the index of the output interface.
+    pub const fn interface(&self) -> u32 {
+        self.interface
+    }
+
+    /// Returns the gateway of the next hop, if any.
+    pub const fn gateway(&self) -> Option<IpAddr> {
+        self.gateway
+    }
+
+    /// Returns the weight of the next hop, between 1 and 256.
+    pub const fn weight(&self) -> u16 {
+        self.weight
+    }
+
+    /// Returns the flags of the next hop (`RTNH_F_*`).
+    pub const fn flags(&self) -> u8 {
+        self.flags
+    }
+}
+
+/// Scope of an [`Address`] or [`Route`] (`RT_SCOPE_*`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Scope {
+    /// Global scope, `RT_SCOPE_UNIVERSE`.
+    Universe,
+    /// Site local scope, `RT_SCOPE_SITE`.
+    Site,
+    /// Link local scope, `RT_SCOPE_LINK`.
+    Link,
+    /// Only valid on this host, `RT_SCOPE_HOST`.
+    Host,
+    /// Not valid anywhere, `RT_SCOPE_NOWHERE`.
+    Nowhere,
+    /// Other, user defined, scope.
+    Other(u8),
+}
+
+impl From<u8> for Scope {
+    fn from(scope: u8) -> Scope {
+        match scope {
+            libc::RT_SCOPE_UNIVERSE => Scope::Universe,
+            libc::RT_SCOPE_SITE => Scope::Site,
+            libc::RT_SCOPE_LINK => Scope::Link,
+            libc::RT_SCOPE_HOST => Scope::Host,
+            libc::RT_SCOPE_NOWHERE => Scope::Nowhere,
+            scope => Scope::Other(scope),
+        }
+    }
+}
+
+/// List all network interfaces using `RTM_GETLINK`.
+pub fn links(socket: &Socket) -> io::Result<Vec<Link>> {
+    let req = MessageBuilder::new(libc::RTM_GETLINK, libc::NLM_F_DUMP as u16)
+        .with_header(&[0; IFINFOMSG_LEN]);
+    let mut links = Vec::new();
+    request(socket, &req, |msg| {
+        if msg.ty() != libc::RTM_NEWLINK || msg.payload().len() < IFINFOMSG_LEN {
+            return Ok(());
+        }
+        let header = msg.payload();
+        let mut link = Link {
+            index: read_u32(header, 4).unwrap(),
+            name: String::new(),
+            link_type: read_u16(header, 2).unwrap(),
+            flags: read_u32(header, 8).unwrap(),
+            mtu: None,
+            address: None,
+        };
+        for attr in msg.attributes(IFINFOMSG_LEN) {
+            match attr.ty() {
+                libc::IFLA_IFNAME => link.name = attr.as_str().unwrap_or("").into(),
+                libc::IFLA_MTU => link.mtu = attr.as_u32(),
+                libc::IFLA_ADDRESS => link.address = Some(attr.data().to_vec()),
+                _ => {}
+            }
+        }
+        links.push(link);
+        Ok(())
+    })?;
+    Ok(links)
+}
+
+/// List all addresses of all network interfaces using `RTM_GETADDR`.
+pub fn addresses(socket: &Socket) -> io::Result<Vec<Address>> {
+    let req = MessageBuilder::new(libc::RTM_GETADDR, libc::NLM_F_DUMP as u16)
+        .with_header(&[0; IFADDRMSG_LEN]);
+    let mut addresses = Vec::new();
+    request(socket, &req, |msg| {
+        if msg.ty() != libc::RTM_NEWADDR || msg.payload().len() < IFADDRMSG_LEN {
+            return Ok(());
+        }
+        let header = msg.payload();
+        let mut local = None;
+        let mut address = None;
+        let mut flags = u32::from(header[2]);
+        let mut label = None;
+        for attr in msg.attributes(IFADDRMSG_LEN) {
+            match attr.ty() {
+                libc::IFA_LOCAL => local = ip_addr(&attr),
+                libc::IFA_ADDRESS => address = ip_addr(&attr),
+                libc::IFA_FLAGS => flags = attr.as_u32().unwrap_or(flags),
+                libc::IFA_LABEL => label = attr.as_str().map(String::from),
+                _ => {}
+            }
+        }
+        // For point-to-point interfaces `IFA_ADDRESS` is the address of the
+        // peer and `IFA_LOCAL` the local address.
+        if let Some(address) = local.or(address) {
+            addresses.push(Address {
+                index: read_u32(header, 4).unwrap(),
+                address,
+                prefix_len: header[1],
+                scope: Scope::from(header[3]),
+                flags,
+                label,
+            });
+        }
+        Ok(())
+    })?;
+    Ok(addresses)
+}
+
+/// List all routes, of all routing tables, using `RTM_GETROUTE`.
+pub fn routes(socket: &Socket) -> io::Result<Vec<Route>> {
+    let req = MessageBuilder::new(libc::RTM_GETROUTE, libc::NLM_F_DUMP as u16)
+        .with_header(&[0; RTMSG_LEN]);
+    let mut routes = Vec::new();
+    request(socket, &req, |msg| {
+        if msg.ty() != libc::RTM_NEWROUTE || msg.payload().len() < RTMSG_LEN {
+            return Ok(());
+        }
+        let header = msg.payload();
+        let unspecified = match header[0] as libc::c_int {
+            libc::AF_INET => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            libc::AF_INET6 => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+            // E.g. multicast routing (`RTNL_FAMILY_IPMR`).
+            _ => return Ok(()),
+        };
+        let mut route = Route {
+            destination: unspecified,
+            prefix_len: header[1],
+            gateway: None,
+            source: None,
+            interface: None,
+            priority: None,
+            table: u32::from(header[4]),
+            protocol: header[5],
+            scope: Scope::from(header[6]),
+            kind: header[7],
+            next_hops: Vec::new(),
+        };
+        for attr in msg.attributes(RTMSG_LEN) {
+            match attr.ty() {
+                libc::RTA_DST => route.destination = ip_addr(&attr).unwrap_or(unspecified),
+                libc::RTA_GATEWAY => route.gateway = ip_addr(&attr),
+                libc::RTA_PREFSRC => route.source = ip_addr(&attr),
+                libc::RTA_OIF => route.interface = attr.as_u32(),
+                libc::RTA_PRIORITY => route.priority = attr.as_u32(),
+                // Supports tables with an id larger than 255.
+                libc::RTA_TABLE => route.table = attr.as_u32().unwrap_or(route.table),
+                libc::RTA_MULTIPATH => route.next_hops = next_hops(attr.data()),
+                _ => {}
+            }
+        }
+        routes.push(route);
+        Ok(())
+    })?;
+    Ok(routes)
+}
+
+/// Returns the next hops in a `RTA_MULTIPATH` attribute, a list of
+/// `rtnexthop` structures each followed by its attributes.
+fn next_hops(mut data: &[u8]) -> Vec<NextHop> {
+    let mut next_hops = Vec::new();
+    while let (Some(len), Some(interface)) = (read_u16(data, 0), read_u32(data, 4)) {
+        let len = len as usize;
+        if len < RTNEXTHOP_LEN || len > data.len() {
+            break;
+        }
+        let gateway = Attributes::new(&data[RTNEXTHOP_LEN..len]).get(libc::RTA_GATEWAY);
+        next_hops.push(NextHop {
+            interface,
+            gateway: gateway.and_then(|attr| ip_addr(&attr)),
+            // `rtnh_hops` holds the weight minus one.
+            weight: u16::from(data[3]) + 1,
+            flags: data[2],
+        });
+        data = &data[align(len).min(data.len())..];
+    }
+    next_hops
+}
+
+/// Returns the IPv4 or IPv6 address in `attr`.
+fn ip_addr(attr: &Attribute<'_>) -> Option<IpAddr> {
+    match attr.data().len() {
+        4 => <[u8; 4]>::try_from(attr.data()).ok().map(IpAddr::from),
+        16 => <[u8; 16]>::try_from(attr.data()).ok().map(IpAddr::from),
+        _ => None,
+    }
+}
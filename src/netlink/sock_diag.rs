@@ -0,0 +1,570 @@
+// Copyright 2015 The Rust Project Developers.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Socket monitoring (`NETLINK_SOCK_DIAG`) to list the sockets of the
+//! system, similar to `ss(8)`, see `sock_diag(7)`.
+//!
+//! All functions expect a socket created using
+//! [`Protocol::NETLINK_SOCK_DIAG`].
+//!
+//! The returned cookies match the value returned by [`Socket::cookie`], which
+//! can be used to find the records of sockets owned by this process.
+//!
+//! [`Protocol::NETLINK_SOCK_DIAG`]: crate::Protocol::NETLINK_SOCK_DIAG
+//! [`Socket::cookie`]: crate::Socket::cookie
+//!
+//! # Examples
+//!
+//! List all listening TCP sockets.
+//!
+//! ```
+//! # fn main() -> std::io::Result<()> {
+//! use socket2::netlink::sock_diag::{self, InetQuery};
+//! use socket2::{Domain, Protocol, Socket, TcpState, Type};
+//!
+//! let socket = Socket::new(Domain::NETLINK, Type::RAW, Some(Protocol::NETLINK_SOCK_DIAG))?;
+//! let query = InetQuery::new(Domain::IPV4, Protocol::TCP).with_states(&[TcpState::LISTEN]);
+//! for record in sock_diag::inet_sockets(&socket, &query)? {
+//!     println!("{} (inode {})", record.local_addr(), record.inode());
+//! }
+//! # Ok(()) }
+//! ```
+
+use std::io;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+
+use super::{read_u32, request, MessageBuilder};
+use crate::sys::{c_int, offset_of_path};
+use crate::{Domain, Protocol, SockAddr, SockAddrStorage, Socket, TcpInfo, TcpState, Type};
+
+// Not defined in libc, see `include/uapi/linux/sock_diag.h`,
+// `include/uapi/linux/inet_diag.h` and `include/uapi/linux/unix_diag.h`.
+const SOCK_DIAG_BY_FAMILY: u16 = 20;
+const INET_DIAG_REQ_V2_LEN: usize = 56;
+const INET_DIAG_MSG_LEN: usize = 72;
+const INET_DIAG_INFO: u16 = 2;
+const INET_DIAG_SKMEMINFO: u16 = 7;
+const UNIX_DIAG_REQ_LEN: usize = 24;
+const UNIX_DIAG_MSG_LEN: usize = 16;
+const UDIAG_SHOW_NAME: u32 = 0x01;
+const UDIAG_SHOW_PEER: u32 = 0x04;
+const UDIAG_SHOW_RQLEN: u32 = 0x10;
+const UDIAG_SHOW_MEMINFO: u32 = 0x20;
+const UDIAG_SHOW_UID: u32 = 0x40;
+const UNIX_DIAG_NAME: u16 = 0;
+const UNIX_DIAG_PEER: u16 = 2;
+const UNIX_DIAG_RQLEN: u16 = 4;
+const UNIX_DIAG_MEMINFO: u16 = 5;
+const UNIX_DIAG_UID: u16 = 7;
+
+/// Returns the bitmask of `states`, ignoring states that don't fit in the
+/// mask (no socket can be in those states).
+fn states_mask(states: &[TcpState]) -> u32 {
+    states.iter().fold(0, |mask, state| {
+        mask | 1u32.checked_shl(u8::from(*state).into()).unwrap_or(0)
+    })
+}
+
+/// Query for internet (IPv4 and IPv6) sockets, see [`inet_sockets`].
+#[derive(Debug, Clone)]
+pub struct InetQuery {
+    family: u8,
+    protocol: u8,
+    states: u32,
+    extensions: u8,
+    source_port: Option<u16>,
+    destination_port: Option<u16>,
+}
+
+impl InetQuery {
+    /// Create a new query for all sockets of `domain` (IPv4 or IPv6) and
+    /// `protocol`, e.g. TCP or UDP.
+    pub fn new(domain: Domain, protocol: Protocol) -> InetQuery {
+        InetQuery {
+            family: c_int::from(domain) as u8,
+            protocol: c_int::from(protocol) as u8,
+            states: !0,
+            extensions: 0,
+            source_port: None,
+            destination_port: None,
+        }
+    }
+
+    /// Only return sockets in one of the `states`. States above 31 are
+    /// ignored.
+    ///
+    /// Note that unconnected UDP sockets are in the `CLOSE` state and
+    /// connected ones in the `ESTABLISHED` state.
+    pub fn with_states(mut self, states: &[TcpState]) -> Self {
+        self.states = states_mask(states);
+        self
+    }
+
+    /// Only return sockets with local (source) port `port`.
+    pub fn with_source_port(mut self, port: u16) -> Self {
+        self.source_port = Some(port);
+        self
+    }
+
+    /// Only return sockets with remote (destination) port `port`.
+    pub fn with_destination_port(mut self, port: u16) -> Self {
+        self.destination_port = Some(port);
+        self
+    }
+
+    /// Request the `tcp_info` of TCP sockets, see [`InetSocket::tcp_info`].
+    pub fn with_tcp_info(mut self) -> Self {
+        self.extensions |= 1 << (INET_DIAG_INFO - 1);
+        self
+    }
+
+    /// Request the memory information, see [`InetSocket::meminfo`].
+    pub fn with_meminfo(mut self) -> Self {
+        self.extensions |= 1 << (INET_DIAG_SKMEMINFO - 1);
+        self
+    }
+
+    /// Encode the `inet_diag_req_v2` structure.
+    fn encode(&self) -> [u8; INET_DIAG_REQ_V2_LEN] {
+        let mut req = [0; INET_DIAG_REQ_V2_LEN];
+        req[0] = self.family;
+        req[1] = self.protocol;
+        req[2] = self.extensions;
+        req[4..8].copy_from_slice(&self.states.to_ne_bytes());
+        req
+    }
+
+    /// Returns true if `record` matches the ports of this query.
+    fn matches(&self, record: &InetSocket) -> bool {
+        self.source_port
+            .map_or(true, |port| record.local.port() == port)
+            && self
+                .destination_port
+                .map_or(true, |port| record.peer.port() == port)
+    }
+}
+
+/// Record of an internet socket, see [`inet_sockets`].
+#[derive(Debug, Clone)]
+pub struct InetSocket {
+    state: TcpState,
+    local: SocketAddr,
+    peer: SocketAddr,
+    interface: u32,
+    cookie: u64,
+    uid: u32,
+    inode: u32,
+    recv_queue: u32,
+    send_queue: u32,
+    tcp_info: Option<TcpInfo>,
+    meminfo: Option<MemInfo>,
+}
+
+impl InetSocket {
+    /// Decode an `inet_diag_msg` structure and its attributes.
+    fn decode(payload: &[u8], attributes: super::Attributes<'_>) -> Option<InetSocket> {
+        let msg = payload.get(..INET_DIAG_MSG_LEN)?;
+        let (local, peer) = match msg[0] as c_int {
+            libc::AF_INET => {
+                let src = <[u8; 4]>::try_from(&msg[8..12]).unwrap();
+                let dst = <[u8; 4]>::try_from(&msg[24..28]).unwrap();
+                (
+                    SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::from(src), read_be16(msg, 4))),
+                    SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::from(dst), read_be16(msg, 6))),
+                )
+            }
+            libc::AF_INET6 => {
+                let src = <[u8; 16]>::try_from(&msg[8..24]).unwrap();
+                let dst = <[u8; 16]>::try_from(&msg[24..40]).unwrap();
+                (
+                    SocketAddr::V6(SocketAddrV6::new(
+                        Ipv6Addr::from(src),
+                        read_be16(msg, 4),
+                        0,
+                        0,
+                    )),
+                    SocketAddr::V6(SocketAddrV6::new(
+                        Ipv6Addr::from(dst),
+                        read_be16(msg, 6),
+                        0,
+                        0,
+                    )),
+                )
+            }
+            _ => return None,
+        };
+        let mut record = InetSocket {
+            state: TcpState::from(msg[1]),
+            local,
+            peer,
+            interface: read_u32(msg, 40)?,
+            cookie: read_cookie(msg, 44)?,
+            recv_queue: read_u32(msg, 56)?,
+            send_queue: read_u32(msg, 60)?,
+            uid: read_u32(msg, 64)?,
+            inode: read_u32(msg, 68)?,
+            tcp_info: None,
+            meminfo: None,
+        };
+        for attr in attributes {
+            match attr.ty() {
+                INET_DIAG_INFO => record.tcp_info = TcpInfo::from_bytes(attr.data()),
+                INET_DIAG_SKMEMINFO => record.meminfo = Some(MemInfo::decode(attr.data())),
+                _ => {}
+            }
+        }
+        Some(record)
+    }
+
+    /// Returns the state of the socket.
+    pub const fn state(&self) -> TcpState {
+        self.state
+    }
+
+    /// Returns the local address of the socket.
+    pub const fn local_addr(&self) -> SocketAddr {
+        self.local
+    }
+
+    /// Returns the remote address of the socket, unspecified if the socket is
+    /// not connected.
+    pub const fn peer_addr(&self) -> SocketAddr {
+        self.peer
+    }
+
+    /// Returns the index of the interface the socket is bound to, or zero.
+    pub const fn interface(&self) -> u32 {
+        self.interface
+    }
+
+    /// Returns the cookie of the socket, see [`Socket::cookie`].
+    ///
+    /// [`Socket::cookie`]: crate::Socket::cookie
+    pub const fn cookie(&self) -> u64 {
+        self.cookie
+    }
+
+    /// Returns the user id of the owner of the socket.
+    pub const fn uid(&self) -> u32 {
+        self.uid
+    }
+
+    /// Returns the inode number of the socket.
+    pub const fn inode(&self) -> u32 {
+        self.inode
+    }
+
+    /// Returns the number of bytes in the receive queue. For listening TCP
+    /// sockets this is the number of connections waiting to be accepted.
+    pub const fn recv_queue(&self) -> u32 {
+        self.recv_queue
+    }
+
+    /// Returns the number of bytes in the send queue. For listening TCP
+    /// sockets this is the backlog.
+    pub const fn send_queue(&self) -> u32 {
+        self.send_queue
+    }
+
+    /// Returns the TCP information, only returned for TCP sockets if
+    /// requested using [`InetQuery::with_tcp_info`].
+    pub const fn tcp_info(&self) -> Option<&TcpInfo> {
+        self.tcp_info.as_ref()
+    }
+
+    /// Returns the memory information, only returned if requested using
+    /// [`InetQuery::with_meminfo`].
+    pub const fn meminfo(&self) -> Option<&MemInfo> {
+        self.meminfo.as_ref()
+    }
+}
+
+/// List the internet sockets matching `query`.
+///
+/// Family, protocol and states are filtered by the kernel, the ports are
+/// filtered after receiving the records.
+pub fn inet_sockets(socket: &Socket, query: &InetQuery) -> io::Result<Vec<InetSocket>> {
+    let req = MessageBuilder::new(SOCK_DIAG_BY_FAMILY, libc::NLM_F_DUMP as u16)
+        .with_header(&query.encode());
+    let mut records = Vec::new();
+    request(socket, &req, |msg| {
+        if msg.ty() != SOCK_DIAG_BY_FAMILY {
+            return Ok(());
+        }
+        let attributes = msg.attributes(INET_DIAG_MSG_LEN);
+        match InetSocket::decode(msg.payload(), attributes) {
+            Some(record) if query.matches(&record) => records.push(record),
+            _ => {}
+        }
+        Ok(())
+    })?;
+    Ok(records)
+}
+
+/// Query for Unix sockets, see [`unix_sockets`].
+#[derive(Debug, Clone)]
+pub struct UnixQuery {
+    states: u32,
+    show: u32,
+}
+
+impl UnixQuery {
+    /// Create a new query for all Unix sockets.
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> UnixQuery {
+        UnixQuery {
+            states: !0,
+            show: UDIAG_SHOW_NAME | UDIAG_SHOW_PEER | UDIAG_SHOW_RQLEN | UDIAG_SHOW_UID,
+        }
+    }
+
+    /// Only return sockets in one of the `states`. States above 31 are
+    /// ignored.
+    ///
+    /// Unix sockets use `ESTABLISHED` for connected sockets, `LISTEN` for
+    /// listening sockets and `CLOSE` for all other sockets.
+    pub fn with_states(mut self, states: &[TcpState]) -> Self {
+        self.states = states_mask(states);
+        self
+    }
+
+    /// Request the memory information, see [`UnixSocket::meminfo`].
+    pub fn with_meminfo(mut self) -> Self {
+        self.show |= UDIAG_SHOW_MEMINFO;
+        self
+    }
+
+    /// Encode the `unix_diag_req` structure.
+    fn encode(&self) -> [u8; UNIX_DIAG_REQ_LEN] {
+        let mut req = [0; UNIX_DIAG_REQ_LEN];
+        req[0] = libc::AF_UNIX as u8;
+        req[4..8].copy_from_slice(&self.states.to_ne_bytes());
+        req[12..16].copy_from_slice(&self.show.to_ne_bytes());
+        req
+    }
+}
+
+/// Record of a Unix socket, see [`unix_sockets`].
+#[derive(Debug, Clone)]
+pub struct UnixSocket {
+    ty: Type,
+    state: TcpState,
+    inode: u32,
+    cookie: u64,
+    addr: Option<SockAddr>,
+    peer_inode: Option<u32>,
+    recv_queue: Option<u32>,
+    send_queue: Option<u32>,
+    uid: Option<u32>,
+    meminfo: Option<MemInfo>,
+}
+
+impl UnixSocket {
+    /// Decode a `unix_diag_msg` structure and its attributes.
+    fn decode(payload: &[u8], attributes: super::Attributes<'_>) -> Option<UnixSocket> {
+        let msg = payload.get(..UNIX_DIAG_MSG_LEN)?;
+        let mut record = UnixSocket {
+            ty: Type::from(c_int::from(msg[1])),
+            state: TcpState::from(msg[2]),
+            inode: read_u32(msg, 4)?,
+            cookie: read_cookie(msg, 8)?,
+            addr: None,
+            peer_inode: None,
+            recv_queue: None,
+            send_queue: None,
+            uid: None,
+            meminfo: None,
+        };
+        for attr in attributes {
+            match attr.ty() {
+                UNIX_DIAG_NAME => record.addr = unix_addr(attr.data()),
+                UNIX_DIAG_PEER => record.peer_inode = attr.as_u32(),
+                UNIX_DIAG_RQLEN => {
+                    record.recv_queue = read_u32(attr.data(), 0);
+                    record.send_queue = read_u32(attr.data(), 4);
+                }
+                UNIX_DIAG_MEMINFO => record.meminfo = Some(MemInfo::decode(attr.data())),
+                UNIX_DIAG_UID => record.uid = attr.as_u32(),
+                _ => {}
+            }
+        }
+        Some(record)
+    }
+
+    /// Returns the type of the socket.
+    pub const fn r#type(&self) -> Type {
+        self.ty
+    }
+
+    /// Returns the state of the socket.
+    pub const fn state(&self) -> TcpState {
+        self.state
+    }
+
+    /// Returns the inode number of the socket.
+    pub const fn inode(&self) -> u32 {
+        self.inode
+    }
+
+    /// Returns the cookie of the socket, see [`Socket::cookie`].
+    ///
+    /// [`Socket::cookie`]: crate::Socket::cookie
+    pub const fn cookie(&self) -> u64 {
+        self.cookie
+    }
+
+    /// Returns the address the socket is bound to, if any.
+    pub const fn local_addr(&self) -> Option<&SockAddr> {
+        self.addr.as_ref()
+    }
+
+    /// Returns the inode number of the peer socket, if connected.
+    pub const fn peer_inode(&self) -> Option<u32> {
+        self.peer_inode
+    }
+
+    /// Returns the number of bytes in the receive queue. For listening sockets
+    /// this is the number of connections waiting to be accepted.
+    pub const fn recv_queue(&self) -> Option<u32> {
+        self.recv_queue
+    }
+
+    /// Returns the number of bytes in the send queue. For listening sockets
+    /// this is the backlog.
+    pub const fn send_queue(&self) -> Option<u32> {
+        self.send_queue
+    }
+
+    /// Returns the user id of the owner of the socket (since Linux 5.3).
+    pub const fn uid(&self) -> Option<u32> {
+        self.uid
+    }
+
+    /// Returns the memory information, only returned if requested using
+    /// [`UnixQuery::with_meminfo`].
+    pub const fn meminfo(&self) -> Option<&MemInfo> {
+        self.meminfo.as_ref()
+    }
+}
+
+/// List the Unix sockets matching `query`.
+pub fn unix_sockets(socket: &Socket, query: &UnixQuery) -> io::Result<Vec<UnixSocket>> {
+    let req = MessageBuilder::new(SOCK_DIAG_BY_FAMILY, libc::NLM_F_DUMP as u16)
+        .with_header(&query.encode());
+    let mut records = Vec::new();
+    request(socket, &req, |msg| {
+        if msg.ty() != SOCK_DIAG_BY_FAMILY {
+            return Ok(());
+        }
+        let attributes = msg.attributes(UNIX_DIAG_MSG_LEN);
+        if let Some(record) = UnixSocket::decode(msg.payload(), attributes) {
+            records.push(record);
+        }
+        Ok(())
+    })?;
+    Ok(records)
+}
+
+/// Memory usage of a socket (`SK_MEMINFO_*`), see [`InetSocket::meminfo`]
+/// and [`UnixSocket::meminfo`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemInfo {
+    values: [u32; 9],
+    len: usize,
+}
+
+impl MemInfo {
+    /// Decode the `SK_MEMINFO_*` array.
+    fn decode(data: &[u8]) -> MemInfo {
+        let mut values = [0; 9];
+        let mut len = 0;
+        for (value, bytes) in values.iter_mut().zip(data.chunks_exact(4)) {
+            *value = u32::from_ne_bytes(bytes.try_into().unwrap());
+            len += 1;
+        }
+        MemInfo { values, len }
+    }
+
+    /// Returns the value at `index`, if returned by the kernel.
+    fn get(&self, index: usize) -> Option<u32> {
+        (index < self.len).then(|| self.values[index])
+    }
+
+    /// Bytes allocated for received data (`SK_MEMINFO_RMEM_ALLOC`).
+    pub fn rmem_alloc(&self) -> u32 {
+        self.get(0).unwrap_or(0)
+    }
+
+    /// Size of the receive buffer (`SK_MEMINFO_RCVBUF`).
+    pub fn rcvbuf(&self) -> u32 {
+        self.get(1).unwrap_or(0)
+    }
+
+    /// Bytes allocated for data being sent (`SK_MEMINFO_WMEM_ALLOC`).
+    pub fn wmem_alloc(&self) -> u32 {
+        self.get(2).unwrap_or(0)
+    }
+
+    /// Size of the send buffer (`SK_MEMINFO_SNDBUF`).
+    pub fn sndbuf(&self) -> u32 {
+        self.get(3).unwrap_or(0)
+    }
+
+    /// Bytes allocated but not yet used (`SK_MEMINFO_FWD_ALLOC`).
+    pub fn fwd_alloc(&self) -> u32 {
+        self.get(4).unwrap_or(0)
+    }
+
+    /// Bytes queued in the send queue (`SK_MEMINFO_WMEM_QUEUED`).
+    pub fn wmem_queued(&self) -> u32 {
+        self.get(5).unwrap_or(0)
+    }
+
+    /// Bytes used for socket options and ancillary data
+    /// (`SK_MEMINFO_OPTMEM`).
+    pub fn optmem(&self) -> u32 {
+        self.get(6).unwrap_or(0)
+    }
+
+    /// Bytes in the backlog queue (`SK_MEMINFO_BACKLOG`).
+    pub fn backlog(&self) -> Option<u32> {
+        self.get(7)
+    }
+
+    /// Number of packets dropped (`SK_MEMINFO_DROPS`).
+    pub fn drops(&self) -> Option<u32> {
+        self.get(8)
+    }
+}
+
+/// Read an `u16` in network byte order at `offset`.
+fn read_be16(buf: &[u8], offset: usize) -> u16 {
+    u16::from_be_bytes([buf[offset], buf[offset + 1]])
+}
+
+/// Read a cookie, stored as two `u32`s, at `offset`.
+fn read_cookie(buf: &[u8], offset: usize) -> Option<u64> {
+    Some(u64::from(read_u32(buf, offset)?) | u64::from(read_u32(buf, offset + 4)?) << 32)
+}
+
+/// Create a Unix address from `UNIX_DIAG_NAME`, i.e. the `sun_path` field.
+fn unix_addr(path: &[u8]) -> Option<SockAddr> {
+    let mut storage = SockAddrStorage::zeroed();
+    // SAFETY: sockaddr_un is one of the sockaddr_* types defined by this platform.
+    let sockaddr = unsafe { storage.view_as::<libc::sockaddr_un>() };
+    if path.len() > sockaddr.sun_path.len() {
+        return None;
+    }
+    sockaddr.sun_family = libc::AF_UNIX as libc::sa_family_t;
+    for (dst, src) in sockaddr.sun_path.iter_mut().zip(path) {
+        *dst = *src as libc::c_char;
+    }
+    let len = offset_of_path(sockaddr) + path.len();
+    // SAFETY: initialised the storage above.
+    Some(unsafe { SockAddr::new(storage, len as libc::socklen_t) })
+}
@@ -0,0 +1,621 @@
+// Copyright 2015 The Rust Project Developers.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::mem::size_of;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::{Duration, SystemTime};
+use std::{fmt, io, ptr, slice};
+
+use crate::sys::{self, c_int};
+use crate::{LinkLayerAddr, Socket};
+
+/// Round `len` up to `TPACKET_ALIGNMENT`.
+const fn tpacket_align(len: usize) -> usize {
+    (len + libc::TPACKET_ALIGNMENT - 1) & !(libc::TPACKET_ALIGNMENT - 1)
+}
+
+/// Offset of the data in a `TPACKET_V2` transmit frame.
+const TX_DATA_OFFSET: usize = tpacket_align(size_of::<libc::tpacket2_hdr>());
+
+/// Sizing of a [`PacketRing`] or [`PacketTxRing`].
+///
+/// The ring consists of `block_count` blocks of `block_size` bytes, which must
+/// be a multiple of the page size. Each block holds `block_size / frame_size`
+/// frames. Receive rings (`TPACKET_V3`) pack variable sized frames into the
+/// blocks, so there `frame_size` only limits the size of a single frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PacketRingConfig {
+    block_size: u32,
+    block_count: u32,
+    frame_size: u32,
+    retire_timeout: Option<Duration>,
+}
+
+impl PacketRingConfig {
+    /// Create a new configuration.
+    pub const fn new(block_size: u32, block_count: u32, frame_size: u32) -> PacketRingConfig {
+        PacketRingConfig {
+            block_size,
+            block_count,
+            frame_size,
+            retire_timeout: None,
+        }
+    }
+
+    /// Set the timeout after which the kernel returns a block that is not
+    /// completely filled to user space (`tp_retire_blk_tov`).
+    ///
+    /// Only used for receive rings. If not set the kernel determines the
+    /// timeout based on the link speed.
+    pub const fn with_retire_timeout(mut self, timeout: Duration) -> Self {
+        self.retire_timeout = Some(timeout);
+        self
+    }
+
+    /// Total size of the ring in bytes.
+    fn size(&self) -> usize {
+        self.block_size as usize * self.block_count as usize
+    }
+
+    /// Returns the total number of frames in the ring, or an error if the
+    /// configuration is invalid.
+    ///
+    /// The kernel validates the configuration in more detail, this only
+    /// ensures that the sizes can be computed.
+    fn frame_count(&self) -> io::Result<u32> {
+        let invalid = || {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid packet ring configuration",
+            )
+        };
+        if self.frame_size == 0 || self.block_size.checked_mul(self.block_count).is_none() {
+            return Err(invalid());
+        }
+        (self.block_size / self.frame_size)
+            .checked_mul(self.block_count)
+            .ok_or_else(invalid)
+    }
+}
+
+/// A memory mapped ring.
+struct Mapping {
+    ptr: *mut u8,
+    size: usize,
+}
+
+impl Mapping {
+    /// Map the ring of `socket` of `size` bytes.
+    fn new(socket: &Socket, size: usize) -> io::Result<Mapping> {
+        // SAFETY: we pass a null pointer and a valid file descriptor.
+        let ptr = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                size,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED,
+                socket.as_raw(),
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Mapping {
+            ptr: ptr.cast(),
+            size,
+        })
+    }
+
+    /// Returns the status field at `offset`.
+    ///
+    /// # Safety
+    ///
+    /// `offset` must be in bounds and aligned to 4 bytes.
+    unsafe fn status(&self, offset: usize) -> &AtomicU32 {
+        &*self.ptr.add(offset).cast::<AtomicU32>()
+    }
+}
+
+impl Drop for Mapping {
+    fn drop(&mut self) {
+        // SAFETY: we mapped the memory in `Mapping::new`.
+        unsafe {
+            let _ = libc::munmap(self.ptr.cast(), self.size);
+        }
+    }
+}
+
+/// Set the ring `version` and `opt` (`PACKET_RX_RING` or `PACKET_TX_RING`)
+/// to `req` on `socket`.
+fn setup_ring<T>(
+    socket: &Socket,
+    version: libc::tpacket_versions,
+    opt: c_int,
+    req: T,
+) -> io::Result<()> {
+    // SAFETY: `PACKET_VERSION` takes an int and `req` is the request type
+    // expected for `version`.
+    unsafe {
+        sys::setsockopt(
+            socket.as_raw(),
+            libc::SOL_PACKET,
+            libc::PACKET_VERSION,
+            version as c_int,
+        )?;
+        sys::setsockopt(socket.as_raw(), libc::SOL_PACKET, opt, req)
+    }
+}
+
+/// Memory mapped receive ring of a packet socket, using `TPACKET_V3`.
+///
+/// The kernel fills the blocks of the ring with received packets and hands
+/// them over to user space, which can read all packets in a block without
+/// any system calls. See [`PacketRing::next_block`].
+///
+/// # Examples
+///
+/// ```no_run
+/// # fn main() -> std::io::Result<()> {
+/// use std::time::Duration;
+///
+/// use socket2::{Domain, PacketRing, PacketRingConfig, Protocol, Socket, Type};
+///
+/// let protocol = Protocol::from((libc::ETH_P_ALL as u16).to_be() as i32);
+/// let socket = Socket::new(Domain::PACKET, Type::RAW, Some(protocol))?;
+/// let config = PacketRingConfig::new(1 << 20, 64, 2048)
+///     .with_retire_timeout(Duration::from_millis(10));
+/// let mut ring = PacketRing::new(socket, config)?;
+/// loop {
+///     if let Some(block) = ring.next_block(None)? {
+///         for frame in block.frames() {
+///             println!("received {} bytes", frame.len());
+///         }
+///         // Dropping `block` returns it to the kernel.
+///     }
+/// }
+/// # }
+/// ```
+pub struct PacketRing {
+    socket: Socket,
+    mapping: Mapping,
+    block_size: usize,
+    block_count: usize,
+    current: usize,
+}
+
+// SAFETY: the ring exclusively owns the mapping.
+unsafe impl Send for PacketRing {}
+
+impl PacketRing {
+    /// Set up a `PACKET_RX_RING` on `socket` and map it into memory.
+    ///
+    /// This sets the `PACKET_VERSION` of `socket` to `TPACKET_V3`.
+    pub fn new(socket: Socket, config: PacketRingConfig) -> io::Result<PacketRing> {
+        let req = libc::tpacket_req3 {
+            tp_block_size: config.block_size,
+            tp_block_nr: config.block_count,
+            tp_frame_size: config.frame_size,
+            tp_frame_nr: config.frame_count()?,
+            tp_retire_blk_tov: config.retire_timeout.map_or(0, |timeout| {
+                timeout.as_millis().clamp(1, u32::MAX as u128) as u32
+            }),
+            tp_sizeof_priv: 0,
+            tp_feature_req_word: 0,
+        };
+        setup_ring(
+            &socket,
+            libc::tpacket_versions::TPACKET_V3,
+            libc::PACKET_RX_RING,
+            req,
+        )?;
+        let mapping = Mapping::new(&socket, config.size())?;
+        Ok(PacketRing {
+            socket,
+            mapping,
+            block_size: config.block_size as usize,
+            block_count: config.block_count as usize,
+            current: 0,
+        })
+    }
+
+    /// Returns the underlying socket.
+    pub const fn socket(&self) -> &Socket {
+        &self.socket
+    }
+
+    /// Returns the block status of the current block.
+    fn block_status(&self) -> &AtomicU32 {
+        // SAFETY: `current` is always in bounds and each block starts with a
+        // `tpacket_block_desc`, which is aligned as blocks are page aligned.
+        unsafe {
+            let desc = self
+                .mapping
+                .ptr
+                .add(self.current * self.block_size)
+                .cast::<libc::tpacket_block_desc>();
+            &*ptr::addr_of!((*desc).hdr.bh1.block_status).cast::<AtomicU32>()
+        }
+    }
+
+    /// Returns the next block filled by the kernel, if any, without
+    /// blocking.
+    pub fn try_next_block(&mut self) -> Option<PacketBlock<'_>> {
+        if self.block_status().load(Ordering::Acquire) & libc::TP_STATUS_USER == 0 {
+            return None;
+        }
+        let offset = self.current * self.block_size;
+        // SAFETY: the block is in bounds and owned by user space until the
+        // status is reset.
+        let data = unsafe { slice::from_raw_parts(self.mapping.ptr.add(offset), self.block_size) };
+        Some(PacketBlock { data, ring: self })
+    }
+
+    /// Returns the next block filled by the kernel, waiting up to `timeout`
+    /// (forever if `None`) for it. Returns `None` if the timeout expired.
+    pub fn next_block(&mut self, timeout: Option<Duration>) -> io::Result<Option<PacketBlock<'_>>> {
+        if self.block_status().load(Ordering::Acquire) & libc::TP_STATUS_USER == 0 {
+            sys::poll_readable(self.socket.as_raw(), timeout)?;
+        }
+        Ok(self.try_next_block())
+    }
+}
+
+impl fmt::Debug for PacketRing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PacketRing")
+            .field("socket", &self.socket)
+            .field("block_size", &self.block_size)
+            .field("block_count", &self.block_count)
+            .finish()
+    }
+}
+
+/// A block of received packets, see [`PacketRing::next_block`].
+///
+/// The block is returned to the kernel when dropped.
+pub struct PacketBlock<'a> {
+    data: &'a [u8],
+    ring: &'a mut PacketRing,
+}
+
+impl<'a> PacketBlock<'a> {
+    /// Returns the `tpacket_hdr_v1` block header.
+    fn header(&self) -> libc::tpacket_hdr_v1 {
+        // SAFETY: the block starts with a `tpacket_block_desc`, which is
+        // aligned as the mapping is page aligned.
+        unsafe {
+            let desc = self.data.as_ptr().cast::<libc::tpacket_block_desc>();
+            ptr::read(ptr::addr_of!((*desc).hdr.bh1))
+        }
+    }
+
+    /// Returns the number of packets in the block.
+    pub fn num_packets(&self) -> u32 {
+        self.header().num_pkts
+    }
+
+    /// Returns the sequence number of the block.
+    pub fn seq(&self) -> u64 {
+        self.header().seq_num
+    }
+
+    /// Returns `true` if the block was returned because the retire timeout
+    /// expired, rather than being full (`TP_STATUS_BLK_TMO`).
+    pub fn is_timed_out(&self) -> bool {
+        self.header().block_status & libc::TP_STATUS_BLK_TMO != 0
+    }
+
+    /// Returns an iterator over the packets in the block.
+    pub fn frames(&self) -> PacketFrames<'_> {
+        let header = self.header();
+        PacketFrames {
+            block: self.data,
+            offset: header.offset_to_first_pkt as usize,
+            remaining: header.num_pkts,
+        }
+    }
+}
+
+impl<'a> Drop for PacketBlock<'a> {
+    fn drop(&mut self) {
+        self.ring
+            .block_status()
+            .store(libc::TP_STATUS_KERNEL, Ordering::Release);
+        self.ring.current = (self.ring.current + 1) % self.ring.block_count;
+    }
+}
+
+impl<'a> fmt::Debug for PacketBlock<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PacketBlock")
+            .field("seq", &self.seq())
+            .field("num_packets", &self.num_packets())
+            .finish()
+    }
+}
+
+/// Iterator over the packets in a [`PacketBlock`].
+#[derive(Clone)]
+pub struct PacketFrames<'a> {
+    block: &'a [u8],
+    offset: usize,
+    remaining: u32,
+}
+
+impl<'a> Iterator for PacketFrames<'a> {
+    type Item = PacketFrame<'a>;
+
+    fn next(&mut self) -> Option<PacketFrame<'a>> {
+        if self.remaining == 0
+            || self.offset % libc::TPACKET_ALIGNMENT != 0
+            || self.offset + libc::TPACKET3_HDRLEN > self.block.len()
+        {
+            return None;
+        }
+        // SAFETY: checked the bounds and alignment above.
+        let header = unsafe {
+            ptr::read(
+                self.block
+                    .as_ptr()
+                    .add(self.offset)
+                    .cast::<libc::tpacket3_hdr>(),
+            )
+        };
+        let addr = unsafe {
+            let ptr = self
+                .block
+                .as_ptr()
+                .add(self.offset + tpacket_align(size_of::<libc::tpacket3_hdr>()));
+            ptr::read_unaligned(ptr.cast::<libc::sockaddr_ll>())
+        };
+        let start = self.offset + header.tp_mac as usize;
+        let data = self.block.get(start..start + header.tp_snaplen as usize)?;
+        self.remaining -= 1;
+        self.offset += header.tp_next_offset as usize;
+        Some(PacketFrame {
+            header,
+            addr: LinkLayerAddr::from_sockaddr_ll(&addr),
+            data,
+        })
+    }
+}
+
+impl<'a> fmt::Debug for PacketFrames<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.clone()).finish()
+    }
+}
+
+/// A packet in a [`PacketBlock`], with its `tpacket3_hdr` metadata.
+pub struct PacketFrame<'a> {
+    header: libc::tpacket3_hdr,
+    addr: LinkLayerAddr,
+    data: &'a [u8],
+}
+
+impl<'a> PacketFrame<'a> {
+    /// Returns the captured data of the packet, starting at the link-layer
+    /// header.
+    pub const fn data(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// Returns the original length of the packet.
+    pub const fn len(&self) -> u32 {
+        self.header.tp_len
+    }
+
+    /// Returns `true` if the original packet was empty.
+    pub const fn is_empty(&self) -> bool {
+        self.header.tp_len == 0
+    }
+
+    /// Returns the status flags (`TP_STATUS_*`) of the packet.
+    pub const fn status(&self) -> u32 {
+        self.header.tp_status
+    }
+
+    /// Returns the time the packet was received.
+    pub fn timestamp(&self) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::new(self.header.tp_sec.into(), self.header.tp_nsec)
+    }
+
+    /// Returns the link-layer address of the packet, including the interface
+    /// it was received on and the packet type, e.g. `PACKET_OUTGOING`.
+    pub const fn addr(&self) -> &LinkLayerAddr {
+        &self.addr
+    }
+
+    /// Returns the offset of the network-layer header in the packet data.
+    pub const fn net_offset(&self) -> u16 {
+        self.header.tp_net.wrapping_sub(self.header.tp_mac)
+    }
+
+    /// Returns the VLAN tag control information (TCI), if the packet was
+    /// VLAN tagged (`TP_STATUS_VLAN_VALID`).
+    pub const fn vlan_tci(&self) -> Option<u32> {
+        if self.header.tp_status & libc::TP_STATUS_VLAN_VALID != 0 {
+            Some(self.header.hv1.tp_vlan_tci)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the receive hash of the packet, only filled in if supported by
+    /// the kernel (`TP_FT_REQ_FILL_RXHASH`), otherwise zero.
+    pub const fn rxhash(&self) -> u32 {
+        self.header.hv1.tp_rxhash
+    }
+}
+
+impl<'a> fmt::Debug for PacketFrame<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PacketFrame")
+            .field("len", &self.len())
+            .field("snaplen", &self.data.len())
+            .field("status", &self.status())
+            .field("addr", &self.addr)
+            .finish()
+    }
+}
+
+/// Memory mapped transmit ring of a packet socket, using `TPACKET_V2`.
+///
+/// Frames are filled using [`PacketTxRing::frame`] and sent in a single
+/// system call using [`PacketTxRing::flush`].
+pub struct PacketTxRing {
+    socket: Socket,
+    mapping: Mapping,
+    block_size: usize,
+    frame_size: usize,
+    frames_per_block: usize,
+    frame_count: usize,
+    current: usize,
+}
+
+// SAFETY: the ring exclusively owns the mapping.
+unsafe impl Send for PacketTxRing {}
+
+impl PacketTxRing {
+    /// Set up a `PACKET_TX_RING` on `socket` and map it into memory.
+    ///
+    /// This sets the `PACKET_VERSION` of `socket` to `TPACKET_V2`. The socket
+    /// should be bound to an interface before sending.
+    pub fn new(socket: Socket, config: PacketRingConfig) -> io::Result<PacketTxRing> {
+        let frame_count = config.frame_count()?;
+        let req = libc::tpacket_req {
+            tp_block_size: config.block_size,
+            tp_block_nr: config.block_count,
+            tp_frame_size: config.frame_size,
+            tp_frame_nr: frame_count,
+        };
+        setup_ring(
+            &socket,
+            libc::tpacket_versions::TPACKET_V2,
+            libc::PACKET_TX_RING,
+            req,
+        )?;
+        let mapping = Mapping::new(&socket, config.size())?;
+        Ok(PacketTxRing {
+            socket,
+            mapping,
+            block_size: config.block_size as usize,
+            frame_size: config.frame_size as usize,
+            frames_per_block: (config.block_size / config.frame_size) as usize,
+            frame_count: frame_count as usize,
+            current: 0,
+        })
+    }
+
+    /// Returns the underlying socket.
+    pub const fn socket(&self) -> &Socket {
+        &self.socket
+    }
+
+    /// Returns the offset of the current frame in the mapping.
+    fn frame_offset(&self) -> usize {
+        (self.current / self.frames_per_block) * self.block_size
+            + (self.current % self.frames_per_block) * self.frame_size
+    }
+
+    /// Returns the next available frame, or `None` if all frames are waiting
+    /// to be sent.
+    pub fn frame(&mut self) -> Option<PacketTxFrame<'_>> {
+        let offset = self.frame_offset();
+        // SAFETY: the frame is in bounds and `tp_status` is the first field.
+        let status = unsafe { self.mapping.status(offset) };
+        let status = status.load(Ordering::Acquire);
+        if status & (libc::TP_STATUS_SEND_REQUEST | libc::TP_STATUS_SENDING) != 0 {
+            return None;
+        }
+        Some(PacketTxFrame { offset, ring: self })
+    }
+
+    /// Send all frames queued using [`PacketTxFrame::send`].
+    ///
+    /// If the socket is in blocking mode this waits until all frames are
+    /// sent. Returns the number of bytes sent.
+    pub fn flush(&self) -> io::Result<usize> {
+        // With a transmit ring the kernel ignores the buffer and sends the
+        // queued frames instead.
+        self.socket.send(&[])
+    }
+}
+
+impl fmt::Debug for PacketTxRing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PacketTxRing")
+            .field("socket", &self.socket)
+            .field("frame_size", &self.frame_size)
+            .field("frame_count", &self.frame_count)
+            .finish()
+    }
+}
+
+/// A frame of a [`PacketTxRing`] to fill with a packet.
+///
+/// Dropping the frame without calling [`send`] leaves it available.
+///
+/// [`send`]: PacketTxFrame::send
+pub struct PacketTxFrame<'a> {
+    offset: usize,
+    ring: &'a mut PacketTxRing,
+}
+
+impl<'a> PacketTxFrame<'a> {
+    /// Returns the buffer to write the packet into, starting at the
+    /// link-layer header (for `SOCK_RAW` sockets).
+    pub fn buffer(&mut self) -> &mut [u8] {
+        let len = self.ring.frame_size - TX_DATA_OFFSET;
+        // SAFETY: the frame is owned by user space until its status is set
+        // to `TP_STATUS_SEND_REQUEST`.
+        unsafe {
+            let ptr = self.ring.mapping.ptr.add(self.offset + TX_DATA_OFFSET);
+            slice::from_raw_parts_mut(ptr, len)
+        }
+    }
+
+    /// Queue the first `len` bytes of the [`buffer`] to be sent by the next
+    /// call to [`PacketTxRing::flush`].
+    ///
+    /// [`buffer`]: PacketTxFrame::buffer
+    ///
+    /// # Panics
+    ///
+    /// Panics if `len` is larger than the buffer.
+    pub fn send(self, len: usize) {
+        assert!(
+            len <= self.ring.frame_size - TX_DATA_OFFSET,
+            "packet too large for frame"
+        );
+        // SAFETY: the frame is in bounds and aligned.
+        unsafe {
+            let header = self
+                .ring
+                .mapping
+                .ptr
+                .add(self.offset)
+                .cast::<libc::tpacket2_hdr>();
+            ptr::addr_of_mut!((*header).tp_len).write(len as u32);
+            self.ring
+                .mapping
+                .status(self.offset)
+                .store(libc::TP_STATUS_SEND_REQUEST, Ordering::Release);
+        }
+        self.ring.current = (self.ring.current + 1) % self.ring.frame_count;
+    }
+}
+
+impl<'a> fmt::Debug for PacketTxFrame<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PacketTxFrame")
+            .field("index", &self.ring.current)
+            .finish()
+    }
+}
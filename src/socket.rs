@@ -23,6 +23,8 @@ use std::time::Duration;
 use crate::sys::{self, c_int, getsockopt, setsockopt, Bool};
 #[cfg(all(unix, not(target_os = "redox")))]
 use crate::MsgHdrMut;
+#[cfg(all(feature = "all", target_os = "linux"))]
+use crate::PmtuDiscovery;
 use crate::{Domain, Protocol, SockAddr, TcpKeepalive, Type};
 #[cfg(not(target_os = "redox"))]
 use crate::{MaybeUninitSlice, MsgHdr, RecvFlags};
@@ -1454,6 +1456,69 @@ impl Socket {
         }
     }
 
+    /// Get the value of the `IP_RECVERR` option for this socket.
+    ///
+    /// For more information about this option, see [`set_recv_err_v4`].
+    ///
+    /// [`set_recv_err_v4`]: Socket::set_recv_err_v4
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn recv_err_v4(&self) -> io::Result<bool> {
+        unsafe {
+            getsockopt::<c_int>(self.as_raw(), sys::IPPROTO_IP, libc::IP_RECVERR)
+                .map(|recv_err| recv_err != 0)
+        }
+    }
+
+    /// Set the value of the `IP_RECVERR` option for this socket.
+    ///
+    /// If enabled, extended errors, such as ICMP messages received in
+    /// response to sent packets, are queued on the error queue of the socket.
+    /// These can be read using [`Socket::recv_error_queue`].
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn set_recv_err_v4(&self, recv_err: bool) -> io::Result<()> {
+        unsafe {
+            setsockopt(
+                self.as_raw(),
+                sys::IPPROTO_IP,
+                libc::IP_RECVERR,
+                recv_err as c_int,
+            )
+        }
+    }
+
+    /// Get the value of the `IP_PKTINFO` option for this socket.
+    ///
+    /// For more information about this option, see [`set_recv_pktinfo_v4`].
+    ///
+    /// [`set_recv_pktinfo_v4`]: Socket::set_recv_pktinfo_v4
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn recv_pktinfo_v4(&self) -> io::Result<bool> {
+        unsafe {
+            getsockopt::<c_int>(self.as_raw(), sys::IPPROTO_IP, libc::IP_PKTINFO)
+                .map(|pktinfo| pktinfo != 0)
+        }
+    }
+
+    /// Set the value of the `IP_PKTINFO` option for this socket.
+    ///
+    /// If enabled, an `IP_PKTINFO` control message is received with each
+    /// packet, containing the destination address of the packet and the
+    /// interface it was received on. See [`ControlMessage::PktInfoV4`] and
+    /// [`Socket::recv_from_with_dst`].
+    ///
+    /// [`ControlMessage::PktInfoV4`]: crate::ControlMessage::PktInfoV4
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn set_recv_pktinfo_v4(&self, pktinfo: bool) -> io::Result<()> {
+        unsafe {
+            setsockopt(
+                self.as_raw(),
+                sys::IPPROTO_IP,
+                libc::IP_PKTINFO,
+                pktinfo as c_int,
+            )
+        }
+    }
+
     /// Get the value of the `IP_MULTICAST_IF` option for this socket.
     ///
     /// For more information about this option, see [`set_multicast_if_v4`].
@@ -1556,6 +1621,47 @@ impl Socket {
         unsafe { setsockopt(self.as_raw(), sys::IPPROTO_IP, sys::IP_TTL, ttl as c_int) }
     }
 
+    /// Get the value of the `IP_MTU_DISCOVER` option for this socket.
+    ///
+    /// For more information about this option, see [`set_mtu_discover_v4`].
+    ///
+    /// [`set_mtu_discover_v4`]: Socket::set_mtu_discover_v4
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn mtu_discover_v4(&self) -> io::Result<PmtuDiscovery> {
+        unsafe {
+            getsockopt::<c_int>(self.as_raw(), sys::IPPROTO_IP, libc::IP_MTU_DISCOVER)
+                .and_then(PmtuDiscovery::from_raw)
+        }
+    }
+
+    /// Set the value of the `IP_MTU_DISCOVER` option for this socket.
+    ///
+    /// Sets the path MTU discovery mode, which determines whether the
+    /// don't-fragment flag is set on outgoing packets and how the path MTU
+    /// is used. See [`PmtuDiscovery`] for the modes.
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn set_mtu_discover_v4(&self, mode: PmtuDiscovery) -> io::Result<()> {
+        unsafe {
+            setsockopt(
+                self.as_raw(),
+                sys::IPPROTO_IP,
+                libc::IP_MTU_DISCOVER,
+                mode.to_raw(),
+            )
+        }
+    }
+
+    /// Get the value of the `IP_MTU` option for this socket.
+    ///
+    /// Returns the currently known path MTU of the connected peer. Only valid
+    /// for connected sockets.
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn mtu_v4(&self) -> io::Result<u32> {
+        unsafe {
+            getsockopt::<c_int>(self.as_raw(), sys::IPPROTO_IP, libc::IP_MTU).map(|mtu| mtu as u32)
+        }
+    }
+
     /// Set the value of the `IP_TOS` option for this socket.
     ///
     /// This value sets the type-of-service field that is used in every packet
@@ -1847,6 +1953,69 @@ impl Socket {
         }
     }
 
+    /// Get the value of the `IPV6_RECVERR` option for this socket.
+    ///
+    /// For more information about this option, see [`set_recv_err_v6`].
+    ///
+    /// [`set_recv_err_v6`]: Socket::set_recv_err_v6
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn recv_err_v6(&self) -> io::Result<bool> {
+        unsafe {
+            getsockopt::<c_int>(self.as_raw(), sys::IPPROTO_IPV6, libc::IPV6_RECVERR)
+                .map(|recv_err| recv_err != 0)
+        }
+    }
+
+    /// Set the value of the `IPV6_RECVERR` option for this socket.
+    ///
+    /// If enabled, extended errors, such as ICMPv6 messages received in
+    /// response to sent packets, are queued on the error queue of the socket.
+    /// These can be read using [`Socket::recv_error_queue`].
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn set_recv_err_v6(&self, recv_err: bool) -> io::Result<()> {
+        unsafe {
+            setsockopt(
+                self.as_raw(),
+                sys::IPPROTO_IPV6,
+                libc::IPV6_RECVERR,
+                recv_err as c_int,
+            )
+        }
+    }
+
+    /// Get the value of the `IPV6_RECVPKTINFO` option for this socket.
+    ///
+    /// For more information about this option, see [`set_recv_pktinfo_v6`].
+    ///
+    /// [`set_recv_pktinfo_v6`]: Socket::set_recv_pktinfo_v6
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn recv_pktinfo_v6(&self) -> io::Result<bool> {
+        unsafe {
+            getsockopt::<c_int>(self.as_raw(), sys::IPPROTO_IPV6, libc::IPV6_RECVPKTINFO)
+                .map(|pktinfo| pktinfo != 0)
+        }
+    }
+
+    /// Set the value of the `IPV6_RECVPKTINFO` option for this socket.
+    ///
+    /// If enabled, an `IPV6_PKTINFO` control message is received with each
+    /// packet, containing the destination address of the packet and the
+    /// interface it was received on. See [`ControlMessage::PktInfoV6`] and
+    /// [`Socket::recv_from_with_dst`].
+    ///
+    /// [`ControlMessage::PktInfoV6`]: crate::ControlMessage::PktInfoV6
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn set_recv_pktinfo_v6(&self, pktinfo: bool) -> io::Result<()> {
+        unsafe {
+            setsockopt(
+                self.as_raw(),
+                sys::IPPROTO_IPV6,
+                libc::IPV6_RECVPKTINFO,
+                pktinfo as c_int,
+            )
+        }
+    }
+
     /// Get the value of the `IPV6_MULTICAST_IF` option for this socket.
     ///
     /// For more information about this option, see [`set_multicast_if_v6`].
@@ -1926,6 +2095,116 @@ impl Socket {
         }
     }
 
+    /// Get the value of the `IPV6_MTU_DISCOVER` option for this socket.
+    ///
+    /// For more information about this option, see [`set_mtu_discover_v6`].
+    ///
+    /// [`set_mtu_discover_v6`]: Socket::set_mtu_discover_v6
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn mtu_discover_v6(&self) -> io::Result<PmtuDiscovery> {
+        unsafe {
+            getsockopt::<c_int>(self.as_raw(), sys::IPPROTO_IPV6, libc::IPV6_MTU_DISCOVER)
+                .and_then(PmtuDiscovery::from_raw)
+        }
+    }
+
+    /// Set the value of the `IPV6_MTU_DISCOVER` option for this socket.
+    ///
+    /// Sets the path MTU discovery mode, which determines whether packets
+    /// larger than the path MTU are fragmented locally. See [`PmtuDiscovery`]
+    /// for the modes.
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn set_mtu_discover_v6(&self, mode: PmtuDiscovery) -> io::Result<()> {
+        unsafe {
+            setsockopt(
+                self.as_raw(),
+                sys::IPPROTO_IPV6,
+                libc::IPV6_MTU_DISCOVER,
+                mode.to_raw(),
+            )
+        }
+    }
+
+    /// Get the value of the `IPV6_MTU` option for this socket.
+    ///
+    /// Returns the currently known path MTU of the connected peer. Only valid
+    /// for connected sockets.
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn mtu_v6(&self) -> io::Result<u32> {
+        unsafe {
+            getsockopt::<c_int>(self.as_raw(), sys::IPPROTO_IPV6, libc::IPV6_MTU)
+                .map(|mtu| mtu as u32)
+        }
+    }
+
+    /// Get the value of the `IPV6_DONTFRAG` option for this socket.
+    ///
+    /// For more information about this option, see [`set_dontfrag_v6`].
+    ///
+    /// [`set_dontfrag_v6`]: Socket::set_dontfrag_v6
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn dontfrag_v6(&self) -> io::Result<bool> {
+        unsafe {
+            getsockopt::<c_int>(self.as_raw(), sys::IPPROTO_IPV6, libc::IPV6_DONTFRAG)
+                .map(|dontfrag| dontfrag != 0)
+        }
+    }
+
+    /// Set the value of the `IPV6_DONTFRAG` option for this socket.
+    ///
+    /// If enabled, packets larger than the path MTU are not fragmented but
+    /// the send fails with `EMSGSIZE` instead. Combined with
+    /// [`set_recv_path_mtu_v6`] the path MTU is reported using a
+    /// [`ControlMessage::PathMtuV6`] message.
+    ///
+    /// [`set_recv_path_mtu_v6`]: Socket::set_recv_path_mtu_v6
+    /// [`ControlMessage::PathMtuV6`]: crate::ControlMessage::PathMtuV6
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn set_dontfrag_v6(&self, dontfrag: bool) -> io::Result<()> {
+        unsafe {
+            setsockopt(
+                self.as_raw(),
+                sys::IPPROTO_IPV6,
+                libc::IPV6_DONTFRAG,
+                dontfrag as c_int,
+            )
+        }
+    }
+
+    /// Get the value of the `IPV6_RECVPATHMTU` option for this socket.
+    ///
+    /// For more information about this option, see [`set_recv_path_mtu_v6`].
+    ///
+    /// [`set_recv_path_mtu_v6`]: Socket::set_recv_path_mtu_v6
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn recv_path_mtu_v6(&self) -> io::Result<bool> {
+        unsafe {
+            getsockopt::<c_int>(self.as_raw(), sys::IPPROTO_IPV6, libc::IPV6_RECVPATHMTU)
+                .map(|recv_path_mtu| recv_path_mtu != 0)
+        }
+    }
+
+    /// Set the value of the `IPV6_RECVPATHMTU` option for this socket.
+    ///
+    /// If enabled, the path MTU is reported when a send fails because the
+    /// packet is larger than the path MTU (with [`set_dontfrag_v6`] enabled).
+    /// The next receive then returns no data, but only a
+    /// [`ControlMessage::PathMtuV6`] message.
+    ///
+    /// [`set_dontfrag_v6`]: Socket::set_dontfrag_v6
+    /// [`ControlMessage::PathMtuV6`]: crate::ControlMessage::PathMtuV6
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn set_recv_path_mtu_v6(&self, recv_path_mtu: bool) -> io::Result<()> {
+        unsafe {
+            setsockopt(
+                self.as_raw(),
+                sys::IPPROTO_IPV6,
+                libc::IPV6_RECVPATHMTU,
+                recv_path_mtu as c_int,
+            )
+        }
+    }
+
     /// Get the value of the `IPV6_V6ONLY` option for this socket.
     ///
     /// For more information about this option, see [`set_only_v6`].
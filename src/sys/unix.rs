@@ -12,6 +12,8 @@ use std::ffi::OsStr;
 use std::io::IoSlice;
 use std::marker::PhantomData;
 use std::mem::{self, size_of, MaybeUninit};
+#[cfg(all(feature = "all", target_os = "linux"))]
+use std::net::IpAddr;
 use std::net::Shutdown;
 use std::net::{Ipv4Addr, Ipv6Addr};
 #[cfg(all(
@@ -98,6 +100,10 @@ pub(crate) use libc::IPPROTO_SCTP;
 ))]
 pub(crate) use libc::IPPROTO_UDPLITE;
 pub(crate) use libc::{IPPROTO_ICMP, IPPROTO_ICMPV6, IPPROTO_TCP, IPPROTO_UDP};
+#[cfg(all(feature = "all", target_os = "linux"))]
+pub(crate) use libc::{
+    NETLINK_GENERIC, NETLINK_KOBJECT_UEVENT, NETLINK_NETFILTER, NETLINK_ROUTE, NETLINK_SOCK_DIAG,
+};
 // Used in `SockAddr`.
 #[cfg(all(feature = "all", any(target_os = "freebsd", target_os = "openbsd")))]
 pub(crate) use libc::IPPROTO_DIVERT;
@@ -397,6 +403,11 @@ impl Domain {
     /// Domain for low-level VSOCK interface, corresponding to `AF_VSOCK`.
     #[cfg(all(feature = "all", any(target_os = "android", target_os = "linux")))]
     pub const VSOCK: Domain = Domain(libc::AF_VSOCK);
+
+    /// Domain for communication with the kernel, corresponding to
+    /// `AF_NETLINK`.
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub const NETLINK: Domain = Domain(libc::AF_NETLINK);
 }
 
 impl_debug!(
@@ -408,6 +419,8 @@ impl_debug!(
     libc::AF_PACKET,
     #[cfg(any(target_os = "android", target_os = "linux"))]
     libc::AF_VSOCK,
+    #[cfg(target_os = "linux")]
+    libc::AF_NETLINK,
     libc::AF_UNSPEC, // = 0.
 );
 
@@ -562,6 +575,18 @@ impl RecvFlags {
         self.0 & libc::MSG_OOB != 0
     }
 
+    /// Check if the control messages were truncated.
+    ///
+    /// This is set when the control buffer was too small to hold all control
+    /// messages the OS wanted to pass along, see [`MsgHdrMut::with_control`].
+    ///
+    /// On Unix this corresponds to the `MSG_CTRUNC` flag.
+    ///
+    /// [`MsgHdrMut::with_control`]: crate::MsgHdrMut::with_control
+    pub const fn is_control_truncated(self) -> bool {
+        self.0 & libc::MSG_CTRUNC != 0
+    }
+
     /// Check if the confirm flag is set.
     ///
     /// This is used by SocketCAN to indicate a frame was sent via the
@@ -598,6 +623,7 @@ impl std::fmt::Debug for RecvFlags {
         s.field("is_out_of_band", &self.is_out_of_band());
         #[cfg(not(target_os = "espidf"))]
         s.field("is_truncated", &self.is_truncated());
+        s.field("is_control_truncated", &self.is_control_truncated());
         #[cfg(all(feature = "all", any(target_os = "android", target_os = "linux")))]
         s.field("is_confirm", &self.is_confirm());
         #[cfg(all(
@@ -764,6 +790,93 @@ impl SockAddr {
         }
     }
 
+    /// Constructs a `SockAddr` with the family `AF_PACKET` and the provided
+    /// interface index, protocol, hardware type, packet type and hardware
+    /// address.
+    ///
+    /// `protocol` is the Ethernet protocol (`ETH_P_*`) in native byte order,
+    /// `hatype` the `ARPHRD_*` hardware type and `pkttype` the `PACKET_*`
+    /// packet type. For binding and sending only `interface`, `protocol` and
+    /// (for sending) `hwaddr` are used, the other fields can be zero.
+    ///
+    /// Returns an error if `hwaddr` is longer than 8 bytes.
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn link_layer(
+        interface: u32,
+        protocol: u16,
+        hatype: u16,
+        pkttype: u8,
+        hwaddr: &[u8],
+    ) -> io::Result<SockAddr> {
+        let mut storage = SockAddrStorage::zeroed();
+        {
+            // SAFETY: sockaddr_ll is one of the sockaddr_* types defined by this platform.
+            let storage = unsafe { storage.view_as::<libc::sockaddr_ll>() };
+            if hwaddr.len() > storage.sll_addr.len() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "hardware address too long",
+                ));
+            }
+            storage.sll_family = libc::AF_PACKET as sa_family_t;
+            storage.sll_protocol = protocol.to_be();
+            storage.sll_ifindex = interface as c_int;
+            storage.sll_hatype = hatype;
+            storage.sll_pkttype = pkttype;
+            storage.sll_halen = hwaddr.len() as u8;
+            storage.sll_addr[..hwaddr.len()].copy_from_slice(hwaddr);
+        }
+        Ok(unsafe { SockAddr::new(storage, mem::size_of::<libc::sockaddr_ll>() as socklen_t) })
+    }
+
+    /// Returns this address as link-layer address if it is in the `AF_PACKET`
+    /// family, otherwise return `None`.
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn as_link_layer(&self) -> Option<LinkLayerAddr> {
+        if self.family() == libc::AF_PACKET as sa_family_t {
+            // Safety: if the ss_family field is AF_PACKET then storage must be a sockaddr_ll.
+            let addr = unsafe { &*(self.as_ptr() as *const libc::sockaddr_ll) };
+            Some(LinkLayerAddr::from_sockaddr_ll(addr))
+        } else {
+            None
+        }
+    }
+
+    /// Constructs a `SockAddr` with the family `AF_NETLINK` and the provided
+    /// port id and multicast groups.
+    ///
+    /// A port id of zero lets the kernel assign one when binding, or refers to
+    /// the kernel itself when sending. `groups` is a bitmask of the multicast
+    /// groups (1 to 32) to join, see [`Socket::netlink_add_membership`] for
+    /// other groups.
+    ///
+    /// [`Socket::netlink_add_membership`]: crate::Socket::netlink_add_membership
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn netlink(port_id: u32, groups: u32) -> SockAddr {
+        let mut storage = SockAddrStorage::zeroed();
+        {
+            // SAFETY: sockaddr_nl is one of the sockaddr_* types defined by this platform.
+            let storage = unsafe { storage.view_as::<libc::sockaddr_nl>() };
+            storage.nl_family = libc::AF_NETLINK as sa_family_t;
+            storage.nl_pid = port_id;
+            storage.nl_groups = groups;
+        }
+        unsafe { SockAddr::new(storage, mem::size_of::<libc::sockaddr_nl>() as socklen_t) }
+    }
+
+    /// Returns this address netlink port id and multicast groups if it is in
+    /// the `AF_NETLINK` family, otherwise return `None`.
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn as_netlink(&self) -> Option<(u32, u32)> {
+        if self.family() == libc::AF_NETLINK as sa_family_t {
+            // Safety: if the ss_family field is AF_NETLINK then storage must be a sockaddr_nl.
+            let addr = unsafe { &*(self.as_ptr() as *const libc::sockaddr_nl) };
+            Some((addr.nl_pid, addr.nl_groups))
+        } else {
+            None
+        }
+    }
+
     /// Returns true if this address is an unnamed address from the `AF_UNIX` family (for local
     /// interprocess communication), false otherwise.
     pub fn is_unnamed(&self) -> bool {
@@ -934,6 +1047,26 @@ pub(crate) fn poll_connect(socket: &crate::Socket, timeout: Duration) -> io::Res
     }
 }
 
+/// Wait up to `timeout` (forever if `None`) for `fd` to become readable.
+#[cfg(all(feature = "all", target_os = "linux"))]
+pub(crate) fn poll_readable(fd: RawSocket, timeout: Option<Duration>) -> io::Result<()> {
+    let timeout = timeout.map_or(-1, |timeout| {
+        // Round up to not return before the timeout expired.
+        let timeout = (timeout.as_nanos() + 999_999) / 1_000_000;
+        timeout.min(c_int::MAX as u128) as c_int
+    });
+    let mut pollfd = libc::pollfd {
+        fd,
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    match syscall!(poll(&mut pollfd, 1, timeout)) {
+        Ok(_) => Ok(()),
+        Err(ref err) if err.kind() == io::ErrorKind::Interrupted => Ok(()),
+        Err(err) => Err(err),
+    }
+}
+
 pub(crate) fn listen(fd: RawSocket, backlog: c_int) -> io::Result<()> {
     syscall!(listen(fd, backlog)).map(|_| ())
 }
@@ -1137,6 +1270,141 @@ pub(crate) fn sendmsg(fd: RawSocket, msg: &MsgHdr<'_, '_, '_>, flags: c_int) ->
     syscall!(sendmsg(fd, &msg.inner, flags)).map(|n| n as usize)
 }
 
+// Not defined in libc.
+#[cfg(all(feature = "all", target_os = "linux"))]
+const SO_EE_CODE_ZEROCOPY_COPIED: u8 = 1;
+#[cfg(all(feature = "all", target_os = "linux"))]
+const PACKET_IGNORE_OUTGOING: c_int = 23;
+#[cfg(all(feature = "all", target_os = "linux"))]
+const PACKET_FANOUT_DATA: c_int = 22;
+#[cfg(all(feature = "all", target_os = "linux"))]
+const TCP_MD5SIG_FLAG_PREFIX: u8 = 0x1;
+#[cfg(all(feature = "all", target_os = "linux"))]
+const TCP_MD5SIG_FLAG_IFINDEX: u8 = 0x2;
+#[cfg(all(feature = "all", target_os = "linux"))]
+const TCP_AO_ADD_KEY: c_int = 38;
+#[cfg(all(feature = "all", target_os = "linux"))]
+const TCP_AO_DEL_KEY: c_int = 39;
+#[cfg(all(feature = "all", target_os = "linux"))]
+const TCP_AO_INFO: c_int = 40;
+#[cfg(all(feature = "all", target_os = "linux"))]
+const TCP_AO_GET_KEYS: c_int = 41;
+#[cfg(all(feature = "all", target_os = "linux"))]
+const TCP_AO_MAXKEYLEN: usize = 80;
+#[cfg(all(feature = "all", target_os = "linux"))]
+const TCP_AO_KEYF_IFINDEX: u8 = 1 << 0;
+#[cfg(all(feature = "all", target_os = "linux"))]
+const TCP_AO_KEYF_EXCLUDE_OPT: u8 = 1 << 1;
+
+/// Receive a message from the error queue, returning the number of bytes read
+/// into `buf`, the original destination address, the extended error and
+/// timestamps (if any).
+#[cfg(all(feature = "all", target_os = "linux"))]
+fn recv_errqueue(
+    fd: RawSocket,
+    buf: &mut [MaybeUninit<u8>],
+) -> io::Result<(
+    usize,
+    SockAddr,
+    Option<crate::ExtendedError>,
+    Option<crate::Timestamps>,
+)> {
+    let mut bufs = [crate::MaybeUninitSlice::new(buf)];
+    // Large enough for `SCM_TIMESTAMPING` (64 bytes), `IP(V6)_RECVERR` (64),
+    // `SCM_TIMESTAMPING_PKTINFO` (24) and `SCM_TIMESTAMPING_OPT_STATS`
+    // (variable, a few hundred bytes).
+    let mut control = [MaybeUninit::uninit(); 1024];
+    let mut msg = MsgHdrMut::new()
+        .with_buffers(&mut bufs)
+        .with_control(&mut control);
+    // SAFETY: `recvmsg` initialises the address storage and we set the
+    // length manually.
+    let (n, addr) = unsafe {
+        SockAddr::try_init(|storage, len| {
+            msg.inner.msg_name = storage.cast();
+            msg.inner.msg_namelen = *len;
+            let n = recvmsg(fd, &mut msg, libc::MSG_ERRQUEUE)?;
+            // Set the correct address length.
+            *len = msg.inner.msg_namelen;
+            Ok(n)
+        })?
+    };
+    if msg.flags().is_control_truncated() {
+        // The message is already removed from the queue, but we can't return
+        // the (possibly) truncated information.
+        return Err(control_truncated());
+    }
+    let mut timestamps = None;
+    let mut err = None;
+    for cmsg in msg.control_messages() {
+        match cmsg {
+            crate::ControlMessage::Timestamping(ts) => timestamps = Some(ts),
+            crate::ControlMessage::ExtendedError(e) => err = Some(e),
+            _ => {}
+        }
+    }
+    Ok((n, addr, err, timestamps))
+}
+
+/// Returns the error to return if the control messages were truncated.
+#[cfg(all(feature = "all", target_os = "linux"))]
+fn control_truncated() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "control messages truncated")
+}
+
+/// Returns the error to return for an unexpected message from the error
+/// queue, e.g. an ICMP error while expecting a timestamp.
+#[cfg(all(feature = "all", target_os = "linux"))]
+fn unexpected_error_queue_message(err: Option<crate::ExtendedError>) -> io::Error {
+    match err {
+        Some(err) if err.errno() != 0 => err.error(),
+        _ => io::Error::new(
+            io::ErrorKind::InvalidData,
+            "received unexpected message from error queue",
+        ),
+    }
+}
+
+/// Returns the `sock_fprog` for `filters`, which must outlive its use.
+#[cfg(all(feature = "all", any(target_os = "linux", target_os = "android")))]
+fn sock_fprog(filters: &[SockFilter]) -> libc::sock_fprog {
+    libc::sock_fprog {
+        len: filters.len() as u16,
+        // SAFETY: this is safe due to `repr(transparent)`.
+        filter: filters.as_ptr() as *mut _,
+    }
+}
+
+/// Get a socket option of variable length, starting with a buffer of
+/// `capacity` bytes and growing it if the kernel returns `ERANGE`.
+#[cfg(all(feature = "all", target_os = "linux"))]
+fn getsockopt_vec(fd: RawSocket, level: c_int, opt: c_int, capacity: usize) -> io::Result<Vec<u8>> {
+    let mut buf: Vec<u8> = Vec::with_capacity(capacity);
+    loop {
+        let mut len = buf.capacity() as libc::socklen_t;
+        match syscall!(getsockopt(
+            fd,
+            level,
+            opt,
+            buf.as_mut_ptr().cast(),
+            &mut len
+        )) {
+            Ok(_) => {
+                // SAFETY: the kernel initialised `len` bytes.
+                unsafe { buf.set_len(len as usize) };
+                return Ok(buf);
+            }
+            // The kernel sets `len` to the required size.
+            Err(ref err)
+                if err.raw_os_error() == Some(libc::ERANGE) && len as usize > buf.capacity() =>
+            {
+                buf.reserve(len as usize);
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
 /// Wrapper around `getsockopt` to deal with platform specific timeouts.
 pub(crate) fn timeout_opt(fd: RawSocket, opt: c_int, val: c_int) -> io::Result<Option<Duration>> {
     unsafe { getsockopt(fd, opt, val).map(from_timeval) }
@@ -1525,448 +1793,452 @@ impl crate::Socket {
         .map(|_| ())
     }
 
-    /// Sets `SO_NOSIGPIPE` on the socket.
-    #[cfg(all(
-        feature = "all",
-        any(
-            target_os = "ios",
-            target_os = "visionos",
-            target_os = "macos",
-            target_os = "tvos",
-            target_os = "watchos",
-        )
-    ))]
-    pub fn set_nosigpipe(&self, nosigpipe: bool) -> io::Result<()> {
-        self._set_nosigpipe(nosigpipe)
+    /// Get the value of the `SO_PEERCRED` option on this socket.
+    ///
+    /// Returns the credentials of the peer process at the time it called
+    /// `connect(2)` or `socketpair(2)`. Only supported on connected Unix
+    /// sockets.
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn peer_cred(&self) -> io::Result<UnixCredentials> {
+        unsafe {
+            getsockopt::<libc::ucred>(self.as_raw(), libc::SOL_SOCKET, libc::SO_PEERCRED)
+                .map(|creds| UnixCredentials::new(creds.pid as u32, creds.uid, creds.gid))
+        }
     }
 
-    #[cfg(any(
-        target_os = "ios",
-        target_os = "visionos",
-        target_os = "macos",
-        target_os = "tvos",
-        target_os = "watchos",
-    ))]
-    pub(crate) fn _set_nosigpipe(&self, nosigpipe: bool) -> io::Result<()> {
-        unsafe {
-            setsockopt(
-                self.as_raw(),
-                libc::SOL_SOCKET,
-                libc::SO_NOSIGPIPE,
-                nosigpipe as c_int,
-            )
+    /// Get the value of the `SO_PEERGROUPS` option on this socket.
+    ///
+    /// Returns the supplementary groups of the peer process at the time it
+    /// called `connect(2)` or `socketpair(2)`. Only supported on connected
+    /// Unix sockets.
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn peer_groups(&self) -> io::Result<Vec<u32>> {
+        let buf = getsockopt_vec(self.as_raw(), libc::SOL_SOCKET, libc::SO_PEERGROUPS, 64)?;
+        let groups = buf
+            .chunks_exact(size_of::<libc::gid_t>())
+            .map(|gid| libc::gid_t::from_ne_bytes(gid.try_into().unwrap()))
+            .collect();
+        Ok(groups)
+    }
+
+    /// Get the value of the `SO_PEERSEC` option on this socket.
+    ///
+    /// Returns the security context (label) of the peer as determined by the
+    /// active Linux Security Module, e.g. SELinux. Returns an error
+    /// (`ENOPROTOOPT`) if no LSM supporting this is active.
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn peer_security(&self) -> io::Result<Vec<u8>> {
+        let mut buf = getsockopt_vec(self.as_raw(), libc::SOL_SOCKET, libc::SO_PEERSEC, 64)?;
+        // Some LSMs include a terminating null byte.
+        if buf.last() == Some(&0) {
+            let _ = buf.pop();
         }
+        Ok(buf)
     }
 
-    /// Gets the value of the `TCP_MAXSEG` option on this socket.
+    /// Get the value of the `SO_PEERPIDFD` option on this socket.
     ///
-    /// For more information about this option, see [`set_tcp_mss`].
+    /// Returns a pidfd (see `pidfd_open(2)`) referring to the peer process.
+    /// Unlike the process id returned by [`Socket::peer_cred`] this can't be
+    /// reused by another process. Requires Linux 6.5 or later.
     ///
-    /// [`set_tcp_mss`]: crate::Socket::set_tcp_mss
-    #[cfg(all(feature = "all", not(target_os = "redox")))]
-    pub fn tcp_mss(&self) -> io::Result<u32> {
+    /// [`Socket::peer_cred`]: crate::Socket::peer_cred
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn peer_pidfd(&self) -> io::Result<OwnedFd> {
         unsafe {
-            getsockopt::<c_int>(self.as_raw(), libc::IPPROTO_TCP, libc::TCP_MAXSEG)
-                .map(|mss| mss as u32)
+            getsockopt::<c_int>(self.as_raw(), libc::SOL_SOCKET, libc::SO_PEERPIDFD)
+                .map(|fd| OwnedFd::from_raw_fd(fd))
         }
     }
 
-    /// Sets the value of the `TCP_MAXSEG` option on this socket.
+    /// Join the netlink multicast group `group` using the
+    /// `NETLINK_ADD_MEMBERSHIP` option on this socket.
     ///
-    /// The `TCP_MAXSEG` option denotes the TCP Maximum Segment Size and is only
-    /// available on TCP sockets.
-    #[cfg(all(feature = "all", not(target_os = "redox")))]
-    pub fn set_tcp_mss(&self, mss: u32) -> io::Result<()> {
+    /// Unlike the groups bitmask passed to [`SockAddr::netlink`] this supports
+    /// all groups, not just the first 32.
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn netlink_add_membership(&self, group: u32) -> io::Result<()> {
         unsafe {
             setsockopt(
                 self.as_raw(),
-                libc::IPPROTO_TCP,
-                libc::TCP_MAXSEG,
-                mss as c_int,
+                libc::SOL_NETLINK,
+                libc::NETLINK_ADD_MEMBERSHIP,
+                group,
             )
         }
     }
 
-    /// Returns `true` if `listen(2)` was called on this socket by checking the
-    /// `SO_ACCEPTCONN` option on this socket.
-    #[cfg(all(
-        feature = "all",
-        any(
-            target_os = "aix",
-            target_os = "android",
-            target_os = "freebsd",
-            target_os = "fuchsia",
-            target_os = "linux",
-            target_os = "cygwin",
-        )
-    ))]
-    pub fn is_listener(&self) -> io::Result<bool> {
+    /// Leave the netlink multicast group `group` using the
+    /// `NETLINK_DROP_MEMBERSHIP` option on this socket.
+    ///
+    /// For more information about this option, see
+    /// [`netlink_add_membership`].
+    ///
+    /// [`netlink_add_membership`]: crate::Socket::netlink_add_membership
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn netlink_drop_membership(&self, group: u32) -> io::Result<()> {
         unsafe {
-            getsockopt::<c_int>(self.as_raw(), libc::SOL_SOCKET, libc::SO_ACCEPTCONN)
-                .map(|v| v != 0)
+            setsockopt(
+                self.as_raw(),
+                libc::SOL_NETLINK,
+                libc::NETLINK_DROP_MEMBERSHIP,
+                group,
+            )
         }
     }
 
-    /// Returns the [`Domain`] of this socket by checking the `SO_DOMAIN` option
-    /// on this socket.
-    #[cfg(all(
-        feature = "all",
-        any(
-            target_os = "android",
-            // TODO: add FreeBSD.
-            // target_os = "freebsd",
-            target_os = "fuchsia",
-            target_os = "linux",
-        )
-    ))]
-    pub fn domain(&self) -> io::Result<Domain> {
-        unsafe { getsockopt::<c_int>(self.as_raw(), libc::SOL_SOCKET, libc::SO_DOMAIN).map(Domain) }
+    /// Get the value of the `NETLINK_EXT_ACK` option on this socket.
+    ///
+    /// For more information about this option, see [`set_netlink_ext_ack`].
+    ///
+    /// [`set_netlink_ext_ack`]: crate::Socket::set_netlink_ext_ack
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn netlink_ext_ack(&self) -> io::Result<bool> {
+        unsafe {
+            getsockopt::<c_int>(self.as_raw(), libc::SOL_NETLINK, libc::NETLINK_EXT_ACK)
+                .map(|ext_ack| ext_ack != 0)
+        }
     }
 
-    /// Returns the [`Protocol`] of this socket by checking the `SO_PROTOCOL`
-    /// option on this socket.
-    #[cfg(all(
-        feature = "all",
-        any(
-            target_os = "android",
-            target_os = "freebsd",
-            target_os = "fuchsia",
-            target_os = "linux",
-        )
-    ))]
-    pub fn protocol(&self) -> io::Result<Option<Protocol>> {
+    /// Set the value of the `NETLINK_EXT_ACK` option on this socket.
+    ///
+    /// If enabled, the kernel includes extended acknowledgement attributes,
+    /// such as a human readable error message, in `NLMSG_ERROR` messages.
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn set_netlink_ext_ack(&self, ext_ack: bool) -> io::Result<()> {
         unsafe {
-            getsockopt::<c_int>(self.as_raw(), libc::SOL_SOCKET, libc::SO_PROTOCOL).map(|v| match v
-            {
-                0 => None,
-                p => Some(Protocol(p)),
-            })
+            setsockopt(
+                self.as_raw(),
+                libc::SOL_NETLINK,
+                libc::NETLINK_EXT_ACK,
+                ext_ack as c_int,
+            )
         }
     }
 
-    /// Gets the value for the `SO_MARK` option on this socket.
+    /// Get the value of the `NETLINK_GET_STRICT_CHK` option on this socket.
     ///
-    /// This value gets the socket mark field for each packet sent through
-    /// this socket.
+    /// For more information about this option, see [`set_netlink_get_strict_chk`].
     ///
-    /// On Linux this function requires the `CAP_NET_ADMIN` capability.
-    #[cfg(all(
-        feature = "all",
-        any(target_os = "android", target_os = "fuchsia", target_os = "linux")
-    ))]
-    pub fn mark(&self) -> io::Result<u32> {
+    /// [`set_netlink_get_strict_chk`]: crate::Socket::set_netlink_get_strict_chk
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn netlink_get_strict_chk(&self) -> io::Result<bool> {
         unsafe {
-            getsockopt::<c_int>(self.as_raw(), libc::SOL_SOCKET, libc::SO_MARK)
-                .map(|mark| mark as u32)
+            getsockopt::<c_int>(
+                self.as_raw(),
+                libc::SOL_NETLINK,
+                libc::NETLINK_GET_STRICT_CHK,
+            )
+            .map(|get_strict_chk| get_strict_chk != 0)
         }
     }
 
-    /// Sets the value for the `SO_MARK` option on this socket.
-    ///
-    /// This value sets the socket mark field for each packet sent through
-    /// this socket. Changing the mark can be used for mark-based routing
-    /// without netfilter or for packet filtering.
+    /// Set the value of the `NETLINK_GET_STRICT_CHK` option on this socket.
     ///
-    /// On Linux this function requires the `CAP_NET_ADMIN` capability.
-    #[cfg(all(
-        feature = "all",
-        any(target_os = "android", target_os = "fuchsia", target_os = "linux")
-    ))]
-    pub fn set_mark(&self, mark: u32) -> io::Result<()> {
+    /// If enabled, the kernel strictly validates the header and attributes of
+    /// dump requests, rather than silently ignoring unknown data.
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn set_netlink_get_strict_chk(&self, get_strict_chk: bool) -> io::Result<()> {
         unsafe {
-            setsockopt::<c_int>(
+            setsockopt(
                 self.as_raw(),
-                libc::SOL_SOCKET,
-                libc::SO_MARK,
-                mark as c_int,
+                libc::SOL_NETLINK,
+                libc::NETLINK_GET_STRICT_CHK,
+                get_strict_chk as c_int,
             )
         }
     }
 
-    /// Get the value of the `TCP_CORK` option on this socket.
+    /// Get the value of the `NETLINK_NO_ENOBUFS` option on this socket.
     ///
-    /// For more information about this option, see [`set_tcp_cork`].
+    /// For more information about this option, see [`set_netlink_no_enobufs`].
     ///
-    /// [`set_tcp_cork`]: crate::Socket::set_tcp_cork
-    #[cfg(all(
-        feature = "all",
-        any(target_os = "android", target_os = "fuchsia", target_os = "linux")
-    ))]
-    pub fn tcp_cork(&self) -> io::Result<bool> {
+    /// [`set_netlink_no_enobufs`]: crate::Socket::set_netlink_no_enobufs
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn netlink_no_enobufs(&self) -> io::Result<bool> {
         unsafe {
-            getsockopt::<Bool>(self.as_raw(), libc::IPPROTO_TCP, libc::TCP_CORK)
-                .map(|cork| cork != 0)
+            getsockopt::<c_int>(self.as_raw(), libc::SOL_NETLINK, libc::NETLINK_NO_ENOBUFS)
+                .map(|no_enobufs| no_enobufs != 0)
         }
     }
 
-    /// Set the value of the `TCP_CORK` option on this socket.
+    /// Set the value of the `NETLINK_NO_ENOBUFS` option on this socket.
     ///
-    /// If set, don't send out partial frames. All queued partial frames are
-    /// sent when the option is cleared again. There is a 200 millisecond ceiling on
-    /// the time for which output is corked by `TCP_CORK`. If this ceiling is reached,
-    /// then queued data is automatically transmitted.
-    #[cfg(all(
-        feature = "all",
-        any(target_os = "android", target_os = "fuchsia", target_os = "linux")
-    ))]
-    pub fn set_tcp_cork(&self, cork: bool) -> io::Result<()> {
+    /// If enabled, the kernel no longer reports `ENOBUFS` errors when the
+    /// receive buffer overflows and messages are dropped.
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn set_netlink_no_enobufs(&self, no_enobufs: bool) -> io::Result<()> {
         unsafe {
             setsockopt(
                 self.as_raw(),
-                libc::IPPROTO_TCP,
-                libc::TCP_CORK,
-                cork as c_int,
+                libc::SOL_NETLINK,
+                libc::NETLINK_NO_ENOBUFS,
+                no_enobufs as c_int,
             )
         }
     }
 
-    /// Get the value of the `TCP_QUICKACK` option on this socket.
+    /// Get the value of the `NETLINK_LISTEN_ALL_NSID` option on this socket.
     ///
-    /// For more information about this option, see [`set_tcp_quickack`].
+    /// For more information about this option, see [`set_netlink_listen_all_nsid`].
     ///
-    /// [`set_tcp_quickack`]: crate::Socket::set_tcp_quickack
-    #[cfg(all(
-        feature = "all",
-        any(
-            target_os = "android",
-            target_os = "fuchsia",
-            target_os = "linux",
-            target_os = "cygwin",
-        )
-    ))]
-    pub fn tcp_quickack(&self) -> io::Result<bool> {
+    /// [`set_netlink_listen_all_nsid`]: crate::Socket::set_netlink_listen_all_nsid
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn netlink_listen_all_nsid(&self) -> io::Result<bool> {
         unsafe {
-            getsockopt::<Bool>(self.as_raw(), libc::IPPROTO_TCP, libc::TCP_QUICKACK)
-                .map(|quickack| quickack != 0)
+            getsockopt::<c_int>(
+                self.as_raw(),
+                libc::SOL_NETLINK,
+                libc::NETLINK_LISTEN_ALL_NSID,
+            )
+            .map(|listen_all_nsid| listen_all_nsid != 0)
         }
     }
 
-    /// Set the value of the `TCP_QUICKACK` option on this socket.
+    /// Set the value of the `NETLINK_LISTEN_ALL_NSID` option on this socket.
     ///
-    /// If set, acks are sent immediately, rather than delayed if needed in accordance to normal
-    /// TCP operation. This flag is not permanent, it only enables a switch to or from quickack mode.
-    /// Subsequent operation of the TCP protocol will once again enter/leave quickack mode depending on
-    /// internal protocol processing and factors such as delayed ack timeouts occurring and data transfer.
-    #[cfg(all(
-        feature = "all",
-        any(
-            target_os = "android",
-            target_os = "fuchsia",
-            target_os = "linux",
-            target_os = "cygwin",
-        )
-    ))]
-    pub fn set_tcp_quickack(&self, quickack: bool) -> io::Result<()> {
+    /// If enabled, the socket receives multicast messages from all network
+    /// namespaces that have an id assigned in the namespace of the socket.
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn set_netlink_listen_all_nsid(&self, listen_all_nsid: bool) -> io::Result<()> {
         unsafe {
             setsockopt(
                 self.as_raw(),
-                libc::IPPROTO_TCP,
-                libc::TCP_QUICKACK,
-                quickack as c_int,
+                libc::SOL_NETLINK,
+                libc::NETLINK_LISTEN_ALL_NSID,
+                listen_all_nsid as c_int,
             )
         }
     }
 
-    /// Get the value of the `TCP_THIN_LINEAR_TIMEOUTS` option on this socket.
+    /// Add a packet membership to the interface with index `interface`
+    /// using the `PACKET_ADD_MEMBERSHIP` option on this socket.
     ///
-    /// For more information about this option, see [`set_tcp_thin_linear_timeouts`].
-    ///
-    /// [`set_tcp_thin_linear_timeouts`]: crate::Socket::set_tcp_thin_linear_timeouts
-    #[cfg(all(
-        feature = "all",
-        any(target_os = "android", target_os = "fuchsia", target_os = "linux")
-    ))]
-    pub fn tcp_thin_linear_timeouts(&self) -> io::Result<bool> {
+    /// The membership is removed when the socket is closed.
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn packet_add_membership(
+        &self,
+        interface: u32,
+        membership: PacketMembership<'_>,
+    ) -> io::Result<()> {
+        let mreq = membership.to_mreq(interface)?;
         unsafe {
-            getsockopt::<Bool>(
+            setsockopt(
                 self.as_raw(),
-                libc::IPPROTO_TCP,
-                libc::TCP_THIN_LINEAR_TIMEOUTS,
+                libc::SOL_PACKET,
+                libc::PACKET_ADD_MEMBERSHIP,
+                mreq,
             )
-            .map(|timeouts| timeouts != 0)
         }
     }
 
-    /// Set the value of the `TCP_THIN_LINEAR_TIMEOUTS` option on this socket.
+    /// Remove a packet membership from the interface with index `interface`
+    /// using the `PACKET_DROP_MEMBERSHIP` option on this socket.
     ///
-    /// If set, the kernel will dynamically detect a thin-stream connection if there are less than four packets in flight.
-    /// With less than four packets in flight the normal TCP fast retransmission will not be effective.
-    /// The kernel will modify the retransmission to avoid the very high latencies that thin stream suffer because of exponential backoff.
-    #[cfg(all(
-        feature = "all",
-        any(target_os = "android", target_os = "fuchsia", target_os = "linux")
-    ))]
-    pub fn set_tcp_thin_linear_timeouts(&self, timeouts: bool) -> io::Result<()> {
+    /// For more information about this option, see [`packet_add_membership`].
+    ///
+    /// [`packet_add_membership`]: crate::Socket::packet_add_membership
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn packet_drop_membership(
+        &self,
+        interface: u32,
+        membership: PacketMembership<'_>,
+    ) -> io::Result<()> {
+        let mreq = membership.to_mreq(interface)?;
         unsafe {
             setsockopt(
                 self.as_raw(),
-                libc::IPPROTO_TCP,
-                libc::TCP_THIN_LINEAR_TIMEOUTS,
-                timeouts as c_int,
+                libc::SOL_PACKET,
+                libc::PACKET_DROP_MEMBERSHIP,
+                mreq,
             )
         }
     }
 
-    /// Get the value of the `TCP_NOTSENT_LOWAT` option on this socket.
+    /// Get the value of the `PACKET_AUXDATA` option on this socket.
     ///
-    /// For more information about this option, see [`set_tcp_notsent_lowat`].
+    /// For more information about this option, see [`set_packet_auxdata`].
     ///
-    /// [`set_tcp_notsent_lowat`]: crate::Socket::set_tcp_notsent_lowat
-    #[cfg(all(feature = "all", any(target_os = "android", target_os = "linux")))]
-    pub fn tcp_notsent_lowat(&self) -> io::Result<u32> {
+    /// [`set_packet_auxdata`]: crate::Socket::set_packet_auxdata
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn packet_auxdata(&self) -> io::Result<bool> {
         unsafe {
-            getsockopt::<c_int>(self.as_raw(), libc::IPPROTO_TCP, libc::TCP_NOTSENT_LOWAT)
-                .map(|lowat| lowat as u32)
+            getsockopt::<c_int>(self.as_raw(), libc::SOL_PACKET, libc::PACKET_AUXDATA)
+                .map(|auxdata| auxdata != 0)
         }
     }
 
-    /// Set the value of the `TCP_NOTSENT_LOWAT` option on this socket.
+    /// Set the value of the `PACKET_AUXDATA` option on this socket.
     ///
-    /// If set the kernel will limit the amount of _unsent_ data in the sendbuffer.
-    /// This differs from `set_send_buffer_size` which limits the sum of unsent and unacknowledged data.
-    #[cfg(all(feature = "all", any(target_os = "android", target_os = "linux")))]
-    pub fn set_tcp_notsent_lowat(&self, lowat: u32) -> io::Result<()> {
+    /// If enabled, every received packet includes a `PACKET_AUXDATA` control
+    /// message, see [`ControlMessage::PacketAuxData`].
+    ///
+    /// [`ControlMessage::PacketAuxData`]: crate::ControlMessage::PacketAuxData
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn set_packet_auxdata(&self, auxdata: bool) -> io::Result<()> {
         unsafe {
             setsockopt(
                 self.as_raw(),
-                libc::IPPROTO_TCP,
-                libc::TCP_NOTSENT_LOWAT,
-                lowat as c_int,
+                libc::SOL_PACKET,
+                libc::PACKET_AUXDATA,
+                auxdata as c_int,
             )
         }
     }
 
-    /// Gets the value for the `SO_BINDTODEVICE` option on this socket.
+    /// Get the value of the `PACKET_STATISTICS` option on this socket.
     ///
-    /// This value gets the socket binded device's interface name.
-    #[cfg(all(
-        feature = "all",
-        any(target_os = "android", target_os = "fuchsia", target_os = "linux")
-    ))]
-    pub fn device(&self) -> io::Result<Option<Vec<u8>>> {
-        // TODO: replace with `MaybeUninit::uninit_array` once stable.
-        let mut buf: [MaybeUninit<u8>; libc::IFNAMSIZ] =
-            unsafe { MaybeUninit::uninit().assume_init() };
-        let mut len = buf.len() as libc::socklen_t;
+    /// Returns the number of packets received and dropped since the previous
+    /// call, i.e. reading the statistics resets the counters.
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn packet_statistics(&self) -> io::Result<PacketStatistics> {
+        let mut stats = libc::tpacket_stats_v3 {
+            tp_packets: 0,
+            tp_drops: 0,
+            tp_freeze_q_cnt: 0,
+        };
+        let mut len = size_of::<libc::tpacket_stats_v3>() as libc::socklen_t;
         syscall!(getsockopt(
             self.as_raw(),
-            libc::SOL_SOCKET,
-            libc::SO_BINDTODEVICE,
-            buf.as_mut_ptr().cast(),
+            libc::SOL_PACKET,
+            libc::PACKET_STATISTICS,
+            ptr::addr_of_mut!(stats).cast(),
             &mut len,
         ))?;
-        if len == 0 {
-            Ok(None)
-        } else {
-            let buf = &buf[..len as usize - 1];
-            // TODO: use `MaybeUninit::slice_assume_init_ref` once stable.
-            Ok(Some(unsafe { &*(buf as *const [_] as *const [u8]) }.into()))
-        }
+        Ok(PacketStatistics {
+            packets: stats.tp_packets,
+            drops: stats.tp_drops,
+            // Only returned for `TPACKET_V3` rings.
+            freeze_queue_count: (len as usize == size_of::<libc::tpacket_stats_v3>())
+                .then_some(stats.tp_freeze_q_cnt),
+        })
     }
 
-    /// Sets the value for the `SO_BINDTODEVICE` option on this socket.
+    /// Get the value of the `PACKET_IGNORE_OUTGOING` option on this socket.
     ///
-    /// If a socket is bound to an interface, only packets received from that
-    /// particular interface are processed by the socket. Note that this only
-    /// works for some socket types, particularly `AF_INET` sockets.
+    /// For more information about this option, see [`set_packet_ignore_outgoing`].
     ///
-    /// If `interface` is `None` or an empty string it removes the binding.
-    #[cfg(all(
-        feature = "all",
-        any(target_os = "android", target_os = "fuchsia", target_os = "linux")
-    ))]
-    pub fn bind_device(&self, interface: Option<&[u8]>) -> io::Result<()> {
-        let (value, len) = if let Some(interface) = interface {
-            (interface.as_ptr(), interface.len())
-        } else {
-            (ptr::null(), 0)
-        };
-        syscall!(setsockopt(
-            self.as_raw(),
-            libc::SOL_SOCKET,
-            libc::SO_BINDTODEVICE,
-            value.cast(),
-            len as libc::socklen_t,
-        ))
-        .map(|_| ())
+    /// [`set_packet_ignore_outgoing`]: crate::Socket::set_packet_ignore_outgoing
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn packet_ignore_outgoing(&self) -> io::Result<bool> {
+        unsafe {
+            getsockopt::<c_int>(self.as_raw(), libc::SOL_PACKET, PACKET_IGNORE_OUTGOING)
+                .map(|ignore_outgoing| ignore_outgoing != 0)
+        }
     }
 
-    /// Sets the value for the `SO_SETFIB` option on this socket.
+    /// Set the value of the `PACKET_IGNORE_OUTGOING` option on this socket.
     ///
-    /// Bind socket to the specified forwarding table (VRF) on a FreeBSD.
-    #[cfg(all(feature = "all", target_os = "freebsd"))]
-    pub fn set_fib(&self, fib: u32) -> io::Result<()> {
-        syscall!(setsockopt(
-            self.as_raw(),
-            libc::SOL_SOCKET,
-            libc::SO_SETFIB,
-            (&fib as *const u32).cast(),
-            mem::size_of::<u32>() as libc::socklen_t,
-        ))
-        .map(|_| ())
+    /// If enabled, packets sent by this host (`PACKET_OUTGOING`) are not
+    /// received on this socket. Requires Linux 4.20 or later.
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn set_packet_ignore_outgoing(&self, ignore_outgoing: bool) -> io::Result<()> {
+        unsafe {
+            setsockopt(
+                self.as_raw(),
+                libc::SOL_PACKET,
+                PACKET_IGNORE_OUTGOING,
+                ignore_outgoing as c_int,
+            )
+        }
     }
 
-    /// Sets the value for `IP_BOUND_IF` or `SO_BINDTOIFINDEX` option on this socket.
+    /// Get the value of the `PACKET_QDISC_BYPASS` option on this socket.
     ///
-    /// If a socket is bound to an interface, only packets received from that
-    /// particular interface are processed by the socket.
+    /// For more information about this option, see [`set_packet_qdisc_bypass`].
     ///
-    /// If `interface` is `None`, the binding is removed. If the `interface`
-    /// index is not valid, an error is returned.
+    /// [`set_packet_qdisc_bypass`]: crate::Socket::set_packet_qdisc_bypass
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn packet_qdisc_bypass(&self) -> io::Result<bool> {
+        unsafe {
+            getsockopt::<c_int>(self.as_raw(), libc::SOL_PACKET, libc::PACKET_QDISC_BYPASS)
+                .map(|qdisc_bypass| qdisc_bypass != 0)
+        }
+    }
+
+    /// Set the value of the `PACKET_QDISC_BYPASS` option on this socket.
     ///
-    /// One can use [`libc::if_nametoindex`] to convert an interface alias to an
-    /// index.
-    #[cfg(all(
-        feature = "all",
-        any(
-            target_os = "ios",
-            target_os = "visionos",
-            target_os = "macos",
-            target_os = "tvos",
-            target_os = "watchos",
-            target_os = "illumos",
-            target_os = "solaris",
-            target_os = "linux",
-            target_os = "android",
-        )
-    ))]
-    pub fn bind_device_by_index_v4(&self, interface: Option<NonZeroU32>) -> io::Result<()> {
-        let index = interface.map_or(0, NonZeroU32::get);
+    /// If enabled, packets sent on this socket bypass the traffic control
+    /// (qdisc) layer of the kernel and are passed to the driver directly.
+    /// This improves performance, but packets are dropped if the transmit
+    /// queue of the device is full.
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn set_packet_qdisc_bypass(&self, qdisc_bypass: bool) -> io::Result<()> {
+        unsafe {
+            setsockopt(
+                self.as_raw(),
+                libc::SOL_PACKET,
+                libc::PACKET_QDISC_BYPASS,
+                qdisc_bypass as c_int,
+            )
+        }
+    }
 
-        #[cfg(any(
-            target_os = "ios",
-            target_os = "visionos",
-            target_os = "macos",
-            target_os = "tvos",
-            target_os = "watchos",
-            target_os = "illumos",
-            target_os = "solaris",
-        ))]
+    /// Get the value of the `PACKET_FANOUT` option on this socket.
+    ///
+    /// Returns `None` if the socket is not part of a fanout group. Note that
+    /// the kernel reports this the same way as group 0 using
+    /// [`FanoutMode::Hash`] without any flags.
+    ///
+    /// For more information about this option, see [`set_packet_fanout`].
+    ///
+    /// [`set_packet_fanout`]: crate::Socket::set_packet_fanout
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn packet_fanout(&self) -> io::Result<Option<PacketFanout>> {
         unsafe {
-            setsockopt(self.as_raw(), IPPROTO_IP, libc::IP_BOUND_IF, index)
+            getsockopt::<c_int>(self.as_raw(), libc::SOL_PACKET, libc::PACKET_FANOUT)
+                .map(|value| (value != 0).then(|| PacketFanout::from_raw(value)))
         }
+    }
 
-        #[cfg(any(target_os = "linux", target_os = "android",))]
+    /// Set the value of the `PACKET_FANOUT` option on this socket.
+    ///
+    /// This adds the socket to the fanout group, creating it if needed. The
+    /// kernel distributes the packets received by the sockets in a group
+    /// among them, see [`FanoutMode`]. The socket must be bound before
+    /// joining a group and can't leave it again, other than by closing it.
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn set_packet_fanout(&self, fanout: PacketFanout) -> io::Result<()> {
         unsafe {
             setsockopt(
                 self.as_raw(),
-                libc::SOL_SOCKET,
-                libc::SO_BINDTOIFINDEX,
-                index,
+                libc::SOL_PACKET,
+                libc::PACKET_FANOUT,
+                fanout.to_raw(),
             )
         }
     }
 
-    /// Sets the value for `IPV6_BOUND_IF` or `SO_BINDTOIFINDEX` option on this socket.
-    ///
-    /// If a socket is bound to an interface, only packets received from that
-    /// particular interface are processed by the socket.
+    /// Attach a classic BPF program to the fanout group of this socket,
+    /// `PACKET_FANOUT_DATA`.
     ///
-    /// If `interface` is `None`, the binding is removed. If the `interface`
-    /// index is not valid, an error is returned.
+    /// The program returns the index of the socket in the group to deliver
+    /// the packet to. The group must use [`FanoutMode::Cbpf`].
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn attach_packet_fanout_filter(&self, filters: &[SockFilter]) -> io::Result<()> {
+        let prog = sock_fprog(filters);
+
+        unsafe { setsockopt(self.as_raw(), libc::SOL_PACKET, PACKET_FANOUT_DATA, prog) }
+    }
+
+    /// Attach a loaded eBPF program (of type `BPF_PROG_TYPE_SOCKET_FILTER`) to
+    /// the fanout group of this socket, `PACKET_FANOUT_DATA`.
     ///
-    /// One can use [`libc::if_nametoindex`] to convert an interface alias to an
-    /// index.
+    /// The program returns the index of the socket in the group to deliver
+    /// the packet to. The group must use [`FanoutMode::Ebpf`].
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn attach_packet_fanout_ebpf(&self, program: BorrowedFd<'_>) -> io::Result<()> {
+        unsafe {
+            setsockopt(
+                self.as_raw(),
+                libc::SOL_PACKET,
+                PACKET_FANOUT_DATA,
+                program.as_raw_fd(),
+            )
+        }
+    }
+
+    /// Sets `SO_NOSIGPIPE` on the socket.
     #[cfg(all(
         feature = "all",
         any(
@@ -1975,945 +2247,4665 @@ impl crate::Socket {
             target_os = "macos",
             target_os = "tvos",
             target_os = "watchos",
-            target_os = "illumos",
-            target_os = "solaris",
-            target_os = "linux",
-            target_os = "android",
         )
     ))]
-    pub fn bind_device_by_index_v6(&self, interface: Option<NonZeroU32>) -> io::Result<()> {
-        let index = interface.map_or(0, NonZeroU32::get);
+    pub fn set_nosigpipe(&self, nosigpipe: bool) -> io::Result<()> {
+        self._set_nosigpipe(nosigpipe)
+    }
 
-        #[cfg(any(
-            target_os = "ios",
-            target_os = "visionos",
-            target_os = "macos",
-            target_os = "tvos",
-            target_os = "watchos",
-            target_os = "illumos",
-            target_os = "solaris",
-        ))]
+    #[cfg(any(
+        target_os = "ios",
+        target_os = "visionos",
+        target_os = "macos",
+        target_os = "tvos",
+        target_os = "watchos",
+    ))]
+    pub(crate) fn _set_nosigpipe(&self, nosigpipe: bool) -> io::Result<()> {
         unsafe {
-            setsockopt(self.as_raw(), IPPROTO_IPV6, libc::IPV6_BOUND_IF, index)
+            setsockopt(
+                self.as_raw(),
+                libc::SOL_SOCKET,
+                libc::SO_NOSIGPIPE,
+                nosigpipe as c_int,
+            )
         }
+    }
 
-        #[cfg(any(target_os = "linux", target_os = "android",))]
+    /// Gets the value of the `TCP_MAXSEG` option on this socket.
+    ///
+    /// For more information about this option, see [`set_tcp_mss`].
+    ///
+    /// [`set_tcp_mss`]: crate::Socket::set_tcp_mss
+    #[cfg(all(feature = "all", not(target_os = "redox")))]
+    pub fn tcp_mss(&self) -> io::Result<u32> {
+        unsafe {
+            getsockopt::<c_int>(self.as_raw(), libc::IPPROTO_TCP, libc::TCP_MAXSEG)
+                .map(|mss| mss as u32)
+        }
+    }
+
+    /// Sets the value of the `TCP_MAXSEG` option on this socket.
+    ///
+    /// The `TCP_MAXSEG` option denotes the TCP Maximum Segment Size and is only
+    /// available on TCP sockets.
+    #[cfg(all(feature = "all", not(target_os = "redox")))]
+    pub fn set_tcp_mss(&self, mss: u32) -> io::Result<()> {
         unsafe {
             setsockopt(
                 self.as_raw(),
-                libc::SOL_SOCKET,
-                libc::SO_BINDTOIFINDEX,
-                index,
+                libc::IPPROTO_TCP,
+                libc::TCP_MAXSEG,
+                mss as c_int,
             )
         }
     }
 
-    /// Gets the value for `IP_BOUND_IF` or `SO_BINDTOIFINDEX` option on this
-    /// socket, i.e. the index for the interface to which the socket is bound.
+    /// Get the value of the `TCP_INFO` option on this socket.
     ///
-    /// Returns `None` if the socket is not bound to any interface, otherwise
-    /// returns an interface index.
+    /// Returns information about the TCP connection, see [`TcpInfo`].
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn tcp_info(&self) -> io::Result<TcpInfo> {
+        let mut info = TcpInfo {
+            // SAFETY: all zeroes is valid for `tcp_info`.
+            info: unsafe { mem::zeroed() },
+            len: 0,
+        };
+        let mut len = size_of::<TcpInfoRaw>() as libc::socklen_t;
+        syscall!(getsockopt(
+            self.as_raw(),
+            libc::IPPROTO_TCP,
+            libc::TCP_INFO,
+            ptr::addr_of_mut!(info.info).cast(),
+            &mut len,
+        ))?;
+        if (len as usize) < size_of::<libc::tcp_info>() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "tcp_info truncated",
+            ));
+        }
+        info.len = len as usize;
+        Ok(info)
+    }
+
+    /// Returns `true` if `listen(2)` was called on this socket by checking the
+    /// `SO_ACCEPTCONN` option on this socket.
     #[cfg(all(
         feature = "all",
         any(
-            target_os = "ios",
-            target_os = "visionos",
-            target_os = "macos",
-            target_os = "tvos",
-            target_os = "watchos",
-            target_os = "illumos",
-            target_os = "solaris",
-            target_os = "linux",
+            target_os = "aix",
             target_os = "android",
+            target_os = "freebsd",
+            target_os = "fuchsia",
+            target_os = "linux",
+            target_os = "cygwin",
         )
     ))]
-    pub fn device_index_v4(&self) -> io::Result<Option<NonZeroU32>> {
-        #[cfg(any(
-            target_os = "ios",
-            target_os = "visionos",
-            target_os = "macos",
-            target_os = "tvos",
-            target_os = "watchos",
-            target_os = "illumos",
-            target_os = "solaris",
-        ))]
-        let index =
-            unsafe { getsockopt::<libc::c_uint>(self.as_raw(), IPPROTO_IP, libc::IP_BOUND_IF)? };
-
-        #[cfg(any(target_os = "linux", target_os = "android",))]
-        let index = unsafe {
-            getsockopt::<libc::c_uint>(self.as_raw(), libc::SOL_SOCKET, libc::SO_BINDTOIFINDEX)?
-        };
-
-        Ok(NonZeroU32::new(index))
+    pub fn is_listener(&self) -> io::Result<bool> {
+        unsafe {
+            getsockopt::<c_int>(self.as_raw(), libc::SOL_SOCKET, libc::SO_ACCEPTCONN)
+                .map(|v| v != 0)
+        }
     }
 
-    /// Gets the value for `IPV6_BOUND_IF` or `SO_BINDTOIFINDEX` option on this
-    /// socket, i.e. the index for the interface to which the socket is bound.
-    ///
-    /// Returns `None` if the socket is not bound to any interface, otherwise
-    /// returns an interface index.
+    /// Returns the [`Domain`] of this socket by checking the `SO_DOMAIN` option
+    /// on this socket.
     #[cfg(all(
         feature = "all",
         any(
-            target_os = "ios",
-            target_os = "visionos",
-            target_os = "macos",
-            target_os = "tvos",
-            target_os = "watchos",
-            target_os = "illumos",
-            target_os = "solaris",
-            target_os = "linux",
             target_os = "android",
+            // TODO: add FreeBSD.
+            // target_os = "freebsd",
+            target_os = "fuchsia",
+            target_os = "linux",
         )
     ))]
-    pub fn device_index_v6(&self) -> io::Result<Option<NonZeroU32>> {
-        #[cfg(any(
-            target_os = "ios",
-            target_os = "visionos",
-            target_os = "macos",
-            target_os = "tvos",
-            target_os = "watchos",
-            target_os = "illumos",
-            target_os = "solaris",
-        ))]
-        let index = unsafe {
-            getsockopt::<libc::c_uint>(self.as_raw(), IPPROTO_IPV6, libc::IPV6_BOUND_IF)?
-        };
-
-        #[cfg(any(target_os = "linux", target_os = "android",))]
-        let index = unsafe {
-            getsockopt::<libc::c_uint>(self.as_raw(), libc::SOL_SOCKET, libc::SO_BINDTOIFINDEX)?
-        };
+    pub fn domain(&self) -> io::Result<Domain> {
+        unsafe { getsockopt::<c_int>(self.as_raw(), libc::SOL_SOCKET, libc::SO_DOMAIN).map(Domain) }
+    }
 
-        Ok(NonZeroU32::new(index))
+    /// Returns the [`Protocol`] of this socket by checking the `SO_PROTOCOL`
+    /// option on this socket.
+    #[cfg(all(
+        feature = "all",
+        any(
+            target_os = "android",
+            target_os = "freebsd",
+            target_os = "fuchsia",
+            target_os = "linux",
+        )
+    ))]
+    pub fn protocol(&self) -> io::Result<Option<Protocol>> {
+        unsafe {
+            getsockopt::<c_int>(self.as_raw(), libc::SOL_SOCKET, libc::SO_PROTOCOL).map(|v| match v
+            {
+                0 => None,
+                p => Some(Protocol(p)),
+            })
+        }
     }
 
-    /// Get the value of the `SO_INCOMING_CPU` option on this socket.
+    /// Gets the value for the `SO_MARK` option on this socket.
     ///
-    /// For more information about this option, see [`set_cpu_affinity`].
+    /// This value gets the socket mark field for each packet sent through
+    /// this socket.
     ///
-    /// [`set_cpu_affinity`]: crate::Socket::set_cpu_affinity
-    #[cfg(all(feature = "all", target_os = "linux"))]
-    pub fn cpu_affinity(&self) -> io::Result<usize> {
+    /// On Linux this function requires the `CAP_NET_ADMIN` capability.
+    #[cfg(all(
+        feature = "all",
+        any(target_os = "android", target_os = "fuchsia", target_os = "linux")
+    ))]
+    pub fn mark(&self) -> io::Result<u32> {
         unsafe {
-            getsockopt::<c_int>(self.as_raw(), libc::SOL_SOCKET, libc::SO_INCOMING_CPU)
-                .map(|cpu| cpu as usize)
+            getsockopt::<c_int>(self.as_raw(), libc::SOL_SOCKET, libc::SO_MARK)
+                .map(|mark| mark as u32)
         }
     }
 
-    /// Set value for the `SO_INCOMING_CPU` option on this socket.
+    /// Sets the value for the `SO_MARK` option on this socket.
     ///
-    /// Sets the CPU affinity of the socket.
-    #[cfg(all(feature = "all", target_os = "linux"))]
-    pub fn set_cpu_affinity(&self, cpu: usize) -> io::Result<()> {
+    /// This value sets the socket mark field for each packet sent through
+    /// this socket. Changing the mark can be used for mark-based routing
+    /// without netfilter or for packet filtering.
+    ///
+    /// On Linux this function requires the `CAP_NET_ADMIN` capability.
+    #[cfg(all(
+        feature = "all",
+        any(target_os = "android", target_os = "fuchsia", target_os = "linux")
+    ))]
+    pub fn set_mark(&self, mark: u32) -> io::Result<()> {
         unsafe {
-            setsockopt(
+            setsockopt::<c_int>(
                 self.as_raw(),
                 libc::SOL_SOCKET,
-                libc::SO_INCOMING_CPU,
-                cpu as c_int,
+                libc::SO_MARK,
+                mark as c_int,
             )
         }
     }
 
-    /// Get the value of the `SO_REUSEPORT` option on this socket.
+    /// Get the value of the `TCP_CORK` option on this socket.
     ///
-    /// For more information about this option, see [`set_reuse_port`].
+    /// For more information about this option, see [`set_tcp_cork`].
     ///
-    /// [`set_reuse_port`]: crate::Socket::set_reuse_port
+    /// [`set_tcp_cork`]: crate::Socket::set_tcp_cork
     #[cfg(all(
         feature = "all",
-        not(any(target_os = "solaris", target_os = "illumos", target_os = "cygwin"))
+        any(target_os = "android", target_os = "fuchsia", target_os = "linux")
     ))]
-    pub fn reuse_port(&self) -> io::Result<bool> {
+    pub fn tcp_cork(&self) -> io::Result<bool> {
         unsafe {
-            getsockopt::<c_int>(self.as_raw(), libc::SOL_SOCKET, libc::SO_REUSEPORT)
-                .map(|reuse| reuse != 0)
+            getsockopt::<Bool>(self.as_raw(), libc::IPPROTO_TCP, libc::TCP_CORK)
+                .map(|cork| cork != 0)
         }
     }
 
-    /// Set value for the `SO_REUSEPORT` option on this socket.
+    /// Set the value of the `TCP_CORK` option on this socket.
     ///
-    /// This indicates that further calls to `bind` may allow reuse of local
-    /// addresses. For IPv4 sockets this means that a socket may bind even when
-    /// there's a socket already listening on this port.
+    /// If set, don't send out partial frames. All queued partial frames are
+    /// sent when the option is cleared again. There is a 200 millisecond ceiling on
+    /// the time for which output is corked by `TCP_CORK`. If this ceiling is reached,
+    /// then queued data is automatically transmitted.
     #[cfg(all(
         feature = "all",
-        not(any(target_os = "solaris", target_os = "illumos", target_os = "cygwin"))
+        any(target_os = "android", target_os = "fuchsia", target_os = "linux")
     ))]
-    pub fn set_reuse_port(&self, reuse: bool) -> io::Result<()> {
+    pub fn set_tcp_cork(&self, cork: bool) -> io::Result<()> {
         unsafe {
             setsockopt(
                 self.as_raw(),
-                libc::SOL_SOCKET,
-                libc::SO_REUSEPORT,
-                reuse as c_int,
+                libc::IPPROTO_TCP,
+                libc::TCP_CORK,
+                cork as c_int,
             )
         }
     }
 
-    /// Get the value of the `SO_REUSEPORT_LB` option on this socket.
+    /// Get the value of the `TCP_QUICKACK` option on this socket.
     ///
-    /// For more information about this option, see [`set_reuse_port_lb`].
+    /// For more information about this option, see [`set_tcp_quickack`].
     ///
-    /// [`set_reuse_port_lb`]: crate::Socket::set_reuse_port_lb
-    #[cfg(all(feature = "all", target_os = "freebsd"))]
-    pub fn reuse_port_lb(&self) -> io::Result<bool> {
-        unsafe {
-            getsockopt::<c_int>(self.as_raw(), libc::SOL_SOCKET, libc::SO_REUSEPORT_LB)
-                .map(|reuse| reuse != 0)
-        }
-    }
-
-    /// Set value for the `SO_REUSEPORT_LB` option on this socket.
+    /// [`set_tcp_quickack`]: crate::Socket::set_tcp_quickack
+    #[cfg(all(
+        feature = "all",
+        any(
+            target_os = "android",
+            target_os = "fuchsia",
+            target_os = "linux",
+            target_os = "cygwin",
+        )
+    ))]
+    pub fn tcp_quickack(&self) -> io::Result<bool> {
+        unsafe {
+            getsockopt::<Bool>(self.as_raw(), libc::IPPROTO_TCP, libc::TCP_QUICKACK)
+                .map(|quickack| quickack != 0)
+        }
+    }
+
+    /// Set the value of the `TCP_QUICKACK` option on this socket.
     ///
-    /// This allows multiple programs or threads to bind to the same port and
-    /// incoming connections will be load balanced using a hash function.
-    #[cfg(all(feature = "all", target_os = "freebsd"))]
-    pub fn set_reuse_port_lb(&self, reuse: bool) -> io::Result<()> {
+    /// If set, acks are sent immediately, rather than delayed if needed in accordance to normal
+    /// TCP operation. This flag is not permanent, it only enables a switch to or from quickack mode.
+    /// Subsequent operation of the TCP protocol will once again enter/leave quickack mode depending on
+    /// internal protocol processing and factors such as delayed ack timeouts occurring and data transfer.
+    #[cfg(all(
+        feature = "all",
+        any(
+            target_os = "android",
+            target_os = "fuchsia",
+            target_os = "linux",
+            target_os = "cygwin",
+        )
+    ))]
+    pub fn set_tcp_quickack(&self, quickack: bool) -> io::Result<()> {
         unsafe {
             setsockopt(
                 self.as_raw(),
-                libc::SOL_SOCKET,
-                libc::SO_REUSEPORT_LB,
-                reuse as c_int,
+                libc::IPPROTO_TCP,
+                libc::TCP_QUICKACK,
+                quickack as c_int,
             )
         }
     }
 
-    /// Get the value of the `IP_FREEBIND` option on this socket.
+    /// Get the value of the `TCP_THIN_LINEAR_TIMEOUTS` option on this socket.
     ///
-    /// For more information about this option, see [`set_freebind_v4`].
+    /// For more information about this option, see [`set_tcp_thin_linear_timeouts`].
     ///
-    /// [`set_freebind_v4`]: crate::Socket::set_freebind_v4
+    /// [`set_tcp_thin_linear_timeouts`]: crate::Socket::set_tcp_thin_linear_timeouts
     #[cfg(all(
         feature = "all",
         any(target_os = "android", target_os = "fuchsia", target_os = "linux")
     ))]
-    pub fn freebind_v4(&self) -> io::Result<bool> {
+    pub fn tcp_thin_linear_timeouts(&self) -> io::Result<bool> {
         unsafe {
-            getsockopt::<c_int>(self.as_raw(), libc::SOL_IP, libc::IP_FREEBIND)
-                .map(|freebind| freebind != 0)
+            getsockopt::<Bool>(
+                self.as_raw(),
+                libc::IPPROTO_TCP,
+                libc::TCP_THIN_LINEAR_TIMEOUTS,
+            )
+            .map(|timeouts| timeouts != 0)
         }
     }
 
-    /// Set value for the `IP_FREEBIND` option on this socket.
+    /// Set the value of the `TCP_THIN_LINEAR_TIMEOUTS` option on this socket.
     ///
-    /// If enabled, this boolean option allows binding to an IP address that is
-    /// nonlocal or does not (yet) exist.  This permits listening on a socket,
-    /// without requiring the underlying network interface or the specified
-    /// dynamic IP address to be up at the time that the application is trying
-    /// to bind to it.
+    /// If set, the kernel will dynamically detect a thin-stream connection if there are less than four packets in flight.
+    /// With less than four packets in flight the normal TCP fast retransmission will not be effective.
+    /// The kernel will modify the retransmission to avoid the very high latencies that thin stream suffer because of exponential backoff.
     #[cfg(all(
         feature = "all",
         any(target_os = "android", target_os = "fuchsia", target_os = "linux")
     ))]
-    pub fn set_freebind_v4(&self, freebind: bool) -> io::Result<()> {
+    pub fn set_tcp_thin_linear_timeouts(&self, timeouts: bool) -> io::Result<()> {
         unsafe {
             setsockopt(
                 self.as_raw(),
-                libc::SOL_IP,
-                libc::IP_FREEBIND,
-                freebind as c_int,
+                libc::IPPROTO_TCP,
+                libc::TCP_THIN_LINEAR_TIMEOUTS,
+                timeouts as c_int,
             )
         }
     }
 
-    /// Get the value of the `IPV6_FREEBIND` option on this socket.
+    /// Get the value of the `TCP_NOTSENT_LOWAT` option on this socket.
     ///
-    /// This is an IPv6 counterpart of `IP_FREEBIND` socket option on
-    /// Android/Linux. For more information about this option, see
-    /// [`set_freebind_v4`].
+    /// For more information about this option, see [`set_tcp_notsent_lowat`].
     ///
-    /// [`set_freebind_v4`]: crate::Socket::set_freebind_v4
+    /// [`set_tcp_notsent_lowat`]: crate::Socket::set_tcp_notsent_lowat
     #[cfg(all(feature = "all", any(target_os = "android", target_os = "linux")))]
-    pub fn freebind_v6(&self) -> io::Result<bool> {
+    pub fn tcp_notsent_lowat(&self) -> io::Result<u32> {
         unsafe {
-            getsockopt::<c_int>(self.as_raw(), libc::SOL_IPV6, libc::IPV6_FREEBIND)
-                .map(|freebind| freebind != 0)
+            getsockopt::<c_int>(self.as_raw(), libc::IPPROTO_TCP, libc::TCP_NOTSENT_LOWAT)
+                .map(|lowat| lowat as u32)
         }
     }
 
-    /// Set value for the `IPV6_FREEBIND` option on this socket.
-    ///
-    /// This is an IPv6 counterpart of `IP_FREEBIND` socket option on
-    /// Android/Linux. For more information about this option, see
-    /// [`set_freebind_v4`].
+    /// Set the value of the `TCP_NOTSENT_LOWAT` option on this socket.
     ///
-    /// [`set_freebind_v4`]: crate::Socket::set_freebind_v4
+    /// If set the kernel will limit the amount of _unsent_ data in the sendbuffer.
+    /// This differs from `set_send_buffer_size` which limits the sum of unsent and unacknowledged data.
+    #[cfg(all(feature = "all", any(target_os = "android", target_os = "linux")))]
+    pub fn set_tcp_notsent_lowat(&self, lowat: u32) -> io::Result<()> {
+        unsafe {
+            setsockopt(
+                self.as_raw(),
+                libc::IPPROTO_TCP,
+                libc::TCP_NOTSENT_LOWAT,
+                lowat as c_int,
+            )
+        }
+    }
+
+    /// Get the value of the `TCP_FASTOPEN` option on this socket.
     ///
-    /// # Examples
+    /// For more information about this option, see [`set_tcp_fastopen`].
     ///
-    /// On Linux:
+    /// [`set_tcp_fastopen`]: crate::Socket::set_tcp_fastopen
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn tcp_fastopen(&self) -> io::Result<u32> {
+        unsafe {
+            getsockopt::<c_int>(self.as_raw(), libc::IPPROTO_TCP, libc::TCP_FASTOPEN)
+                .map(|queue_len| queue_len as u32)
+        }
+    }
+
+    /// Set the value of the `TCP_FASTOPEN` option on this socket.
     ///
-    /// ```
-    /// use socket2::{Domain, Socket, Type};
-    /// use std::io::{self, Error, ErrorKind};
+    /// Enables TCP Fast Open (RFC 7413) on a listening socket, accepting
+    /// data in the SYN of clients that present a valid cookie. `queue_len` is
+    /// the maximum number of pending Fast Open requests, i.e. connections
+    /// accepted before the handshake completed. Must be set before calling
+    /// [`listen`].
     ///
-    /// fn enable_freebind(socket: &Socket) -> io::Result<()> {
-    ///     match socket.domain()? {
-    ///         Domain::IPV4 => socket.set_freebind_v4(true)?,
-    ///         Domain::IPV6 => socket.set_freebind_v6(true)?,
-    ///         _ => return Err(Error::new(ErrorKind::Other, "unsupported domain")),
-    ///     };
-    ///     Ok(())
-    /// }
+    /// Server side Fast Open must be enabled in the `net.ipv4.tcp_fastopen`
+    /// sysctl.
     ///
-    /// # fn main() -> io::Result<()> {
-    /// #     let socket = Socket::new(Domain::IPV6, Type::STREAM, None)?;
-    /// #     enable_freebind(&socket)
-    /// # }
-    /// ```
-    #[cfg(all(feature = "all", any(target_os = "android", target_os = "linux")))]
-    pub fn set_freebind_v6(&self, freebind: bool) -> io::Result<()> {
+    /// [`listen`]: crate::Socket::listen
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn set_tcp_fastopen(&self, queue_len: u32) -> io::Result<()> {
         unsafe {
             setsockopt(
                 self.as_raw(),
-                libc::SOL_IPV6,
-                libc::IPV6_FREEBIND,
-                freebind as c_int,
+                libc::IPPROTO_TCP,
+                libc::TCP_FASTOPEN,
+                queue_len as c_int,
             )
         }
     }
 
-    /// Copies data between a `file` and this socket using the `sendfile(2)`
-    /// system call. Because this copying is done within the kernel,
-    /// `sendfile()` is more efficient than the combination of `read(2)` and
-    /// `write(2)`, which would require transferring data to and from user
-    /// space.
+    /// Get the value of the `TCP_FASTOPEN_CONNECT` option on this socket.
     ///
-    /// Different OSs support different kinds of `file`s, see the OS
-    /// documentation for what kind of files are supported. Generally *regular*
-    /// files are supported by all OSs.
-    #[doc = man_links!(unix: sendfile(2))]
+    /// For more information about this option, see [`set_tcp_fastopen_connect`].
     ///
-    /// The `offset` is the absolute offset into the `file` to use as starting
-    /// point.
+    /// [`set_tcp_fastopen_connect`]: crate::Socket::set_tcp_fastopen_connect
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn tcp_fastopen_connect(&self) -> io::Result<bool> {
+        unsafe {
+            getsockopt::<c_int>(self.as_raw(), libc::IPPROTO_TCP, libc::TCP_FASTOPEN_CONNECT)
+                .map(|connect| connect != 0)
+        }
+    }
+
+    /// Set the value of the `TCP_FASTOPEN_CONNECT` option on this socket.
     ///
-    /// Depending on the OS this function *may* change the offset of `file`. For
-    /// the best results reset the offset of the file before using it again.
+    /// If enabled, [`connect`] doesn't send a SYN but returns immediately,
+    /// the first write on the socket then sends the SYN including the data
+    /// (and a cached Fast Open cookie, if any). This allows using Fast Open
+    /// without changing the connect logic, as an alternative to
+    /// [`connect_with_data`].
     ///
-    /// The `length` determines how many bytes to send, where a length of `None`
-    /// means it will try to send all bytes.
-    #[cfg(all(
-        feature = "all",
-        any(
-            target_os = "aix",
-            target_os = "android",
-            target_os = "freebsd",
-            target_os = "ios",
-            target_os = "visionos",
-            target_os = "linux",
-            target_os = "macos",
-            target_os = "tvos",
-            target_os = "watchos",
-        )
-    ))]
-    pub fn sendfile<F>(
-        &self,
-        file: &F,
-        offset: usize,
-        length: Option<NonZeroUsize>,
-    ) -> io::Result<usize>
-    where
-        F: AsRawFd,
-    {
-        self._sendfile(file.as_raw_fd(), offset as _, length)
+    /// Client side Fast Open must be enabled in the `net.ipv4.tcp_fastopen`
+    /// sysctl.
+    ///
+    /// [`connect`]: crate::Socket::connect
+    /// [`connect_with_data`]: crate::Socket::connect_with_data
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn set_tcp_fastopen_connect(&self, connect: bool) -> io::Result<()> {
+        unsafe {
+            setsockopt(
+                self.as_raw(),
+                libc::IPPROTO_TCP,
+                libc::TCP_FASTOPEN_CONNECT,
+                connect as c_int,
+            )
+        }
     }
 
-    #[cfg(all(
-        feature = "all",
-        any(
-            target_os = "ios",
-            target_os = "visionos",
-            target_os = "macos",
-            target_os = "tvos",
-            target_os = "watchos",
-        )
-    ))]
-    fn _sendfile(
-        &self,
-        file: RawFd,
-        offset: libc::off_t,
-        length: Option<NonZeroUsize>,
-    ) -> io::Result<usize> {
-        // On macOS `length` is value-result parameter. It determines the number
-        // of bytes to write and returns the number of bytes written.
-        let mut length = match length {
-            Some(n) => n.get() as libc::off_t,
-            // A value of `0` means send all bytes.
-            None => 0,
-        };
-        syscall!(sendfile(
-            file,
+    /// Get the value of the `TCP_FASTOPEN_KEY` option on this socket.
+    ///
+    /// Returns the primary key and, if set, the backup key. If no key was
+    /// set on the socket the keys of the network namespace are returned.
+    ///
+    /// For more information about this option, see [`set_tcp_fastopen_key`].
+    ///
+    /// [`set_tcp_fastopen_key`]: crate::Socket::set_tcp_fastopen_key
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn tcp_fastopen_key(&self) -> io::Result<([u8; 16], Option<[u8; 16]>)> {
+        let mut keys = [0; 32];
+        let mut len = keys.len() as libc::socklen_t;
+        syscall!(getsockopt(
             self.as_raw(),
-            offset,
-            &mut length,
-            ptr::null_mut(),
-            0,
-        ))
-        .map(|_| length as usize)
+            IPPROTO_TCP,
+            libc::TCP_FASTOPEN_KEY,
+            keys.as_mut_ptr().cast(),
+            &mut len,
+        ))?;
+        let mut primary = [0; 16];
+        primary.copy_from_slice(&keys[..16]);
+        let backup = (len as usize == keys.len()).then(|| {
+            let mut backup = [0; 16];
+            backup.copy_from_slice(&keys[16..]);
+            backup
+        });
+        Ok((primary, backup))
     }
 
-    #[cfg(all(feature = "all", any(target_os = "android", target_os = "linux")))]
-    fn _sendfile(
+    /// Set the value of the `TCP_FASTOPEN_KEY` option on this socket.
+    ///
+    /// Sets the keys used to generate and validate Fast Open cookies for this
+    /// listener. Cookies are generated using the `primary` key, but cookies
+    /// generated by the `backup` key are still accepted. This allows the keys
+    /// to be rotated without rejecting the cookies of existing clients: first
+    /// distribute the new key as backup key, then swap the keys.
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn set_tcp_fastopen_key(
         &self,
-        file: RawFd,
-        offset: libc::off_t,
-        length: Option<NonZeroUsize>,
-    ) -> io::Result<usize> {
-        let count = match length {
-            Some(n) => n.get() as libc::size_t,
-            // The maximum the Linux kernel will write in a single call.
-            None => 0x7ffff000, // 2,147,479,552 bytes.
+        primary: [u8; 16],
+        backup: Option<[u8; 16]>,
+    ) -> io::Result<()> {
+        let mut keys = [0; 32];
+        keys[..16].copy_from_slice(&primary);
+        let len = match backup {
+            Some(backup) => {
+                keys[16..].copy_from_slice(&backup);
+                32
+            }
+            None => 16,
         };
-        let mut offset = offset;
-        syscall!(sendfile(self.as_raw(), file, &mut offset, count)).map(|n| n as usize)
+        syscall!(setsockopt(
+            self.as_raw(),
+            IPPROTO_TCP,
+            libc::TCP_FASTOPEN_KEY,
+            keys.as_ptr().cast(),
+            len,
+        ))
+        .map(|_| ())
     }
 
-    #[cfg(all(feature = "all", target_os = "freebsd"))]
-    fn _sendfile(
-        &self,
-        file: RawFd,
-        offset: libc::off_t,
-        length: Option<NonZeroUsize>,
-    ) -> io::Result<usize> {
-        let nbytes = match length {
-            Some(n) => n.get() as libc::size_t,
-            // A value of `0` means send all bytes.
-            None => 0,
-        };
-        let mut sbytes: libc::off_t = 0;
-        syscall!(sendfile(
-            file,
-            self.as_raw(),
-            offset,
-            nbytes,
-            ptr::null_mut(),
-            &mut sbytes,
-            0,
-        ))
-        .map(|_| sbytes as usize)
+    /// Initiate a connection to `addr`, sending `buf` in the SYN using TCP
+    /// Fast Open (`MSG_FASTOPEN`).
+    ///
+    /// If a Fast Open cookie for `addr` is cached the data is sent in the SYN,
+    /// saving a round trip. Otherwise a cookie is requested and the data is
+    /// sent once the connection is established, after which later connections
+    /// can use the cookie. Returns the number of bytes queued.
+    ///
+    /// For non-blocking sockets this returns an [`io::ErrorKind::WouldBlock`]
+    /// error if no cookie is available and the connection is still in
+    /// progress, in which case no data is sent. See [`connect`] for handling
+    /// connections in progress.
+    ///
+    /// [`connect`]: crate::Socket::connect
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn connect_with_data(&self, addr: &SockAddr, buf: &[u8]) -> io::Result<usize> {
+        send_to(self.as_raw(), buf, addr, libc::MSG_FASTOPEN)
     }
 
-    #[cfg(all(feature = "all", target_os = "aix"))]
-    fn _sendfile(
-        &self,
-        file: RawFd,
-        offset: libc::off_t,
-        length: Option<NonZeroUsize>,
-    ) -> io::Result<usize> {
-        let nbytes = match length {
-            Some(n) => n.get() as i64,
-            None => -1,
-        };
-        let mut params = libc::sf_parms {
-            header_data: ptr::null_mut(),
-            header_length: 0,
-            file_descriptor: file,
-            file_size: 0,
-            file_offset: offset as u64,
-            file_bytes: nbytes,
-            trailer_data: ptr::null_mut(),
-            trailer_length: 0,
-            bytes_sent: 0,
-        };
-        // AIX doesn't support SF_REUSE, socket will be closed after successful transmission.
-        syscall!(send_file(
-            &mut self.as_raw() as *mut _,
-            &mut params as *mut _,
-            libc::SF_CLOSE as libc::c_uint,
-        ))
-        .map(|_| params.bytes_sent as usize)
+    /// Add a TCP MD5 signature (RFC 2385) key using the `TCP_MD5SIG` or
+    /// `TCP_MD5SIG_EXT` option on this socket.
+    ///
+    /// All segments to and from the peer matching the key are signed and
+    /// segments with a missing or invalid signature are dropped. For
+    /// listeners the key must be added before the peer connects, for clients
+    /// before calling [`connect`]. Adding a key for an address that already
+    /// has one replaces it.
+    ///
+    /// [`connect`]: crate::Socket::connect
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn add_tcp_md5sig(&self, key: &TcpMd5Key<'_>) -> io::Result<()> {
+        let (opt, md5sig) = key.to_raw(key.key)?;
+        unsafe { setsockopt(self.as_raw(), libc::IPPROTO_TCP, opt, md5sig) }
     }
 
-    /// Set the value of the `TCP_USER_TIMEOUT` option on this socket.
+    /// Remove a TCP MD5 signature key previously added using
+    /// [`add_tcp_md5sig`].
     ///
-    /// If set, this specifies the maximum amount of time that transmitted data may remain
-    /// unacknowledged or buffered data may remain untransmitted before TCP will forcibly close the
-    /// corresponding connection.
+    /// The key is matched on address, prefix length and interface, the key
+    /// data is ignored.
     ///
-    /// Setting `timeout` to `None` or a zero duration causes the system default timeouts to
-    /// be used. If `timeout` in milliseconds is larger than `c_uint::MAX`, the timeout is clamped
-    /// to `c_uint::MAX`. For example, when `c_uint` is a 32-bit value, this limits the timeout to
-    /// approximately 49.71 days.
-    #[cfg(all(
-        feature = "all",
-        any(
-            target_os = "android",
-            target_os = "fuchsia",
-            target_os = "linux",
-            target_os = "cygwin",
-        )
-    ))]
-    pub fn set_tcp_user_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
-        let timeout = timeout.map_or(0, |to| {
-            min(to.as_millis(), libc::c_uint::MAX as u128) as libc::c_uint
-        });
-        unsafe {
-            setsockopt(
-                self.as_raw(),
-                libc::IPPROTO_TCP,
-                libc::TCP_USER_TIMEOUT,
-                timeout,
-            )
-        }
+    /// [`add_tcp_md5sig`]: crate::Socket::add_tcp_md5sig
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn remove_tcp_md5sig(&self, key: &TcpMd5Key<'_>) -> io::Result<()> {
+        let (opt, md5sig) = key.to_raw(&[])?;
+        unsafe { setsockopt(self.as_raw(), libc::IPPROTO_TCP, opt, md5sig) }
     }
 
-    /// Get the value of the `TCP_USER_TIMEOUT` option on this socket.
+    /// Add a TCP Authentication Option (RFC 5925) key using the
+    /// `TCP_AO_ADD_KEY` option on this socket.
     ///
-    /// For more information about this option, see [`set_tcp_user_timeout`].
+    /// Like TCP MD5 signature keys, keys must be added before connecting.
+    /// Multiple keys with different send and receive ids can be added for the
+    /// same peer, which allows keys to be rotated on established connections
+    /// using [`set_tcp_ao_info`].
     ///
-    /// [`set_tcp_user_timeout`]: crate::Socket::set_tcp_user_timeout
-    #[cfg(all(
-        feature = "all",
-        any(
-            target_os = "android",
-            target_os = "fuchsia",
-            target_os = "linux",
-            target_os = "cygwin",
-        )
-    ))]
-    pub fn tcp_user_timeout(&self) -> io::Result<Option<Duration>> {
-        unsafe {
-            getsockopt::<libc::c_uint>(self.as_raw(), libc::IPPROTO_TCP, libc::TCP_USER_TIMEOUT)
-                .map(|millis| {
-                    if millis == 0 {
-                        None
-                    } else {
-                        Some(Duration::from_millis(millis as u64))
-                    }
-                })
-        }
+    /// Requires Linux 6.7 or later, built with `CONFIG_TCP_AO`.
+    ///
+    /// [`set_tcp_ao_info`]: crate::Socket::set_tcp_ao_info
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn add_tcp_ao_key(&self, key: &TcpAoKey<'_>) -> io::Result<()> {
+        let add = key.to_add()?;
+        unsafe { setsockopt(self.as_raw(), libc::IPPROTO_TCP, TCP_AO_ADD_KEY, add) }
     }
 
-    /// Attach Berkeley Packet Filter (BPF) on this socket.
+    /// Remove a TCP Authentication Option key previously added using
+    /// [`add_tcp_ao_key`], `TCP_AO_DEL_KEY`.
     ///
-    /// BPF allows a user-space program to attach a filter onto any socket
-    /// and allow or disallow certain types of data to come through the socket.
+    /// The key is matched on address, prefix length, interface and send and
+    /// receive ids, the algorithm and key data are ignored. The current key
+    /// of an established connection can't be removed.
     ///
-    /// For more information about this option, see [filter](https://www.kernel.org/doc/html/v5.12/networking/filter.html)
-    #[cfg(all(feature = "all", any(target_os = "linux", target_os = "android")))]
-    pub fn attach_filter(&self, filters: &[SockFilter]) -> io::Result<()> {
-        let prog = libc::sock_fprog {
-            len: filters.len() as u16,
-            // SAFETY: this is safe due to `repr(transparent)`.
-            filter: filters.as_ptr() as *mut _,
-        };
-
-        unsafe {
-            setsockopt(
-                self.as_raw(),
-                libc::SOL_SOCKET,
-                libc::SO_ATTACH_FILTER,
-                prog,
-            )
-        }
+    /// [`add_tcp_ao_key`]: crate::Socket::add_tcp_ao_key
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn remove_tcp_ao_key(&self, key: &TcpAoKey<'_>) -> io::Result<()> {
+        let del = key.to_del()?;
+        unsafe { setsockopt(self.as_raw(), libc::IPPROTO_TCP, TCP_AO_DEL_KEY, del) }
     }
 
-    /// Detach Berkeley Packet Filter(BPF) from this socket.
+    /// Get the value of the `TCP_AO_INFO` option on this socket.
     ///
-    /// For more information about this option, see [`attach_filter`]
+    /// For more information about this option, see [`set_tcp_ao_info`].
     ///
-    /// [`attach_filter`]: crate::Socket::attach_filter
-    #[cfg(all(feature = "all", any(target_os = "linux", target_os = "android")))]
-    pub fn detach_filter(&self) -> io::Result<()> {
-        unsafe { setsockopt(self.as_raw(), libc::SOL_SOCKET, libc::SO_DETACH_FILTER, 0) }
+    /// Returns an error with `ENOENT` if no TCP Authentication Option keys
+    /// were added to the socket.
+    ///
+    /// [`set_tcp_ao_info`]: crate::Socket::set_tcp_ao_info
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn tcp_ao_info(&self) -> io::Result<TcpAoInfo> {
+        // The kernel reads the option before filling it in and rejects
+        // non-zero reserved fields, so it can't be left uninitialised.
+        // SAFETY: all zeroes is valid for `TcpAoInfoOpt`.
+        let mut info: TcpAoInfoOpt = unsafe { mem::zeroed() };
+        let mut len = size_of::<TcpAoInfoOpt>() as libc::socklen_t;
+        syscall!(getsockopt(
+            self.as_raw(),
+            IPPROTO_TCP,
+            TCP_AO_INFO,
+            ptr::addr_of_mut!(info).cast(),
+            &mut len,
+        ))?;
+        Ok(TcpAoInfo { info })
     }
 
-    /// Gets the value for the `SO_COOKIE` option on this socket.
+    /// Set the value of the `TCP_AO_INFO` option on this socket.
     ///
-    /// The socket cookie is a unique, kernel-managed identifier tied to each socket.
-    /// Therefore, there is no corresponding `set` helper.
+    /// This selects the current key (used to sign outgoing segments) and the
+    /// RNext key (requested from the peer), and whether segments without
+    /// TCP-AO are accepted. The segment counters of `info` are ignored.
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn set_tcp_ao_info(&self, info: &TcpAoInfo) -> io::Result<()> {
+        let mut info = info.info;
+        info.flags &= tcp_ao_bit32(0) | tcp_ao_bit32(1) | tcp_ao_bit32(2) | tcp_ao_bit32(4);
+        unsafe { setsockopt(self.as_raw(), libc::IPPROTO_TCP, TCP_AO_INFO, info) }
+    }
+
+    /// Get all TCP Authentication Option keys of this socket,
+    /// `TCP_AO_GET_KEYS`.
     ///
-    /// For more information about this option, see [Linux patch](https://git.kernel.org/pub/scm/linux/kernel/git/torvalds/linux.git/commit/?id=5daab9db7b65df87da26fd8cfa695fb9546a1ddb)
+    /// Returns an empty list if no keys were added to the socket.
     #[cfg(all(feature = "all", target_os = "linux"))]
-    pub fn cookie(&self) -> io::Result<u64> {
-        unsafe { getsockopt::<libc::c_ulonglong>(self.as_raw(), libc::SOL_SOCKET, libc::SO_COOKIE) }
+    pub fn tcp_ao_keys(&self) -> io::Result<Vec<TcpAoKeyInfo>> {
+        let mut count = 8;
+        loop {
+            let mut keys = tcp_ao_keys_request(count);
+            let mut len = size_of::<TcpAoGetsockopt>() as libc::socklen_t;
+            match syscall!(getsockopt(
+                self.as_raw(),
+                IPPROTO_TCP,
+                TCP_AO_GET_KEYS,
+                keys.as_mut_ptr().cast(),
+                &mut len,
+            )) {
+                Ok(_) => {}
+                // The socket doesn't have any keys.
+                Err(ref err) if err.raw_os_error() == Some(libc::ENOENT) => return Ok(Vec::new()),
+                Err(err) => return Err(err),
+            }
+            // On return `nkeys` holds the number of keys that matched, which
+            // may be more than we had room for.
+            let matched = keys[0].nkeys as usize;
+            if matched > count {
+                count = matched;
+                continue;
+            }
+            keys.truncate(matched);
+            return keys
+                .into_iter()
+                .map(|key| TcpAoKeyInfo::from_raw(&key))
+                .collect();
+        }
     }
 
-    /// Get the value of the `IPV6_TCLASS` option for this socket.
+    /// Get the value of the `UDP_SEGMENT` option on this socket.
     ///
-    /// For more information about this option, see [`set_tclass_v6`].
+    /// For more information about this option, see [`set_udp_segment`].
     ///
-    /// [`set_tclass_v6`]: crate::Socket::set_tclass_v6
-    #[cfg(all(
-        feature = "all",
-        any(
-            target_os = "android",
-            target_os = "dragonfly",
-            target_os = "freebsd",
-            target_os = "fuchsia",
-            target_os = "linux",
-            target_os = "macos",
-            target_os = "netbsd",
-            target_os = "openbsd",
-            target_os = "cygwin",
-        )
-    ))]
-    pub fn tclass_v6(&self) -> io::Result<u32> {
+    /// [`set_udp_segment`]: crate::Socket::set_udp_segment
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn udp_segment(&self) -> io::Result<u16> {
         unsafe {
-            getsockopt::<c_int>(self.as_raw(), IPPROTO_IPV6, libc::IPV6_TCLASS)
-                .map(|tclass| tclass as u32)
+            getsockopt::<c_int>(self.as_raw(), libc::SOL_UDP, libc::UDP_SEGMENT)
+                .map(|size| size as u16)
         }
     }
 
-    /// Set the value of the `IPV6_TCLASS` option for this socket.
+    /// Set the value of the `UDP_SEGMENT` option on this socket.
     ///
-    /// Specifies the traffic class field that is used in every packets
-    /// sent from this socket.
-    #[cfg(all(
-        feature = "all",
-        any(
-            target_os = "android",
-            target_os = "dragonfly",
-            target_os = "freebsd",
-            target_os = "fuchsia",
-            target_os = "linux",
-            target_os = "macos",
-            target_os = "netbsd",
-            target_os = "openbsd",
-            target_os = "cygwin",
-        )
-    ))]
-    pub fn set_tclass_v6(&self, tclass: u32) -> io::Result<()> {
+    /// If set to a non-zero value, data passed in a single send call is split
+    /// into multiple datagrams of `size` bytes each (only the last one can be
+    /// smaller), known as generic segmentation offload (GSO). This can also be
+    /// set per message using [`ControlMessageBuilder::with_udp_segment`].
+    ///
+    /// [`ControlMessageBuilder::with_udp_segment`]: crate::ControlMessageBuilder::with_udp_segment
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn set_udp_segment(&self, size: u16) -> io::Result<()> {
         unsafe {
             setsockopt(
                 self.as_raw(),
-                IPPROTO_IPV6,
-                libc::IPV6_TCLASS,
-                tclass as c_int,
+                libc::SOL_UDP,
+                libc::UDP_SEGMENT,
+                size as c_int,
             )
         }
     }
 
-    /// Get the value of the `TCP_CONGESTION` option for this socket.
+    /// Get the value of the `UDP_GRO` option on this socket.
     ///
-    /// For more information about this option, see [`set_tcp_congestion`].
+    /// For more information about this option, see [`set_udp_gro`].
     ///
-    /// [`set_tcp_congestion`]: crate::Socket::set_tcp_congestion
-    #[cfg(all(feature = "all", any(target_os = "freebsd", target_os = "linux")))]
-    pub fn tcp_congestion(&self) -> io::Result<Vec<u8>> {
-        let mut payload: [u8; TCP_CA_NAME_MAX] = [0; TCP_CA_NAME_MAX];
-        let mut len = payload.len() as libc::socklen_t;
-        syscall!(getsockopt(
-            self.as_raw(),
-            IPPROTO_TCP,
-            libc::TCP_CONGESTION,
-            payload.as_mut_ptr().cast(),
-            &mut len,
-        ))
-        .map(|_| payload[..len as usize].to_vec())
+    /// [`set_udp_gro`]: crate::Socket::set_udp_gro
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn udp_gro(&self) -> io::Result<bool> {
+        unsafe {
+            getsockopt::<c_int>(self.as_raw(), libc::SOL_UDP, libc::UDP_GRO).map(|gro| gro != 0)
+        }
     }
 
-    /// Set the value of the `TCP_CONGESTION` option for this socket.
+    /// Set the value of the `UDP_GRO` option on this socket.
     ///
-    /// Specifies the TCP congestion control algorithm to use for this socket.
+    /// If set, the kernel may coalesce multiple datagrams of the same size
+    /// into a single receive, known as generic receive offload (GRO). The size
+    /// of the segments is passed in a [`ControlMessage::UdpGro`] message, see
+    /// [`Socket::recv_from_gro`] to receive the separate datagrams.
     ///
-    /// The value must be a valid TCP congestion control algorithm name of the
-    /// platform. For example, Linux may supports "reno", "cubic".
-    #[cfg(all(feature = "all", any(target_os = "freebsd", target_os = "linux")))]
-    pub fn set_tcp_congestion(&self, tcp_ca_name: &[u8]) -> io::Result<()> {
-        syscall!(setsockopt(
-            self.as_raw(),
-            IPPROTO_TCP,
-            libc::TCP_CONGESTION,
-            tcp_ca_name.as_ptr() as *const _,
-            tcp_ca_name.len() as libc::socklen_t,
-        ))
-        .map(|_| ())
+    /// [`ControlMessage::UdpGro`]: crate::ControlMessage::UdpGro
+    /// [`Socket::recv_from_gro`]: crate::Socket::recv_from_gro
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn set_udp_gro(&self, gro: bool) -> io::Result<()> {
+        unsafe { setsockopt(self.as_raw(), libc::SOL_UDP, libc::UDP_GRO, gro as c_int) }
     }
 
-    /// Set value for the `DCCP_SOCKOPT_SERVICE` option on this socket.
+    /// Receive a, possibly coalesced, datagram from the socket.
     ///
-    /// Sets the DCCP service. The specification mandates use of service codes.
-    /// If this socket option is not set, the socket will fall back to 0 (which
-    /// means that no meaningful service code is present). On active sockets
-    /// this is set before [`connect`]. On passive sockets up to 32 service
-    /// codes can be set before calling [`bind`]
+    /// Returns the separate datagrams (segments) that were received, see
+    /// [`Socket::set_udp_gro`]. If the datagram wasn't coalesced the returned
+    /// iterator only yields a single datagram, which is empty for a zero
+    /// length datagram.
     ///
-    /// [`connect`]: crate::Socket::connect
-    /// [`bind`]: crate::Socket::bind
+    /// [`Socket::set_udp_gro`]: crate::Socket::set_udp_gro
     #[cfg(all(feature = "all", target_os = "linux"))]
-    pub fn set_dccp_service(&self, code: u32) -> io::Result<()> {
-        unsafe {
-            setsockopt(
-                self.as_raw(),
-                libc::SOL_DCCP,
-                libc::DCCP_SOCKOPT_SERVICE,
-                code,
-            )
-        }
+    pub fn recv_from_gro<'a>(
+        &self,
+        buf: &'a mut [MaybeUninit<u8>],
+    ) -> io::Result<(GroSegments<'a>, RecvFlags, SockAddr)> {
+        let mut control = [MaybeUninit::uninit(); crate::cmsg::cmsg_space(size_of::<c_int>())];
+        let (n, flags, segment_size, addr) = {
+            let mut bufs = [crate::MaybeUninitSlice::new(buf)];
+            let mut msg = MsgHdrMut::new()
+                .with_buffers(&mut bufs)
+                .with_control(&mut control);
+            // SAFETY: `recvmsg` initialises the address storage and we set the
+            // length manually.
+            let (n, addr) = unsafe {
+                SockAddr::try_init(|storage, len| {
+                    msg.inner.msg_name = storage.cast();
+                    msg.inner.msg_namelen = *len;
+                    let n = recvmsg(self.as_raw(), &mut msg, 0)?;
+                    // Set the correct address length.
+                    *len = msg.inner.msg_namelen;
+                    Ok(n)
+                })?
+            };
+            let segment_size = msg.control_messages().find_map(|msg| match msg {
+                crate::ControlMessage::UdpGro(size) => Some(size as usize),
+                _ => None,
+            });
+            (n, msg.flags(), segment_size, addr)
+        };
+        // SAFETY: `recvmsg` initialised the first `n` bytes.
+        // TODO: use `MaybeUninit::slice_assume_init_ref` once stable.
+        let buf = unsafe { &*(&buf[..n] as *const [_] as *const [u8]) };
+        let segments = GroSegments::new(buf, segment_size.unwrap_or(n));
+        Ok((segments, flags, addr))
     }
 
-    /// Get the value of the `DCCP_SOCKOPT_SERVICE` option on this socket.
+    /// Gets the value for the `SO_BINDTODEVICE` option on this socket.
     ///
-    /// For more information about this option see [`set_dccp_service`]
-    ///
-    /// [`set_dccp_service`]: crate::Socket::set_dccp_service
-    #[cfg(all(feature = "all", target_os = "linux"))]
-    pub fn dccp_service(&self) -> io::Result<u32> {
-        unsafe { getsockopt(self.as_raw(), libc::SOL_DCCP, libc::DCCP_SOCKOPT_SERVICE) }
+    /// This value gets the socket binded device's interface name.
+    #[cfg(all(
+        feature = "all",
+        any(target_os = "android", target_os = "fuchsia", target_os = "linux")
+    ))]
+    pub fn device(&self) -> io::Result<Option<Vec<u8>>> {
+        // TODO: replace with `MaybeUninit::uninit_array` once stable.
+        let mut buf: [MaybeUninit<u8>; libc::IFNAMSIZ] =
+            unsafe { MaybeUninit::uninit().assume_init() };
+        let mut len = buf.len() as libc::socklen_t;
+        syscall!(getsockopt(
+            self.as_raw(),
+            libc::SOL_SOCKET,
+            libc::SO_BINDTODEVICE,
+            buf.as_mut_ptr().cast(),
+            &mut len,
+        ))?;
+        if len == 0 {
+            Ok(None)
+        } else {
+            let buf = &buf[..len as usize - 1];
+            // TODO: use `MaybeUninit::slice_assume_init_ref` once stable.
+            Ok(Some(unsafe { &*(buf as *const [_] as *const [u8]) }.into()))
+        }
     }
 
-    /// Set value for the `DCCP_SOCKOPT_CCID` option on this socket.
+    /// Sets the value for the `SO_BINDTODEVICE` option on this socket.
     ///
-    /// This option sets both the TX and RX CCIDs at the same time.
-    #[cfg(all(feature = "all", target_os = "linux"))]
-    pub fn set_dccp_ccid(&self, ccid: u8) -> io::Result<()> {
-        unsafe { setsockopt(self.as_raw(), libc::SOL_DCCP, libc::DCCP_SOCKOPT_CCID, ccid) }
+    /// If a socket is bound to an interface, only packets received from that
+    /// particular interface are processed by the socket. Note that this only
+    /// works for some socket types, particularly `AF_INET` sockets.
+    ///
+    /// If `interface` is `None` or an empty string it removes the binding.
+    #[cfg(all(
+        feature = "all",
+        any(target_os = "android", target_os = "fuchsia", target_os = "linux")
+    ))]
+    pub fn bind_device(&self, interface: Option<&[u8]>) -> io::Result<()> {
+        let (value, len) = if let Some(interface) = interface {
+            (interface.as_ptr(), interface.len())
+        } else {
+            (ptr::null(), 0)
+        };
+        syscall!(setsockopt(
+            self.as_raw(),
+            libc::SOL_SOCKET,
+            libc::SO_BINDTODEVICE,
+            value.cast(),
+            len as libc::socklen_t,
+        ))
+        .map(|_| ())
     }
 
-    /// Get the value of the `DCCP_SOCKOPT_TX_CCID` option on this socket.
-    ///
-    /// For more information about this option see [`set_dccp_ccid`].
+    /// Sets the value for the `SO_SETFIB` option on this socket.
     ///
-    /// [`set_dccp_ccid`]: crate::Socket::set_dccp_ccid
-    #[cfg(all(feature = "all", target_os = "linux"))]
-    pub fn dccp_tx_ccid(&self) -> io::Result<u32> {
-        unsafe { getsockopt(self.as_raw(), libc::SOL_DCCP, libc::DCCP_SOCKOPT_TX_CCID) }
+    /// Bind socket to the specified forwarding table (VRF) on a FreeBSD.
+    #[cfg(all(feature = "all", target_os = "freebsd"))]
+    pub fn set_fib(&self, fib: u32) -> io::Result<()> {
+        syscall!(setsockopt(
+            self.as_raw(),
+            libc::SOL_SOCKET,
+            libc::SO_SETFIB,
+            (&fib as *const u32).cast(),
+            mem::size_of::<u32>() as libc::socklen_t,
+        ))
+        .map(|_| ())
     }
 
-    /// Get the value of the `DCCP_SOCKOPT_RX_CCID` option on this socket.
+    /// Sets the value for `IP_BOUND_IF` or `SO_BINDTOIFINDEX` option on this socket.
     ///
-    /// For more information about this option see [`set_dccp_ccid`].
+    /// If a socket is bound to an interface, only packets received from that
+    /// particular interface are processed by the socket.
     ///
-    /// [`set_dccp_ccid`]: crate::Socket::set_dccp_ccid
-    #[cfg(all(feature = "all", target_os = "linux"))]
-    pub fn dccp_xx_ccid(&self) -> io::Result<u32> {
-        unsafe { getsockopt(self.as_raw(), libc::SOL_DCCP, libc::DCCP_SOCKOPT_RX_CCID) }
+    /// If `interface` is `None`, the binding is removed. If the `interface`
+    /// index is not valid, an error is returned.
+    ///
+    /// One can use [`libc::if_nametoindex`] to convert an interface alias to an
+    /// index.
+    #[cfg(all(
+        feature = "all",
+        any(
+            target_os = "ios",
+            target_os = "visionos",
+            target_os = "macos",
+            target_os = "tvos",
+            target_os = "watchos",
+            target_os = "illumos",
+            target_os = "solaris",
+            target_os = "linux",
+            target_os = "android",
+        )
+    ))]
+    pub fn bind_device_by_index_v4(&self, interface: Option<NonZeroU32>) -> io::Result<()> {
+        let index = interface.map_or(0, NonZeroU32::get);
+
+        #[cfg(any(
+            target_os = "ios",
+            target_os = "visionos",
+            target_os = "macos",
+            target_os = "tvos",
+            target_os = "watchos",
+            target_os = "illumos",
+            target_os = "solaris",
+        ))]
+        unsafe {
+            setsockopt(self.as_raw(), IPPROTO_IP, libc::IP_BOUND_IF, index)
+        }
+
+        #[cfg(any(target_os = "linux", target_os = "android",))]
+        unsafe {
+            setsockopt(
+                self.as_raw(),
+                libc::SOL_SOCKET,
+                libc::SO_BINDTOIFINDEX,
+                index,
+            )
+        }
     }
 
-    /// Set value for the `DCCP_SOCKOPT_SERVER_TIMEWAIT` option on this socket.
+    /// Sets the value for `IPV6_BOUND_IF` or `SO_BINDTOIFINDEX` option on this socket.
     ///
-    /// Enables a listening socket to hold timewait state when closing the
-    /// connection. This option must be set after `accept` returns.
-    #[cfg(all(feature = "all", target_os = "linux"))]
-    pub fn set_dccp_server_timewait(&self, hold_timewait: bool) -> io::Result<()> {
+    /// If a socket is bound to an interface, only packets received from that
+    /// particular interface are processed by the socket.
+    ///
+    /// If `interface` is `None`, the binding is removed. If the `interface`
+    /// index is not valid, an error is returned.
+    ///
+    /// One can use [`libc::if_nametoindex`] to convert an interface alias to an
+    /// index.
+    #[cfg(all(
+        feature = "all",
+        any(
+            target_os = "ios",
+            target_os = "visionos",
+            target_os = "macos",
+            target_os = "tvos",
+            target_os = "watchos",
+            target_os = "illumos",
+            target_os = "solaris",
+            target_os = "linux",
+            target_os = "android",
+        )
+    ))]
+    pub fn bind_device_by_index_v6(&self, interface: Option<NonZeroU32>) -> io::Result<()> {
+        let index = interface.map_or(0, NonZeroU32::get);
+
+        #[cfg(any(
+            target_os = "ios",
+            target_os = "visionos",
+            target_os = "macos",
+            target_os = "tvos",
+            target_os = "watchos",
+            target_os = "illumos",
+            target_os = "solaris",
+        ))]
+        unsafe {
+            setsockopt(self.as_raw(), IPPROTO_IPV6, libc::IPV6_BOUND_IF, index)
+        }
+
+        #[cfg(any(target_os = "linux", target_os = "android",))]
         unsafe {
             setsockopt(
                 self.as_raw(),
-                libc::SOL_DCCP,
-                libc::DCCP_SOCKOPT_SERVER_TIMEWAIT,
-                hold_timewait as c_int,
+                libc::SOL_SOCKET,
+                libc::SO_BINDTOIFINDEX,
+                index,
             )
         }
     }
 
-    /// Get the value of the `DCCP_SOCKOPT_SERVER_TIMEWAIT` option on this socket.
-    ///
-    /// For more information see [`set_dccp_server_timewait`]
-    ///
-    /// [`set_dccp_server_timewait`]: crate::Socket::set_dccp_server_timewait
-    #[cfg(all(feature = "all", target_os = "linux"))]
-    pub fn dccp_server_timewait(&self) -> io::Result<bool> {
-        unsafe {
-            getsockopt(
-                self.as_raw(),
-                libc::SOL_DCCP,
-                libc::DCCP_SOCKOPT_SERVER_TIMEWAIT,
-            )
-        }
+    /// Gets the value for `IP_BOUND_IF` or `SO_BINDTOIFINDEX` option on this
+    /// socket, i.e. the index for the interface to which the socket is bound.
+    ///
+    /// Returns `None` if the socket is not bound to any interface, otherwise
+    /// returns an interface index.
+    #[cfg(all(
+        feature = "all",
+        any(
+            target_os = "ios",
+            target_os = "visionos",
+            target_os = "macos",
+            target_os = "tvos",
+            target_os = "watchos",
+            target_os = "illumos",
+            target_os = "solaris",
+            target_os = "linux",
+            target_os = "android",
+        )
+    ))]
+    pub fn device_index_v4(&self) -> io::Result<Option<NonZeroU32>> {
+        #[cfg(any(
+            target_os = "ios",
+            target_os = "visionos",
+            target_os = "macos",
+            target_os = "tvos",
+            target_os = "watchos",
+            target_os = "illumos",
+            target_os = "solaris",
+        ))]
+        let index =
+            unsafe { getsockopt::<libc::c_uint>(self.as_raw(), IPPROTO_IP, libc::IP_BOUND_IF)? };
+
+        #[cfg(any(target_os = "linux", target_os = "android",))]
+        let index = unsafe {
+            getsockopt::<libc::c_uint>(self.as_raw(), libc::SOL_SOCKET, libc::SO_BINDTOIFINDEX)?
+        };
+
+        Ok(NonZeroU32::new(index))
+    }
+
+    /// Gets the value for `IPV6_BOUND_IF` or `SO_BINDTOIFINDEX` option on this
+    /// socket, i.e. the index for the interface to which the socket is bound.
+    ///
+    /// Returns `None` if the socket is not bound to any interface, otherwise
+    /// returns an interface index.
+    #[cfg(all(
+        feature = "all",
+        any(
+            target_os = "ios",
+            target_os = "visionos",
+            target_os = "macos",
+            target_os = "tvos",
+            target_os = "watchos",
+            target_os = "illumos",
+            target_os = "solaris",
+            target_os = "linux",
+            target_os = "android",
+        )
+    ))]
+    pub fn device_index_v6(&self) -> io::Result<Option<NonZeroU32>> {
+        #[cfg(any(
+            target_os = "ios",
+            target_os = "visionos",
+            target_os = "macos",
+            target_os = "tvos",
+            target_os = "watchos",
+            target_os = "illumos",
+            target_os = "solaris",
+        ))]
+        let index = unsafe {
+            getsockopt::<libc::c_uint>(self.as_raw(), IPPROTO_IPV6, libc::IPV6_BOUND_IF)?
+        };
+
+        #[cfg(any(target_os = "linux", target_os = "android",))]
+        let index = unsafe {
+            getsockopt::<libc::c_uint>(self.as_raw(), libc::SOL_SOCKET, libc::SO_BINDTOIFINDEX)?
+        };
+
+        Ok(NonZeroU32::new(index))
+    }
+
+    /// Get the value of the `SO_INCOMING_CPU` option on this socket.
+    ///
+    /// For more information about this option, see [`set_cpu_affinity`].
+    ///
+    /// [`set_cpu_affinity`]: crate::Socket::set_cpu_affinity
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn cpu_affinity(&self) -> io::Result<usize> {
+        unsafe {
+            getsockopt::<c_int>(self.as_raw(), libc::SOL_SOCKET, libc::SO_INCOMING_CPU)
+                .map(|cpu| cpu as usize)
+        }
+    }
+
+    /// Set value for the `SO_INCOMING_CPU` option on this socket.
+    ///
+    /// Sets the CPU affinity of the socket.
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn set_cpu_affinity(&self, cpu: usize) -> io::Result<()> {
+        unsafe {
+            setsockopt(
+                self.as_raw(),
+                libc::SOL_SOCKET,
+                libc::SO_INCOMING_CPU,
+                cpu as c_int,
+            )
+        }
+    }
+
+    /// Get the value of the `SO_REUSEPORT` option on this socket.
+    ///
+    /// For more information about this option, see [`set_reuse_port`].
+    ///
+    /// [`set_reuse_port`]: crate::Socket::set_reuse_port
+    #[cfg(all(
+        feature = "all",
+        not(any(target_os = "solaris", target_os = "illumos", target_os = "cygwin"))
+    ))]
+    pub fn reuse_port(&self) -> io::Result<bool> {
+        unsafe {
+            getsockopt::<c_int>(self.as_raw(), libc::SOL_SOCKET, libc::SO_REUSEPORT)
+                .map(|reuse| reuse != 0)
+        }
+    }
+
+    /// Set value for the `SO_REUSEPORT` option on this socket.
+    ///
+    /// This indicates that further calls to `bind` may allow reuse of local
+    /// addresses. For IPv4 sockets this means that a socket may bind even when
+    /// there's a socket already listening on this port.
+    #[cfg(all(
+        feature = "all",
+        not(any(target_os = "solaris", target_os = "illumos", target_os = "cygwin"))
+    ))]
+    pub fn set_reuse_port(&self, reuse: bool) -> io::Result<()> {
+        unsafe {
+            setsockopt(
+                self.as_raw(),
+                libc::SOL_SOCKET,
+                libc::SO_REUSEPORT,
+                reuse as c_int,
+            )
+        }
+    }
+
+    /// Get the value of the `SO_REUSEPORT_LB` option on this socket.
+    ///
+    /// For more information about this option, see [`set_reuse_port_lb`].
+    ///
+    /// [`set_reuse_port_lb`]: crate::Socket::set_reuse_port_lb
+    #[cfg(all(feature = "all", target_os = "freebsd"))]
+    pub fn reuse_port_lb(&self) -> io::Result<bool> {
+        unsafe {
+            getsockopt::<c_int>(self.as_raw(), libc::SOL_SOCKET, libc::SO_REUSEPORT_LB)
+                .map(|reuse| reuse != 0)
+        }
+    }
+
+    /// Set value for the `SO_REUSEPORT_LB` option on this socket.
+    ///
+    /// This allows multiple programs or threads to bind to the same port and
+    /// incoming connections will be load balanced using a hash function.
+    #[cfg(all(feature = "all", target_os = "freebsd"))]
+    pub fn set_reuse_port_lb(&self, reuse: bool) -> io::Result<()> {
+        unsafe {
+            setsockopt(
+                self.as_raw(),
+                libc::SOL_SOCKET,
+                libc::SO_REUSEPORT_LB,
+                reuse as c_int,
+            )
+        }
+    }
+
+    /// Get the value of the `IP_FREEBIND` option on this socket.
+    ///
+    /// For more information about this option, see [`set_freebind_v4`].
+    ///
+    /// [`set_freebind_v4`]: crate::Socket::set_freebind_v4
+    #[cfg(all(
+        feature = "all",
+        any(target_os = "android", target_os = "fuchsia", target_os = "linux")
+    ))]
+    pub fn freebind_v4(&self) -> io::Result<bool> {
+        unsafe {
+            getsockopt::<c_int>(self.as_raw(), libc::SOL_IP, libc::IP_FREEBIND)
+                .map(|freebind| freebind != 0)
+        }
+    }
+
+    /// Set value for the `IP_FREEBIND` option on this socket.
+    ///
+    /// If enabled, this boolean option allows binding to an IP address that is
+    /// nonlocal or does not (yet) exist.  This permits listening on a socket,
+    /// without requiring the underlying network interface or the specified
+    /// dynamic IP address to be up at the time that the application is trying
+    /// to bind to it.
+    #[cfg(all(
+        feature = "all",
+        any(target_os = "android", target_os = "fuchsia", target_os = "linux")
+    ))]
+    pub fn set_freebind_v4(&self, freebind: bool) -> io::Result<()> {
+        unsafe {
+            setsockopt(
+                self.as_raw(),
+                libc::SOL_IP,
+                libc::IP_FREEBIND,
+                freebind as c_int,
+            )
+        }
+    }
+
+    /// Get the value of the `IPV6_FREEBIND` option on this socket.
+    ///
+    /// This is an IPv6 counterpart of `IP_FREEBIND` socket option on
+    /// Android/Linux. For more information about this option, see
+    /// [`set_freebind_v4`].
+    ///
+    /// [`set_freebind_v4`]: crate::Socket::set_freebind_v4
+    #[cfg(all(feature = "all", any(target_os = "android", target_os = "linux")))]
+    pub fn freebind_v6(&self) -> io::Result<bool> {
+        unsafe {
+            getsockopt::<c_int>(self.as_raw(), libc::SOL_IPV6, libc::IPV6_FREEBIND)
+                .map(|freebind| freebind != 0)
+        }
+    }
+
+    /// Set value for the `IPV6_FREEBIND` option on this socket.
+    ///
+    /// This is an IPv6 counterpart of `IP_FREEBIND` socket option on
+    /// Android/Linux. For more information about this option, see
+    /// [`set_freebind_v4`].
+    ///
+    /// [`set_freebind_v4`]: crate::Socket::set_freebind_v4
+    ///
+    /// # Examples
+    ///
+    /// On Linux:
+    ///
+    /// ```
+    /// use socket2::{Domain, Socket, Type};
+    /// use std::io::{self, Error, ErrorKind};
+    ///
+    /// fn enable_freebind(socket: &Socket) -> io::Result<()> {
+    ///     match socket.domain()? {
+    ///         Domain::IPV4 => socket.set_freebind_v4(true)?,
+    ///         Domain::IPV6 => socket.set_freebind_v6(true)?,
+    ///         _ => return Err(Error::new(ErrorKind::Other, "unsupported domain")),
+    ///     };
+    ///     Ok(())
+    /// }
+    ///
+    /// # fn main() -> io::Result<()> {
+    /// #     let socket = Socket::new(Domain::IPV6, Type::STREAM, None)?;
+    /// #     enable_freebind(&socket)
+    /// # }
+    /// ```
+    #[cfg(all(feature = "all", any(target_os = "android", target_os = "linux")))]
+    pub fn set_freebind_v6(&self, freebind: bool) -> io::Result<()> {
+        unsafe {
+            setsockopt(
+                self.as_raw(),
+                libc::SOL_IPV6,
+                libc::IPV6_FREEBIND,
+                freebind as c_int,
+            )
+        }
+    }
+
+    /// Copies data between a `file` and this socket using the `sendfile(2)`
+    /// system call. Because this copying is done within the kernel,
+    /// `sendfile()` is more efficient than the combination of `read(2)` and
+    /// `write(2)`, which would require transferring data to and from user
+    /// space.
+    ///
+    /// Different OSs support different kinds of `file`s, see the OS
+    /// documentation for what kind of files are supported. Generally *regular*
+    /// files are supported by all OSs.
+    #[doc = man_links!(unix: sendfile(2))]
+    ///
+    /// The `offset` is the absolute offset into the `file` to use as starting
+    /// point.
+    ///
+    /// Depending on the OS this function *may* change the offset of `file`. For
+    /// the best results reset the offset of the file before using it again.
+    ///
+    /// The `length` determines how many bytes to send, where a length of `None`
+    /// means it will try to send all bytes.
+    #[cfg(all(
+        feature = "all",
+        any(
+            target_os = "aix",
+            target_os = "android",
+            target_os = "freebsd",
+            target_os = "ios",
+            target_os = "visionos",
+            target_os = "linux",
+            target_os = "macos",
+            target_os = "tvos",
+            target_os = "watchos",
+        )
+    ))]
+    pub fn sendfile<F>(
+        &self,
+        file: &F,
+        offset: usize,
+        length: Option<NonZeroUsize>,
+    ) -> io::Result<usize>
+    where
+        F: AsRawFd,
+    {
+        self._sendfile(file.as_raw_fd(), offset as _, length)
+    }
+
+    #[cfg(all(
+        feature = "all",
+        any(
+            target_os = "ios",
+            target_os = "visionos",
+            target_os = "macos",
+            target_os = "tvos",
+            target_os = "watchos",
+        )
+    ))]
+    fn _sendfile(
+        &self,
+        file: RawFd,
+        offset: libc::off_t,
+        length: Option<NonZeroUsize>,
+    ) -> io::Result<usize> {
+        // On macOS `length` is value-result parameter. It determines the number
+        // of bytes to write and returns the number of bytes written.
+        let mut length = match length {
+            Some(n) => n.get() as libc::off_t,
+            // A value of `0` means send all bytes.
+            None => 0,
+        };
+        syscall!(sendfile(
+            file,
+            self.as_raw(),
+            offset,
+            &mut length,
+            ptr::null_mut(),
+            0,
+        ))
+        .map(|_| length as usize)
+    }
+
+    #[cfg(all(feature = "all", any(target_os = "android", target_os = "linux")))]
+    fn _sendfile(
+        &self,
+        file: RawFd,
+        offset: libc::off_t,
+        length: Option<NonZeroUsize>,
+    ) -> io::Result<usize> {
+        let count = match length {
+            Some(n) => n.get() as libc::size_t,
+            // The maximum the Linux kernel will write in a single call.
+            None => 0x7ffff000, // 2,147,479,552 bytes.
+        };
+        let mut offset = offset;
+        syscall!(sendfile(self.as_raw(), file, &mut offset, count)).map(|n| n as usize)
+    }
+
+    #[cfg(all(feature = "all", target_os = "freebsd"))]
+    fn _sendfile(
+        &self,
+        file: RawFd,
+        offset: libc::off_t,
+        length: Option<NonZeroUsize>,
+    ) -> io::Result<usize> {
+        let nbytes = match length {
+            Some(n) => n.get() as libc::size_t,
+            // A value of `0` means send all bytes.
+            None => 0,
+        };
+        let mut sbytes: libc::off_t = 0;
+        syscall!(sendfile(
+            file,
+            self.as_raw(),
+            offset,
+            nbytes,
+            ptr::null_mut(),
+            &mut sbytes,
+            0,
+        ))
+        .map(|_| sbytes as usize)
+    }
+
+    #[cfg(all(feature = "all", target_os = "aix"))]
+    fn _sendfile(
+        &self,
+        file: RawFd,
+        offset: libc::off_t,
+        length: Option<NonZeroUsize>,
+    ) -> io::Result<usize> {
+        let nbytes = match length {
+            Some(n) => n.get() as i64,
+            None => -1,
+        };
+        let mut params = libc::sf_parms {
+            header_data: ptr::null_mut(),
+            header_length: 0,
+            file_descriptor: file,
+            file_size: 0,
+            file_offset: offset as u64,
+            file_bytes: nbytes,
+            trailer_data: ptr::null_mut(),
+            trailer_length: 0,
+            bytes_sent: 0,
+        };
+        // AIX doesn't support SF_REUSE, socket will be closed after successful transmission.
+        syscall!(send_file(
+            &mut self.as_raw() as *mut _,
+            &mut params as *mut _,
+            libc::SF_CLOSE as libc::c_uint,
+        ))
+        .map(|_| params.bytes_sent as usize)
+    }
+
+    /// Set the value of the `TCP_USER_TIMEOUT` option on this socket.
+    ///
+    /// If set, this specifies the maximum amount of time that transmitted data may remain
+    /// unacknowledged or buffered data may remain untransmitted before TCP will forcibly close the
+    /// corresponding connection.
+    ///
+    /// Setting `timeout` to `None` or a zero duration causes the system default timeouts to
+    /// be used. If `timeout` in milliseconds is larger than `c_uint::MAX`, the timeout is clamped
+    /// to `c_uint::MAX`. For example, when `c_uint` is a 32-bit value, this limits the timeout to
+    /// approximately 49.71 days.
+    #[cfg(all(
+        feature = "all",
+        any(
+            target_os = "android",
+            target_os = "fuchsia",
+            target_os = "linux",
+            target_os = "cygwin",
+        )
+    ))]
+    pub fn set_tcp_user_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        let timeout = timeout.map_or(0, |to| {
+            min(to.as_millis(), libc::c_uint::MAX as u128) as libc::c_uint
+        });
+        unsafe {
+            setsockopt(
+                self.as_raw(),
+                libc::IPPROTO_TCP,
+                libc::TCP_USER_TIMEOUT,
+                timeout,
+            )
+        }
+    }
+
+    /// Get the value of the `TCP_USER_TIMEOUT` option on this socket.
+    ///
+    /// For more information about this option, see [`set_tcp_user_timeout`].
+    ///
+    /// [`set_tcp_user_timeout`]: crate::Socket::set_tcp_user_timeout
+    #[cfg(all(
+        feature = "all",
+        any(
+            target_os = "android",
+            target_os = "fuchsia",
+            target_os = "linux",
+            target_os = "cygwin",
+        )
+    ))]
+    pub fn tcp_user_timeout(&self) -> io::Result<Option<Duration>> {
+        unsafe {
+            getsockopt::<libc::c_uint>(self.as_raw(), libc::IPPROTO_TCP, libc::TCP_USER_TIMEOUT)
+                .map(|millis| {
+                    if millis == 0 {
+                        None
+                    } else {
+                        Some(Duration::from_millis(millis as u64))
+                    }
+                })
+        }
+    }
+
+    /// Attach Berkeley Packet Filter (BPF) on this socket.
+    ///
+    /// BPF allows a user-space program to attach a filter onto any socket
+    /// and allow or disallow certain types of data to come through the socket.
+    ///
+    /// For more information about this option, see [filter](https://www.kernel.org/doc/html/v5.12/networking/filter.html)
+    ///
+    /// On Linux the [`bpf`] module can be used to assemble and validate the
+    /// program.
+    ///
+    /// [`bpf`]: crate::bpf
+    #[cfg(all(feature = "all", any(target_os = "linux", target_os = "android")))]
+    pub fn attach_filter(&self, filters: &[SockFilter]) -> io::Result<()> {
+        let prog = sock_fprog(filters);
+
+        unsafe {
+            setsockopt(
+                self.as_raw(),
+                libc::SOL_SOCKET,
+                libc::SO_ATTACH_FILTER,
+                prog,
+            )
+        }
+    }
+
+    /// Detach Berkeley Packet Filter(BPF) from this socket.
+    ///
+    /// For more information about this option, see [`attach_filter`]
+    ///
+    /// [`attach_filter`]: crate::Socket::attach_filter
+    #[cfg(all(feature = "all", any(target_os = "linux", target_os = "android")))]
+    pub fn detach_filter(&self) -> io::Result<()> {
+        unsafe { setsockopt(self.as_raw(), libc::SOL_SOCKET, libc::SO_DETACH_FILTER, 0) }
+    }
+
+    /// Attach a classic BPF program to the `SO_REUSEPORT` group of this
+    /// socket, `SO_ATTACH_REUSEPORT_CBPF`.
+    ///
+    /// The program selects the socket in the group that receives an incoming
+    /// packet (UDP) or connection (TCP): it returns the index of the socket,
+    /// in the order the sockets were added to the group. If the returned
+    /// index is out of range the socket is selected by the default hash
+    /// instead. See [`SockFilter::reuseport_cpu`] and
+    /// [`SockFilter::reuseport_hash`] for ready-made programs.
+    ///
+    /// The program replaces any program already attached to the group.
+    ///
+    /// [`SockFilter::reuseport_cpu`]: crate::SockFilter::reuseport_cpu
+    /// [`SockFilter::reuseport_hash`]: crate::SockFilter::reuseport_hash
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn attach_reuseport_filter(&self, filters: &[SockFilter]) -> io::Result<()> {
+        let prog = sock_fprog(filters);
+
+        unsafe {
+            setsockopt(
+                self.as_raw(),
+                libc::SOL_SOCKET,
+                libc::SO_ATTACH_REUSEPORT_CBPF,
+                prog,
+            )
+        }
+    }
+
+    /// Attach a loaded eBPF program (of type `BPF_PROG_TYPE_SOCKET_FILTER` or
+    /// `BPF_PROG_TYPE_SK_REUSEPORT`) to the `SO_REUSEPORT` group of this
+    /// socket, `SO_ATTACH_REUSEPORT_EBPF`.
+    ///
+    /// For more information, see [`attach_reuseport_filter`].
+    ///
+    /// [`attach_reuseport_filter`]: crate::Socket::attach_reuseport_filter
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn attach_reuseport_ebpf(&self, program: BorrowedFd<'_>) -> io::Result<()> {
+        unsafe {
+            setsockopt(
+                self.as_raw(),
+                libc::SOL_SOCKET,
+                libc::SO_ATTACH_REUSEPORT_EBPF,
+                program.as_raw_fd(),
+            )
+        }
+    }
+
+    /// Detach the program from the `SO_REUSEPORT` group of this socket,
+    /// `SO_DETACH_REUSEPORT_BPF`.
+    ///
+    /// For more information, see [`attach_reuseport_filter`].
+    ///
+    /// [`attach_reuseport_filter`]: crate::Socket::attach_reuseport_filter
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn detach_reuseport_filter(&self) -> io::Result<()> {
+        unsafe {
+            setsockopt(
+                self.as_raw(),
+                libc::SOL_SOCKET,
+                libc::SO_DETACH_REUSEPORT_BPF,
+                0,
+            )
+        }
+    }
+
+    /// Gets the value for the `SO_COOKIE` option on this socket.
+    ///
+    /// The socket cookie is a unique, kernel-managed identifier tied to each socket.
+    /// Therefore, there is no corresponding `set` helper.
+    ///
+    /// For more information about this option, see [Linux patch](https://git.kernel.org/pub/scm/linux/kernel/git/torvalds/linux.git/commit/?id=5daab9db7b65df87da26fd8cfa695fb9546a1ddb)
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn cookie(&self) -> io::Result<u64> {
+        unsafe { getsockopt::<libc::c_ulonglong>(self.as_raw(), libc::SOL_SOCKET, libc::SO_COOKIE) }
+    }
+
+    /// Get the value of the `IPV6_TCLASS` option for this socket.
+    ///
+    /// For more information about this option, see [`set_tclass_v6`].
+    ///
+    /// [`set_tclass_v6`]: crate::Socket::set_tclass_v6
+    #[cfg(all(
+        feature = "all",
+        any(
+            target_os = "android",
+            target_os = "dragonfly",
+            target_os = "freebsd",
+            target_os = "fuchsia",
+            target_os = "linux",
+            target_os = "macos",
+            target_os = "netbsd",
+            target_os = "openbsd",
+            target_os = "cygwin",
+        )
+    ))]
+    pub fn tclass_v6(&self) -> io::Result<u32> {
+        unsafe {
+            getsockopt::<c_int>(self.as_raw(), IPPROTO_IPV6, libc::IPV6_TCLASS)
+                .map(|tclass| tclass as u32)
+        }
+    }
+
+    /// Set the value of the `IPV6_TCLASS` option for this socket.
+    ///
+    /// Specifies the traffic class field that is used in every packets
+    /// sent from this socket.
+    #[cfg(all(
+        feature = "all",
+        any(
+            target_os = "android",
+            target_os = "dragonfly",
+            target_os = "freebsd",
+            target_os = "fuchsia",
+            target_os = "linux",
+            target_os = "macos",
+            target_os = "netbsd",
+            target_os = "openbsd",
+            target_os = "cygwin",
+        )
+    ))]
+    pub fn set_tclass_v6(&self, tclass: u32) -> io::Result<()> {
+        unsafe {
+            setsockopt(
+                self.as_raw(),
+                IPPROTO_IPV6,
+                libc::IPV6_TCLASS,
+                tclass as c_int,
+            )
+        }
+    }
+
+    /// Get the value of the `TCP_CONGESTION` option for this socket.
+    ///
+    /// For more information about this option, see [`set_tcp_congestion`].
+    ///
+    /// [`set_tcp_congestion`]: crate::Socket::set_tcp_congestion
+    #[cfg(all(feature = "all", any(target_os = "freebsd", target_os = "linux")))]
+    pub fn tcp_congestion(&self) -> io::Result<Vec<u8>> {
+        let mut payload: [u8; TCP_CA_NAME_MAX] = [0; TCP_CA_NAME_MAX];
+        let mut len = payload.len() as libc::socklen_t;
+        syscall!(getsockopt(
+            self.as_raw(),
+            IPPROTO_TCP,
+            libc::TCP_CONGESTION,
+            payload.as_mut_ptr().cast(),
+            &mut len,
+        ))
+        .map(|_| payload[..len as usize].to_vec())
+    }
+
+    /// Set the value of the `TCP_CONGESTION` option for this socket.
+    ///
+    /// Specifies the TCP congestion control algorithm to use for this socket.
+    ///
+    /// The value must be a valid TCP congestion control algorithm name of the
+    /// platform. For example, Linux may supports "reno", "cubic".
+    #[cfg(all(feature = "all", any(target_os = "freebsd", target_os = "linux")))]
+    pub fn set_tcp_congestion(&self, tcp_ca_name: &[u8]) -> io::Result<()> {
+        syscall!(setsockopt(
+            self.as_raw(),
+            IPPROTO_TCP,
+            libc::TCP_CONGESTION,
+            tcp_ca_name.as_ptr() as *const _,
+            tcp_ca_name.len() as libc::socklen_t,
+        ))
+        .map(|_| ())
+    }
+
+    /// Set value for the `DCCP_SOCKOPT_SERVICE` option on this socket.
+    ///
+    /// Sets the DCCP service. The specification mandates use of service codes.
+    /// If this socket option is not set, the socket will fall back to 0 (which
+    /// means that no meaningful service code is present). On active sockets
+    /// this is set before [`connect`]. On passive sockets up to 32 service
+    /// codes can be set before calling [`bind`]
+    ///
+    /// [`connect`]: crate::Socket::connect
+    /// [`bind`]: crate::Socket::bind
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn set_dccp_service(&self, code: u32) -> io::Result<()> {
+        unsafe {
+            setsockopt(
+                self.as_raw(),
+                libc::SOL_DCCP,
+                libc::DCCP_SOCKOPT_SERVICE,
+                code,
+            )
+        }
+    }
+
+    /// Get the value of the `DCCP_SOCKOPT_SERVICE` option on this socket.
+    ///
+    /// For more information about this option see [`set_dccp_service`]
+    ///
+    /// [`set_dccp_service`]: crate::Socket::set_dccp_service
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn dccp_service(&self) -> io::Result<u32> {
+        unsafe { getsockopt(self.as_raw(), libc::SOL_DCCP, libc::DCCP_SOCKOPT_SERVICE) }
+    }
+
+    /// Set value for the `DCCP_SOCKOPT_CCID` option on this socket.
+    ///
+    /// This option sets both the TX and RX CCIDs at the same time.
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn set_dccp_ccid(&self, ccid: u8) -> io::Result<()> {
+        unsafe { setsockopt(self.as_raw(), libc::SOL_DCCP, libc::DCCP_SOCKOPT_CCID, ccid) }
+    }
+
+    /// Get the value of the `DCCP_SOCKOPT_TX_CCID` option on this socket.
+    ///
+    /// For more information about this option see [`set_dccp_ccid`].
+    ///
+    /// [`set_dccp_ccid`]: crate::Socket::set_dccp_ccid
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn dccp_tx_ccid(&self) -> io::Result<u32> {
+        unsafe { getsockopt(self.as_raw(), libc::SOL_DCCP, libc::DCCP_SOCKOPT_TX_CCID) }
+    }
+
+    /// Get the value of the `DCCP_SOCKOPT_RX_CCID` option on this socket.
+    ///
+    /// For more information about this option see [`set_dccp_ccid`].
+    ///
+    /// [`set_dccp_ccid`]: crate::Socket::set_dccp_ccid
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn dccp_xx_ccid(&self) -> io::Result<u32> {
+        unsafe { getsockopt(self.as_raw(), libc::SOL_DCCP, libc::DCCP_SOCKOPT_RX_CCID) }
+    }
+
+    /// Set value for the `DCCP_SOCKOPT_SERVER_TIMEWAIT` option on this socket.
+    ///
+    /// Enables a listening socket to hold timewait state when closing the
+    /// connection. This option must be set after `accept` returns.
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn set_dccp_server_timewait(&self, hold_timewait: bool) -> io::Result<()> {
+        unsafe {
+            setsockopt(
+                self.as_raw(),
+                libc::SOL_DCCP,
+                libc::DCCP_SOCKOPT_SERVER_TIMEWAIT,
+                hold_timewait as c_int,
+            )
+        }
+    }
+
+    /// Get the value of the `DCCP_SOCKOPT_SERVER_TIMEWAIT` option on this socket.
+    ///
+    /// For more information see [`set_dccp_server_timewait`]
+    ///
+    /// [`set_dccp_server_timewait`]: crate::Socket::set_dccp_server_timewait
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn dccp_server_timewait(&self) -> io::Result<bool> {
+        unsafe {
+            getsockopt(
+                self.as_raw(),
+                libc::SOL_DCCP,
+                libc::DCCP_SOCKOPT_SERVER_TIMEWAIT,
+            )
+        }
+    }
+
+    /// Set value for the `DCCP_SOCKOPT_SEND_CSCOV` option on this socket.
+    ///
+    /// Both this option and `DCCP_SOCKOPT_RECV_CSCOV` are used for setting the
+    /// partial checksum coverage. The default is that checksums always cover
+    /// the entire packet and that only fully covered application data is
+    /// accepted by the receiver. Hence, when using this feature on the sender,
+    /// it must be enabled at the receiver too, with suitable choice of CsCov.
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn set_dccp_send_cscov(&self, level: u32) -> io::Result<()> {
+        unsafe {
+            setsockopt(
+                self.as_raw(),
+                libc::SOL_DCCP,
+                libc::DCCP_SOCKOPT_SEND_CSCOV,
+                level,
+            )
+        }
+    }
+
+    /// Get the value of the `DCCP_SOCKOPT_SEND_CSCOV` option on this socket.
+    ///
+    /// For more information on this option see [`set_dccp_send_cscov`].
+    ///
+    /// [`set_dccp_send_cscov`]: crate::Socket::set_dccp_send_cscov
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn dccp_send_cscov(&self) -> io::Result<u32> {
+        unsafe { getsockopt(self.as_raw(), libc::SOL_DCCP, libc::DCCP_SOCKOPT_SEND_CSCOV) }
+    }
+
+    /// Set the value of the `DCCP_SOCKOPT_RECV_CSCOV` option on this socket.
+    ///
+    /// This option is only useful when combined with [`set_dccp_send_cscov`].
+    ///
+    /// [`set_dccp_send_cscov`]: crate::Socket::set_dccp_send_cscov
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn set_dccp_recv_cscov(&self, level: u32) -> io::Result<()> {
+        unsafe {
+            setsockopt(
+                self.as_raw(),
+                libc::SOL_DCCP,
+                libc::DCCP_SOCKOPT_RECV_CSCOV,
+                level,
+            )
+        }
+    }
+
+    /// Get the value of the `DCCP_SOCKOPT_RECV_CSCOV` option on this socket.
+    ///
+    /// For more information on this option see [`set_dccp_recv_cscov`].
+    ///
+    /// [`set_dccp_recv_cscov`]: crate::Socket::set_dccp_recv_cscov
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn dccp_recv_cscov(&self) -> io::Result<u32> {
+        unsafe { getsockopt(self.as_raw(), libc::SOL_DCCP, libc::DCCP_SOCKOPT_RECV_CSCOV) }
+    }
+
+    /// Set value for the `DCCP_SOCKOPT_QPOLICY_TXQLEN` option on this socket.
+    ///
+    /// This option sets the maximum length of the output queue. A zero value is
+    /// interpreted as unbounded queue length.
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn set_dccp_qpolicy_txqlen(&self, length: u32) -> io::Result<()> {
+        unsafe {
+            setsockopt(
+                self.as_raw(),
+                libc::SOL_DCCP,
+                libc::DCCP_SOCKOPT_QPOLICY_TXQLEN,
+                length,
+            )
+        }
+    }
+
+    /// Get the value of the `DCCP_SOCKOPT_QPOLICY_TXQLEN` on this socket.
+    ///
+    /// For more information on this option see [`set_dccp_qpolicy_txqlen`].
+    ///
+    /// [`set_dccp_qpolicy_txqlen`]: crate::Socket::set_dccp_qpolicy_txqlen
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn dccp_qpolicy_txqlen(&self) -> io::Result<u32> {
+        unsafe {
+            getsockopt(
+                self.as_raw(),
+                libc::SOL_DCCP,
+                libc::DCCP_SOCKOPT_QPOLICY_TXQLEN,
+            )
+        }
+    }
+
+    /// Get the value of the `DCCP_SOCKOPT_AVAILABLE_CCIDS` option on this socket.
+    ///
+    /// Returns the list of CCIDs supported by the endpoint.
+    ///
+    /// The parameter `N` is used to get the maximum number of supported
+    /// endpoints. The [documentation] recommends a minimum of four at the time
+    /// of writing.
+    ///
+    /// [documentation]: https://www.kernel.org/doc/html/latest/networking/dccp.html
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn dccp_available_ccids<const N: usize>(&self) -> io::Result<CcidEndpoints<N>> {
+        let mut endpoints = [0; N];
+        let mut length = endpoints.len() as libc::socklen_t;
+        syscall!(getsockopt(
+            self.as_raw(),
+            libc::SOL_DCCP,
+            libc::DCCP_SOCKOPT_AVAILABLE_CCIDS,
+            endpoints.as_mut_ptr().cast(),
+            &mut length,
+        ))?;
+        Ok(CcidEndpoints { endpoints, length })
+    }
+
+    /// Get the value of the `DCCP_SOCKOPT_GET_CUR_MPS` option on this socket.
+    ///
+    /// This option retrieves the current maximum packet size (application
+    /// payload size) in bytes.
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn dccp_cur_mps(&self) -> io::Result<u32> {
+        unsafe {
+            getsockopt(
+                self.as_raw(),
+                libc::SOL_DCCP,
+                libc::DCCP_SOCKOPT_GET_CUR_MPS,
+            )
+        }
+    }
+
+    /// Get the value for the `SO_BUSY_POLL` option on this socket.
+    ///
+    /// On Linux this function requires the `CAP_NET_ADMIN` capability.
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn busy_poll(&self) -> io::Result<u32> {
+        unsafe { getsockopt(self.as_raw(), libc::SOL_SOCKET, libc::SO_BUSY_POLL) }
+    }
+
+    /// Set the value for the `SO_BUSY_POLL` option on this socket.
+    ///
+    /// On Linux this function requires the `CAP_NET_ADMIN` capability.
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn set_busy_poll(&self, busy_poll: u32) -> io::Result<()> {
+        unsafe {
+            setsockopt(
+                self.as_raw(),
+                libc::SOL_SOCKET,
+                libc::SO_BUSY_POLL,
+                busy_poll as c_int,
+            )
+        }
+    }
+
+    /// Get the value of the `SO_TIMESTAMP` option on this socket.
+    ///
+    /// For more information about this option, see [`set_timestamp`].
+    ///
+    /// [`set_timestamp`]: crate::Socket::set_timestamp
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn timestamp(&self) -> io::Result<bool> {
+        unsafe {
+            getsockopt::<c_int>(self.as_raw(), libc::SOL_SOCKET, libc::SO_TIMESTAMP)
+                .map(|timestamp| timestamp != 0)
+        }
+    }
+
+    /// Set the value of the `SO_TIMESTAMP` option on this socket.
+    ///
+    /// If set, the time at which a packet was received is passed along in a
+    /// [`ControlMessage::Timestamp`] message, in microsecond resolution.
+    ///
+    /// [`ControlMessage::Timestamp`]: crate::ControlMessage::Timestamp
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn set_timestamp(&self, timestamp: bool) -> io::Result<()> {
+        unsafe {
+            setsockopt(
+                self.as_raw(),
+                libc::SOL_SOCKET,
+                libc::SO_TIMESTAMP,
+                timestamp as c_int,
+            )
+        }
+    }
+
+    /// Get the value of the `SO_TIMESTAMPNS` option on this socket.
+    ///
+    /// For more information about this option, see [`set_timestamp_ns`].
+    ///
+    /// [`set_timestamp_ns`]: crate::Socket::set_timestamp_ns
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn timestamp_ns(&self) -> io::Result<bool> {
+        unsafe {
+            getsockopt::<c_int>(self.as_raw(), libc::SOL_SOCKET, libc::SO_TIMESTAMPNS)
+                .map(|timestamp| timestamp != 0)
+        }
+    }
+
+    /// Set the value of the `SO_TIMESTAMPNS` option on this socket.
+    ///
+    /// If set, the time at which a packet was received is passed along in a
+    /// [`ControlMessage::TimestampNs`] message, in nanosecond resolution.
+    ///
+    /// [`ControlMessage::TimestampNs`]: crate::ControlMessage::TimestampNs
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn set_timestamp_ns(&self, timestamp: bool) -> io::Result<()> {
+        unsafe {
+            setsockopt(
+                self.as_raw(),
+                libc::SOL_SOCKET,
+                libc::SO_TIMESTAMPNS,
+                timestamp as c_int,
+            )
+        }
+    }
+
+    /// Get the value of the `SO_TIMESTAMPING` option on this socket.
+    ///
+    /// For more information about this option, see [`set_timestamping`].
+    ///
+    /// [`set_timestamping`]: crate::Socket::set_timestamping
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn timestamping(&self) -> io::Result<TimestampingFlags> {
+        unsafe {
+            getsockopt::<c_int>(self.as_raw(), libc::SOL_SOCKET, libc::SO_TIMESTAMPING)
+                .map(|flags| TimestampingFlags(flags as u32))
+        }
+    }
+
+    /// Set the value of the `SO_TIMESTAMPING` option on this socket.
+    ///
+    /// The `flags` determine which timestamps are generated and reported, see
+    /// [`TimestampingFlags`]. Receive timestamps are passed along in a
+    /// [`ControlMessage::Timestamping`] message, transmit timestamps are
+    /// queued on the error queue and can be read using
+    /// [`Socket::recv_tx_timestamp`].
+    ///
+    /// [`ControlMessage::Timestamping`]: crate::ControlMessage::Timestamping
+    /// [`Socket::recv_tx_timestamp`]: crate::Socket::recv_tx_timestamp
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn set_timestamping(&self, flags: TimestampingFlags) -> io::Result<()> {
+        unsafe {
+            setsockopt(
+                self.as_raw(),
+                libc::SOL_SOCKET,
+                libc::SO_TIMESTAMPING,
+                flags.0 as c_int,
+            )
+        }
+    }
+
+    /// Receive a transmit timestamp from the error queue of this socket.
+    ///
+    /// Returns the number of bytes of the original packet copied into `buf`,
+    /// which is zero if [`TimestampingFlags::OPT_TSONLY`] is set, and the
+    /// timestamp. If the error queue holds an error, rather than a timestamp,
+    /// the error is returned.
+    ///
+    /// This uses `recvmsg(2)` with the `MSG_ERRQUEUE` flag, which never
+    /// blocks. An error with kind [`io::ErrorKind::WouldBlock`] is returned
+    /// if no timestamp is queued (yet).
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn recv_tx_timestamp(
+        &self,
+        buf: &mut [MaybeUninit<u8>],
+    ) -> io::Result<(usize, crate::TxTimestamp)> {
+        let (n, _, err, timestamps) = recv_errqueue(self.as_raw(), buf)?;
+        match (err, timestamps) {
+            (Some(err), Some(timestamps)) if err.origin() == crate::ErrorOrigin::Timestamping => {
+                let timestamp = crate::TxTimestamp::new(err.info(), err.data(), timestamps);
+                Ok((n, timestamp))
+            }
+            (err, _) => Err(unexpected_error_queue_message(err)),
+        }
+    }
+
+    /// Get the value of the `SO_ZEROCOPY` option on this socket.
+    ///
+    /// For more information about this option, see [`set_zerocopy`].
+    ///
+    /// [`set_zerocopy`]: crate::Socket::set_zerocopy
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn zerocopy(&self) -> io::Result<bool> {
+        unsafe {
+            getsockopt::<c_int>(self.as_raw(), libc::SOL_SOCKET, libc::SO_ZEROCOPY)
+                .map(|zerocopy| zerocopy != 0)
+        }
+    }
+
+    /// Set the value of the `SO_ZEROCOPY` option on this socket.
+    ///
+    /// This must be enabled before data can be sent using
+    /// [`Socket::send_zerocopy`] (or the `MSG_ZEROCOPY` flag).
+    ///
+    /// [`Socket::send_zerocopy`]: crate::Socket::send_zerocopy
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn set_zerocopy(&self, zerocopy: bool) -> io::Result<()> {
+        unsafe {
+            setsockopt(
+                self.as_raw(),
+                libc::SOL_SOCKET,
+                libc::SO_ZEROCOPY,
+                zerocopy as c_int,
+            )
+        }
+    }
+
+    /// Send data on the socket to a connected peer without copying it, using
+    /// the `MSG_ZEROCOPY` flag. See [`Socket::send_to_zerocopy`] for
+    /// unconnected sockets.
+    ///
+    /// Requires [`Socket::set_zerocopy`] to be enabled. Every successful call
+    /// is assigned an identifier, starting at zero and incremented for each
+    /// call, which is reported by [`Socket::recv_zerocopy_completion`] once the
+    /// kernel no longer uses the buffer.
+    ///
+    /// # Notes
+    ///
+    /// The data in `buf` must not be modified until a completion notification
+    /// covering this call is received, otherwise the modified data may be
+    /// sent.
+    ///
+    /// [`Socket::send_to_zerocopy`]: crate::Socket::send_to_zerocopy
+    /// [`Socket::set_zerocopy`]: crate::Socket::set_zerocopy
+    /// [`Socket::recv_zerocopy_completion`]: crate::Socket::recv_zerocopy_completion
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn send_zerocopy(&self, buf: &[u8]) -> io::Result<usize> {
+        send(self.as_raw(), buf, libc::MSG_ZEROCOPY)
+    }
+
+    /// Send data on the socket to `addr` without copying it, like
+    /// [`Socket::send_zerocopy`], e.g. for unconnected UDP sockets.
+    ///
+    /// # Notes
+    ///
+    /// The data in `buf` must not be modified until a completion notification
+    /// covering this call is received, otherwise the modified data may be
+    /// sent.
+    ///
+    /// [`Socket::send_zerocopy`]: crate::Socket::send_zerocopy
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn send_to_zerocopy(&self, buf: &[u8], addr: &SockAddr) -> io::Result<usize> {
+        send_to(self.as_raw(), buf, addr, libc::MSG_ZEROCOPY)
+    }
+
+    /// Receive a zerocopy completion notification from the error queue of
+    /// this socket.
+    ///
+    /// See [`Socket::send_zerocopy`] and [`ZerocopyCompletion`]. If the error
+    /// queue holds an error, rather than a notification, the error is
+    /// returned.
+    ///
+    /// This uses `recvmsg(2)` with the `MSG_ERRQUEUE` flag, which never
+    /// blocks. An error with kind [`io::ErrorKind::WouldBlock`] is returned
+    /// if no notification is queued (yet).
+    ///
+    /// [`Socket::send_zerocopy`]: crate::Socket::send_zerocopy
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn recv_zerocopy_completion(&self) -> io::Result<ZerocopyCompletion> {
+        match recv_errqueue(self.as_raw(), &mut [])? {
+            (_, _, Some(err), _) if err.origin() == crate::ErrorOrigin::Zerocopy => {
+                Ok(ZerocopyCompletion {
+                    start: err.info(),
+                    end: err.data(),
+                    copied: err.code() & SO_EE_CODE_ZEROCOPY_COPIED != 0,
+                })
+            }
+            (_, _, err, _) => Err(unexpected_error_queue_message(err)),
+        }
+    }
+
+    /// Receive an error from the error queue of this socket.
+    ///
+    /// Requires [`Socket::set_recv_err_v4`] or [`Socket::set_recv_err_v6`]
+    /// to be enabled for errors such as ICMP messages to be queued. Returns
+    /// the number of bytes of the original payload copied into `buf`, the
+    /// error and the original destination address of the payload, if any.
+    ///
+    /// This uses `recvmsg(2)` with the `MSG_ERRQUEUE` flag, which never
+    /// blocks. An error with kind [`io::ErrorKind::WouldBlock`] is returned
+    /// if no error is queued.
+    ///
+    /// [`Socket::set_recv_err_v4`]: crate::Socket::set_recv_err_v4
+    /// [`Socket::set_recv_err_v6`]: crate::Socket::set_recv_err_v6
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn recv_error_queue(
+        &self,
+        buf: &mut [MaybeUninit<u8>],
+    ) -> io::Result<(usize, crate::ExtendedError, Option<SockAddr>)> {
+        match recv_errqueue(self.as_raw(), buf)? {
+            (n, addr, Some(err), _) => {
+                let addr = (addr.len() != 0).then_some(addr);
+                Ok((n, err, addr))
+            }
+            (_, _, None, _) => Err(unexpected_error_queue_message(None)),
+        }
+    }
+
+    /// Send data together with the file descriptors `fds` to the connected
+    /// peer.
+    ///
+    /// The file descriptors are passed in a `SCM_RIGHTS` control message, see
+    /// [`ControlMessageBuilder::with_rights`], which is only supported on Unix
+    /// sockets. At least one byte of data must be sent with the file
+    /// descriptors.
+    ///
+    /// [`ControlMessageBuilder::with_rights`]: crate::ControlMessageBuilder::with_rights
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn send_with_fds(&self, bufs: &[IoSlice<'_>], fds: &[BorrowedFd<'_>]) -> io::Result<usize> {
+        let control = crate::ControlMessageBuilder::new().with_rights(fds);
+        let mut buf = vec![MaybeUninit::uninit(); control.space()];
+        let control = control.encode(&mut buf)?;
+        let msg = MsgHdr::new().with_buffers(bufs).with_control(control);
+        sendmsg(self.as_raw(), &msg, 0)
+    }
+
+    /// Receive data together with at most `max_fds` file descriptors from the
+    /// connected peer.
+    ///
+    /// Returns the number of bytes read, the flags of the message and the
+    /// file descriptors received. The file descriptors are received with the
+    /// `MSG_CMSG_CLOEXEC` flag, so they're closed on `exec(2)`.
+    ///
+    /// If the peer sent more than `max_fds` file descriptors, the excess ones
+    /// are closed by the kernel and [`RecvFlags::is_control_truncated`] is
+    /// set on the returned flags. Note that if [`Socket::set_passcred`] is
+    /// enabled the credentials take up room in the control buffer as well.
+    ///
+    /// [`Socket::set_passcred`]: crate::Socket::set_passcred
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn recv_with_fds(
+        &self,
+        bufs: &mut [crate::MaybeUninitSlice<'_>],
+        max_fds: usize,
+    ) -> io::Result<(usize, RecvFlags, Vec<OwnedFd>)> {
+        let fds_len = max_fds * size_of::<RawFd>();
+        let mut control = vec![MaybeUninit::uninit(); crate::cmsg::cmsg_space(fds_len)];
+        // NOTE: `CMSG_SPACE` includes padding which could fit additional file
+        // descriptors, limit the buffer so the kernel doesn't pass us more
+        // than `max_fds`.
+        let control_len = if max_fds == 0 {
+            0
+        } else {
+            crate::cmsg::cmsg_len(fds_len)
+        };
+        let mut msg = MsgHdrMut::new()
+            .with_buffers(bufs)
+            .with_control(&mut control[..control_len]);
+        let n = recvmsg(self.as_raw(), &mut msg, libc::MSG_CMSG_CLOEXEC)?;
+        let mut fds = Vec::new();
+        for cmsg in msg.control_messages() {
+            if let crate::ControlMessage::Other {
+                level: libc::SOL_SOCKET,
+                ty: libc::SCM_RIGHTS,
+                data,
+            } = cmsg
+            {
+                for fd in data.chunks_exact(size_of::<RawFd>()) {
+                    let fd = RawFd::from_ne_bytes(fd.try_into().unwrap());
+                    // SAFETY: the kernel created the file descriptor for us.
+                    fds.push(unsafe { OwnedFd::from_raw_fd(fd) });
+                }
+            }
+        }
+        Ok((n, msg.flags(), fds))
+    }
+
+    /// Receive a message, like [`recv_from`], returning the destination
+    /// address of the packet and the interface it was received on as well.
+    ///
+    /// Requires [`set_recv_pktinfo_v4`] (for IPv4 packets) or
+    /// [`set_recv_pktinfo_v6`] (for IPv6 packets) to be enabled, otherwise
+    /// the returned packet information is `None`. The packet information can
+    /// also be missing if the control messages didn't fit in the buffer, e.g.
+    /// because many other `recv` options are enabled, this is indicated by
+    /// [`RecvFlags::is_control_truncated`].
+    ///
+    /// [`recv_from`]: crate::Socket::recv_from
+    /// [`set_recv_pktinfo_v4`]: crate::Socket::set_recv_pktinfo_v4
+    /// [`set_recv_pktinfo_v6`]: crate::Socket::set_recv_pktinfo_v6
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn recv_from_with_dst(
+        &self,
+        buf: &mut [MaybeUninit<u8>],
+    ) -> io::Result<(usize, RecvFlags, SockAddr, Option<PktInfo>)> {
+        let mut bufs = [crate::MaybeUninitSlice::new(buf)];
+        // NOTE: room for other control messages the user enabled as well.
+        let mut control = [MaybeUninit::uninit(); 128];
+        let mut msg = MsgHdrMut::new()
+            .with_buffers(&mut bufs)
+            .with_control(&mut control);
+        // SAFETY: `recvmsg` initialises the address storage and we set the
+        // length manually.
+        let (n, addr) = unsafe {
+            SockAddr::try_init(|storage, len| {
+                msg.inner.msg_name = storage.cast();
+                msg.inner.msg_namelen = *len;
+                let n = recvmsg(self.as_raw(), &mut msg, 0)?;
+                // Set the correct address length.
+                *len = msg.inner.msg_namelen;
+                Ok(n)
+            })?
+        };
+        let pktinfo = msg.control_messages().find_map(|cmsg| match cmsg {
+            crate::ControlMessage::PktInfoV4 {
+                interface,
+                local,
+                destination,
+            } => Some(PktInfo {
+                destination: destination.into(),
+                local: local.into(),
+                interface,
+            }),
+            crate::ControlMessage::PktInfoV6 {
+                interface,
+                destination,
+            } => Some(PktInfo {
+                destination: destination.into(),
+                local: destination.into(),
+                interface,
+            }),
+            _ => None,
+        });
+        Ok((n, msg.flags(), addr, pktinfo))
+    }
+
+    /// Send data to `addr`, like [`send_to`], using `source` as source
+    /// address and sending it on the interface with index `interface`.
+    ///
+    /// If `interface` is zero the interface is selected by the routing
+    /// table. This is typically used to reply to a packet received using
+    /// [`recv_from_with_dst`], passing [`PktInfo::local`] and
+    /// [`PktInfo::interface`], so that the reply comes from the address the
+    /// request was sent to on multi-homed hosts.
+    ///
+    /// Uses an `IP_PKTINFO` or `IPV6_PKTINFO` control message, depending on
+    /// the type of `source`. The source address must be a local address,
+    /// unless [`Socket::set_ip_transparent_v4`] is enabled.
+    ///
+    /// [`send_to`]: crate::Socket::send_to
+    /// [`recv_from_with_dst`]: crate::Socket::recv_from_with_dst
+    /// [`Socket::set_ip_transparent_v4`]: crate::Socket::set_ip_transparent_v4
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn send_to_from(
+        &self,
+        buf: &[u8],
+        addr: &SockAddr,
+        source: IpAddr,
+        interface: u32,
+    ) -> io::Result<usize> {
+        let control = crate::ControlMessageBuilder::new();
+        let control = match source {
+            IpAddr::V4(source) => control.with_pktinfo_v4(interface, source),
+            IpAddr::V6(source) => control.with_pktinfo_v6(interface, source),
+        };
+        let mut control_buf = [MaybeUninit::uninit(); 64];
+        let control = control.encode(&mut control_buf)?;
+        let bufs = [IoSlice::new(buf)];
+        let msg = MsgHdr::new()
+            .with_addr(addr)
+            .with_buffers(&bufs)
+            .with_control(control);
+        sendmsg(self.as_raw(), &msg, 0)
+    }
+
+    /// Send multiple messages on this socket using a single system call.
+    ///
+    /// Returns the number of messages sent, the number of bytes sent of each
+    /// message can be retrieved using [`MmsgHdr::len`]. Only messages up to
+    /// the returned number have been sent, i.e. if fewer messages were sent
+    /// than passed the remaining ones can be retried in another call.
+    ///
+    /// This function directly corresponds to the `sendmmsg(2)` function.
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn send_multiple(
+        &self,
+        msgs: &mut [MmsgHdr<'_, '_, '_>],
+        flags: c_int,
+    ) -> io::Result<usize> {
+        syscall!(sendmmsg(
+            self.as_raw(),
+            msgs.as_mut_ptr().cast(),
+            min(msgs.len(), libc::c_uint::MAX as usize) as libc::c_uint,
+            flags as _,
+        ))
+        .map(|n| n as usize)
+    }
+
+    /// Receive multiple messages from this socket using a single system call.
+    ///
+    /// Returns the number of messages received, the number of bytes, address
+    /// and flags of each message can be retrieved using [`MmsgHdrMut::len`],
+    /// [`MmsgHdrMut::addr`] and [`MmsgHdrMut::flags`] respectively.
+    ///
+    /// If `timeout` is `Some` the call returns once the timeout expires, even
+    /// if fewer messages than requested are received. Note however that the
+    /// timeout is only checked after a message is received, so this can still
+    /// block indefinitely waiting for the first message, see the bugs section
+    /// of `recvmmsg(2)`. Use [`Socket::set_read_timeout`] or the `MSG_DONTWAIT`
+    /// flag to prevent that.
+    ///
+    /// This function directly corresponds to the `recvmmsg(2)` function.
+    ///
+    /// [`Socket::set_read_timeout`]: crate::Socket::set_read_timeout
+    #[cfg(all(feature = "all", target_os = "linux"))]
+    pub fn recv_multiple(
+        &self,
+        msgs: &mut [MmsgHdrMut<'_, '_, '_>],
+        flags: c_int,
+        timeout: Option<Duration>,
+    ) -> io::Result<usize> {
+        let mut timeout = timeout.map(|timeout| libc::timespec {
+            tv_sec: min(timeout.as_secs(), libc::time_t::MAX as u64) as libc::time_t,
+            tv_nsec: timeout.subsec_nanos() as _,
+        });
+        let timeout_ptr = match timeout.as_mut() {
+            Some(timeout) => timeout as *mut libc::timespec,
+            None => ptr::null_mut(),
+        };
+        syscall!(recvmmsg(
+            self.as_raw(),
+            msgs.as_mut_ptr().cast(),
+            min(msgs.len(), libc::c_uint::MAX as usize) as libc::c_uint,
+            flags as _,
+            timeout_ptr,
+        ))
+        .map(|n| n as usize)
+    }
+}
+
+/// Berkeley Packet Filter (BPF).
+///
+/// See [`Socket::attach_filter`].
+///
+/// [`Socket::attach_filter`]: crate::Socket::attach_filter
+#[cfg(all(feature = "all", any(target_os = "linux", target_os = "android")))]
+#[repr(transparent)]
+pub struct SockFilter {
+    filter: libc::sock_filter,
+}
+
+#[cfg(all(feature = "all", any(target_os = "linux", target_os = "android")))]
+impl SockFilter {
+    /// Create new `SockFilter`.
+    pub const fn new(code: u16, jt: u8, jf: u8, k: u32) -> SockFilter {
+        SockFilter {
+            filter: libc::sock_filter { code, jt, jf, k },
+        }
+    }
+
+    /// Returns the opcode.
+    pub const fn code(&self) -> u16 {
+        self.filter.code
+    }
+
+    /// Returns the jump offset if true.
+    pub const fn jt(&self) -> u8 {
+        self.filter.jt
+    }
+
+    /// Returns the jump offset if false.
+    pub const fn jf(&self) -> u8 {
+        self.filter.jf
+    }
+
+    /// Returns the generic field `k`.
+    pub const fn k(&self) -> u32 {
+        self.filter.k
+    }
+}
+
+#[cfg(all(feature = "all", target_os = "linux"))]
+impl SockFilter {
+    /// Program for [`Socket::attach_reuseport_filter`] that selects the
+    /// socket based on the CPU handling the packet (`SKF_AD_CPU`): socket
+    /// `cpu % group_size`.
+    ///
+    /// Combined with one socket per CPU (in order) and
+    /// [`Socket::set_cpu_affinity`] this keeps packets on the CPU they're
+    /// received on.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `group_size` is zero.
+    ///
+    /// [`Socket::attach_reuseport_filter`]: crate::Socket::attach_reuseport_filter
+    /// [`Socket::set_cpu_affinity`]: crate::Socket::set_cpu_affinity
+    pub const fn reuseport_cpu(group_size: u32) -> [SockFilter; 3] {
+        assert!(group_size != 0, "group size must not be zero");
+        [
+            // A = CPU.
+            SockFilter::new(
+                (libc::BPF_LD | libc::BPF_W | libc::BPF_ABS) as u16,
+                0,
+                0,
+                (libc::SKF_AD_OFF + libc::SKF_AD_CPU) as u32,
+            ),
+            // A %= group_size.
+            SockFilter::new(
+                (libc::BPF_ALU | libc::BPF_MOD | libc::BPF_K) as u16,
+                0,
+                0,
+                group_size,
+            ),
+            // Return A.
+            SockFilter::new((libc::BPF_RET | libc::BPF_A) as u16, 0, 0, 0),
+        ]
+    }
+
+    /// Program for [`Socket::attach_reuseport_filter`] that selects the
+    /// socket based on a hash of the source and destination addresses and
+    /// ports of the packet: socket `hash % group_size`.
+    ///
+    /// Unlike the flow hash of the kernel (`SKF_AD_RXHASH`), which isn't
+    /// always computed when the program runs, the hash is computed by the
+    /// program from the IPv4 or IPv6 header and TCP or UDP header. IPv6
+    /// extension headers are not skipped: for such packets the first four
+    /// bytes of the first extension header are hashed in place of the ports.
+    /// These are the same for all packets of a flow, so a flow is still
+    /// delivered to a single socket, but flows only differing in their ports
+    /// are not spread over the group.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `group_size` is zero.
+    ///
+    /// [`Socket::attach_reuseport_filter`]: crate::Socket::attach_reuseport_filter
+    pub const fn reuseport_hash(group_size: u32) -> [SockFilter; 42] {
+        assert!(group_size != 0, "group size must not be zero");
+        const NET: u32 = libc::SKF_NET_OFF as u32;
+        const LD_B: u16 = (libc::BPF_LD | libc::BPF_B | libc::BPF_ABS) as u16;
+        const LD_W: u16 = (libc::BPF_LD | libc::BPF_W | libc::BPF_ABS) as u16;
+        const LD_IND: u16 = (libc::BPF_LD | libc::BPF_W | libc::BPF_IND) as u16;
+        const LDX_MSH: u16 = (libc::BPF_LDX | libc::BPF_B | libc::BPF_MSH) as u16;
+        const LDX_MEM: u16 = (libc::BPF_LDX | libc::BPF_W | libc::BPF_MEM) as u16;
+        const ST: u16 = libc::BPF_ST as u16;
+        const TAX: u16 = (libc::BPF_MISC | libc::BPF_TAX) as u16;
+        const XOR: u16 = (libc::BPF_ALU | libc::BPF_XOR | libc::BPF_X) as u16;
+        const ALU_K: u16 = (libc::BPF_ALU | libc::BPF_K) as u16;
+        const JEQ: u16 = (libc::BPF_JMP | libc::BPF_JEQ | libc::BPF_K) as u16;
+        const JA: u16 = (libc::BPF_JMP | libc::BPF_JA) as u16;
+        const RET_A: u16 = (libc::BPF_RET | libc::BPF_A) as u16;
+        const fn op(code: u16, k: u32) -> SockFilter {
+            SockFilter::new(code, 0, 0, k)
+        }
+        [
+            // A = IP version.
+            op(LD_B, NET),
+            op(ALU_K | libc::BPF_RSH as u16, 4),
+            SockFilter::new(JEQ, 10, 0, 6),
+            // IPv4: A = source ^ destination address.
+            op(LD_W, NET + 12),
+            op(TAX, 0),
+            op(LD_W, NET + 16),
+            op(XOR, 0),
+            op(ST, 0),
+            // X = IP header length, A ^= ports.
+            op(LDX_MSH, NET),
+            op(LD_IND, NET),
+            op(LDX_MEM, 0),
+            op(XOR, 0),
+            op(JA, 25),
+            // IPv6: A = xor of all words of the addresses.
+            op(LD_W, NET + 8),
+            op(TAX, 0),
+            op(LD_W, NET + 12),
+            op(XOR, 0),
+            op(TAX, 0),
+            op(LD_W, NET + 16),
+            op(XOR, 0),
+            op(TAX, 0),
+            op(LD_W, NET + 20),
+            op(XOR, 0),
+            op(TAX, 0),
+            op(LD_W, NET + 24),
+            op(XOR, 0),
+            op(TAX, 0),
+            op(LD_W, NET + 28),
+            op(XOR, 0),
+            op(TAX, 0),
+            op(LD_W, NET + 32),
+            op(XOR, 0),
+            op(TAX, 0),
+            op(LD_W, NET + 36),
+            op(XOR, 0),
+            // A ^= ports, following the fixed size header.
+            op(TAX, 0),
+            op(LD_W, NET + 40),
+            op(XOR, 0),
+            // Mix the bits (Fibonacci hashing) and select the socket.
+            op(ALU_K | libc::BPF_MUL as u16, 0x9e37_79b1),
+            op(ALU_K | libc::BPF_RSH as u16, 16),
+            op(ALU_K | libc::BPF_MOD as u16, group_size),
+            op(RET_A, 0),
+        ]
+    }
+}
+
+#[cfg(all(feature = "all", any(target_os = "linux", target_os = "android")))]
+impl std::fmt::Debug for SockFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        #[cfg(target_os = "linux")]
+        if let Some(insn) = crate::bpf::Instruction::decode(self) {
+            return write!(f, "SockFilter({insn})");
+        }
+        f.debug_struct("SockFilter")
+            .field("code", &self.code())
+            .field("jt", &self.jt())
+            .field("jf", &self.jf())
+            .field("k", &self.k())
+            .finish()
+    }
+}
+
+/// See [`Socket::dccp_available_ccids`].
+///
+/// [`Socket::dccp_available_ccids`]: crate::Socket::dccp_available_ccids
+#[cfg(all(feature = "all", target_os = "linux"))]
+#[derive(Debug)]
+pub struct CcidEndpoints<const N: usize> {
+    endpoints: [u8; N],
+    length: u32,
+}
+
+#[cfg(all(feature = "all", target_os = "linux"))]
+impl<const N: usize> std::ops::Deref for CcidEndpoints<N> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.endpoints[0..self.length as usize]
+    }
+}
+
+/// Credentials of a Unix process, see `unix(7)`.
+///
+/// Can be sent to another process using [`ControlMessageBuilder::with_credentials`].
+///
+/// [`ControlMessageBuilder::with_credentials`]: crate::ControlMessageBuilder::with_credentials
+#[cfg(all(feature = "all", target_os = "linux"))]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct UnixCredentials {
+    pid: u32,
+    uid: u32,
+    gid: u32,
+}
+
+#[cfg(all(feature = "all", target_os = "linux"))]
+impl UnixCredentials {
+    /// Create new credentials from the process id, user id and group id.
+    pub const fn new(pid: u32, uid: u32, gid: u32) -> UnixCredentials {
+        UnixCredentials { pid, uid, gid }
+    }
+
+    /// Returns the process id.
+    pub const fn pid(&self) -> u32 {
+        self.pid
+    }
+
+    /// Returns the user id.
+    pub const fn uid(&self) -> u32 {
+        self.uid
+    }
+
+    /// Returns the group id.
+    pub const fn gid(&self) -> u32 {
+        self.gid
+    }
+}
+
+/// Configuration of a single message sent using [`Socket::send_multiple`].
+///
+/// This wraps `mmsghdr`, see [`MsgHdr`] for the variant used by `sendmsg(2)`.
+///
+/// [`Socket::send_multiple`]: crate::Socket::send_multiple
+/// [`MsgHdr`]: crate::MsgHdr
+#[cfg(all(feature = "all", target_os = "linux"))]
+#[repr(transparent)]
+pub struct MmsgHdr<'addr, 'bufs, 'control> {
+    inner: libc::mmsghdr,
+    #[allow(clippy::type_complexity)]
+    _lifetimes: PhantomData<(&'addr SockAddr, &'bufs IoSlice<'bufs>, &'control [u8])>,
+}
+
+#[cfg(all(feature = "all", target_os = "linux"))]
+impl<'addr, 'bufs, 'control> MmsgHdr<'addr, 'bufs, 'control> {
+    /// Create a new `MmsgHdr` with all empty/zero fields.
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> MmsgHdr<'addr, 'bufs, 'control> {
+        // SAFETY: all zero is valid for `mmsghdr`.
+        MmsgHdr {
+            inner: unsafe { mem::zeroed() },
+            _lifetimes: PhantomData,
+        }
+    }
+
+    /// Set the address (name) of the message.
+    ///
+    /// Corresponds to setting `msg_name` and `msg_namelen`.
+    pub fn with_addr(mut self, addr: &'addr SockAddr) -> Self {
+        set_msghdr_name(&mut self.inner.msg_hdr, addr);
+        self
+    }
+
+    /// Set the buffer(s) of the message.
+    ///
+    /// Corresponds to setting `msg_iov` and `msg_iovlen`.
+    pub fn with_buffers(mut self, bufs: &'bufs [IoSlice<'_>]) -> Self {
+        let ptr = bufs.as_ptr() as *mut _;
+        set_msghdr_iov(&mut self.inner.msg_hdr, ptr, bufs.len());
+        self
+    }
+
+    /// Set the control buffer of the message.
+    ///
+    /// Corresponds to setting `msg_control` and `msg_controllen`.
+    pub fn with_control(mut self, buf: &'control [u8]) -> Self {
+        let ptr = buf.as_ptr() as *mut _;
+        set_msghdr_control(&mut self.inner.msg_hdr, ptr, buf.len());
+        self
+    }
+
+    /// Returns the number of bytes sent for this message.
+    ///
+    /// Corresponds to `msg_len`.
+    pub fn len(&self) -> usize {
+        self.inner.msg_len as usize
+    }
+
+    /// Returns `true` if no bytes were sent for this message.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(all(feature = "all", target_os = "linux"))]
+impl<'addr, 'bufs, 'control> std::fmt::Debug for MmsgHdr<'addr, 'bufs, 'control> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MmsgHdr")
+            .field("len", &self.len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Configuration of a single message received using [`Socket::recv_multiple`].
+///
+/// This wraps `mmsghdr`, see [`MsgHdrMut`] for the variant used by
+/// `recvmsg(2)`. Like `MsgHdrMut` the header is updated by the system call, so
+/// it should be recreated before it's used again.
+///
+/// [`Socket::recv_multiple`]: crate::Socket::recv_multiple
+/// [`MsgHdrMut`]: crate::MsgHdrMut
+#[cfg(all(feature = "all", target_os = "linux"))]
+#[repr(transparent)]
+pub struct MmsgHdrMut<'addr, 'bufs, 'control> {
+    inner: libc::mmsghdr,
+    #[allow(clippy::type_complexity)]
+    _lifetimes: PhantomData<(
+        &'addr mut SockAddrStorage,
+        &'bufs mut crate::MaybeUninitSlice<'bufs>,
+        &'control mut [u8],
+    )>,
+}
+
+#[cfg(all(feature = "all", target_os = "linux"))]
+impl<'addr, 'bufs, 'control> MmsgHdrMut<'addr, 'bufs, 'control> {
+    /// Create a new `MmsgHdrMut` with all empty/zero fields.
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> MmsgHdrMut<'addr, 'bufs, 'control> {
+        // SAFETY: all zero is valid for `mmsghdr`.
+        MmsgHdrMut {
+            inner: unsafe { mem::zeroed() },
+            _lifetimes: PhantomData,
+        }
+    }
+
+    /// Set the storage for the address (name) of the message.
+    ///
+    /// Corresponds to setting `msg_name` and `msg_namelen`. The received
+    /// address can be retrieved using [`MmsgHdrMut::addr`].
+    pub fn with_addr(mut self, storage: &'addr mut SockAddrStorage) -> Self {
+        self.inner.msg_hdr.msg_namelen = storage.size_of();
+        self.inner.msg_hdr.msg_name = (storage as *mut SockAddrStorage).cast();
+        self
+    }
+
+    /// Set the mutable buffer(s) of the message.
+    ///
+    /// Corresponds to setting `msg_iov` and `msg_iovlen`.
+    pub fn with_buffers(mut self, bufs: &'bufs mut [crate::MaybeUninitSlice<'_>]) -> Self {
+        set_msghdr_iov(
+            &mut self.inner.msg_hdr,
+            bufs.as_mut_ptr().cast(),
+            bufs.len(),
+        );
+        self
+    }
+
+    /// Set the mutable control buffer of the message.
+    ///
+    /// Corresponds to setting `msg_control` and `msg_controllen`.
+    pub fn with_control(mut self, buf: &'control mut [MaybeUninit<u8>]) -> Self {
+        set_msghdr_control(&mut self.inner.msg_hdr, buf.as_mut_ptr().cast(), buf.len());
+        self
+    }
+
+    /// Returns the number of bytes received for this message.
+    ///
+    /// Corresponds to `msg_len`.
+    pub fn len(&self) -> usize {
+        self.inner.msg_len as usize
+    }
+
+    /// Returns `true` if no bytes were received for this message.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the address the message was received from.
+    ///
+    /// Returns `None` if no address storage was set using
+    /// [`MmsgHdrMut::with_addr`].
+    pub fn addr(&self) -> Option<SockAddr> {
+        let name = self.inner.msg_hdr.msg_name as *const SockAddrStorage;
+        if name.is_null() {
+            return None;
+        }
+        let len = min(
+            self.inner.msg_hdr.msg_namelen,
+            size_of::<libc::sockaddr_storage>() as socklen_t,
+        );
+        // SAFETY: `with_addr` ensures `name` points to a valid storage, which
+        // the OS filled in (up to) `len` bytes of.
+        Some(unsafe { SockAddr::new(ptr::read(name), len) })
+    }
+
+    /// Returns the flags of the message.
+    pub fn flags(&self) -> RecvFlags {
+        msghdr_flags(&self.inner.msg_hdr)
+    }
+
+    /// Gets the length of the control buffer.
+    ///
+    /// Can be used to determine how much, if any, of the control buffer was
+    /// filled by `recvmmsg`.
+    ///
+    /// Corresponds to `msg_controllen`.
+    pub fn control_len(&self) -> usize {
+        msghdr_control_len(&self.inner.msg_hdr)
+    }
+
+    /// Returns an iterator over the control messages received.
+    ///
+    /// See [`MsgHdrMut::control_messages`].
+    ///
+    /// [`MsgHdrMut::control_messages`]: crate::MsgHdrMut::control_messages
+    pub fn control_messages(&self) -> crate::ControlMessages<'_> {
+        // SAFETY: `msg_controllen` is set to the length of the control buffer
+        // in `with_control` and only lowered by the OS.
+        unsafe { crate::cmsg::control_messages(&self.inner.msg_hdr) }
+    }
+}
+
+#[cfg(all(feature = "all", target_os = "linux"))]
+impl<'addr, 'bufs, 'control> std::fmt::Debug for MmsgHdrMut<'addr, 'bufs, 'control> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MmsgHdrMut")
+            .field("len", &self.len())
+            .field("flags", &self.flags())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Iterator over the datagrams in a buffer coalesced by generic receive
+/// offload (GRO).
+///
+/// See [`Socket::recv_from_gro`].
+///
+/// [`Socket::recv_from_gro`]: crate::Socket::recv_from_gro
+#[cfg(all(feature = "all", target_os = "linux"))]
+#[derive(Clone, Debug)]
+pub struct GroSegments<'a> {
+    /// `None` once all segments are returned.
+    buf: Option<&'a [u8]>,
+    segment_size: usize,
+}
+
+#[cfg(all(feature = "all", target_os = "linux"))]
+impl<'a> GroSegments<'a> {
+    /// Create a new iterator that splits `buf` into datagrams of
+    /// `segment_size` bytes (only the last one can be smaller).
+    ///
+    /// The segment size is passed in a [`ControlMessage::UdpGro`] message.
+    /// A `segment_size` of zero returns the entire buffer as one datagram. An
+    /// empty `buf` is returned as a single, empty, datagram.
+    ///
+    /// [`ControlMessage::UdpGro`]: crate::ControlMessage::UdpGro
+    pub const fn new(buf: &'a [u8], segment_size: usize) -> GroSegments<'a> {
+        GroSegments {
+            buf: Some(buf),
+            segment_size,
+        }
+    }
+
+    /// Returns the size of the segments.
+    pub const fn segment_size(&self) -> usize {
+        self.segment_size
+    }
+}
+
+#[cfg(all(feature = "all", target_os = "linux"))]
+impl<'a> Iterator for GroSegments<'a> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<&'a [u8]> {
+        let buf = self.buf?;
+        let size = match self.segment_size {
+            0 => buf.len(),
+            size => min(size, buf.len()),
+        };
+        let (segment, rest) = buf.split_at(size);
+        self.buf = (!rest.is_empty()).then_some(rest);
+        Some(segment)
+    }
+}
+
+/// Flags for the `SO_TIMESTAMPING` option, see [`Socket::set_timestamping`].
+///
+/// Flags can be combined using the `|` operator. See the Linux kernel's
+/// [timestamping documentation] for a description of the flags.
+///
+/// [`Socket::set_timestamping`]: crate::Socket::set_timestamping
+/// [timestamping documentation]: https://docs.kernel.org/networking/timestamping.html
+#[cfg(all(feature = "all", target_os = "linux"))]
+#[derive(Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub struct TimestampingFlags(u32);
+
+#[cfg(all(feature = "all", target_os = "linux"))]
+impl TimestampingFlags {
+    /// Request transmit timestamps generated by the network adapter,
+    /// `SOF_TIMESTAMPING_TX_HARDWARE`.
+    pub const TX_HARDWARE: TimestampingFlags =
+        TimestampingFlags(libc::SOF_TIMESTAMPING_TX_HARDWARE);
+    /// Request transmit timestamps when the data leaves the kernel,
+    /// `SOF_TIMESTAMPING_TX_SOFTWARE`.
+    pub const TX_SOFTWARE: TimestampingFlags =
+        TimestampingFlags(libc::SOF_TIMESTAMPING_TX_SOFTWARE);
+    /// Request receive timestamps generated by the network adapter,
+    /// `SOF_TIMESTAMPING_RX_HARDWARE`.
+    pub const RX_HARDWARE: TimestampingFlags =
+        TimestampingFlags(libc::SOF_TIMESTAMPING_RX_HARDWARE);
+    /// Request receive timestamps when the data enters the kernel,
+    /// `SOF_TIMESTAMPING_RX_SOFTWARE`.
+    pub const RX_SOFTWARE: TimestampingFlags =
+        TimestampingFlags(libc::SOF_TIMESTAMPING_RX_SOFTWARE);
+    /// Report software timestamps, `SOF_TIMESTAMPING_SOFTWARE`.
+    pub const SOFTWARE: TimestampingFlags = TimestampingFlags(libc::SOF_TIMESTAMPING_SOFTWARE);
+    /// Deprecated and ignored by the kernel, `SOF_TIMESTAMPING_SYS_HARDWARE`.
+    pub const SYS_HARDWARE: TimestampingFlags =
+        TimestampingFlags(libc::SOF_TIMESTAMPING_SYS_HARDWARE);
+    /// Report hardware timestamps, `SOF_TIMESTAMPING_RAW_HARDWARE`.
+    pub const RAW_HARDWARE: TimestampingFlags =
+        TimestampingFlags(libc::SOF_TIMESTAMPING_RAW_HARDWARE);
+    /// Add an identifier to every transmit timestamp,
+    /// `SOF_TIMESTAMPING_OPT_ID`. See [`TxTimestamp::id`].
+    ///
+    /// [`TxTimestamp::id`]: crate::TxTimestamp::id
+    pub const OPT_ID: TimestampingFlags = TimestampingFlags(libc::SOF_TIMESTAMPING_OPT_ID);
+    /// Request transmit timestamps before the data enters the packet
+    /// scheduler, `SOF_TIMESTAMPING_TX_SCHED`.
+    pub const TX_SCHED: TimestampingFlags = TimestampingFlags(libc::SOF_TIMESTAMPING_TX_SCHED);
+    /// Request transmit timestamps when all data in the send buffer has been
+    /// acknowledged (TCP only), `SOF_TIMESTAMPING_TX_ACK`.
+    pub const TX_ACK: TimestampingFlags = TimestampingFlags(libc::SOF_TIMESTAMPING_TX_ACK);
+    /// Pass along `IP_PKTINFO` messages with transmit timestamps,
+    /// `SOF_TIMESTAMPING_OPT_CMSG`.
+    pub const OPT_CMSG: TimestampingFlags = TimestampingFlags(libc::SOF_TIMESTAMPING_OPT_CMSG);
+    /// Only return the timestamp, not the original packet, with transmit
+    /// timestamps, `SOF_TIMESTAMPING_OPT_TSONLY`.
+    pub const OPT_TSONLY: TimestampingFlags = TimestampingFlags(libc::SOF_TIMESTAMPING_OPT_TSONLY);
+    /// Pass along statistics with transmit timestamps,
+    /// `SOF_TIMESTAMPING_OPT_STATS`.
+    pub const OPT_STATS: TimestampingFlags = TimestampingFlags(libc::SOF_TIMESTAMPING_OPT_STATS);
+    /// Pass along the receiving interface with hardware receive timestamps,
+    /// `SOF_TIMESTAMPING_OPT_PKTINFO`.
+    pub const OPT_PKTINFO: TimestampingFlags =
+        TimestampingFlags(libc::SOF_TIMESTAMPING_OPT_PKTINFO);
+    /// Report both software and hardware transmit timestamps, if both are
+    /// enabled, `SOF_TIMESTAMPING_OPT_TX_SWHW`.
+    pub const OPT_TX_SWHW: TimestampingFlags =
+        TimestampingFlags(libc::SOF_TIMESTAMPING_OPT_TX_SWHW);
+
+    /// Create flags from the raw `SOF_TIMESTAMPING_*` bits.
+    pub const fn from_bits(bits: u32) -> TimestampingFlags {
+        TimestampingFlags(bits)
+    }
+
+    /// Returns the raw `SOF_TIMESTAMPING_*` bits.
+    pub const fn bits(self) -> u32 {
+        self.0
+    }
+
+    /// Returns `true` if all flags in `other` are set in `self`.
+    pub const fn contains(self, other: TimestampingFlags) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+#[cfg(all(feature = "all", target_os = "linux"))]
+impl std::ops::BitOr for TimestampingFlags {
+    type Output = TimestampingFlags;
+
+    fn bitor(self, rhs: TimestampingFlags) -> TimestampingFlags {
+        TimestampingFlags(self.0 | rhs.0)
+    }
+}
+
+#[cfg(all(feature = "all", target_os = "linux"))]
+impl std::ops::BitOrAssign for TimestampingFlags {
+    fn bitor_assign(&mut self, rhs: TimestampingFlags) {
+        self.0 |= rhs.0;
+    }
+}
+
+#[cfg(all(feature = "all", target_os = "linux"))]
+impl std::fmt::Debug for TimestampingFlags {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        const NAMES: [(TimestampingFlags, &str); 15] = [
+            (TimestampingFlags::TX_HARDWARE, "TX_HARDWARE"),
+            (TimestampingFlags::TX_SOFTWARE, "TX_SOFTWARE"),
+            (TimestampingFlags::RX_HARDWARE, "RX_HARDWARE"),
+            (TimestampingFlags::RX_SOFTWARE, "RX_SOFTWARE"),
+            (TimestampingFlags::SOFTWARE, "SOFTWARE"),
+            (TimestampingFlags::SYS_HARDWARE, "SYS_HARDWARE"),
+            (TimestampingFlags::RAW_HARDWARE, "RAW_HARDWARE"),
+            (TimestampingFlags::OPT_ID, "OPT_ID"),
+            (TimestampingFlags::TX_SCHED, "TX_SCHED"),
+            (TimestampingFlags::TX_ACK, "TX_ACK"),
+            (TimestampingFlags::OPT_CMSG, "OPT_CMSG"),
+            (TimestampingFlags::OPT_TSONLY, "OPT_TSONLY"),
+            (TimestampingFlags::OPT_STATS, "OPT_STATS"),
+            (TimestampingFlags::OPT_PKTINFO, "OPT_PKTINFO"),
+            (TimestampingFlags::OPT_TX_SWHW, "OPT_TX_SWHW"),
+        ];
+        let mut s = f.debug_set();
+        let mut rest = self.0;
+        for (flag, name) in NAMES {
+            if self.contains(flag) {
+                s.entry(&format_args!("{name}"));
+                rest &= !flag.0;
+            }
+        }
+        if rest != 0 {
+            s.entry(&format_args!("{rest:#x}"));
+        }
+        s.finish()
+    }
+}
+
+/// Information about a TCP connection, see [`Socket::tcp_info`].
+///
+/// Older kernels return a shorter `tcp_info` structure, fields added after the
+/// original structure return `None` if they weren't returned by the kernel.
+///
+/// [`Socket::tcp_info`]: crate::Socket::tcp_info
+#[cfg(all(feature = "all", target_os = "linux"))]
+#[derive(Clone)]
+pub struct TcpInfo {
+    info: TcpInfoRaw,
+    len: usize,
+}
+
+/// `struct tcp_info` as of Linux 6.7: the original structure, as defined by
+/// libc, followed by the fields added since.
+#[cfg(all(feature = "all", target_os = "linux"))]
+#[repr(C)]
+#[derive(Copy, Clone)]
+struct TcpInfoRaw {
+    base: libc::tcp_info,
+    ext: [u8; TCP_INFO_SIZE - size_of::<libc::tcp_info>()],
+}
+
+/// State of a TCP connection, see [`TcpInfo::state`].
+///
+/// This is a newtype wrapper around the `TCP_*` states defined in the kernel's
+/// `include/net/tcp_states.h`, which libc doesn't define.
+#[cfg(all(feature = "all", target_os = "linux"))]
+#[derive(Copy, Clone, Eq, PartialEq, Hash)]
+pub struct TcpState(u8);
+
+#[cfg(all(feature = "all", target_os = "linux"))]
+impl TcpState {
+    /// `TCP_ESTABLISHED`.
+    pub const ESTABLISHED: TcpState = TcpState(1);
+    /// `TCP_SYN_SENT`.
+    pub const SYN_SENT: TcpState = TcpState(2);
+    /// `TCP_SYN_RECV`.
+    pub const SYN_RECV: TcpState = TcpState(3);
+    /// `TCP_FIN_WAIT1`.
+    pub const FIN_WAIT1: TcpState = TcpState(4);
+    /// `TCP_FIN_WAIT2`.
+    pub const FIN_WAIT2: TcpState = TcpState(5);
+    /// `TCP_TIME_WAIT`.
+    pub const TIME_WAIT: TcpState = TcpState(6);
+    /// `TCP_CLOSE`.
+    pub const CLOSE: TcpState = TcpState(7);
+    /// `TCP_CLOSE_WAIT`.
+    pub const CLOSE_WAIT: TcpState = TcpState(8);
+    /// `TCP_LAST_ACK`.
+    pub const LAST_ACK: TcpState = TcpState(9);
+    /// `TCP_LISTEN`.
+    pub const LISTEN: TcpState = TcpState(10);
+    /// `TCP_CLOSING`.
+    pub const CLOSING: TcpState = TcpState(11);
+    /// `TCP_NEW_SYN_RECV`.
+    pub const NEW_SYN_RECV: TcpState = TcpState(12);
+}
+
+#[cfg(all(feature = "all", target_os = "linux"))]
+impl From<u8> for TcpState {
+    fn from(state: u8) -> TcpState {
+        TcpState(state)
+    }
+}
+
+#[cfg(all(feature = "all", target_os = "linux"))]
+impl From<TcpState> for u8 {
+    fn from(state: TcpState) -> u8 {
+        state.0
+    }
+}
+
+#[cfg(all(feature = "all", target_os = "linux"))]
+impl std::fmt::Debug for TcpState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let string = match *self {
+            TcpState::ESTABLISHED => "TCP_ESTABLISHED",
+            TcpState::SYN_SENT => "TCP_SYN_SENT",
+            TcpState::SYN_RECV => "TCP_SYN_RECV",
+            TcpState::FIN_WAIT1 => "TCP_FIN_WAIT1",
+            TcpState::FIN_WAIT2 => "TCP_FIN_WAIT2",
+            TcpState::TIME_WAIT => "TCP_TIME_WAIT",
+            TcpState::CLOSE => "TCP_CLOSE",
+            TcpState::CLOSE_WAIT => "TCP_CLOSE_WAIT",
+            TcpState::LAST_ACK => "TCP_LAST_ACK",
+            TcpState::LISTEN => "TCP_LISTEN",
+            TcpState::CLOSING => "TCP_CLOSING",
+            TcpState::NEW_SYN_RECV => "TCP_NEW_SYN_RECV",
+            TcpState(n) => return write!(f, "{n}"),
+        };
+        f.write_str(string)
+    }
+}
+
+/// Size of `struct tcp_info` as of Linux 6.7.
+#[cfg(all(feature = "all", target_os = "linux"))]
+const TCP_INFO_SIZE: usize = 248;
+
+#[cfg(all(feature = "all", target_os = "linux"))]
+impl TcpInfo {
+    /// Create a `TcpInfo` from the `tcp_info` structure in `data`, e.g. as
+    /// returned by `NETLINK_SOCK_DIAG`.
+    ///
+    /// Returns `None` if `data` is shorter than the original structure.
+    pub(crate) fn from_bytes(data: &[u8]) -> Option<TcpInfo> {
+        if data.len() < size_of::<libc::tcp_info>() {
+            return None;
+        }
+        let len = data.len().min(size_of::<TcpInfoRaw>());
+        // SAFETY: all zeroes is valid for `tcp_info`.
+        let mut info: TcpInfoRaw = unsafe { mem::zeroed() };
+        // SAFETY: `len` is at most the size of `TcpInfoRaw`, which is only
+        // made up of integers.
+        unsafe {
+            ptr::copy_nonoverlapping(data.as_ptr(), ptr::addr_of_mut!(info).cast::<u8>(), len)
+        };
+        Some(TcpInfo { info, len })
+    }
+
+    /// Read the integer at `offset` in the fields added after the original
+    /// structure, if returned by the kernel.
+    fn read<T: Copy>(&self, offset: usize) -> Option<T> {
+        if offset + size_of::<T>() > self.len {
+            return None;
+        }
+        let ext = offset - size_of::<libc::tcp_info>();
+        // SAFETY: checked the bounds above, only used for integers.
+        Some(unsafe { ptr::read_unaligned(self.info.ext.as_ptr().add(ext).cast()) })
+    }
+
+    /// Returns the bitfields in the byte at `offset` of the original
+    /// structure, which libc doesn't define on all targets.
+    fn bitfields(&self, offset: usize) -> u8 {
+        debug_assert!(offset < size_of::<libc::tcp_info>());
+        // SAFETY: `offset` is within the original structure.
+        unsafe { *ptr::addr_of!(self.info.base).cast::<u8>().add(offset) }
+    }
+
+    /// Returns the number of bytes of `tcp_info` returned by the kernel.
+    ///
+    /// This is at least the size of the original structure.
+    #[allow(clippy::len_without_is_empty)]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// State of the connection (`tcpi_state`).
+    pub fn state(&self) -> TcpState {
+        TcpState(self.info.base.tcpi_state)
+    }
+
+    /// State of the congestion avoidance, e.g. 0 for `TCP_CA_Open`
+    /// (`tcpi_ca_state`).
+    pub fn ca_state(&self) -> u8 {
+        self.info.base.tcpi_ca_state
+    }
+
+    /// Number of unrecovered retransmission timeouts (`tcpi_retransmits`).
+    pub fn retransmits(&self) -> u8 {
+        self.info.base.tcpi_retransmits
+    }
+
+    /// Number of unanswered zero window probes (`tcpi_probes`).
+    pub fn probes(&self) -> u8 {
+        self.info.base.tcpi_probes
+    }
+
+    /// Exponential backoff of the retransmission timer (`tcpi_backoff`).
+    pub fn backoff(&self) -> u8 {
+        self.info.base.tcpi_backoff
+    }
+
+    /// Options enabled on the connection, a combination of the
+    /// `TCPI_OPT_*` flags (`tcpi_options`).
+    pub fn options(&self) -> u8 {
+        self.info.base.tcpi_options
+    }
+
+    /// Window scale for sending (`tcpi_snd_wscale`).
+    pub fn snd_wscale(&self) -> u8 {
+        tcp_info_bits(self.bitfields(6), 0, 4)
+    }
+
+    /// Window scale for receiving (`tcpi_rcv_wscale`).
+    pub fn rcv_wscale(&self) -> u8 {
+        tcp_info_bits(self.bitfields(6), 4, 4)
+    }
+
+    /// Whether the delivery rate was limited by the application
+    /// (`tcpi_delivery_rate_app_limited`).
+    pub fn delivery_rate_app_limited(&self) -> bool {
+        tcp_info_bits(self.bitfields(7), 0, 1) != 0
+    }
+
+    /// Reason a TCP Fast Open attempt as client failed
+    /// (`tcpi_fastopen_client_fail`).
+    pub fn fastopen_client_fail(&self) -> u8 {
+        tcp_info_bits(self.bitfields(7), 1, 2)
+    }
+
+    /// Retransmission timeout (`tcpi_rto`).
+    pub fn rto(&self) -> Duration {
+        Duration::from_micros(self.info.base.tcpi_rto.into())
+    }
+
+    /// Delayed acknowledgement timeout (`tcpi_ato`).
+    pub fn ato(&self) -> Duration {
+        Duration::from_micros(self.info.base.tcpi_ato.into())
+    }
+
+    /// Maximum segment size for sending, in bytes (`tcpi_snd_mss`).
+    pub fn snd_mss(&self) -> u32 {
+        self.info.base.tcpi_snd_mss
+    }
+
+    /// Estimated maximum segment size of the peer, in bytes (`tcpi_rcv_mss`).
+    pub fn rcv_mss(&self) -> u32 {
+        self.info.base.tcpi_rcv_mss
+    }
+
+    /// Number of segments sent, but not yet acknowledged (`tcpi_unacked`).
+    pub fn unacked(&self) -> u32 {
+        self.info.base.tcpi_unacked
+    }
+
+    /// Number of segments selectively acknowledged (SACK) by the peer (`tcpi_sacked`).
+    pub fn sacked(&self) -> u32 {
+        self.info.base.tcpi_sacked
+    }
+
+    /// Number of segments considered lost (`tcpi_lost`).
+    pub fn lost(&self) -> u32 {
+        self.info.base.tcpi_lost
+    }
+
+    /// Number of segments currently being retransmitted (`tcpi_retrans`).
+    pub fn retrans(&self) -> u32 {
+        self.info.base.tcpi_retrans
+    }
+
+    /// Number of forward acknowledged segments (no longer used by the kernel) (`tcpi_fackets`).
+    pub fn fackets(&self) -> u32 {
+        self.info.base.tcpi_fackets
+    }
+
+    /// Time since the last data was sent (`tcpi_last_data_sent`).
+    pub fn last_data_sent(&self) -> Duration {
+        Duration::from_millis(self.info.base.tcpi_last_data_sent.into())
+    }
+
+    /// Time since the last acknowledgement was sent (`tcpi_last_ack_sent`).
+    ///
+    /// Not tracked by the kernel, always zero.
+    pub fn last_ack_sent(&self) -> Duration {
+        Duration::from_millis(self.info.base.tcpi_last_ack_sent.into())
+    }
+
+    /// Time since the last data was received (`tcpi_last_data_recv`).
+    pub fn last_data_recv(&self) -> Duration {
+        Duration::from_millis(self.info.base.tcpi_last_data_recv.into())
+    }
+
+    /// Time since the last acknowledgement was received (`tcpi_last_ack_recv`).
+    pub fn last_ack_recv(&self) -> Duration {
+        Duration::from_millis(self.info.base.tcpi_last_ack_recv.into())
+    }
+
+    /// Path MTU, in bytes (`tcpi_pmtu`).
+    pub fn pmtu(&self) -> u32 {
+        self.info.base.tcpi_pmtu
+    }
+
+    /// Slow start threshold of the receive window, in bytes (`tcpi_rcv_ssthresh`).
+    pub fn rcv_ssthresh(&self) -> u32 {
+        self.info.base.tcpi_rcv_ssthresh
+    }
+
+    /// Smoothed round trip time (`tcpi_rtt`).
+    pub fn rtt(&self) -> Duration {
+        Duration::from_micros(self.info.base.tcpi_rtt.into())
+    }
+
+    /// Round trip time variance (`tcpi_rttvar`).
+    pub fn rttvar(&self) -> Duration {
+        Duration::from_micros(self.info.base.tcpi_rttvar.into())
+    }
+
+    /// Slow start threshold, in segments (`tcpi_snd_ssthresh`).
+    pub fn snd_ssthresh(&self) -> u32 {
+        self.info.base.tcpi_snd_ssthresh
+    }
+
+    /// Congestion window, in segments (`tcpi_snd_cwnd`).
+    pub fn snd_cwnd(&self) -> u32 {
+        self.info.base.tcpi_snd_cwnd
+    }
+
+    /// Advertised maximum segment size, in bytes (`tcpi_advmss`).
+    pub fn advmss(&self) -> u32 {
+        self.info.base.tcpi_advmss
+    }
+
+    /// Reordering metric, in segments (`tcpi_reordering`).
+    pub fn reordering(&self) -> u32 {
+        self.info.base.tcpi_reordering
+    }
+
+    /// Round trip time as measured by the receiver (`tcpi_rcv_rtt`).
+    pub fn rcv_rtt(&self) -> Duration {
+        Duration::from_micros(self.info.base.tcpi_rcv_rtt.into())
+    }
+
+    /// Space of the receive buffer used for auto tuning, in bytes (`tcpi_rcv_space`).
+    pub fn rcv_space(&self) -> u32 {
+        self.info.base.tcpi_rcv_space
+    }
+
+    /// Total number of retransmitted segments (`tcpi_total_retrans`).
+    pub fn total_retrans(&self) -> u32 {
+        self.info.base.tcpi_total_retrans
+    }
+
+    /// Current pacing rate, in bytes per second (`tcpi_pacing_rate`).
+    ///
+    /// Returns `None` if not supported by the kernel (added in Linux 3.15).
+    pub fn pacing_rate(&self) -> Option<u64> {
+        self.read::<u64>(104)
+    }
+
+    /// Maximum pacing rate (`SO_MAX_PACING_RATE`), in bytes per second (`tcpi_max_pacing_rate`).
+    ///
+    /// Returns `None` if not supported by the kernel (added in Linux 3.15).
+    pub fn max_pacing_rate(&self) -> Option<u64> {
+        self.read::<u64>(112)
+    }
+
+    /// Number of bytes acknowledged by the peer (`tcpi_bytes_acked`).
+    ///
+    /// Returns `None` if not supported by the kernel (added in Linux 4.1).
+    pub fn bytes_acked(&self) -> Option<u64> {
+        self.read::<u64>(120)
+    }
+
+    /// Number of bytes received (`tcpi_bytes_received`).
+    ///
+    /// Returns `None` if not supported by the kernel (added in Linux 4.1).
+    pub fn bytes_received(&self) -> Option<u64> {
+        self.read::<u64>(128)
+    }
+
+    /// Number of segments sent (`tcpi_segs_out`).
+    ///
+    /// Returns `None` if not supported by the kernel (added in Linux 4.2).
+    pub fn segs_out(&self) -> Option<u32> {
+        self.read::<u32>(136)
+    }
+
+    /// Number of segments received (`tcpi_segs_in`).
+    ///
+    /// Returns `None` if not supported by the kernel (added in Linux 4.2).
+    pub fn segs_in(&self) -> Option<u32> {
+        self.read::<u32>(140)
+    }
+
+    /// Number of bytes in the send buffer not yet sent (`tcpi_notsent_bytes`).
+    ///
+    /// Returns `None` if not supported by the kernel (added in Linux 4.6).
+    pub fn notsent_bytes(&self) -> Option<u32> {
+        self.read::<u32>(144)
+    }
+
+    /// Minimum round trip time seen (`tcpi_min_rtt`).
+    ///
+    /// Returns `None` if not supported by the kernel (added in Linux 4.6).
+    pub fn min_rtt(&self) -> Option<Duration> {
+        self.read::<u32>(148)
+            .map(|v| Duration::from_micros(v.into()))
+    }
+
+    /// Number of segments received containing data (`tcpi_data_segs_in`).
+    ///
+    /// Returns `None` if not supported by the kernel (added in Linux 4.6).
+    pub fn data_segs_in(&self) -> Option<u32> {
+        self.read::<u32>(152)
+    }
+
+    /// Number of segments sent containing data (`tcpi_data_segs_out`).
+    ///
+    /// Returns `None` if not supported by the kernel (added in Linux 4.6).
+    pub fn data_segs_out(&self) -> Option<u32> {
+        self.read::<u32>(156)
+    }
+
+    /// Most recent delivery rate, in bytes per second (`tcpi_delivery_rate`).
+    ///
+    /// Returns `None` if not supported by the kernel (added in Linux 4.9).
+    pub fn delivery_rate(&self) -> Option<u64> {
+        self.read::<u64>(160)
+    }
+
+    /// Time spent busy sending data (`tcpi_busy_time`).
+    ///
+    /// Returns `None` if not supported by the kernel (added in Linux 4.10).
+    pub fn busy_time(&self) -> Option<Duration> {
+        self.read::<u64>(168).map(Duration::from_micros)
+    }
+
+    /// Time spent limited by the receive window of the peer (`tcpi_rwnd_limited`).
+    ///
+    /// Returns `None` if not supported by the kernel (added in Linux 4.10).
+    pub fn rwnd_limited(&self) -> Option<Duration> {
+        self.read::<u64>(176).map(Duration::from_micros)
+    }
+
+    /// Time spent limited by the send buffer (`tcpi_sndbuf_limited`).
+    ///
+    /// Returns `None` if not supported by the kernel (added in Linux 4.10).
+    pub fn sndbuf_limited(&self) -> Option<Duration> {
+        self.read::<u64>(184).map(Duration::from_micros)
+    }
+
+    /// Number of segments delivered, including retransmissions (`tcpi_delivered`).
+    ///
+    /// Returns `None` if not supported by the kernel (added in Linux 4.18).
+    pub fn delivered(&self) -> Option<u32> {
+        self.read::<u32>(192)
+    }
+
+    /// Number of segments delivered with an ECN congestion experienced (CE)
+    /// mark (`tcpi_delivered_ce`).
+    ///
+    /// Returns `None` if not supported by the kernel (added in Linux 4.18).
+    pub fn delivered_ce(&self) -> Option<u32> {
+        self.read::<u32>(196)
+    }
+
+    /// Number of bytes sent, including retransmissions (`tcpi_bytes_sent`).
+    ///
+    /// Returns `None` if not supported by the kernel (added in Linux 4.19).
+    pub fn bytes_sent(&self) -> Option<u64> {
+        self.read::<u64>(200)
+    }
+
+    /// Number of bytes retransmitted (`tcpi_bytes_retrans`).
+    ///
+    /// Returns `None` if not supported by the kernel (added in Linux 4.19).
+    pub fn bytes_retrans(&self) -> Option<u64> {
+        self.read::<u64>(208)
+    }
+
+    /// Number of duplicate segments reported by D-SACK (`tcpi_dsack_dups`).
+    ///
+    /// Returns `None` if not supported by the kernel (added in Linux 4.19).
+    pub fn dsack_dups(&self) -> Option<u32> {
+        self.read::<u32>(216)
+    }
+
+    /// Number of reordering events seen (`tcpi_reord_seen`).
+    ///
+    /// Returns `None` if not supported by the kernel (added in Linux 4.19).
+    pub fn reord_seen(&self) -> Option<u32> {
+        self.read::<u32>(220)
+    }
+
+    /// Number of out-of-order packets received (`tcpi_rcv_ooopack`).
+    ///
+    /// Returns `None` if not supported by the kernel (added in Linux 5.4).
+    pub fn rcv_ooopack(&self) -> Option<u32> {
+        self.read::<u32>(224)
+    }
+
+    /// Receive window advertised by the peer, in bytes (`tcpi_snd_wnd`).
+    ///
+    /// Returns `None` if not supported by the kernel (added in Linux 5.4).
+    pub fn snd_wnd(&self) -> Option<u32> {
+        self.read::<u32>(228)
+    }
+
+    /// Receive window advertised to the peer, in bytes (`tcpi_rcv_wnd`).
+    ///
+    /// Returns `None` if not supported by the kernel (added in Linux 6.2).
+    pub fn rcv_wnd(&self) -> Option<u32> {
+        self.read::<u32>(232)
+    }
+
+    /// Number of times the flow label was changed (IPv6) due to timeouts (`tcpi_rehash`).
+    ///
+    /// Returns `None` if not supported by the kernel (added in Linux 6.2).
+    pub fn rehash(&self) -> Option<u32> {
+        self.read::<u32>(236)
+    }
+
+    /// Number of retransmission timeouts (`tcpi_total_rto`).
+    ///
+    /// Returns `None` if not supported by the kernel (added in Linux 6.7).
+    pub fn total_rto(&self) -> Option<u16> {
+        self.read::<u16>(240)
+    }
+
+    /// Number of recoveries from a retransmission timeout (`tcpi_total_rto_recoveries`).
+    ///
+    /// Returns `None` if not supported by the kernel (added in Linux 6.7).
+    pub fn total_rto_recoveries(&self) -> Option<u16> {
+        self.read::<u16>(242)
+    }
+
+    /// Total time spent recovering from retransmission timeouts (`tcpi_total_rto_time`).
+    ///
+    /// Returns `None` if not supported by the kernel (added in Linux 6.7).
+    pub fn total_rto_time(&self) -> Option<Duration> {
+        self.read::<u32>(244)
+            .map(|v| Duration::from_millis(v.into()))
+    }
+}
+
+/// Returns the `bits` wide bitfield starting at bit `n` of `byte`, for the
+/// bitfields in `tcp_info`.
+#[cfg(all(feature = "all", target_os = "linux"))]
+const fn tcp_info_bits(byte: u8, n: u32, bits: u32) -> u8 {
+    let mask = (1 << bits) - 1;
+    if cfg!(target_endian = "little") {
+        (byte >> n) & mask
+    } else {
+        (byte >> (8 - n - bits)) & mask
+    }
+}
+
+#[cfg(all(feature = "all", target_os = "linux"))]
+impl std::fmt::Debug for TcpInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TcpInfo")
+            .field("state", &self.state())
+            .field("ca_state", &self.ca_state())
+            .field("retransmits", &self.retransmits())
+            .field("probes", &self.probes())
+            .field("backoff", &self.backoff())
+            .field("options", &self.options())
+            .field("snd_wscale", &self.snd_wscale())
+            .field("rcv_wscale", &self.rcv_wscale())
+            .field(
+                "delivery_rate_app_limited",
+                &self.delivery_rate_app_limited(),
+            )
+            .field("fastopen_client_fail", &self.fastopen_client_fail())
+            .field("rto", &self.rto())
+            .field("ato", &self.ato())
+            .field("snd_mss", &self.snd_mss())
+            .field("rcv_mss", &self.rcv_mss())
+            .field("unacked", &self.unacked())
+            .field("sacked", &self.sacked())
+            .field("lost", &self.lost())
+            .field("retrans", &self.retrans())
+            .field("fackets", &self.fackets())
+            .field("last_data_sent", &self.last_data_sent())
+            .field("last_ack_sent", &self.last_ack_sent())
+            .field("last_data_recv", &self.last_data_recv())
+            .field("last_ack_recv", &self.last_ack_recv())
+            .field("pmtu", &self.pmtu())
+            .field("rcv_ssthresh", &self.rcv_ssthresh())
+            .field("rtt", &self.rtt())
+            .field("rttvar", &self.rttvar())
+            .field("snd_ssthresh", &self.snd_ssthresh())
+            .field("snd_cwnd", &self.snd_cwnd())
+            .field("advmss", &self.advmss())
+            .field("reordering", &self.reordering())
+            .field("rcv_rtt", &self.rcv_rtt())
+            .field("rcv_space", &self.rcv_space())
+            .field("total_retrans", &self.total_retrans())
+            .field("pacing_rate", &self.pacing_rate())
+            .field("max_pacing_rate", &self.max_pacing_rate())
+            .field("bytes_acked", &self.bytes_acked())
+            .field("bytes_received", &self.bytes_received())
+            .field("segs_out", &self.segs_out())
+            .field("segs_in", &self.segs_in())
+            .field("notsent_bytes", &self.notsent_bytes())
+            .field("min_rtt", &self.min_rtt())
+            .field("data_segs_in", &self.data_segs_in())
+            .field("data_segs_out", &self.data_segs_out())
+            .field("delivery_rate", &self.delivery_rate())
+            .field("busy_time", &self.busy_time())
+            .field("rwnd_limited", &self.rwnd_limited())
+            .field("sndbuf_limited", &self.sndbuf_limited())
+            .field("delivered", &self.delivered())
+            .field("delivered_ce", &self.delivered_ce())
+            .field("bytes_sent", &self.bytes_sent())
+            .field("bytes_retrans", &self.bytes_retrans())
+            .field("dsack_dups", &self.dsack_dups())
+            .field("reord_seen", &self.reord_seen())
+            .field("rcv_ooopack", &self.rcv_ooopack())
+            .field("snd_wnd", &self.snd_wnd())
+            .field("rcv_wnd", &self.rcv_wnd())
+            .field("rehash", &self.rehash())
+            .field("total_rto", &self.total_rto())
+            .field("total_rto_recoveries", &self.total_rto_recoveries())
+            .field("total_rto_time", &self.total_rto_time())
+            .finish()
+    }
+}
+
+/// Zerocopy completion notification, see
+/// [`Socket::recv_zerocopy_completion`].
+///
+/// Reports that the kernel no longer uses the buffers of the
+/// [`Socket::send_zerocopy`] calls with identifiers in the range
+/// `start..=end`. The identifiers are 32 bit counters, which wrap around.
+///
+/// [`Socket::recv_zerocopy_completion`]: crate::Socket::recv_zerocopy_completion
+/// [`Socket::send_zerocopy`]: crate::Socket::send_zerocopy
+#[cfg(all(feature = "all", target_os = "linux"))]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ZerocopyCompletion {
+    start: u32,
+    end: u32,
+    copied: bool,
+}
+
+#[cfg(all(feature = "all", target_os = "linux"))]
+impl ZerocopyCompletion {
+    /// Returns the identifier of the first completed send call.
+    pub const fn start(&self) -> u32 {
+        self.start
+    }
+
+    /// Returns the identifier of the last completed send call (inclusive).
+    pub const fn end(&self) -> u32 {
+        self.end
+    }
+
+    /// Returns `true` if the send call with identifier `id` is completed by
+    /// this notification.
+    pub const fn contains(&self, id: u32) -> bool {
+        // Handles the counter wrapping around.
+        id.wrapping_sub(self.start) <= self.end.wrapping_sub(self.start)
+    }
+
+    /// Returns `true` if the kernel fell back to copying the data
+    /// (`SO_EE_CODE_ZEROCOPY_COPIED`).
+    ///
+    /// In that case zerocopy didn't provide any benefit and a regular send
+    /// call is likely cheaper, e.g. for data sent over the loopback device.
+    pub const fn copied(&self) -> bool {
+        self.copied
+    }
+}
+
+/// Link-layer address of a packet socket, see [`SockAddr::as_link_layer`].
+#[cfg(all(feature = "all", target_os = "linux"))]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct LinkLayerAddr {
+    interface: u32,
+    protocol: u16,
+    hatype: u16,
+    pkttype: u8,
+    addr: [u8; 8],
+    addr_len: u8,
+}
+
+#[cfg(all(feature = "all", target_os = "linux"))]
+impl LinkLayerAddr {
+    pub(crate) fn from_sockaddr_ll(addr: &libc::sockaddr_ll) -> LinkLayerAddr {
+        LinkLayerAddr {
+            interface: addr.sll_ifindex as u32,
+            protocol: u16::from_be(addr.sll_protocol),
+            hatype: addr.sll_hatype,
+            pkttype: addr.sll_pkttype,
+            addr: addr.sll_addr,
+            addr_len: addr.sll_halen.min(8),
+        }
+    }
+
+    /// Index of the interface.
+    pub const fn interface(&self) -> u32 {
+        self.interface
+    }
+
+    /// Ethernet protocol (`ETH_P_*`) in native byte order.
+    pub const fn protocol(&self) -> u16 {
+        self.protocol
+    }
+
+    /// Hardware type (`ARPHRD_*`).
+    pub const fn hatype(&self) -> u16 {
+        self.hatype
+    }
+
+    /// Packet type (`PACKET_*`), e.g. `PACKET_HOST`.
+    pub const fn pkttype(&self) -> u8 {
+        self.pkttype
+    }
+
+    /// Hardware address, e.g. the MAC address.
+    pub fn hardware_address(&self) -> &[u8] {
+        &self.addr[..self.addr_len as usize]
+    }
+}
+
+/// Membership of a packet socket, see [`Socket::packet_add_membership`].
+///
+/// [`Socket::packet_add_membership`]: crate::Socket::packet_add_membership
+#[cfg(all(feature = "all", target_os = "linux"))]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum PacketMembership<'a> {
+    /// Put the interface in promiscuous mode, `PACKET_MR_PROMISC`.
+    Promiscuous,
+    /// Receive all multicast packets, `PACKET_MR_ALLMULTI`.
+    AllMulticast,
+    /// Join the link-layer multicast group with the hardware address,
+    /// `PACKET_MR_MULTICAST`.
+    Multicast(&'a [u8]),
+}
+
+#[cfg(all(feature = "all", target_os = "linux"))]
+impl<'a> PacketMembership<'a> {
+    /// Returns the `packet_mreq` for `interface`.
+    fn to_mreq(self, interface: u32) -> io::Result<libc::packet_mreq> {
+        let mut mreq = libc::packet_mreq {
+            mr_ifindex: interface as c_int,
+            mr_type: 0,
+            mr_alen: 0,
+            mr_address: [0; 8],
+        };
+        match self {
+            PacketMembership::Promiscuous => mreq.mr_type = libc::PACKET_MR_PROMISC as _,
+            PacketMembership::AllMulticast => mreq.mr_type = libc::PACKET_MR_ALLMULTI as _,
+            PacketMembership::Multicast(addr) => {
+                if addr.len() > mreq.mr_address.len() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "hardware address too long",
+                    ));
+                }
+                mreq.mr_type = libc::PACKET_MR_MULTICAST as _;
+                mreq.mr_alen = addr.len() as _;
+                mreq.mr_address[..addr.len()].copy_from_slice(addr);
+            }
+        }
+        Ok(mreq)
+    }
+}
+
+/// Statistics of a packet socket, see [`Socket::packet_statistics`].
+///
+/// [`Socket::packet_statistics`]: crate::Socket::packet_statistics
+#[cfg(all(feature = "all", target_os = "linux"))]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct PacketStatistics {
+    packets: u32,
+    drops: u32,
+    freeze_queue_count: Option<u32>,
+}
+
+#[cfg(all(feature = "all", target_os = "linux"))]
+impl PacketStatistics {
+    /// Number of packets received, including the dropped packets.
+    pub const fn packets(&self) -> u32 {
+        self.packets
+    }
+
+    /// Number of packets dropped, e.g. because the receive buffer was full.
+    pub const fn drops(&self) -> u32 {
+        self.drops
+    }
+
+    /// Number of times the receive queue was frozen, only returned for
+    /// sockets using a `TPACKET_V3` ring.
+    pub const fn freeze_queue_count(&self) -> Option<u32> {
+        self.freeze_queue_count
+    }
+}
+
+/// Path MTU discovery mode, see [`Socket::set_mtu_discover_v4`] and
+/// [`Socket::set_mtu_discover_v6`].
+///
+/// [`Socket::set_mtu_discover_v4`]: crate::Socket::set_mtu_discover_v4
+/// [`Socket::set_mtu_discover_v6`]: crate::Socket::set_mtu_discover_v6
+#[cfg(all(feature = "all", target_os = "linux"))]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum PmtuDiscovery {
+    /// Never set the don't-fragment flag, packets larger than the path MTU
+    /// are fragmented, `IP_PMTUDISC_DONT`.
+    Dont,
+    /// Use per-route settings, `IP_PMTUDISC_WANT`.
+    Want,
+    /// Always set the don't-fragment flag, sending packets larger than the
+    /// known path MTU fails with `EMSGSIZE`, `IP_PMTUDISC_DO`.
+    Do,
+    /// Set the don't-fragment flag, but ignore the path MTU (only limited by
+    /// the interface MTU). Used to probe the path MTU, e.g. by QUIC
+    /// (RFC 8899), `IP_PMTUDISC_PROBE`.
+    Probe,
+    /// Always use the interface MTU and ignore ICMP "fragmentation needed"
+    /// messages, `IP_PMTUDISC_INTERFACE`.
+    Interface,
+    /// Like [`PmtuDiscovery::Interface`], but allow fragmentation of packets
+    /// larger than the interface MTU, `IP_PMTUDISC_OMIT`.
+    Omit,
+}
+
+#[cfg(all(feature = "all", target_os = "linux"))]
+impl PmtuDiscovery {
+    /// Returns the `IP_PMTUDISC_*` value, the `IPV6_PMTUDISC_*` values are
+    /// the same.
+    pub(crate) const fn to_raw(self) -> c_int {
+        match self {
+            PmtuDiscovery::Dont => libc::IP_PMTUDISC_DONT,
+            PmtuDiscovery::Want => libc::IP_PMTUDISC_WANT,
+            PmtuDiscovery::Do => libc::IP_PMTUDISC_DO,
+            PmtuDiscovery::Probe => libc::IP_PMTUDISC_PROBE,
+            PmtuDiscovery::Interface => libc::IP_PMTUDISC_INTERFACE,
+            PmtuDiscovery::Omit => libc::IP_PMTUDISC_OMIT,
+        }
+    }
+
+    pub(crate) fn from_raw(mode: c_int) -> io::Result<PmtuDiscovery> {
+        match mode {
+            libc::IP_PMTUDISC_DONT => Ok(PmtuDiscovery::Dont),
+            libc::IP_PMTUDISC_WANT => Ok(PmtuDiscovery::Want),
+            libc::IP_PMTUDISC_DO => Ok(PmtuDiscovery::Do),
+            libc::IP_PMTUDISC_PROBE => Ok(PmtuDiscovery::Probe),
+            libc::IP_PMTUDISC_INTERFACE => Ok(PmtuDiscovery::Interface),
+            libc::IP_PMTUDISC_OMIT => Ok(PmtuDiscovery::Omit),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "unknown path MTU discovery mode",
+            )),
+        }
+    }
+}
+
+/// Destination of a received packet, see [`Socket::recv_from_with_dst`].
+///
+/// [`Socket::recv_from_with_dst`]: crate::Socket::recv_from_with_dst
+#[cfg(all(feature = "all", target_os = "linux"))]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct PktInfo {
+    destination: IpAddr,
+    local: IpAddr,
+    interface: u32,
+}
+
+#[cfg(all(feature = "all", target_os = "linux"))]
+impl PktInfo {
+    /// Returns the destination address from the header of the packet.
+    pub const fn destination(&self) -> IpAddr {
+        self.destination
+    }
+
+    /// Returns the local address to use as source address when replying.
+    ///
+    /// For IPv4 this differs from the destination address for broadcast and
+    /// multicast packets, it's the address of the interface the packet was
+    /// received on. For IPv6 this is always the destination address.
+    pub const fn local(&self) -> IpAddr {
+        self.local
+    }
+
+    /// Returns the index of the interface the packet was received on.
+    pub const fn interface(&self) -> u32 {
+        self.interface
+    }
+}
+
+/// Configuration of a `PACKET_FANOUT` group, see
+/// [`Socket::set_packet_fanout`].
+///
+/// [`Socket::set_packet_fanout`]: crate::Socket::set_packet_fanout
+#[cfg(all(feature = "all", target_os = "linux"))]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct PacketFanout {
+    id: u16,
+    mode: FanoutMode,
+    flags: u8,
+}
+
+#[cfg(all(feature = "all", target_os = "linux"))]
+impl PacketFanout {
+    /// Create a new fanout configuration for the group with `id`, using
+    /// `mode` to select the socket for each packet.
+    ///
+    /// Group ids are scoped per network namespace. All sockets joining a
+    /// group must use the same mode and flags.
+    pub const fn new(id: u16, mode: FanoutMode) -> PacketFanout {
+        PacketFanout { id, mode, flags: 0 }
+    }
+
+    /// Move packets to the next socket in the group if the selected socket
+    /// is backlogged, `PACKET_FANOUT_FLAG_ROLLOVER`.
+    pub const fn with_rollover(mut self) -> Self {
+        self.flags |= (libc::PACKET_FANOUT_FLAG_ROLLOVER >> 8) as u8;
+        self
+    }
+
+    /// Defragment IP packets before selecting a socket, so that all
+    /// fragments are delivered to the same socket,
+    /// `PACKET_FANOUT_FLAG_DEFRAG`.
+    pub const fn with_defrag(mut self) -> Self {
+        self.flags |= (libc::PACKET_FANOUT_FLAG_DEFRAG >> 8) as u8;
+        self
+    }
+
+    /// Returns the group id.
+    pub const fn id(&self) -> u16 {
+        self.id
+    }
+
+    /// Returns the mode used to select a socket.
+    pub const fn mode(&self) -> FanoutMode {
+        self.mode
+    }
+
+    /// Returns `true` if rollover is enabled.
+    pub const fn is_rollover(&self) -> bool {
+        self.flags & (libc::PACKET_FANOUT_FLAG_ROLLOVER >> 8) as u8 != 0
+    }
+
+    /// Returns `true` if defragmentation is enabled.
+    pub const fn is_defrag(&self) -> bool {
+        self.flags & (libc::PACKET_FANOUT_FLAG_DEFRAG >> 8) as u8 != 0
+    }
+
+    /// Returns the option value, the group id in the low 16 bits and the mode
+    /// and flags in the high 16 bits.
+    fn to_raw(self) -> c_int {
+        let ty = u32::from(self.mode.to_raw()) | (u32::from(self.flags) << 8);
+        (u32::from(self.id) | ty << 16) as c_int
+    }
+
+    /// Inverse of [`PacketFanout::to_raw`].
+    fn from_raw(value: c_int) -> PacketFanout {
+        let value = value as u32;
+        PacketFanout {
+            id: value as u16,
+            mode: FanoutMode::from_raw((value >> 16) as u8),
+            flags: (value >> 24) as u8,
+        }
+    }
+}
+
+/// Mode used to select the socket in a packet fanout group for each packet,
+/// see [`PacketFanout`].
+#[cfg(all(feature = "all", target_os = "linux"))]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum FanoutMode {
+    /// Select a socket based on the flow hash of the packet,
+    /// `PACKET_FANOUT_HASH`.
+    Hash,
+    /// Select sockets in a round-robin manner, `PACKET_FANOUT_LB`.
+    LoadBalance,
+    /// Select the socket based on the CPU the packet arrived on,
+    /// `PACKET_FANOUT_CPU`.
+    Cpu,
+    /// Deliver all packets to a single socket, moving to the next one when
+    /// it is backlogged, `PACKET_FANOUT_ROLLOVER`.
+    Rollover,
+    /// Select a random socket, `PACKET_FANOUT_RND`.
+    Random,
+    /// Select the socket based on the recorded receive queue of the packet,
+    /// `PACKET_FANOUT_QM`.
+    QueueMapping,
+    /// Select the socket using a classic BPF program, `PACKET_FANOUT_CBPF`.
+    ///
+    /// See [`Socket::attach_packet_fanout_filter`].
+    ///
+    /// [`Socket::attach_packet_fanout_filter`]: crate::Socket::attach_packet_fanout_filter
+    Cbpf,
+    /// Select the socket using an eBPF program, `PACKET_FANOUT_EBPF`.
+    ///
+    /// See [`Socket::attach_packet_fanout_ebpf`].
+    ///
+    /// [`Socket::attach_packet_fanout_ebpf`]: crate::Socket::attach_packet_fanout_ebpf
+    Ebpf,
+    /// Mode not known to this crate.
+    Other(u8),
+}
+
+#[cfg(all(feature = "all", target_os = "linux"))]
+impl FanoutMode {
+    const fn to_raw(self) -> u8 {
+        (match self {
+            FanoutMode::Hash => libc::PACKET_FANOUT_HASH,
+            FanoutMode::LoadBalance => libc::PACKET_FANOUT_LB,
+            FanoutMode::Cpu => libc::PACKET_FANOUT_CPU,
+            FanoutMode::Rollover => libc::PACKET_FANOUT_ROLLOVER,
+            FanoutMode::Random => libc::PACKET_FANOUT_RND,
+            FanoutMode::QueueMapping => libc::PACKET_FANOUT_QM,
+            FanoutMode::Cbpf => libc::PACKET_FANOUT_CBPF,
+            FanoutMode::Ebpf => libc::PACKET_FANOUT_EBPF,
+            FanoutMode::Other(mode) => mode as u32,
+        }) as u8
+    }
+
+    const fn from_raw(mode: u8) -> FanoutMode {
+        match mode as u32 {
+            libc::PACKET_FANOUT_HASH => FanoutMode::Hash,
+            libc::PACKET_FANOUT_LB => FanoutMode::LoadBalance,
+            libc::PACKET_FANOUT_CPU => FanoutMode::Cpu,
+            libc::PACKET_FANOUT_ROLLOVER => FanoutMode::Rollover,
+            libc::PACKET_FANOUT_RND => FanoutMode::Random,
+            libc::PACKET_FANOUT_QM => FanoutMode::QueueMapping,
+            libc::PACKET_FANOUT_CBPF => FanoutMode::Cbpf,
+            libc::PACKET_FANOUT_EBPF => FanoutMode::Ebpf,
+            _ => FanoutMode::Other(mode),
+        }
+    }
+}
+
+/// Returns `addr` as `__kernel_sockaddr_storage` and the prefix length to
+/// use, defaulting to the full length of the address.
+#[cfg(all(feature = "all", target_os = "linux"))]
+fn tcp_auth_addr(
+    addr: &SockAddr,
+    prefix_len: Option<u8>,
+) -> io::Result<(libc::sockaddr_storage, u8)> {
+    let max_prefix_len = match addr.family() as c_int {
+        libc::AF_INET => 32,
+        libc::AF_INET6 => 128,
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "TCP authentication requires an IPv4 or IPv6 address",
+            ))
+        }
+    };
+    let prefix_len = prefix_len.unwrap_or(max_prefix_len);
+    if prefix_len > max_prefix_len {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "prefix length too long for address",
+        ));
+    }
+    // SAFETY: all zeroes is valid for `sockaddr_storage` and the address is
+    // at most the size of it.
+    let storage = unsafe {
+        let mut storage: libc::sockaddr_storage = mem::zeroed();
+        ptr::copy_nonoverlapping(
+            addr.as_ptr().cast::<u8>(),
+            ptr::addr_of_mut!(storage).cast::<u8>(),
+            addr.len() as usize,
+        );
+        storage
+    };
+    Ok((storage, prefix_len))
+}
+
+/// Returns an error if `key` is longer than `max_len` bytes.
+#[cfg(all(feature = "all", target_os = "linux"))]
+fn check_tcp_auth_key(key: &[u8], max_len: usize) -> io::Result<()> {
+    if key.len() > max_len {
+        Err(io::Error::new(io::ErrorKind::InvalidInput, "key too long"))
+    } else {
+        Ok(())
+    }
+}
+
+/// `struct tcp_md5sig`, not defined in libc.
+#[cfg(all(feature = "all", target_os = "linux"))]
+#[repr(C)]
+#[derive(Copy, Clone)]
+struct TcpMd5Sig {
+    addr: libc::sockaddr_storage,
+    flags: u8,
+    prefix_len: u8,
+    key_len: u16,
+    ifindex: c_int,
+    key: [u8; libc::TCP_MD5SIG_MAXKEYLEN],
+}
+
+/// A TCP MD5 signature key, see [`Socket::add_tcp_md5sig`].
+///
+/// [`Socket::add_tcp_md5sig`]: crate::Socket::add_tcp_md5sig
+#[cfg(all(feature = "all", target_os = "linux"))]
+#[derive(Clone)]
+pub struct TcpMd5Key<'a> {
+    addr: &'a SockAddr,
+    key: &'a [u8],
+    prefix_len: Option<u8>,
+    interface: Option<u32>,
+}
+
+#[cfg(all(feature = "all", target_os = "linux"))]
+impl<'a> std::fmt::Debug for TcpMd5Key<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TcpMd5Key")
+            .field("addr", &self.addr)
+            .field("key", &RedactedKey(self.key))
+            .field("prefix_len", &self.prefix_len)
+            .field("interface", &self.interface)
+            .finish()
+    }
+}
+
+/// Formats a secret key as its length only, so that it doesn't end up in
+/// logs.
+#[cfg(all(feature = "all", target_os = "linux"))]
+struct RedactedKey<'a>(&'a [u8]);
+
+#[cfg(all(feature = "all", target_os = "linux"))]
+impl<'a> std::fmt::Debug for RedactedKey<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<{} bytes>", self.0.len())
+    }
+}
+
+#[cfg(all(feature = "all", target_os = "linux"))]
+impl<'a> TcpMd5Key<'a> {
+    /// Create a key for the peer with address `addr`, the port is ignored.
+    ///
+    /// The key can be at most 80 bytes (`TCP_MD5SIG_MAXKEYLEN`).
+    pub const fn new(addr: &'a SockAddr, key: &'a [u8]) -> TcpMd5Key<'a> {
+        TcpMd5Key {
+            addr,
+            key,
+            prefix_len: None,
+            interface: None,
+        }
+    }
+
+    /// Use the key for all peers in the network with `prefix_len`, rather
+    /// than only the peer with the address.
+    pub const fn with_prefix_len(mut self, prefix_len: u8) -> Self {
+        self.prefix_len = Some(prefix_len);
+        self
+    }
+
+    /// Only use the key for connections on the layer 3 device with the
+    /// interface index, e.g. a VRF device.
+    pub const fn with_interface(mut self, interface: u32) -> Self {
+        self.interface = Some(interface);
+        self
+    }
+
+    /// Returns the option to use and `tcp_md5sig` for `key`.
+    fn to_raw(&self, key: &[u8]) -> io::Result<(c_int, TcpMd5Sig)> {
+        check_tcp_auth_key(key, libc::TCP_MD5SIG_MAXKEYLEN)?;
+        let (addr, prefix_len) = tcp_auth_addr(self.addr, self.prefix_len)?;
+        let mut md5sig = TcpMd5Sig {
+            addr,
+            flags: 0,
+            prefix_len: 0,
+            key_len: key.len() as u16,
+            ifindex: 0,
+            key: [0; libc::TCP_MD5SIG_MAXKEYLEN],
+        };
+        md5sig.key[..key.len()].copy_from_slice(key);
+        if self.prefix_len.is_some() {
+            md5sig.flags |= TCP_MD5SIG_FLAG_PREFIX;
+            md5sig.prefix_len = prefix_len;
+        }
+        if let Some(interface) = self.interface {
+            md5sig.flags |= TCP_MD5SIG_FLAG_IFINDEX;
+            md5sig.ifindex = interface as c_int;
+        }
+        let opt = if md5sig.flags == 0 {
+            libc::TCP_MD5SIG
+        } else {
+            libc::TCP_MD5SIG_EXT
+        };
+        Ok((opt, md5sig))
+    }
+}
+
+/// Returns the mask of bit `n` of a 32 bit bitfield in the TCP-AO structures.
+#[cfg(all(feature = "all", target_os = "linux"))]
+const fn tcp_ao_bit32(n: u32) -> u32 {
+    if cfg!(target_endian = "little") {
+        1 << n
+    } else {
+        1 << (31 - n)
+    }
+}
+
+/// Returns the mask of bit `n` of a 16 bit bitfield in the TCP-AO structures.
+#[cfg(all(feature = "all", target_os = "linux"))]
+const fn tcp_ao_bit16(n: u16) -> u16 {
+    if cfg!(target_endian = "little") {
+        1 << n
+    } else {
+        1 << (15 - n)
+    }
+}
+
+/// `struct tcp_ao_add`, not defined in libc.
+#[cfg(all(feature = "all", target_os = "linux"))]
+#[repr(C, align(8))]
+#[derive(Copy, Clone)]
+struct TcpAoAdd {
+    addr: libc::sockaddr_storage,
+    alg_name: [u8; 64],
+    ifindex: i32,
+    /// `set_current:1`, `set_rnext:1`.
+    flags: u32,
+    reserved2: u16,
+    prefix: u8,
+    sndid: u8,
+    rcvid: u8,
+    maclen: u8,
+    keyflags: u8,
+    keylen: u8,
+    key: [u8; TCP_AO_MAXKEYLEN],
+}
+
+/// `struct tcp_ao_del`, not defined in libc.
+#[cfg(all(feature = "all", target_os = "linux"))]
+#[repr(C, align(8))]
+#[derive(Copy, Clone)]
+struct TcpAoDel {
+    addr: libc::sockaddr_storage,
+    ifindex: i32,
+    /// `set_current:1`, `set_rnext:1`, `del_async:1`.
+    flags: u32,
+    reserved2: u16,
+    prefix: u8,
+    sndid: u8,
+    rcvid: u8,
+    current_key: u8,
+    rnext: u8,
+    keyflags: u8,
+}
+
+/// `struct tcp_ao_info_opt`, not defined in libc.
+#[cfg(all(feature = "all", target_os = "linux"))]
+#[repr(C, align(8))]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+struct TcpAoInfoOpt {
+    /// `set_current:1`, `set_rnext:1`, `ao_required:1`, `set_counters:1`,
+    /// `accept_icmps:1`.
+    flags: u32,
+    reserved2: u16,
+    current_key: u8,
+    rnext: u8,
+    pkt_good: u64,
+    pkt_bad: u64,
+    pkt_key_not_found: u64,
+    pkt_ao_required: u64,
+    pkt_dropped_icmp: u64,
+}
+
+/// `struct tcp_ao_getsockopt`, not defined in libc.
+#[cfg(all(feature = "all", target_os = "linux"))]
+#[repr(C, align(8))]
+#[derive(Copy, Clone)]
+struct TcpAoGetsockopt {
+    addr: libc::sockaddr_storage,
+    alg_name: [u8; 64],
+    key: [u8; TCP_AO_MAXKEYLEN],
+    nkeys: u32,
+    /// `is_current:1`, `is_rnext:1`, `get_all:1`.
+    flags: u16,
+    reserved2: u16,
+    prefix: u8,
+    sndid: u8,
+    rcvid: u8,
+    maclen: u8,
+    keyflags: u8,
+    keylen: u8,
+    ifindex: i32,
+    pkt_good: u64,
+    pkt_bad: u64,
+}
+
+/// Create the `TCP_AO_GET_KEYS` buffer for up to `nkeys` keys.
+///
+/// The option length passed to the kernel is the size of a single
+/// `tcp_ao_getsockopt`, the number of elements in the buffer is passed in the
+/// `nkeys` field of the first element.
+#[cfg(all(feature = "all", target_os = "linux"))]
+fn tcp_ao_keys_request(nkeys: usize) -> Vec<TcpAoGetsockopt> {
+    // SAFETY: all zeroes is valid for `TcpAoGetsockopt`.
+    let mut keys: Vec<TcpAoGetsockopt> = vec![unsafe { mem::zeroed() }; nkeys.max(1)];
+    keys[0].nkeys = keys.len() as u32;
+    keys[0].flags = tcp_ao_bit16(2); // `get_all`.
+    keys
+}
+
+/// A TCP Authentication Option key, see [`Socket::add_tcp_ao_key`].
+///
+/// [`Socket::add_tcp_ao_key`]: crate::Socket::add_tcp_ao_key
+#[cfg(all(feature = "all", target_os = "linux"))]
+#[derive(Clone)]
+pub struct TcpAoKey<'a> {
+    addr: &'a SockAddr,
+    algorithm: &'a str,
+    key: &'a [u8],
+    send_id: u8,
+    recv_id: u8,
+    prefix_len: Option<u8>,
+    interface: Option<u32>,
+    mac_len: u8,
+    exclude_options: bool,
+    current: bool,
+    rnext: bool,
+}
+
+#[cfg(all(feature = "all", target_os = "linux"))]
+impl<'a> std::fmt::Debug for TcpAoKey<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TcpAoKey")
+            .field("addr", &self.addr)
+            .field("algorithm", &self.algorithm)
+            .field("key", &RedactedKey(self.key))
+            .field("send_id", &self.send_id)
+            .field("recv_id", &self.recv_id)
+            .field("prefix_len", &self.prefix_len)
+            .field("interface", &self.interface)
+            .field("mac_len", &self.mac_len)
+            .field("exclude_options", &self.exclude_options)
+            .field("current", &self.current)
+            .field("rnext", &self.rnext)
+            .finish()
+    }
+}
+
+#[cfg(all(feature = "all", target_os = "linux"))]
+impl<'a> TcpAoKey<'a> {
+    /// Create a key for the peer with address `addr`, the port is ignored.
+    ///
+    /// `algorithm` is the name of the MAC algorithm in the kernel crypto API,
+    /// e.g. `"hmac(sha1)"` or `"cmac(aes128)"`. The key can be at most 80
+    /// bytes (`TCP_AO_MAXKEYLEN`). `send_id` and `recv_id` identify the key
+    /// in outgoing and incoming segments respectively, the peer must use the
+    /// same key with the ids swapped.
+    pub const fn new(
+        addr: &'a SockAddr,
+        algorithm: &'a str,
+        key: &'a [u8],
+        send_id: u8,
+        recv_id: u8,
+    ) -> TcpAoKey<'a> {
+        TcpAoKey {
+            addr,
+            algorithm,
+            key,
+            send_id,
+            recv_id,
+            prefix_len: None,
+            interface: None,
+            mac_len: 0,
+            exclude_options: false,
+            current: false,
+            rnext: false,
+        }
+    }
+
+    /// Use the key for all peers in the network with `prefix_len`, rather
+    /// than only the peer with the address.
+    pub const fn with_prefix_len(mut self, prefix_len: u8) -> Self {
+        self.prefix_len = Some(prefix_len);
+        self
+    }
+
+    /// Only use the key for connections on the layer 3 device with the
+    /// interface index, e.g. a VRF device (`TCP_AO_KEYF_IFINDEX`).
+    pub const fn with_interface(mut self, interface: u32) -> Self {
+        self.interface = Some(interface);
+        self
+    }
+
+    /// Set the length of the message authentication code in bytes. Defaults
+    /// to 12 bytes (96 bits).
+    pub const fn with_mac_len(mut self, mac_len: u8) -> Self {
+        self.mac_len = mac_len;
+        self
+    }
+
+    /// Exclude TCP options from the MAC (`TCP_AO_KEYF_EXCLUDE_OPT`).
+    pub const fn with_exclude_options(mut self) -> Self {
+        self.exclude_options = true;
+        self
+    }
+
+    /// Immediately use the key as current key, i.e. to sign outgoing
+    /// segments.
+    pub const fn with_current(mut self) -> Self {
+        self.current = true;
+        self
+    }
+
+    /// Immediately use the key as RNext key, i.e. request the peer to use it.
+    pub const fn with_rnext(mut self) -> Self {
+        self.rnext = true;
+        self
+    }
+
+    /// Returns the `TCP_AO_KEYF_*` flags.
+    fn keyflags(&self) -> u8 {
+        let mut keyflags = 0;
+        if self.interface.is_some() {
+            keyflags |= TCP_AO_KEYF_IFINDEX;
+        }
+        if self.exclude_options {
+            keyflags |= TCP_AO_KEYF_EXCLUDE_OPT;
+        }
+        keyflags
+    }
+
+    fn to_add(&self) -> io::Result<TcpAoAdd> {
+        check_tcp_auth_key(self.key, TCP_AO_MAXKEYLEN)?;
+        let (addr, prefix) = tcp_auth_addr(self.addr, self.prefix_len)?;
+        let mut add = TcpAoAdd {
+            addr,
+            alg_name: [0; 64],
+            ifindex: self.interface.unwrap_or(0) as i32,
+            flags: 0,
+            reserved2: 0,
+            prefix,
+            sndid: self.send_id,
+            rcvid: self.recv_id,
+            maclen: self.mac_len,
+            keyflags: self.keyflags(),
+            keylen: self.key.len() as u8,
+            key: [0; TCP_AO_MAXKEYLEN],
+        };
+        // Must be NUL terminated.
+        if self.algorithm.len() >= add.alg_name.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "algorithm name too long",
+            ));
+        }
+        add.alg_name[..self.algorithm.len()].copy_from_slice(self.algorithm.as_bytes());
+        add.key[..self.key.len()].copy_from_slice(self.key);
+        if self.current {
+            add.flags |= tcp_ao_bit32(0);
+        }
+        if self.rnext {
+            add.flags |= tcp_ao_bit32(1);
+        }
+        Ok(add)
+    }
+
+    fn to_del(&self) -> io::Result<TcpAoDel> {
+        let (addr, prefix) = tcp_auth_addr(self.addr, self.prefix_len)?;
+        Ok(TcpAoDel {
+            addr,
+            ifindex: self.interface.unwrap_or(0) as i32,
+            flags: 0,
+            reserved2: 0,
+            prefix,
+            sndid: self.send_id,
+            rcvid: self.recv_id,
+            current_key: 0,
+            rnext: 0,
+            keyflags: self.keyflags(),
+        })
     }
+}
 
-    /// Set value for the `DCCP_SOCKOPT_SEND_CSCOV` option on this socket.
-    ///
-    /// Both this option and `DCCP_SOCKOPT_RECV_CSCOV` are used for setting the
-    /// partial checksum coverage. The default is that checksums always cover
-    /// the entire packet and that only fully covered application data is
-    /// accepted by the receiver. Hence, when using this feature on the sender,
-    /// it must be enabled at the receiver too, with suitable choice of CsCov.
-    #[cfg(all(feature = "all", target_os = "linux"))]
-    pub fn set_dccp_send_cscov(&self, level: u32) -> io::Result<()> {
-        unsafe {
-            setsockopt(
-                self.as_raw(),
-                libc::SOL_DCCP,
-                libc::DCCP_SOCKOPT_SEND_CSCOV,
-                level,
-            )
+/// TCP Authentication Option state of a socket, see
+/// [`Socket::tcp_ao_info`] and [`Socket::set_tcp_ao_info`].
+///
+/// [`Socket::tcp_ao_info`]: crate::Socket::tcp_ao_info
+/// [`Socket::set_tcp_ao_info`]: crate::Socket::set_tcp_ao_info
+#[cfg(all(feature = "all", target_os = "linux"))]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct TcpAoInfo {
+    info: TcpAoInfoOpt,
+}
+
+#[cfg(all(feature = "all", target_os = "linux"))]
+impl TcpAoInfo {
+    /// Create a new `TcpAoInfo` that doesn't change any of the keys and
+    /// accepts segments without TCP-AO.
+    pub const fn new() -> TcpAoInfo {
+        TcpAoInfo {
+            info: TcpAoInfoOpt {
+                flags: 0,
+                reserved2: 0,
+                current_key: 0,
+                rnext: 0,
+                pkt_good: 0,
+                pkt_bad: 0,
+                pkt_key_not_found: 0,
+                pkt_ao_required: 0,
+                pkt_dropped_icmp: 0,
+            },
         }
     }
 
-    /// Get the value of the `DCCP_SOCKOPT_SEND_CSCOV` option on this socket.
-    ///
-    /// For more information on this option see [`set_dccp_send_cscov`].
-    ///
-    /// [`set_dccp_send_cscov`]: crate::Socket::set_dccp_send_cscov
-    #[cfg(all(feature = "all", target_os = "linux"))]
-    pub fn dccp_send_cscov(&self) -> io::Result<u32> {
-        unsafe { getsockopt(self.as_raw(), libc::SOL_DCCP, libc::DCCP_SOCKOPT_SEND_CSCOV) }
+    /// Use the key with `send_id` as current key.
+    pub const fn with_current_key(mut self, send_id: u8) -> Self {
+        self.info.flags |= tcp_ao_bit32(0);
+        self.info.current_key = send_id;
+        self
     }
 
-    /// Set the value of the `DCCP_SOCKOPT_RECV_CSCOV` option on this socket.
-    ///
-    /// This option is only useful when combined with [`set_dccp_send_cscov`].
-    ///
-    /// [`set_dccp_send_cscov`]: crate::Socket::set_dccp_send_cscov
-    #[cfg(all(feature = "all", target_os = "linux"))]
-    pub fn set_dccp_recv_cscov(&self, level: u32) -> io::Result<()> {
-        unsafe {
-            setsockopt(
-                self.as_raw(),
-                libc::SOL_DCCP,
-                libc::DCCP_SOCKOPT_RECV_CSCOV,
-                level,
-            )
-        }
+    /// Use the key with `recv_id` as RNext key.
+    pub const fn with_rnext_key(mut self, recv_id: u8) -> Self {
+        self.info.flags |= tcp_ao_bit32(1);
+        self.info.rnext = recv_id;
+        self
     }
 
-    /// Get the value of the `DCCP_SOCKOPT_RECV_CSCOV` option on this socket.
-    ///
-    /// For more information on this option see [`set_dccp_recv_cscov`].
-    ///
-    /// [`set_dccp_recv_cscov`]: crate::Socket::set_dccp_recv_cscov
-    #[cfg(all(feature = "all", target_os = "linux"))]
-    pub fn dccp_recv_cscov(&self) -> io::Result<u32> {
-        unsafe { getsockopt(self.as_raw(), libc::SOL_DCCP, libc::DCCP_SOCKOPT_RECV_CSCOV) }
+    /// Drop segments without TCP-AO.
+    pub const fn with_ao_required(mut self, required: bool) -> Self {
+        self.info.flags = set_flag(self.info.flags, tcp_ao_bit32(2), required);
+        self
     }
 
-    /// Set value for the `DCCP_SOCKOPT_QPOLICY_TXQLEN` option on this socket.
-    ///
-    /// This option sets the maximum length of the output queue. A zero value is
-    /// interpreted as unbounded queue length.
-    #[cfg(all(feature = "all", target_os = "linux"))]
-    pub fn set_dccp_qpolicy_txqlen(&self, length: u32) -> io::Result<()> {
-        unsafe {
-            setsockopt(
-                self.as_raw(),
-                libc::SOL_DCCP,
-                libc::DCCP_SOCKOPT_QPOLICY_TXQLEN,
-                length,
-            )
+    /// Accept ICMP errors that affect the connection, e.g. port
+    /// unreachable. By default these are ignored as they are not
+    /// authenticated (RFC 5925, section 7.8).
+    pub const fn with_accept_icmps(mut self, accept: bool) -> Self {
+        self.info.flags = set_flag(self.info.flags, tcp_ao_bit32(4), accept);
+        self
+    }
+
+    /// Returns the send id of the current key, if any.
+    pub const fn current_key(&self) -> Option<u8> {
+        if self.info.flags & tcp_ao_bit32(0) != 0 {
+            Some(self.info.current_key)
+        } else {
+            None
         }
     }
 
-    /// Get the value of the `DCCP_SOCKOPT_QPOLICY_TXQLEN` on this socket.
-    ///
-    /// For more information on this option see [`set_dccp_qpolicy_txqlen`].
-    ///
-    /// [`set_dccp_qpolicy_txqlen`]: crate::Socket::set_dccp_qpolicy_txqlen
-    #[cfg(all(feature = "all", target_os = "linux"))]
-    pub fn dccp_qpolicy_txqlen(&self) -> io::Result<u32> {
-        unsafe {
-            getsockopt(
-                self.as_raw(),
-                libc::SOL_DCCP,
-                libc::DCCP_SOCKOPT_QPOLICY_TXQLEN,
-            )
+    /// Returns the receive id of the RNext key, if any.
+    pub const fn rnext_key(&self) -> Option<u8> {
+        if self.info.flags & tcp_ao_bit32(1) != 0 {
+            Some(self.info.rnext)
+        } else {
+            None
         }
     }
 
-    /// Get the value of the `DCCP_SOCKOPT_AVAILABLE_CCIDS` option on this socket.
-    ///
-    /// Returns the list of CCIDs supported by the endpoint.
-    ///
-    /// The parameter `N` is used to get the maximum number of supported
-    /// endpoints. The [documentation] recommends a minimum of four at the time
-    /// of writing.
-    ///
-    /// [documentation]: https://www.kernel.org/doc/html/latest/networking/dccp.html
-    #[cfg(all(feature = "all", target_os = "linux"))]
-    pub fn dccp_available_ccids<const N: usize>(&self) -> io::Result<CcidEndpoints<N>> {
-        let mut endpoints = [0; N];
-        let mut length = endpoints.len() as libc::socklen_t;
-        syscall!(getsockopt(
-            self.as_raw(),
-            libc::SOL_DCCP,
-            libc::DCCP_SOCKOPT_AVAILABLE_CCIDS,
-            endpoints.as_mut_ptr().cast(),
-            &mut length,
-        ))?;
-        Ok(CcidEndpoints { endpoints, length })
+    /// Returns `true` if segments without TCP-AO are dropped.
+    pub const fn is_ao_required(&self) -> bool {
+        self.info.flags & tcp_ao_bit32(2) != 0
     }
 
-    /// Get the value of the `DCCP_SOCKOPT_GET_CUR_MPS` option on this socket.
-    ///
-    /// This option retrieves the current maximum packet size (application
-    /// payload size) in bytes.
-    #[cfg(all(feature = "all", target_os = "linux"))]
-    pub fn dccp_cur_mps(&self) -> io::Result<u32> {
-        unsafe {
-            getsockopt(
-                self.as_raw(),
-                libc::SOL_DCCP,
-                libc::DCCP_SOCKOPT_GET_CUR_MPS,
-            )
-        }
+    /// Returns `true` if ICMP errors are accepted.
+    pub const fn is_accepting_icmps(&self) -> bool {
+        self.info.flags & tcp_ao_bit32(4) != 0
     }
 
-    /// Get the value for the `SO_BUSY_POLL` option on this socket.
-    ///
-    /// On Linux this function requires the `CAP_NET_ADMIN` capability.
-    #[cfg(all(feature = "all", target_os = "linux"))]
-    pub fn busy_poll(&self) -> io::Result<u32> {
-        unsafe { getsockopt(self.as_raw(), libc::SOL_SOCKET, libc::SO_BUSY_POLL) }
+    /// Number of segments that were successfully verified.
+    pub const fn packets_good(&self) -> u64 {
+        self.info.pkt_good
     }
 
-    /// Set the value for the `SO_BUSY_POLL` option on this socket.
-    ///
-    /// On Linux this function requires the `CAP_NET_ADMIN` capability.
-    #[cfg(all(feature = "all", target_os = "linux"))]
-    pub fn set_busy_poll(&self, busy_poll: u32) -> io::Result<()> {
-        unsafe {
-            setsockopt(
-                self.as_raw(),
-                libc::SOL_SOCKET,
-                libc::SO_BUSY_POLL,
-                busy_poll as c_int,
-            )
-        }
+    /// Number of segments that failed verification.
+    pub const fn packets_bad(&self) -> u64 {
+        self.info.pkt_bad
     }
-}
 
-/// Berkeley Packet Filter (BPF).
-///
-/// See [`Socket::attach_filter`].
-///
-/// [`Socket::attach_filter`]: crate::Socket::attach_filter
-#[cfg(all(feature = "all", any(target_os = "linux", target_os = "android")))]
-#[repr(transparent)]
-pub struct SockFilter {
-    // This field is only read indirectly by transmutes / pointer casts, so
-    // rustc emits a spurious warning saying that the field is never read.
-    #[allow(dead_code)]
-    filter: libc::sock_filter,
+    /// Number of segments dropped because no matching key was found.
+    pub const fn packets_key_not_found(&self) -> u64 {
+        self.info.pkt_key_not_found
+    }
+
+    /// Number of segments dropped because they were missing TCP-AO.
+    pub const fn packets_ao_required(&self) -> u64 {
+        self.info.pkt_ao_required
+    }
+
+    /// Number of ICMP errors that were ignored.
+    pub const fn packets_dropped_icmp(&self) -> u64 {
+        self.info.pkt_dropped_icmp
+    }
 }
 
-#[cfg(all(feature = "all", any(target_os = "linux", target_os = "android")))]
-impl SockFilter {
-    /// Create new `SockFilter`.
-    pub const fn new(code: u16, jt: u8, jf: u8, k: u32) -> SockFilter {
-        SockFilter {
-            filter: libc::sock_filter { code, jt, jf, k },
-        }
+#[cfg(all(feature = "all", target_os = "linux"))]
+impl Default for TcpAoInfo {
+    fn default() -> TcpAoInfo {
+        TcpAoInfo::new()
     }
 }
 
-#[cfg(all(feature = "all", any(target_os = "linux", target_os = "android")))]
-impl std::fmt::Debug for SockFilter {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("SockFilter").finish_non_exhaustive()
+/// Returns `flags` with `flag` set or cleared.
+#[cfg(all(feature = "all", target_os = "linux"))]
+const fn set_flag(flags: u32, flag: u32, set: bool) -> u32 {
+    if set {
+        flags | flag
+    } else {
+        flags & !flag
     }
 }
 
-/// See [`Socket::dccp_available_ccids`].
+/// A TCP Authentication Option key of a socket, see
+/// [`Socket::tcp_ao_keys`].
 ///
-/// [`Socket::dccp_available_ccids`]: crate::Socket::dccp_available_ccids
+/// [`Socket::tcp_ao_keys`]: crate::Socket::tcp_ao_keys
 #[cfg(all(feature = "all", target_os = "linux"))]
-#[derive(Debug)]
-pub struct CcidEndpoints<const N: usize> {
-    endpoints: [u8; N],
-    length: u32,
+#[derive(Clone)]
+pub struct TcpAoKeyInfo {
+    addr: SockAddr,
+    prefix_len: u8,
+    algorithm: String,
+    key: Vec<u8>,
+    send_id: u8,
+    recv_id: u8,
+    mac_len: u8,
+    keyflags: u8,
+    interface: u32,
+    is_current: bool,
+    is_rnext: bool,
+    packets_good: u64,
+    packets_bad: u64,
 }
 
 #[cfg(all(feature = "all", target_os = "linux"))]
-impl<const N: usize> std::ops::Deref for CcidEndpoints<N> {
-    type Target = [u8];
+impl std::fmt::Debug for TcpAoKeyInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TcpAoKeyInfo")
+            .field("addr", &self.addr)
+            .field("prefix_len", &self.prefix_len)
+            .field("algorithm", &self.algorithm)
+            .field("key", &RedactedKey(&self.key))
+            .field("send_id", &self.send_id)
+            .field("recv_id", &self.recv_id)
+            .field("mac_len", &self.mac_len)
+            .field("keyflags", &self.keyflags)
+            .field("interface", &self.interface)
+            .field("is_current", &self.is_current)
+            .field("is_rnext", &self.is_rnext)
+            .field("packets_good", &self.packets_good)
+            .field("packets_bad", &self.packets_bad)
+            .finish()
+    }
+}
 
-    fn deref(&self) -> &[u8] {
-        &self.endpoints[0..self.length as usize]
+#[cfg(all(feature = "all", target_os = "linux"))]
+impl TcpAoKeyInfo {
+    fn from_raw(key: &TcpAoGetsockopt) -> io::Result<TcpAoKeyInfo> {
+        let addr_len = match c_int::from(key.addr.ss_family) {
+            libc::AF_INET => size_of::<libc::sockaddr_in>(),
+            libc::AF_INET6 => size_of::<libc::sockaddr_in6>(),
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "unexpected address family for TCP-AO key",
+                ))
+            }
+        };
+        // SAFETY: the kernel initialised the address of `addr_len` bytes.
+        let addr = unsafe {
+            let mut storage = SockAddrStorage::zeroed();
+            ptr::copy_nonoverlapping(
+                ptr::addr_of!(key.addr).cast::<u8>(),
+                ptr::addr_of_mut!(storage).cast::<u8>(),
+                addr_len,
+            );
+            SockAddr::new(storage, addr_len as libc::socklen_t)
+        };
+        let alg_len = key
+            .alg_name
+            .iter()
+            .position(|&b| b == 0)
+            .unwrap_or(key.alg_name.len());
+        let key_len = (key.keylen as usize).min(key.key.len());
+        Ok(TcpAoKeyInfo {
+            addr,
+            prefix_len: key.prefix,
+            algorithm: String::from_utf8_lossy(&key.alg_name[..alg_len]).into_owned(),
+            key: key.key[..key_len].to_vec(),
+            send_id: key.sndid,
+            recv_id: key.rcvid,
+            mac_len: key.maclen,
+            keyflags: key.keyflags,
+            interface: key.ifindex as u32,
+            is_current: key.flags & tcp_ao_bit16(0) != 0,
+            is_rnext: key.flags & tcp_ao_bit16(1) != 0,
+            packets_good: key.pkt_good,
+            packets_bad: key.pkt_bad,
+        })
+    }
+
+    /// Returns the address of the peer.
+    pub const fn addr(&self) -> &SockAddr {
+        &self.addr
+    }
+
+    /// Returns the prefix length of the peer address.
+    pub const fn prefix_len(&self) -> u8 {
+        self.prefix_len
+    }
+
+    /// Returns the name of the MAC algorithm.
+    pub fn algorithm(&self) -> &str {
+        &self.algorithm
+    }
+
+    /// Returns the key.
+    pub fn key(&self) -> &[u8] {
+        &self.key
+    }
+
+    /// Returns the send id of the key.
+    pub const fn send_id(&self) -> u8 {
+        self.send_id
+    }
+
+    /// Returns the receive id of the key.
+    pub const fn recv_id(&self) -> u8 {
+        self.recv_id
+    }
+
+    /// Returns the length of the message authentication code in bytes.
+    pub const fn mac_len(&self) -> u8 {
+        self.mac_len
+    }
+
+    /// Returns the interface index the key is bound to, if any.
+    pub const fn interface(&self) -> Option<u32> {
+        if self.keyflags & TCP_AO_KEYF_IFINDEX != 0 {
+            Some(self.interface)
+        } else {
+            None
+        }
+    }
+
+    /// Returns `true` if TCP options are excluded from the MAC.
+    pub const fn is_excluding_options(&self) -> bool {
+        self.keyflags & TCP_AO_KEYF_EXCLUDE_OPT != 0
+    }
+
+    /// Returns `true` if this is the current key.
+    pub const fn is_current(&self) -> bool {
+        self.is_current
+    }
+
+    /// Returns `true` if this is the RNext key.
+    pub const fn is_rnext(&self) -> bool {
+        self.is_rnext
+    }
+
+    /// Number of segments that were successfully verified using the key.
+    pub const fn packets_good(&self) -> u64 {
+        self.packets_good
+    }
+
+    /// Number of segments that failed verification using the key.
+    pub const fn packets_bad(&self) -> u64 {
+        self.packets_bad
     }
 }
 
@@ -2993,3 +6985,113 @@ fn in6_addr_convertion() {
     assert_eq!(raw.s6_addr, want);
     assert_eq!(from_in6_addr(raw), ip);
 }
+
+#[cfg(all(feature = "all", target_os = "linux"))]
+#[test]
+fn tcp_ao_layout() {
+    // Offsets and sizes from `include/uapi/linux/tcp.h`.
+    macro_rules! offset_of {
+        ($ty: ty, $field: ident) => {{
+            // SAFETY: all zeroes is valid for the TCP-AO structures.
+            let value: $ty = unsafe { mem::zeroed() };
+            let base = ptr::addr_of!(value) as usize;
+            ptr::addr_of!(value.$field) as usize - base
+        }};
+    }
+
+    assert_eq!(size_of::<TcpAoAdd>(), 288);
+    assert_eq!(offset_of!(TcpAoAdd, addr), 0);
+    assert_eq!(offset_of!(TcpAoAdd, alg_name), 128);
+    assert_eq!(offset_of!(TcpAoAdd, ifindex), 192);
+    assert_eq!(offset_of!(TcpAoAdd, flags), 196);
+    assert_eq!(offset_of!(TcpAoAdd, reserved2), 200);
+    assert_eq!(offset_of!(TcpAoAdd, prefix), 202);
+    assert_eq!(offset_of!(TcpAoAdd, sndid), 203);
+    assert_eq!(offset_of!(TcpAoAdd, rcvid), 204);
+    assert_eq!(offset_of!(TcpAoAdd, maclen), 205);
+    assert_eq!(offset_of!(TcpAoAdd, keyflags), 206);
+    assert_eq!(offset_of!(TcpAoAdd, keylen), 207);
+    assert_eq!(offset_of!(TcpAoAdd, key), 208);
+
+    assert_eq!(size_of::<TcpAoDel>(), 144);
+    assert_eq!(offset_of!(TcpAoDel, addr), 0);
+    assert_eq!(offset_of!(TcpAoDel, ifindex), 128);
+    assert_eq!(offset_of!(TcpAoDel, flags), 132);
+    assert_eq!(offset_of!(TcpAoDel, reserved2), 136);
+    assert_eq!(offset_of!(TcpAoDel, prefix), 138);
+    assert_eq!(offset_of!(TcpAoDel, sndid), 139);
+    assert_eq!(offset_of!(TcpAoDel, rcvid), 140);
+    assert_eq!(offset_of!(TcpAoDel, current_key), 141);
+    assert_eq!(offset_of!(TcpAoDel, rnext), 142);
+    assert_eq!(offset_of!(TcpAoDel, keyflags), 143);
+
+    assert_eq!(size_of::<TcpAoInfoOpt>(), 48);
+    assert_eq!(offset_of!(TcpAoInfoOpt, flags), 0);
+    assert_eq!(offset_of!(TcpAoInfoOpt, reserved2), 4);
+    assert_eq!(offset_of!(TcpAoInfoOpt, current_key), 6);
+    assert_eq!(offset_of!(TcpAoInfoOpt, rnext), 7);
+    assert_eq!(offset_of!(TcpAoInfoOpt, pkt_good), 8);
+    assert_eq!(offset_of!(TcpAoInfoOpt, pkt_bad), 16);
+    assert_eq!(offset_of!(TcpAoInfoOpt, pkt_key_not_found), 24);
+    assert_eq!(offset_of!(TcpAoInfoOpt, pkt_ao_required), 32);
+    assert_eq!(offset_of!(TcpAoInfoOpt, pkt_dropped_icmp), 40);
+
+    assert_eq!(size_of::<TcpAoGetsockopt>(), 312);
+    assert_eq!(offset_of!(TcpAoGetsockopt, addr), 0);
+    assert_eq!(offset_of!(TcpAoGetsockopt, alg_name), 128);
+    assert_eq!(offset_of!(TcpAoGetsockopt, key), 192);
+    assert_eq!(offset_of!(TcpAoGetsockopt, nkeys), 272);
+    assert_eq!(offset_of!(TcpAoGetsockopt, flags), 276);
+    assert_eq!(offset_of!(TcpAoGetsockopt, reserved2), 278);
+    assert_eq!(offset_of!(TcpAoGetsockopt, prefix), 280);
+    assert_eq!(offset_of!(TcpAoGetsockopt, sndid), 281);
+    assert_eq!(offset_of!(TcpAoGetsockopt, rcvid), 282);
+    assert_eq!(offset_of!(TcpAoGetsockopt, maclen), 283);
+    assert_eq!(offset_of!(TcpAoGetsockopt, keyflags), 284);
+    assert_eq!(offset_of!(TcpAoGetsockopt, keylen), 285);
+    assert_eq!(offset_of!(TcpAoGetsockopt, ifindex), 288);
+    assert_eq!(offset_of!(TcpAoGetsockopt, pkt_good), 296);
+    assert_eq!(offset_of!(TcpAoGetsockopt, pkt_bad), 304);
+
+    // The bitfields are allocated from the least significant bit on little
+    // endian and from the most significant bit on big endian.
+    let get_all = if cfg!(target_endian = "little") {
+        1 << 2
+    } else {
+        1 << 13
+    };
+    assert_eq!(tcp_ao_bit16(2), get_all);
+}
+
+#[cfg(all(feature = "all", target_os = "linux"))]
+#[test]
+fn tcp_info_layout() {
+    assert_eq!(size_of::<libc::tcp_info>(), 104);
+    assert_eq!(size_of::<TcpInfoRaw>(), TCP_INFO_SIZE);
+
+    // `tcpi_snd_wscale:4, tcpi_rcv_wscale:4`.
+    let wscale = if cfg!(target_endian = "little") {
+        0x72
+    } else {
+        0x27
+    };
+    assert_eq!(tcp_info_bits(wscale, 0, 4), 2);
+    assert_eq!(tcp_info_bits(wscale, 4, 4), 7);
+    // `tcpi_delivery_rate_app_limited:1, tcpi_fastopen_client_fail:2`.
+    let flags = if cfg!(target_endian = "little") {
+        0b0000_0101
+    } else {
+        0b1100_0000
+    };
+    assert_eq!(tcp_info_bits(flags, 0, 1), 1);
+    assert_eq!(tcp_info_bits(flags, 1, 2), 2);
+}
+
+#[cfg(all(feature = "all", target_os = "linux"))]
+#[test]
+fn cmsg_space() {
+    for len in [0, 1, 4, 12, 20, 64] {
+        let want = unsafe { libc::CMSG_SPACE(len as libc::c_uint) } as usize;
+        assert_eq!(crate::cmsg::cmsg_space(len), want, "{len}");
+    }
+}
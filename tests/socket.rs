@@ -80,6 +80,8 @@ fn domain_fmt_debug() {
         (Domain::PACKET, "AF_PACKET"),
         #[cfg(all(feature = "all", any(target_os = "android", target_os = "linux")))]
         (Domain::VSOCK, "AF_VSOCK"),
+        #[cfg(all(feature = "all", target_os = "linux"))]
+        (Domain::NETLINK, "AF_NETLINK"),
         (0.into(), "AF_UNSPEC"),
         (500.into(), "500"),
     ];
@@ -213,6 +215,43 @@ fn socket_address_vsock() {
     assert_eq!(addr.as_vsock_address().unwrap(), (1, 9999));
 }
 
+#[test]
+#[cfg(all(feature = "all", target_os = "linux"))]
+fn socket_address_netlink() {
+    let addr = SockAddr::netlink(1234, 0b101);
+    assert_eq!(addr.family(), libc::AF_NETLINK as socket2::sa_family_t);
+    assert!(addr.as_socket_ipv4().is_none());
+    assert!(addr.as_socket_ipv6().is_none());
+    assert_eq!(addr.as_netlink().unwrap(), (1234, 0b101));
+    assert!(any_ipv4().as_netlink().is_none());
+}
+
+#[test]
+#[cfg(all(feature = "all", target_os = "linux"))]
+fn socket_address_link_layer() {
+    let hwaddr = [0x02, 0, 0, 0, 0, 1];
+    let addr = SockAddr::link_layer(
+        3,
+        libc::ETH_P_IP as u16,
+        libc::ARPHRD_ETHER,
+        libc::PACKET_HOST,
+        &hwaddr,
+    )
+    .unwrap();
+    assert_eq!(addr.family(), libc::AF_PACKET as socket2::sa_family_t);
+    assert!(addr.as_socket().is_none());
+    let ll = addr.as_link_layer().unwrap();
+    assert_eq!(ll.interface(), 3);
+    assert_eq!(ll.protocol(), libc::ETH_P_IP as u16);
+    assert_eq!(ll.hatype(), libc::ARPHRD_ETHER);
+    assert_eq!(ll.pkttype(), libc::PACKET_HOST);
+    assert_eq!(ll.hardware_address(), hwaddr);
+    assert!(any_ipv4().as_link_layer().is_none());
+
+    let err = SockAddr::link_layer(3, 0, 0, 0, &[0; 9]).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+}
+
 #[test]
 fn set_nonblocking() {
     let socket = Socket::new(Domain::IPV4, Type::STREAM, None).unwrap();
@@ -654,6 +693,969 @@ fn vsock() {
     assert_eq!(&buf[..n], DATA);
 }
 
+#[test]
+#[cfg(all(feature = "all", target_os = "linux"))]
+fn netlink() {
+    let socket = Socket::new(Domain::NETLINK, Type::RAW, Some(Protocol::NETLINK_ROUTE)).unwrap();
+    socket.bind(&SockAddr::netlink(0, 0)).unwrap();
+    let (port_id, groups) = socket.local_addr().unwrap().as_netlink().unwrap();
+    assert_ne!(port_id, 0);
+    assert_eq!(groups, 0);
+
+    // `RTNLGRP_LINK`.
+    socket.netlink_add_membership(1).unwrap();
+    socket.netlink_drop_membership(1).unwrap();
+
+    assert!(!socket.netlink_ext_ack().unwrap());
+    socket.set_netlink_ext_ack(true).unwrap();
+    assert!(socket.netlink_ext_ack().unwrap());
+
+    assert!(!socket.netlink_get_strict_chk().unwrap());
+    socket.set_netlink_get_strict_chk(true).unwrap();
+    assert!(socket.netlink_get_strict_chk().unwrap());
+
+    assert!(!socket.netlink_no_enobufs().unwrap());
+    socket.set_netlink_no_enobufs(true).unwrap();
+    assert!(socket.netlink_no_enobufs().unwrap());
+
+    assert!(!socket.netlink_listen_all_nsid().unwrap());
+    match socket.set_netlink_listen_all_nsid(true) {
+        Ok(()) => assert!(socket.netlink_listen_all_nsid().unwrap()),
+        // Requires `CAP_NET_BROADCAST`.
+        Err(err) if err.kind() == io::ErrorKind::PermissionDenied => {}
+        Err(err) => panic!("unexpected error: {err}"),
+    }
+}
+
+#[test]
+#[cfg(all(feature = "all", target_os = "linux"))]
+fn netlink_message_roundtrip() {
+    use socket2::netlink::{MessageBuilder, Messages};
+
+    let msg = MessageBuilder::new(100, libc::NLM_F_ACK as u16)
+        .with_header(&[1, 2, 3])
+        .with_attr_u8(1, 0xff)
+        .with_nested(2, |msg| {
+            msg.with_attr_str(3, "abc").with_attr_u64(4, 1 << 40)
+        })
+        .with_attr_u32(5, 123);
+    let bytes = msg.as_bytes();
+    assert_eq!(bytes.len() % 4, 0);
+    // Message header, padded header, 2 attributes and nested attribute.
+    assert_eq!(bytes.len(), 16 + 4 + 8 + (4 + 8 + 12) + 8);
+
+    // Two messages in a single buffer.
+    let buf = [bytes, bytes].concat();
+    let msgs = Messages::new(&buf).collect::<io::Result<Vec<_>>>().unwrap();
+    assert_eq!(msgs.len(), 2);
+    let got = msgs[0];
+    assert_eq!(got.ty(), 100);
+    assert_eq!(got.flags(), (libc::NLM_F_REQUEST | libc::NLM_F_ACK) as u16);
+    assert_eq!(got.seq(), msg.seq());
+    assert!(got.result().is_none());
+    assert_eq!(&got.payload()[..3], &[1, 2, 3]);
+
+    let attrs = got.attributes(3);
+    assert_eq!(attrs.clone().count(), 3);
+    assert_eq!(attrs.get(1).unwrap().as_u8(), Some(0xff));
+    assert_eq!(attrs.get(5).unwrap().as_u32(), Some(123));
+    let nested = attrs.get(2).unwrap();
+    assert!(nested.is_nested());
+    assert_eq!(nested.nested().get(3).unwrap().as_str(), Some("abc"));
+    assert_eq!(nested.nested().get(4).unwrap().as_u64(), Some(1 << 40));
+
+    // Truncated message.
+    let mut msgs = Messages::new(&bytes[..bytes.len() - 1]);
+    assert!(msgs.next().unwrap().is_err());
+    assert!(msgs.next().is_none());
+}
+
+#[test]
+#[cfg(all(feature = "all", target_os = "linux"))]
+fn netlink_error_message() {
+    use socket2::netlink::{MessageBuilder, Messages, NetlinkError};
+
+    // `NLMSG_ERROR` with a capped original message and extended ack.
+    let original = MessageBuilder::new(100, 0);
+    let mut payload = (-libc::EINVAL).to_ne_bytes().to_vec();
+    payload.extend_from_slice(original.as_bytes());
+    let msg = MessageBuilder::new(
+        libc::NLMSG_ERROR as u16,
+        (libc::NLM_F_CAPPED | libc::NLM_F_ACK_TLVS) as u16,
+    )
+    .with_header(&payload)
+    .with_attr_str(1, "invalid attribute")
+    .with_attr_u32(2, 20);
+    let got = Messages::new(msg.as_bytes()).next().unwrap().unwrap();
+    let err = got.result().unwrap().unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    let err = err
+        .get_ref()
+        .unwrap()
+        .downcast_ref::<NetlinkError>()
+        .unwrap();
+    assert_eq!(err.errno(), libc::EINVAL);
+    assert_eq!(err.message(), Some("invalid attribute"));
+    assert_eq!(err.offset(), Some(20));
+
+    // Acknowledgement.
+    let mut payload = 0i32.to_ne_bytes().to_vec();
+    payload.extend_from_slice(original.as_bytes());
+    let msg = MessageBuilder::new(libc::NLMSG_ERROR as u16, 0).with_header(&payload);
+    let got = Messages::new(msg.as_bytes()).next().unwrap().unwrap();
+    assert!(got.result().unwrap().is_ok());
+}
+
+#[test]
+#[cfg(all(feature = "all", target_os = "linux"))]
+fn netlink_resolve_family() {
+    use socket2::netlink;
+
+    let socket = Socket::new(Domain::NETLINK, Type::RAW, Some(Protocol::NETLINK_GENERIC)).unwrap();
+    socket.set_netlink_ext_ack(true).unwrap();
+    let family = netlink::resolve_family(&socket, "nlctrl").unwrap();
+    assert_eq!(family.id(), libc::GENL_ID_CTRL as u16);
+    assert_eq!(family.name(), "nlctrl");
+    assert!(family.version() >= 1);
+    assert!(family.multicast_group("notify").is_some());
+
+    let err = netlink::resolve_family(&socket, "does-not-exist").unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::NotFound);
+}
+
+#[test]
+#[cfg(all(feature = "all", target_os = "linux"))]
+fn netlink_route() {
+    use socket2::netlink::route::{self, Scope};
+    use socket2::netlink::{self, MessageBuilder};
+
+    let socket = Socket::new(Domain::NETLINK, Type::RAW, Some(Protocol::NETLINK_ROUTE)).unwrap();
+
+    let links = route::links(&socket).unwrap();
+    let lo = links.iter().find(|link| link.name() == "lo").unwrap();
+    assert!(lo.is_loopback());
+    assert!(lo.is_up());
+    assert_eq!(lo.link_type(), libc::ARPHRD_LOOPBACK);
+    assert_eq!(lo.hardware_address(), Some(&[0; 6][..]));
+    assert!(lo.mtu().unwrap() > 0);
+    let index = unsafe { libc::if_nametoindex(b"lo\0".as_ptr().cast()) };
+    assert_eq!(lo.index(), index);
+
+    let addresses = route::addresses(&socket).unwrap();
+    let address = addresses
+        .iter()
+        .find(|addr| addr.address() == Ipv4Addr::LOCALHOST)
+        .unwrap();
+    assert_eq!(address.index(), lo.index());
+    assert_eq!(address.prefix_len(), 8);
+    assert_eq!(address.scope(), Scope::Host);
+    assert_eq!(address.label(), Some("lo"));
+
+    let routes = route::routes(&socket).unwrap();
+    let route = routes
+        .iter()
+        .find(|route| route.destination() == Ipv4Addr::LOCALHOST && route.prefix_len() == 32)
+        .unwrap();
+    assert_eq!(route.interface(), Some(lo.index()));
+    assert_eq!(route.table(), libc::RT_TABLE_LOCAL as u32);
+    assert_eq!(route.kind(), libc::RTN_LOCAL);
+    assert_eq!(route.scope(), Scope::Host);
+    assert!(route.next_hops().is_empty());
+
+    // Multipath route to 198.51.100.0/24 using two next hops on `lo`.
+    let header = [
+        libc::AF_INET as u8,
+        24,
+        0,
+        0,
+        libc::RT_TABLE_MAIN,
+        libc::RTPROT_STATIC,
+        libc::RT_SCOPE_UNIVERSE,
+        libc::RTN_UNICAST,
+        0,
+        0,
+        0,
+        0,
+    ];
+    let mut multipath = Vec::new();
+    for (hops, gateway) in [(1u8, Some([127, 0, 0, 2])), (0, None)] {
+        let len = if gateway.is_some() { 16u16 } else { 8 };
+        multipath.extend_from_slice(&len.to_ne_bytes());
+        multipath.extend_from_slice(&[0, hops]);
+        multipath.extend_from_slice(&lo.index().to_ne_bytes());
+        if let Some(gateway) = gateway {
+            multipath.extend_from_slice(&8u16.to_ne_bytes());
+            multipath.extend_from_slice(&libc::RTA_GATEWAY.to_ne_bytes());
+            multipath.extend_from_slice(&gateway);
+        }
+    }
+    let flags = (libc::NLM_F_CREATE | libc::NLM_F_EXCL | libc::NLM_F_ACK) as u16;
+    let req = MessageBuilder::new(libc::RTM_NEWROUTE, flags)
+        .with_header(&header)
+        .with_attr(libc::RTA_DST, &[198, 51, 100, 0])
+        .with_attr(libc::RTA_MULTIPATH, &multipath);
+    match netlink::request(&socket, &req, |_| Ok(())) {
+        Ok(()) => {}
+        // Requires `CAP_NET_ADMIN`.
+        Err(ref err) if err.kind() == io::ErrorKind::PermissionDenied => return,
+        Err(err) => panic!("unexpected error adding route: {err}"),
+    }
+    let routes = route::routes(&socket);
+    let req = MessageBuilder::new(libc::RTM_DELROUTE, libc::NLM_F_ACK as u16)
+        .with_header(&header)
+        .with_attr(libc::RTA_DST, &[198, 51, 100, 0]);
+    netlink::request(&socket, &req, |_| Ok(())).unwrap();
+    let routes = routes.unwrap();
+    let route = routes
+        .iter()
+        .find(|route| route.destination() == Ipv4Addr::new(198, 51, 100, 0))
+        .unwrap();
+    let next_hops = route.next_hops();
+    assert_eq!(next_hops.len(), 2, "{next_hops:?}");
+    assert_eq!(next_hops[0].interface(), lo.index());
+    assert_eq!(
+        next_hops[0].gateway(),
+        Some(Ipv4Addr::new(127, 0, 0, 2).into())
+    );
+    assert_eq!(next_hops[0].weight(), 2);
+    assert_eq!(next_hops[1].interface(), lo.index());
+    assert_eq!(next_hops[1].gateway(), None);
+    assert_eq!(next_hops[1].weight(), 1);
+}
+
+#[test]
+#[cfg(all(feature = "all", target_os = "linux"))]
+fn netlink_sock_diag() {
+    use socket2::netlink::sock_diag::{self, InetQuery, UnixQuery};
+    use socket2::TcpState;
+
+    let socket = Socket::new(
+        Domain::NETLINK,
+        Type::RAW,
+        Some(Protocol::NETLINK_SOCK_DIAG),
+    )
+    .unwrap();
+
+    let listener = Socket::new(Domain::IPV4, Type::STREAM, None).unwrap();
+    listener.bind(&any_ipv4()).unwrap();
+    listener.listen(8).unwrap();
+    let addr = listener.local_addr().unwrap().as_socket().unwrap();
+    let client = Socket::new(Domain::IPV4, Type::STREAM, None).unwrap();
+    client.connect(&addr.into()).unwrap();
+
+    let query = InetQuery::new(Domain::IPV4, Protocol::TCP)
+        .with_states(&[TcpState::LISTEN])
+        .with_source_port(addr.port());
+    let records = sock_diag::inet_sockets(&socket, &query).unwrap();
+    assert_eq!(records.len(), 1);
+    let record = &records[0];
+    assert_eq!(record.cookie(), listener.cookie().unwrap());
+    assert_eq!(record.state(), TcpState::LISTEN);
+    assert_eq!(record.local_addr(), addr);
+    assert_eq!(record.uid(), unsafe { libc::getuid() });
+    assert_eq!(record.send_queue(), 8);
+    // Connection waiting to be accepted.
+    assert_eq!(record.recv_queue(), 1);
+    assert!(record.tcp_info().is_none());
+
+    // States that don't fit in the mask are ignored.
+    let query = InetQuery::new(Domain::IPV4, Protocol::TCP)
+        .with_states(&[TcpState::from(40), TcpState::LISTEN])
+        .with_source_port(addr.port());
+    let records = sock_diag::inet_sockets(&socket, &query).unwrap();
+    assert_eq!(records.len(), 1);
+    let query = query.with_states(&[TcpState::from(40)]);
+    assert!(sock_diag::inet_sockets(&socket, &query).unwrap().is_empty());
+
+    let query = InetQuery::new(Domain::IPV4, Protocol::TCP)
+        .with_states(&[TcpState::ESTABLISHED])
+        .with_destination_port(addr.port())
+        .with_tcp_info()
+        .with_meminfo();
+    let records = sock_diag::inet_sockets(&socket, &query).unwrap();
+    let record = records
+        .iter()
+        .find(|record| record.cookie() == client.cookie().unwrap())
+        .unwrap();
+    assert_eq!(record.peer_addr(), addr);
+    assert_eq!(record.tcp_info().unwrap().state(), TcpState::ESTABLISHED);
+    assert!(record.meminfo().unwrap().sndbuf() > 0);
+
+    let (a, b) = Socket::pair(Domain::UNIX, Type::STREAM, None).unwrap();
+    let query = UnixQuery::new()
+        .with_states(&[TcpState::ESTABLISHED])
+        .with_meminfo();
+    let records = sock_diag::unix_sockets(&socket, &query).unwrap();
+    let find = |socket: &Socket| {
+        let cookie = socket.cookie().unwrap();
+        records
+            .iter()
+            .find(|record| record.cookie() == cookie)
+            .unwrap()
+    };
+    let (a, b) = (find(&a), find(&b));
+    assert_eq!(a.r#type(), Type::STREAM);
+    assert_eq!(a.state(), TcpState::ESTABLISHED);
+    assert_eq!(a.peer_inode(), Some(b.inode()));
+    assert_eq!(b.peer_inode(), Some(a.inode()));
+    assert!(a.local_addr().is_none());
+    assert!(a.meminfo().is_some());
+}
+
+#[test]
+#[cfg(all(feature = "all", target_os = "linux"))]
+fn reuseport_filter() {
+    use socket2::SockFilter;
+
+    for (domain, addr) in [
+        (Domain::IPV4, any_ipv4()),
+        (
+            Domain::IPV6,
+            SocketAddr::from((Ipv6Addr::LOCALHOST, 0)).into(),
+        ),
+    ] {
+        let group: Vec<Socket> = (0..2)
+            .map(|_| {
+                let socket = Socket::new(domain, Type::DGRAM, None).unwrap();
+                socket.set_reuse_port(true).unwrap();
+                socket.set_nonblocking(true).unwrap();
+                socket
+            })
+            .collect();
+        group[0].bind(&addr).unwrap();
+        let addr = group[0].local_addr().unwrap();
+        group[1].bind(&addr).unwrap();
+
+        // Returns the number of datagrams received by each socket, after
+        // sending one datagram from each of `clients` sockets.
+        let send = |clients: usize| {
+            for _ in 0..clients {
+                let client = Socket::new(domain, Type::DGRAM, None).unwrap();
+                client.send_to(DATA, &addr).unwrap();
+            }
+            let mut counts = [0; 2];
+            let mut buf = [MaybeUninit::new(0); DATA.len()];
+            for (socket, count) in group.iter().zip(counts.iter_mut()) {
+                while socket.recv(&mut buf).is_ok() {
+                    *count += 1;
+                }
+            }
+            counts
+        };
+
+        // Always select the second socket.
+        let filter = [SockFilter::new(
+            (libc::BPF_RET | libc::BPF_K) as u16,
+            0,
+            0,
+            1,
+        )];
+        group[0].attach_reuseport_filter(&filter).unwrap();
+        assert_eq!(send(16), [0, 16]);
+
+        // Packets on loopback are processed on the sending CPU.
+        let cpu = unsafe { libc::sched_getcpu() } as usize;
+        let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+        unsafe { libc::CPU_SET(cpu, &mut set) };
+        let res = unsafe { libc::sched_setaffinity(0, std::mem::size_of_val(&set), &set) };
+        assert_eq!(res, 0);
+        group[1]
+            .attach_reuseport_filter(&SockFilter::reuseport_cpu(2))
+            .unwrap();
+        let mut expected = [0; 2];
+        expected[cpu % 2] = 16;
+        assert_eq!(send(16), expected);
+
+        // Each client uses a different source port.
+        group[0]
+            .attach_reuseport_filter(&SockFilter::reuseport_hash(2))
+            .unwrap();
+        let counts = send(64);
+        assert_eq!(counts[0] + counts[1], 64);
+        assert!(counts[0] > 0 && counts[1] > 0, "{counts:?}");
+
+        group[0].detach_reuseport_filter().unwrap();
+        assert_eq!(send(16).iter().sum::<usize>(), 16);
+    }
+}
+
+#[test]
+#[cfg(all(feature = "all", target_os = "linux"))]
+fn bpf_program() {
+    use socket2::bpf::{
+        self, AluOp, Condition, Instruction, Operand, ProgramBuilder, ReturnValue, Size, Source,
+        SourceX,
+    };
+    use socket2::SockFilter;
+
+    // Only accept UDP datagrams with more than 2 bytes of payload.
+    let program = ProgramBuilder::new()
+        .load(Source::Network(Size::Byte, 9))
+        .jump_if(Condition::Eq, Operand::Constant(17), "udp", "drop")
+        .label("udp")
+        .load(Source::Length)
+        .store(0)
+        .load_x(SourceX::Memory(0))
+        .txa()
+        .jump_if(Condition::Gt, Operand::Constant(8 + 2), "accept", "drop")
+        .label("accept")
+        .ret(ReturnValue::Constant(u32::MAX))
+        .label("drop")
+        .ret(ReturnValue::Constant(0))
+        .build()
+        .unwrap();
+    bpf::validate(&program).unwrap();
+
+    let socket = Socket::new(Domain::IPV4, Type::DGRAM, None).unwrap();
+    socket.bind(&any_ipv4()).unwrap();
+    socket.attach_filter(&program).unwrap();
+    let addr = socket.local_addr().unwrap();
+    let client = Socket::new(Domain::IPV4, Type::DGRAM, None).unwrap();
+    client.send_to(b"ab", &addr).unwrap();
+    client.send_to(DATA, &addr).unwrap();
+    let mut buf = [MaybeUninit::new(0); DATA.len() + 1];
+    let n = socket.recv(&mut buf).unwrap();
+    assert_eq!(unsafe { assume_init(&buf[..n]) }, DATA);
+
+    // The kernel accepts metadata loads of any size.
+    for code in [0x20, 0x28, 0x30] {
+        let program = [
+            SockFilter::new(code, 0, 0, 0xfffff000),
+            Instruction::Return(ReturnValue::A).into(),
+        ];
+        bpf::validate(&program).unwrap();
+        socket.attach_filter(&program).unwrap();
+    }
+
+    // Programs rejected by `validate` are also rejected by the kernel.
+    let ret = || SockFilter::from(Instruction::Return(ReturnValue::A));
+    let invalid = [
+        vec![SockFilter::new(0xff, 0, 0, 0), ret()],
+        vec![
+            Instruction::Alu(AluOp::Div, Operand::Constant(0)).into(),
+            ret(),
+        ],
+        vec![
+            Instruction::Alu(AluOp::Lsh, Operand::Constant(32)).into(),
+            ret(),
+        ],
+        vec![Instruction::Store(16).into(), ret()],
+        vec![Instruction::Jump(1).into(), ret()],
+        vec![
+            Instruction::Load(Source::Absolute(Size::Word, 0xfffff040)).into(),
+            ret(),
+        ],
+        vec![ret(), Instruction::Tax.into()],
+        vec![Instruction::Load(Source::Memory(1)).into(), ret()],
+    ];
+    for program in invalid {
+        assert!(bpf::validate(&program).is_err());
+        let err = socket.attach_filter(&program).unwrap_err();
+        assert_eq!(err.raw_os_error(), Some(libc::EINVAL));
+    }
+}
+
+#[test]
+#[cfg(all(feature = "all", target_os = "linux"))]
+fn bpf_interpreter() {
+    use socket2::bpf::{
+        AluOp, Ancillary, Condition, FaultKind, Interpreter, Operand, Packet, ProgramBuilder,
+        ReturnValue, Size, Source, SourceX, Target,
+    };
+    use socket2::SockFilter;
+
+    // Accept datagrams ending in `!`, the program sees the UDP header.
+    let program = ProgramBuilder::new()
+        .load(Source::Length)
+        .alu(AluOp::Sub, Operand::Constant(1))
+        .tax()
+        .load(Source::Indirect(Size::Byte, 0))
+        .jump_if(
+            Condition::Eq,
+            Operand::Constant(b'!'.into()),
+            "accept",
+            "drop",
+        )
+        .label("accept")
+        .ret(ReturnValue::Constant(u32::MAX))
+        .label("drop")
+        .ret(ReturnValue::Constant(0))
+        .build()
+        .unwrap();
+    let interpreter = Interpreter::new(&program).unwrap();
+
+    let socket = Socket::new(Domain::IPV4, Type::DGRAM, None).unwrap();
+    socket.bind(&any_ipv4()).unwrap();
+    socket.set_nonblocking(true).unwrap();
+    socket.attach_filter(&program).unwrap();
+    let addr = socket.local_addr().unwrap();
+    let client = Socket::new(Domain::IPV4, Type::DGRAM, None).unwrap();
+    for payload in [&b"hello!"[..], b"hello?", b"!"] {
+        client.send_to(payload, &addr).unwrap();
+        let mut buf = [MaybeUninit::new(0); 16];
+        let received = socket.recv(&mut buf).is_ok();
+        // IPv4 header, without options, followed by the UDP header.
+        let mut frame = vec![0x45; 20 + 8];
+        frame.extend_from_slice(payload);
+        let packet = Packet::new(&frame).with_data_offset(20);
+        assert_eq!(interpreter.run(&packet).unwrap() != 0, received);
+    }
+
+    let cpu = SockFilter::reuseport_cpu(4);
+    let interpreter = Interpreter::new(&cpu).unwrap();
+    assert_eq!(interpreter.run(&Packet::new(&[]).with_cpu(6)), Ok(2));
+
+    let hash = SockFilter::reuseport_hash(4);
+    let interpreter = Interpreter::new(&hash).unwrap();
+    let mut frame = vec![0; 20 + 8];
+    frame[0] = 0x45;
+    frame[9] = libc::IPPROTO_UDP as u8;
+    frame[12..20].copy_from_slice(&[127, 0, 0, 1, 127, 0, 0, 1]);
+    let mut groups = [0; 4];
+    for port in 1000..1064u16 {
+        frame[20..22].copy_from_slice(&port.to_be_bytes());
+        let packet = Packet::new(&frame).with_data_offset(20);
+        let group = interpreter.run(&packet).unwrap();
+        assert_eq!(interpreter.run(&packet), Ok(group));
+        groups[group as usize] += 1;
+    }
+    assert!(groups.iter().all(|n| *n > 0), "{groups:?}");
+
+    let program = ProgramBuilder::new()
+        .load(Source::Ancillary(Size::Word, Ancillary::VlanTagPresent))
+        .jump_if(
+            Condition::Eq,
+            Operand::Constant(0),
+            "untagged",
+            Target::Next,
+        )
+        .load(Source::Ancillary(Size::Word, Ancillary::VlanTag))
+        .alu(AluOp::And, Operand::Constant(0xfff))
+        .ret(ReturnValue::A)
+        .label("untagged")
+        .load(Source::LinkLayer(Size::Half, 12))
+        .load_x(SourceX::IpHeaderLength(14))
+        .alu(AluOp::Div, Operand::X)
+        .ret(ReturnValue::A)
+        .build()
+        .unwrap();
+    let interpreter = Interpreter::new(&program).unwrap();
+    let frame = [0; 14 + 20];
+    let packet = Packet::new(&frame).with_vlan(0x2064, 0x8100);
+    assert_eq!(interpreter.run(&packet), Ok(0x64));
+    // The link layer header isn't set.
+    let fault = interpreter.run(&Packet::new(&frame)).unwrap_err();
+    assert_eq!((fault.kind(), fault.index()), (FaultKind::OutOfBounds, 5));
+    let packet = Packet::new(&frame).with_link_layer_offset(0);
+    let fault = interpreter.run(&packet).unwrap_err();
+    assert_eq!(
+        (fault.kind(), fault.index()),
+        (FaultKind::DivisionByZero, 7)
+    );
+    let fault = interpreter.run(&packet.with_data_offset(20)).unwrap_err();
+    assert_eq!((fault.kind(), fault.index()), (FaultKind::OutOfBounds, 6));
+
+    // Find attribute 2 in the attribute nested in attribute 1.
+    let program = ProgramBuilder::new()
+        .load_x(SourceX::Immediate(1))
+        .load(Source::Ancillary(Size::Word, Ancillary::NetlinkAttr))
+        .load_x(SourceX::Immediate(2))
+        .load(Source::Ancillary(Size::Word, Ancillary::NetlinkAttrNested))
+        .ret(ReturnValue::A)
+        .build()
+        .unwrap();
+    let interpreter = Interpreter::new(&program).unwrap();
+    let mut attrs = Vec::new();
+    for (len, kind, payload) in [(8u16, 3u16, 4), (20, 1, 0), (5, 4, 4), (8, 2, 4)] {
+        attrs.extend_from_slice(&len.to_ne_bytes());
+        attrs.extend_from_slice(&kind.to_ne_bytes());
+        attrs.resize(attrs.len() + payload, 0);
+    }
+    assert_eq!(interpreter.run(&Packet::new(&attrs)), Ok(20));
+    assert_eq!(interpreter.run(&Packet::new(&attrs[..16])), Ok(0));
+}
+
+#[test]
+#[cfg(all(feature = "all", target_os = "linux"))]
+fn bpf_pcap() {
+    use socket2::bpf::pcap::{compile, CompileErrorKind, LinkType};
+    use socket2::bpf::{Interpreter, Packet};
+
+    let server = Socket::new(Domain::IPV4, Type::DGRAM, None).unwrap();
+    server.bind(&any_ipv4()).unwrap();
+    let addr = server.local_addr().unwrap();
+    let port = addr.as_socket().unwrap().port();
+    let other = Socket::new(Domain::IPV4, Type::DGRAM, None).unwrap();
+    other.bind(&any_ipv4()).unwrap();
+    let client = Socket::new(Domain::IPV4, Type::DGRAM, None).unwrap();
+    client.bind(&any_ipv4()).unwrap();
+
+    // Raw IPv4 socket, which sees the IP header.
+    let raw = match Socket::new(Domain::IPV4, Type::RAW, Some(Protocol::UDP)) {
+        Ok(socket) => socket,
+        // Requires `CAP_NET_RAW`.
+        Err(ref err) if err.kind() == io::ErrorKind::PermissionDenied => return,
+        Err(err) => panic!("unexpected error creating raw socket: {err}"),
+    };
+    let program = compile(&format!("udp dst port {port} and len > 28"), LinkType::Ip).unwrap();
+    raw.attach_filter(&program).unwrap();
+    client.send_to(DATA, &other.local_addr().unwrap()).unwrap();
+    client.send_to(&[], &addr).unwrap();
+    client.send_to(DATA, &addr).unwrap();
+    let mut buf = [MaybeUninit::new(0); 128];
+    let n = raw.recv(&mut buf).unwrap();
+    let packet = unsafe { assume_init(&buf[..n]) };
+    assert_eq!(u16::from_be_bytes([packet[22], packet[23]]), port);
+    assert_eq!(&packet[28..], DATA);
+
+    // Packet socket, which sees the Ethernet header on loopback.
+    let protocol = Protocol::from((libc::ETH_P_ALL as u16).to_be() as libc::c_int);
+    let socket = Socket::new(Domain::PACKET, Type::RAW, Some(protocol)).unwrap();
+    let lo = unsafe { libc::if_nametoindex(b"lo\0".as_ptr().cast()) };
+    let expression = format!("ip and udp and src port {port} and not icmp");
+    let program = compile(&expression, LinkType::Ethernet).unwrap();
+    socket.attach_filter(&program).unwrap();
+    socket
+        .bind(&SockAddr::link_layer(lo, libc::ETH_P_ALL as u16, 0, 0, &[]).unwrap())
+        .unwrap();
+    socket
+        .set_read_timeout(Some(Duration::from_secs(1)))
+        .unwrap();
+    server.send_to(DATA, &client.local_addr().unwrap()).unwrap();
+    let n = socket.recv(&mut buf).unwrap();
+    let frame = unsafe { assume_init(&buf[..n]) };
+    assert_eq!(u16::from_be_bytes([frame[14 + 20], frame[14 + 21]]), port);
+
+    // Ethernet frame with an IPv4 (with options) or IPv6 header followed by
+    // the ports of a TCP or UDP header.
+    fn build_frame(v6: bool, protocol: u8, src_port: u16, dst_port: u16) -> Vec<u8> {
+        let mut frame = vec![0; 12];
+        if v6 {
+            frame.extend_from_slice(&[0x86, 0xdd, 0x60, 0, 0, 0, 0, 0, protocol, 64]);
+            frame.extend_from_slice(&Ipv6Addr::LOCALHOST.octets());
+            frame.extend_from_slice(&"2001:db8::1".parse::<Ipv6Addr>().unwrap().octets());
+        } else {
+            frame.extend_from_slice(&[0x08, 0x00, 0x46, 0, 0, 0, 0, 0, 0, 0, 64, protocol, 0, 0]);
+            frame.extend_from_slice(&[127, 0, 0, 1, 192, 0, 2, 1]);
+            // Options.
+            frame.extend_from_slice(&[1, 1, 1, 0]);
+        }
+        frame.extend_from_slice(&src_port.to_be_bytes());
+        frame.extend_from_slice(&dst_port.to_be_bytes());
+        frame.resize(frame.len() + 16, 0);
+        frame
+    }
+    let tcp4 = build_frame(false, libc::IPPROTO_TCP as u8, 1234, 80);
+    let udp6 = build_frame(true, libc::IPPROTO_UDP as u8, 53, 5353);
+    let mut fragment = build_frame(false, libc::IPPROTO_UDP as u8, 1234, 53);
+    fragment[14 + 7] = 1;
+
+    let tests: &[(&str, &[u8], bool)] = &[
+        ("", &tcp4, true),
+        ("ip", &tcp4, true),
+        ("ip6", &tcp4, false),
+        ("tcp and ip", &tcp4, true),
+        ("udp || icmp", &tcp4, false),
+        ("udp && ip6", &udp6, true),
+        ("host 127.0.0.1", &tcp4, true),
+        ("src host 192.0.2.1", &tcp4, false),
+        ("dst 192.0.2.1", &tcp4, true),
+        ("src and dst net 127.0.0.0/8", &tcp4, false),
+        ("ip6 dst net 2001:db8::/32", &udp6, true),
+        ("ip6 dst net 2001:db9::/32", &udp6, false),
+        ("net ::/0", &udp6, true),
+        ("src host ::1", &udp6, true),
+        ("port 80", &tcp4, true),
+        ("tcp src port 80", &tcp4, false),
+        ("udp port 80", &tcp4, false),
+        ("dst port 5353", &udp6, true),
+        ("portrange 1-1000 and portrange 1000-2000", &tcp4, true),
+        ("src portrange 54-5353", &udp6, false),
+        ("port 53", &fragment, false),
+        ("udp and !(port 53)", &fragment, true),
+        ("len = 58", &tcp4, true),
+        ("len != 58 or less 10 or greater 59", &tcp4, false),
+        ("len < 58", &tcp4, false),
+        ("len <= 58 and len >= 58", &tcp4, true),
+        ("not vlan", &tcp4, true),
+    ];
+    for (expression, frame, matches) in tests {
+        let program = compile(expression, LinkType::Ethernet).unwrap();
+        let interpreter = Interpreter::new(&program).unwrap();
+        let result = interpreter.run(&Packet::new(frame)).unwrap();
+        assert_eq!(result != 0, *matches, "{expression}");
+
+        // The same packet without the Ethernet header.
+        let program = compile(expression, LinkType::Ip).unwrap();
+        let interpreter = Interpreter::new(&program).unwrap();
+        let protocol = u16::from_be_bytes([frame[12], frame[13]]);
+        let packet = Packet::new(&frame[14..]).with_protocol(protocol);
+        let result = interpreter.run(&packet).unwrap();
+        // Length checks see a shorter packet.
+        if !expression.contains("len") && !expression.contains("less") {
+            assert_eq!(result != 0, *matches, "{expression}");
+        }
+
+        // Loads are relative to the network header, so the link layer header
+        // can also be present.
+        let packet = Packet::new(frame)
+            .with_protocol(protocol)
+            .with_network_offset(14);
+        let result = interpreter.run(&packet).unwrap();
+        if !expression.contains("len") && !expression.contains("less") {
+            assert_eq!(result != 0, *matches, "{expression}");
+        }
+    }
+
+    let program = compile("vlan 100 and ip", LinkType::Ethernet).unwrap();
+    let interpreter = Interpreter::new(&program).unwrap();
+    let packet = Packet::new(&tcp4).with_vlan(0x2064, 0x8100);
+    assert_eq!(interpreter.run(&packet), Ok(u32::MAX));
+    let packet = Packet::new(&tcp4).with_vlan(0x2065, 0x8100);
+    assert_eq!(interpreter.run(&packet), Ok(0));
+
+    // Jumps to the accept and reject instructions too far for a conditional
+    // jump.
+    let hosts = |range: std::ops::Range<u32>| {
+        let hosts: Vec<String> = range.map(|i| format!("host 2001:db8::{i:x}")).collect();
+        hosts.join(" or ")
+    };
+    let mut last = udp6.clone();
+    last[14 + 39] = 0x15;
+    for (expression, frame, matches) in [
+        (hosts(0..20), &udp6, true),
+        (hosts(2..22), &udp6, false),
+        (hosts(2..22), &last, true),
+    ] {
+        let program = compile(&expression, LinkType::Ethernet).unwrap();
+        assert!(program.len() > 255, "{}", program.len());
+        let interpreter = Interpreter::new(&program).unwrap();
+        let result = interpreter.run(&Packet::new(frame)).unwrap();
+        assert_eq!(result != 0, matches, "{expression}");
+    }
+
+    let errors = [
+        ("ip and", CompileErrorKind::UnexpectedEnd, Some(6)),
+        ("(ip or ip6", CompileErrorKind::UnexpectedEnd, Some(10)),
+        ("ip ip6", CompileErrorKind::UnexpectedToken, Some(3)),
+        ("ip $", CompileErrorKind::UnexpectedToken, Some(3)),
+        ("len ~ 1", CompileErrorKind::UnexpectedToken, Some(4)),
+        ("port 65536", CompileErrorKind::InvalidNumber, Some(5)),
+        ("portrange 2-1", CompileErrorKind::InvalidNumber, Some(10)),
+        ("vlan 4096", CompileErrorKind::InvalidNumber, Some(5)),
+        ("host 1.2.3", CompileErrorKind::InvalidAddress, Some(5)),
+        ("net 10.0.0.1/8", CompileErrorKind::InvalidAddress, Some(4)),
+        (
+            "ip6 src 127.0.0.1",
+            CompileErrorKind::InvalidAddress,
+            Some(8),
+        ),
+        ("tcp host ::1", CompileErrorKind::InvalidQualifier, Some(0)),
+        ("ip port 1", CompileErrorKind::InvalidQualifier, Some(0)),
+    ];
+    for (expression, kind, offset) in errors {
+        let err = compile(expression, LinkType::Ethernet).unwrap_err();
+        assert_eq!((err.kind(), err.offset()), (kind, offset), "{expression}");
+    }
+}
+
+#[test]
+#[cfg(all(feature = "all", target_os = "linux"))]
+fn packet_socket() {
+    use socket2::{ControlMessage, PacketMembership};
+
+    let protocol = Protocol::from((libc::ETH_P_ALL as u16).to_be() as libc::c_int);
+    let socket = match Socket::new(Domain::PACKET, Type::RAW, Some(protocol)) {
+        Ok(socket) => socket,
+        // Requires `CAP_NET_RAW`.
+        Err(ref err) if err.kind() == io::ErrorKind::PermissionDenied => return,
+        Err(err) => panic!("unexpected error creating packet socket: {err}"),
+    };
+    let lo = unsafe { libc::if_nametoindex(b"lo\0".as_ptr().cast()) };
+    let addr = SockAddr::link_layer(lo, libc::ETH_P_ALL as u16, 0, 0, &[]).unwrap();
+    socket.bind(&addr).unwrap();
+    let local = socket.local_addr().unwrap().as_link_layer().unwrap();
+    assert_eq!(local.interface(), lo);
+    assert_eq!(local.hatype(), libc::ARPHRD_LOOPBACK);
+
+    socket
+        .packet_add_membership(lo, PacketMembership::AllMulticast)
+        .unwrap();
+    socket
+        .packet_drop_membership(lo, PacketMembership::AllMulticast)
+        .unwrap();
+
+    assert!(!socket.packet_qdisc_bypass().unwrap());
+    socket.set_packet_qdisc_bypass(true).unwrap();
+    assert!(socket.packet_qdisc_bypass().unwrap());
+
+    assert!(!socket.packet_ignore_outgoing().unwrap());
+    socket.set_packet_ignore_outgoing(true).unwrap();
+    assert!(socket.packet_ignore_outgoing().unwrap());
+
+    assert!(!socket.packet_auxdata().unwrap());
+    socket.set_packet_auxdata(true).unwrap();
+    assert!(socket.packet_auxdata().unwrap());
+
+    // Reset the statistics.
+    let _ = socket.packet_statistics().unwrap();
+
+    let udp = Socket::new(Domain::IPV4, Type::DGRAM, None).unwrap();
+    let target = Socket::new(Domain::IPV4, Type::DGRAM, None).unwrap();
+    target.bind(&any_ipv4()).unwrap();
+    udp.send_to(DATA, &target.local_addr().unwrap()).unwrap();
+
+    socket
+        .set_read_timeout(Some(Duration::from_secs(1)))
+        .unwrap();
+    let mut data = [MaybeUninit::new(0); 1500];
+    let mut bufs = [MaybeUninitSlice::new(&mut data)];
+    let mut control = [MaybeUninit::uninit(); 64];
+    let mut msg = socket2::MsgHdrMut::new()
+        .with_buffers(&mut bufs)
+        .with_control(&mut control);
+    let n = socket.recvmsg(&mut msg, 0).unwrap();
+    let aux = msg
+        .control_messages()
+        .find_map(|msg| match msg {
+            ControlMessage::PacketAuxData(aux) => Some(aux),
+            _ => None,
+        })
+        .unwrap();
+    assert_eq!(aux.len() as usize, n);
+    assert_eq!(aux.snaplen() as usize, n);
+    assert_eq!(aux.vlan_tci(), None);
+
+    let stats = socket.packet_statistics().unwrap();
+    assert!(stats.packets() >= 1);
+    assert_eq!(stats.freeze_queue_count(), None);
+}
+
+#[test]
+#[cfg(all(feature = "all", target_os = "linux"))]
+fn packet_ring() {
+    use socket2::{PacketRing, PacketRingConfig, PacketTxRing};
+
+    const ETH_P_EXPERIMENTAL: u16 = 0x88b5;
+
+    let protocol = Protocol::from((ETH_P_EXPERIMENTAL).to_be() as libc::c_int);
+    let rx = match Socket::new(Domain::PACKET, Type::RAW, Some(protocol)) {
+        Ok(socket) => socket,
+        // Requires `CAP_NET_RAW`.
+        Err(ref err) if err.kind() == io::ErrorKind::PermissionDenied => return,
+        Err(err) => panic!("unexpected error creating packet socket: {err}"),
+    };
+    let tx = Socket::new(Domain::PACKET, Type::RAW, Some(protocol)).unwrap();
+    let lo = unsafe { libc::if_nametoindex(b"lo\0".as_ptr().cast()) };
+    let addr = SockAddr::link_layer(lo, ETH_P_EXPERIMENTAL, 0, 0, &[]).unwrap();
+    rx.bind(&addr).unwrap();
+    tx.bind(&addr).unwrap();
+
+    for config in [
+        PacketRingConfig::new(1 << 14, 4, 0),
+        PacketRingConfig::new(1 << 20, 1 << 17, 16),
+    ] {
+        let socket = Socket::new(Domain::PACKET, Type::RAW, Some(protocol)).unwrap();
+        let err = PacketRing::new(socket, config).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        let socket = Socket::new(Domain::PACKET, Type::RAW, Some(protocol)).unwrap();
+        let err = PacketTxRing::new(socket, config).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    let config = PacketRingConfig::new(1 << 14, 4, 2048);
+    let mut rx =
+        PacketRing::new(rx, config.with_retire_timeout(Duration::from_millis(10))).unwrap();
+    let mut tx = PacketTxRing::new(tx, config).unwrap();
+    assert!(rx.try_next_block().is_none());
+
+    let mut frame = tx.frame().unwrap();
+    let buf = frame.buffer();
+    // Zero MAC addresses followed by the ethertype.
+    buf[..12].fill(0);
+    buf[12..14].copy_from_slice(&ETH_P_EXPERIMENTAL.to_be_bytes());
+    buf[14..14 + DATA.len()].copy_from_slice(DATA);
+    frame.send(14 + DATA.len());
+    assert_eq!(tx.flush().unwrap(), 14 + DATA.len());
+
+    let start = std::time::Instant::now();
+    loop {
+        assert!(
+            start.elapsed() < Duration::from_secs(5),
+            "no packet received"
+        );
+        let Some(block) = rx.next_block(Some(Duration::from_secs(1))).unwrap() else {
+            continue;
+        };
+        assert!(block.num_packets() >= 1);
+        let found = block.frames().find(|frame| frame.data().ends_with(DATA));
+        if let Some(frame) = found {
+            assert_eq!(frame.len() as usize, 14 + DATA.len());
+            assert_eq!(frame.addr().interface(), lo);
+            assert_eq!(frame.addr().protocol(), ETH_P_EXPERIMENTAL);
+            assert_eq!(frame.vlan_tci(), None);
+            assert!(frame.timestamp() > std::time::SystemTime::UNIX_EPOCH);
+            break;
+        }
+    }
+}
+
+#[test]
+#[cfg(all(feature = "all", target_os = "linux"))]
+fn packet_fanout() {
+    use socket2::{FanoutMode, PacketFanout, SockFilter};
+
+    let protocol = Protocol::from((libc::ETH_P_ALL as u16).to_be() as libc::c_int);
+    let new_socket = || match Socket::new(Domain::PACKET, Type::RAW, Some(protocol)) {
+        Ok(socket) => Some(socket),
+        // Requires `CAP_NET_RAW`.
+        Err(ref err) if err.kind() == io::ErrorKind::PermissionDenied => None,
+        Err(err) => panic!("unexpected error creating packet socket: {err}"),
+    };
+    let Some(socket) = new_socket() else {
+        return;
+    };
+    let lo = unsafe { libc::if_nametoindex(b"lo\0".as_ptr().cast()) };
+    let addr = SockAddr::link_layer(lo, libc::ETH_P_ALL as u16, 0, 0, &[]).unwrap();
+    socket.bind(&addr).unwrap();
+    assert_eq!(socket.packet_fanout().unwrap(), None);
+
+    // Group ids are shared by all sockets in the network namespace.
+    let id = std::process::id() as u16;
+    let fanout = PacketFanout::new(id, FanoutMode::LoadBalance)
+        .with_rollover()
+        .with_defrag();
+    socket.set_packet_fanout(fanout).unwrap();
+    let got = socket.packet_fanout().unwrap().unwrap();
+    assert_eq!(got, fanout);
+    assert_eq!(got.id(), id);
+    assert_eq!(got.mode(), FanoutMode::LoadBalance);
+    assert!(got.is_rollover());
+    assert!(got.is_defrag());
+
+    // Joining with a different mode is not allowed.
+    let other = new_socket().unwrap();
+    other.bind(&addr).unwrap();
+    let err = other
+        .set_packet_fanout(PacketFanout::new(id, FanoutMode::Hash))
+        .unwrap_err();
+    assert_eq!(err.raw_os_error(), Some(libc::EINVAL));
+    other.set_packet_fanout(fanout).unwrap();
+
+    let cbpf = new_socket().unwrap();
+    cbpf.bind(&addr).unwrap();
+    cbpf.set_packet_fanout(PacketFanout::new(id.wrapping_add(1), FanoutMode::Cbpf))
+        .unwrap();
+    // Always select the first socket.
+    let filter = [SockFilter::new(
+        (libc::BPF_RET | libc::BPF_K) as u16,
+        0,
+        0,
+        0,
+    )];
+    cbpf.attach_packet_fanout_filter(&filter).unwrap();
+}
+
 #[test]
 #[cfg(not(target_os = "vita"))] // Vita does not support OOB
 fn out_of_band() {
@@ -818,6 +1820,428 @@ fn sendmsg() {
     assert_eq!(received, DATA.len());
 }
 
+#[test]
+#[cfg(all(feature = "all", target_os = "linux"))]
+fn control_message_builder() {
+    use std::os::fd::AsFd;
+
+    let (a, b) = Socket::pair(Domain::UNIX, Type::DGRAM, None).unwrap();
+
+    let fds = [b.as_fd(), a.as_fd()];
+    let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+    let credentials = socket2::UnixCredentials::new(std::process::id(), uid, gid);
+    let control = socket2::ControlMessageBuilder::new()
+        .with_rights(&fds)
+        .with_credentials(credentials);
+    let want = unsafe {
+        libc::CMSG_SPACE(2 * mem::size_of::<libc::c_int>() as u32)
+            + libc::CMSG_SPACE(mem::size_of::<libc::ucred>() as u32)
+    };
+    assert_eq!(control.space(), want as usize);
+
+    let mut small = [MaybeUninit::uninit(); 8];
+    assert_eq!(
+        control.encode(&mut small).unwrap_err().kind(),
+        io::ErrorKind::InvalidInput
+    );
+
+    let mut buf = [MaybeUninit::uninit(); 256];
+    let encoded = control.encode(&mut buf).unwrap();
+    assert_eq!(encoded.len(), control.space());
+    let bufs = &[IoSlice::new(DATA)];
+    let msg = socket2::MsgHdr::new()
+        .with_buffers(bufs)
+        .with_control(encoded);
+    assert_eq!(b.sendmsg(&msg, 0).unwrap(), DATA.len());
+
+    let mut data = [MaybeUninit::new(0); DATA.len()];
+    let mut bufs = [MaybeUninitSlice::new(&mut data)];
+    let mut control = [MaybeUninit::new(0); 256];
+    let control_len = {
+        let mut msg = socket2::MsgHdrMut::new()
+            .with_buffers(&mut bufs)
+            .with_control(&mut control);
+        assert_eq!(a.recvmsg(&mut msg, 0).unwrap(), DATA.len());
+        msg.control_len()
+    };
+    // Without `SO_PASSCRED` the credentials are not passed along.
+    let want = unsafe { libc::CMSG_SPACE(2 * mem::size_of::<libc::c_int>() as u32) };
+    assert_eq!(control_len, want as usize);
+
+    let header = unsafe { (control.as_ptr() as *const libc::cmsghdr).read_unaligned() };
+    assert_eq!(header.cmsg_level, libc::SOL_SOCKET);
+    assert_eq!(header.cmsg_type, libc::SCM_RIGHTS);
+    let data_offset = unsafe { libc::CMSG_LEN(0) } as usize;
+    let fds = unsafe { assume_init(&control[data_offset..data_offset + 8]) };
+    for fd in fds.chunks(4) {
+        let fd = libc::c_int::from_ne_bytes(fd.try_into().unwrap());
+        assert_eq!(unsafe { libc::close(fd) }, 0);
+    }
+}
+
+#[test]
+#[cfg(all(feature = "all", target_os = "linux"))]
+fn control_messages() {
+    use socket2::{ControlMessage, ControlMessageBuilder, ControlMessages};
+
+    // Credentials.
+    let (a, b) = Socket::pair(Domain::UNIX, Type::DGRAM, None).unwrap();
+    a.set_passcred(true).unwrap();
+    let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+    let credentials = socket2::UnixCredentials::new(std::process::id(), uid, gid);
+    let mut buf = [MaybeUninit::uninit(); 64];
+    let control = ControlMessageBuilder::new()
+        .with_credentials(credentials)
+        .encode(&mut buf)
+        .unwrap();
+    let bufs = &[IoSlice::new(DATA)];
+    let msg = socket2::MsgHdr::new()
+        .with_buffers(bufs)
+        .with_control(control);
+    assert_eq!(b.sendmsg(&msg, 0).unwrap(), DATA.len());
+
+    let mut data = [MaybeUninit::new(0); DATA.len()];
+    let mut bufs = [MaybeUninitSlice::new(&mut data)];
+    let mut control = [MaybeUninit::uninit(); 64];
+    let mut msg = socket2::MsgHdrMut::new()
+        .with_buffers(&mut bufs)
+        .with_control(&mut control);
+    assert_eq!(a.recvmsg(&mut msg, 0).unwrap(), DATA.len());
+    assert!(!msg.flags().is_control_truncated());
+    let got: Vec<_> = msg.control_messages().collect();
+    assert_eq!(got, [ControlMessage::Credentials(credentials)]);
+
+    // Control buffer too small for the credentials.
+    assert_eq!(b.send(DATA).unwrap(), DATA.len());
+    let mut bufs = [MaybeUninitSlice::new(&mut data)];
+    let mut control = [MaybeUninit::uninit(); 8];
+    let mut msg = socket2::MsgHdrMut::new()
+        .with_buffers(&mut bufs)
+        .with_control(&mut control);
+    assert_eq!(a.recvmsg(&mut msg, 0).unwrap(), DATA.len());
+    assert!(msg.flags().is_control_truncated());
+    assert_eq!(msg.control_messages().count(), 0);
+
+    // Hop limit and traffic class.
+    let (a, b) = udp_pair_connected();
+    b.set_recv_hoplimit_v6(true).unwrap();
+    b.set_recv_tclass_v6(true).unwrap();
+    let mut buf = [MaybeUninit::uninit(); 64];
+    let control = ControlMessageBuilder::new()
+        .with_hoplimit_v6(7)
+        .with_tclass_v6(0x20)
+        .encode(&mut buf)
+        .unwrap();
+    let bufs = &[IoSlice::new(DATA)];
+    let msg = socket2::MsgHdr::new()
+        .with_buffers(bufs)
+        .with_control(control);
+    assert_eq!(a.sendmsg(&msg, 0).unwrap(), DATA.len());
+
+    let mut bufs = [MaybeUninitSlice::new(&mut data)];
+    let mut control = [MaybeUninit::uninit(); 64];
+    let mut msg = socket2::MsgHdrMut::new()
+        .with_buffers(&mut bufs)
+        .with_control(&mut control);
+    assert_eq!(b.recvmsg(&mut msg, 0).unwrap(), DATA.len());
+    let got: Vec<_> = msg.control_messages().collect();
+    let want = [
+        ControlMessage::HopLimitV6(7),
+        ControlMessage::TclassV6(0x20),
+    ];
+    assert_eq!(got, want);
+
+    // Unknown messages.
+    let mut buf = [MaybeUninit::uninit(); 64];
+    let control = ControlMessageBuilder::new()
+        .with_other(1234, 5, &[1, 2, 3])
+        .with_tos_v4(4)
+        .encode(&mut buf)
+        .unwrap();
+    let got: Vec<_> = ControlMessages::new(control).collect();
+    let want = [
+        ControlMessage::Other {
+            level: 1234,
+            ty: 5,
+            data: &[1, 2, 3],
+        },
+        ControlMessage::TosV4(4),
+    ];
+    assert_eq!(got, want);
+}
+
+#[test]
+#[cfg(all(feature = "all", target_os = "linux"))]
+fn send_recv_multiple() {
+    use socket2::{MmsgHdr, MmsgHdrMut, SockAddrStorage};
+
+    let (a, b) = udp_pair_unconnected();
+    let addr_a = a.local_addr().unwrap();
+    let addr_b = b.local_addr().unwrap();
+
+    let data: [&[u8]; 3] = [b"one", b"two", b"three"];
+    let bufs: Vec<_> = data.iter().map(|data| [IoSlice::new(data)]).collect();
+    let mut msgs: Vec<_> = bufs
+        .iter()
+        .map(|bufs| MmsgHdr::new().with_addr(&addr_b).with_buffers(bufs))
+        .collect();
+    assert_eq!(a.send_multiple(&mut msgs, 0).unwrap(), 3);
+    for (msg, data) in msgs.iter().zip(data) {
+        assert_eq!(msg.len(), data.len());
+    }
+
+    let mut buffers = [[MaybeUninit::new(0); 16]; 4];
+    let mut bufs: Vec<_> = buffers
+        .iter_mut()
+        .map(|buf| [MaybeUninitSlice::new(buf)])
+        .collect();
+    let mut storages = [
+        SockAddrStorage::zeroed(),
+        SockAddrStorage::zeroed(),
+        SockAddrStorage::zeroed(),
+        SockAddrStorage::zeroed(),
+    ];
+    let mut msgs: Vec<_> = bufs
+        .iter_mut()
+        .zip(storages.iter_mut())
+        .map(|(bufs, storage)| MmsgHdrMut::new().with_addr(storage).with_buffers(bufs))
+        .collect();
+    let timeout = Some(Duration::from_millis(10));
+    let n = b
+        .recv_multiple(&mut msgs, libc::MSG_WAITFORONE, timeout)
+        .unwrap();
+    assert_eq!(n, 3);
+    let lens: Vec<_> = msgs.iter().map(|msg| msg.len()).collect();
+    for msg in &msgs[..n] {
+        assert_eq!(msg.addr().unwrap(), addr_a);
+        assert!(!msg.flags().is_truncated());
+    }
+    drop(msgs);
+    for ((buf, len), data) in buffers.iter().zip(lens).zip(data) {
+        assert_eq!(unsafe { assume_init(&buf[..len]) }, data);
+    }
+
+    // No messages pending.
+    let mut msgs = [MmsgHdrMut::new()];
+    let err = b
+        .recv_multiple(&mut msgs, libc::MSG_DONTWAIT, None)
+        .unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+}
+
+#[test]
+#[cfg(all(feature = "all", target_os = "linux"))]
+fn udp_gso_gro() {
+    let (a, b) = udp_pair_connected();
+
+    assert_eq!(a.udp_segment().unwrap(), 0);
+    a.set_udp_segment(100).unwrap();
+    assert_eq!(a.udp_segment().unwrap(), 100);
+    a.set_udp_segment(0).unwrap();
+    assert!(!b.udp_gro().unwrap());
+    b.set_udp_gro(true).unwrap();
+    assert!(b.udp_gro().unwrap());
+
+    // Three datagrams in a single send call, the last one smaller.
+    let data: Vec<u8> = (0..250).map(|i| i as u8).collect();
+    let mut buf = [MaybeUninit::uninit(); 64];
+    let control = socket2::ControlMessageBuilder::new()
+        .with_udp_segment(100)
+        .encode(&mut buf)
+        .unwrap();
+    let bufs = &[IoSlice::new(&data)];
+    let msg = socket2::MsgHdr::new()
+        .with_buffers(bufs)
+        .with_control(control);
+    assert_eq!(a.sendmsg(&msg, 0).unwrap(), data.len());
+
+    // Depending on the kernel the datagrams may or may not be coalesced.
+    let addr_a = a.local_addr().unwrap();
+    let mut segments = Vec::new();
+    let mut buf = [MaybeUninit::uninit(); 1024];
+    while segments.len() < 3 {
+        let (got, flags, addr) = b.recv_from_gro(&mut buf).unwrap();
+        assert!(!flags.is_truncated());
+        assert_eq!(addr, addr_a);
+        segments.extend(got.map(|segment| segment.to_vec()));
+    }
+    let want: Vec<_> = data.chunks(100).collect();
+    assert_eq!(segments, want);
+
+    let segments: Vec<_> = socket2::GroSegments::new(&data, 0).collect();
+    assert_eq!(segments, [&*data]);
+
+    // A zero length datagram is a single empty segment.
+    a.send(&[]).unwrap();
+    let (got, _, _) = b.recv_from_gro(&mut buf).unwrap();
+    assert_eq!(got.collect::<Vec<_>>(), [&[] as &[u8]]);
+}
+
+#[test]
+#[cfg(all(feature = "all", target_os = "linux"))]
+fn timestamping() {
+    use std::time::SystemTime;
+
+    use socket2::{ControlMessage, TimestampKind, TimestampingFlags};
+
+    let (a, b) = udp_pair_connected();
+
+    assert!(!b.timestamp_ns().unwrap());
+    b.set_timestamp_ns(true).unwrap();
+    assert!(b.timestamp_ns().unwrap());
+    assert!(!b.timestamp().unwrap());
+    let rx_flags = TimestampingFlags::RX_SOFTWARE | TimestampingFlags::SOFTWARE;
+    b.set_timestamping(rx_flags).unwrap();
+    assert_eq!(b.timestamping().unwrap(), rx_flags);
+    let tx_flags = TimestampingFlags::TX_SOFTWARE
+        | TimestampingFlags::SOFTWARE
+        | TimestampingFlags::OPT_ID
+        | TimestampingFlags::OPT_TSONLY;
+    a.set_timestamping(tx_flags).unwrap();
+
+    let start = SystemTime::now();
+    assert_eq!(a.send(DATA).unwrap(), DATA.len());
+    assert_eq!(a.send(DATA).unwrap(), DATA.len());
+
+    let mut data = [MaybeUninit::new(0); DATA.len()];
+    let mut bufs = [MaybeUninitSlice::new(&mut data)];
+    let mut control = [MaybeUninit::uninit(); 128];
+    let mut msg = socket2::MsgHdrMut::new()
+        .with_buffers(&mut bufs)
+        .with_control(&mut control);
+    assert_eq!(b.recvmsg(&mut msg, 0).unwrap(), DATA.len());
+    let mut got = (None, None);
+    for cmsg in msg.control_messages() {
+        match cmsg {
+            ControlMessage::TimestampNs(time) => got.0 = Some(time),
+            ControlMessage::Timestamping(timestamps) => got.1 = Some(timestamps),
+            cmsg => panic!("unexpected control message: {cmsg:?}"),
+        }
+    }
+    let (time, timestamps) = got;
+    let time = time.unwrap();
+    let timestamps = timestamps.unwrap();
+    assert!(time >= start);
+    assert_eq!(timestamps.software(), Some(time));
+    assert_eq!(timestamps.hardware(), None);
+
+    for id in 0..2 {
+        let mut buf = [MaybeUninit::new(0); 64];
+        // On loopback the timestamps are queued while sending.
+        let (n, timestamp) = a.recv_tx_timestamp(&mut buf).unwrap();
+        assert_eq!(n, 0);
+        assert_eq!(timestamp.kind(), TimestampKind::Sent);
+        assert_eq!(timestamp.id(), id);
+        assert!(timestamp.timestamps().software().unwrap() >= start);
+    }
+
+    // `OPT_STATS` adds a large control message for TCP.
+    let listener = Socket::new(Domain::IPV4, Type::STREAM, None).unwrap();
+    listener.bind(&any_ipv4()).unwrap();
+    listener.listen(1).unwrap();
+    let a = Socket::new(Domain::IPV4, Type::STREAM, None).unwrap();
+    a.connect(&listener.local_addr().unwrap()).unwrap();
+    a.set_timestamping(tx_flags | TimestampingFlags::OPT_STATS)
+        .unwrap();
+    assert_eq!(a.send(DATA).unwrap(), DATA.len());
+    let mut buf = [MaybeUninit::new(0); 64];
+    let mut timestamp = None;
+    for _ in 0..100 {
+        match a.recv_tx_timestamp(&mut buf) {
+            Ok((_, got)) => {
+                timestamp = Some(got);
+                break;
+            }
+            Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => {
+                thread::sleep(Duration::from_millis(10))
+            }
+            Err(err) => panic!("unexpected error: {err}"),
+        }
+    }
+    let timestamp = timestamp.expect("missing TX timestamp");
+    assert!(timestamp.timestamps().software().unwrap() >= start);
+}
+
+#[test]
+#[cfg(all(feature = "all", target_os = "linux"))]
+fn send_recv_with_fds() {
+    use std::os::fd::{AsFd, AsRawFd};
+
+    for ty in [Type::STREAM, Type::SEQPACKET] {
+        let (a, b) = Socket::pair(Domain::UNIX, ty, None).unwrap();
+        let (c, d) = Socket::pair(Domain::UNIX, Type::DGRAM, None).unwrap();
+
+        let fds = [c.as_fd(), d.as_fd()];
+        let sent = a.send_with_fds(&[IoSlice::new(DATA)], &fds).unwrap();
+        assert_eq!(sent, DATA.len());
+
+        let mut data = [MaybeUninit::new(0); DATA.len()];
+        let mut bufs = [MaybeUninitSlice::new(&mut data)];
+        let (n, flags, fds) = b.recv_with_fds(&mut bufs, 2).unwrap();
+        assert_eq!(n, DATA.len());
+        assert!(!flags.is_control_truncated());
+        assert_eq!(unsafe { assume_init(&data) }, DATA);
+        assert_eq!(fds.len(), 2);
+        for fd in &fds {
+            let flags = unsafe { libc::fcntl(fd.as_raw_fd(), libc::F_GETFD) };
+            assert_eq!(flags & libc::FD_CLOEXEC, libc::FD_CLOEXEC);
+        }
+        // The received file descriptors should refer to the same sockets.
+        let c = Socket::from(fds.into_iter().next().unwrap());
+        c.send(DATA).unwrap();
+        let mut buf = [MaybeUninit::new(0); DATA.len()];
+        assert_eq!(d.recv(&mut buf).unwrap(), DATA.len());
+
+        // More file descriptors than we have room for.
+        let fds = [c.as_fd(), d.as_fd(), b.as_fd()];
+        let sent = a.send_with_fds(&[IoSlice::new(DATA)], &fds).unwrap();
+        assert_eq!(sent, DATA.len());
+        let mut bufs = [MaybeUninitSlice::new(&mut data)];
+        let (n, flags, fds) = b.recv_with_fds(&mut bufs, 1).unwrap();
+        assert_eq!(n, DATA.len());
+        assert!(flags.is_control_truncated());
+        assert_eq!(fds.len(), 1);
+    }
+}
+
+#[test]
+#[cfg(all(feature = "all", target_os = "linux"))]
+fn peer_credentials() {
+    use std::os::fd::AsRawFd;
+
+    let (a, _b) = Socket::pair(Domain::UNIX, Type::STREAM, None).unwrap();
+
+    let creds = a.peer_cred().unwrap();
+    assert_eq!(creds.pid(), std::process::id());
+    assert_eq!(creds.uid(), unsafe { libc::getuid() });
+    assert_eq!(creds.gid(), unsafe { libc::getgid() });
+
+    let mut want = vec![0; 256];
+    let n = unsafe { libc::getgroups(want.len() as libc::c_int, want.as_mut_ptr()) };
+    want.truncate(n as usize);
+    let mut groups = a.peer_groups().unwrap();
+    groups.sort_unstable();
+    want.sort_unstable();
+    assert_eq!(groups, want);
+
+    // Requires a Linux Security Module supporting it.
+    match a.peer_security() {
+        Ok(label) => assert!(!label.is_empty()),
+        Err(err) => assert_eq!(err.raw_os_error(), Some(libc::ENOPROTOOPT)),
+    }
+
+    // Requires Linux 6.5.
+    match a.peer_pidfd() {
+        Ok(pidfd) => {
+            let path = format!("/proc/self/fdinfo/{}", pidfd.as_raw_fd());
+            let fdinfo = fs::read_to_string(path).unwrap();
+            let want = format!("Pid:\t{}\n", std::process::id());
+            assert!(fdinfo.contains(&want), "{fdinfo}");
+        }
+        Err(err) => assert_eq!(err.raw_os_error(), Some(libc::ENOPROTOOPT)),
+    }
+}
+
 #[test]
 #[cfg(not(any(target_os = "redox", target_os = "vita")))]
 fn recv_vectored_truncated() {
@@ -905,6 +2329,451 @@ fn udp_pair_connected() -> (Socket, Socket) {
     (socket_a, socket_b)
 }
 
+#[test]
+#[cfg(all(feature = "all", target_os = "linux"))]
+fn tcp_info() {
+    let listener = Socket::new(Domain::IPV4, Type::STREAM, None).unwrap();
+    listener.bind(&any_ipv4()).unwrap();
+    listener.listen(1).unwrap();
+    let info = listener.tcp_info().unwrap();
+    assert_eq!(info.state(), socket2::TcpState::LISTEN);
+
+    let client = Socket::new(Domain::IPV4, Type::STREAM, None).unwrap();
+    client.connect(&listener.local_addr().unwrap()).unwrap();
+    let (server, _) = listener.accept().unwrap();
+    client.send(DATA).unwrap();
+    let mut buf = [MaybeUninit::new(0); DATA.len()];
+    assert_eq!(server.recv(&mut buf).unwrap(), DATA.len());
+
+    let info = client.tcp_info().unwrap();
+    assert_eq!(info.state(), socket2::TcpState::ESTABLISHED);
+    assert!(info.snd_mss() > 0);
+    assert!(info.snd_cwnd() > 0);
+    assert!(info.rtt() > Duration::ZERO);
+    assert!(info.len() >= 104);
+    // Added in Linux 4.19.
+    if info.len() >= 216 {
+        assert_eq!(info.bytes_sent(), Some(DATA.len() as u64));
+    }
+    let info = server.tcp_info().unwrap();
+    assert_eq!(info.bytes_received(), Some(DATA.len() as u64));
+}
+
+#[test]
+#[cfg(all(feature = "all", target_os = "linux"))]
+fn tcp_fastopen_connect_with_data() {
+    const TCPI_OPT_SYN_DATA: u8 = 32;
+
+    let listener = Socket::new(Domain::IPV4, Type::STREAM, None).unwrap();
+    listener.bind(&any_ipv4()).unwrap();
+    listener.set_tcp_fastopen(16).unwrap();
+    let primary = [1; 16];
+    let backup = [2; 16];
+    listener.set_tcp_fastopen_key(primary, None).unwrap();
+    assert_eq!(listener.tcp_fastopen_key().unwrap(), (primary, None));
+    listener
+        .set_tcp_fastopen_key(primary, Some(backup))
+        .unwrap();
+    assert_eq!(
+        listener.tcp_fastopen_key().unwrap(),
+        (primary, Some(backup))
+    );
+    listener.listen(16).unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    // Both client (1) and server (2) side must be enabled for data in the SYN.
+    let sysctl = std::fs::read_to_string("/proc/sys/net/ipv4/tcp_fastopen").unwrap();
+    let enabled = sysctl.trim().parse::<u32>().unwrap() & 3 == 3;
+
+    // The first connection requests a cookie, the second uses it.
+    for attempt in 0..2 {
+        let client = Socket::new(Domain::IPV4, Type::STREAM, None).unwrap();
+        assert_eq!(client.connect_with_data(&addr, DATA).unwrap(), DATA.len());
+        let (server, _) = listener.accept().unwrap();
+        let mut buf = [MaybeUninit::new(0); DATA.len()];
+        assert_eq!(server.recv(&mut buf).unwrap(), DATA.len());
+        assert_eq!(unsafe { assume_init(&buf) }, DATA);
+
+        let syn_data = client.tcp_info().unwrap().options() & TCPI_OPT_SYN_DATA != 0;
+        assert_eq!(syn_data, enabled && attempt == 1);
+    }
+}
+
+#[test]
+#[cfg(all(feature = "all", target_os = "linux"))]
+fn tcp_md5sig() {
+    use socket2::TcpMd5Key;
+
+    let listener = Socket::new(Domain::IPV4, Type::STREAM, None).unwrap();
+    listener.bind(&any_ipv4()).unwrap();
+    let addr = listener.local_addr().unwrap();
+    let peer = SockAddr::from(SocketAddr::from((Ipv4Addr::LOCALHOST, 0)));
+    listener
+        .add_tcp_md5sig(&TcpMd5Key::new(&peer, b"secret").with_prefix_len(8))
+        .unwrap();
+    listener.listen(1).unwrap();
+
+    let connect = |key: Option<&[u8]>| {
+        let client = Socket::new(Domain::IPV4, Type::STREAM, None).unwrap();
+        if let Some(key) = key {
+            client.add_tcp_md5sig(&TcpMd5Key::new(&addr, key)).unwrap();
+        }
+        client.connect_timeout(&addr, Duration::from_millis(200))
+    };
+
+    connect(Some(b"secret")).unwrap();
+    listener.accept().unwrap();
+    // Segments with a missing or invalid signature are dropped.
+    let err = connect(Some(b"wrong")).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+    let err = connect(None).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+
+    listener
+        .remove_tcp_md5sig(&TcpMd5Key::new(&peer, &[]).with_prefix_len(8))
+        .unwrap();
+    connect(None).unwrap();
+
+    let err = listener
+        .add_tcp_md5sig(&TcpMd5Key::new(&peer, &[0; 81]))
+        .unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+    // The key is not printed.
+    let debug = format!("{:?}", TcpMd5Key::new(&peer, b"secret"));
+    assert!(!debug.contains("115, 101, 99"), "{debug}");
+    assert!(debug.contains("<6 bytes>"), "{debug}");
+}
+
+#[test]
+#[cfg(all(feature = "all", target_os = "linux"))]
+fn tcp_ao() {
+    use socket2::{TcpAoInfo, TcpAoKey};
+
+    let listener = Socket::new(Domain::IPV4, Type::STREAM, None).unwrap();
+    listener.bind(&any_ipv4()).unwrap();
+    let addr = listener.local_addr().unwrap();
+    let key = TcpAoKey::new(&addr, "hmac(sha1)", b"secret", 100, 200);
+    match listener.add_tcp_ao_key(&key) {
+        Ok(()) => {}
+        // Requires Linux 6.7 with `CONFIG_TCP_AO`.
+        Err(ref err) if err.raw_os_error() == Some(libc::ENOPROTOOPT) => return,
+        Err(err) => panic!("unexpected error adding TCP-AO key: {err}"),
+    }
+    let debug = format!("{key:?}");
+    assert!(debug.contains("<6 bytes>"), "{debug}");
+
+    // No keys added.
+    let socket = Socket::new(Domain::IPV4, Type::STREAM, None).unwrap();
+    assert!(socket.tcp_ao_keys().unwrap().is_empty());
+    let err = socket.tcp_ao_info().unwrap_err();
+    assert_eq!(err.raw_os_error(), Some(libc::ENOENT));
+
+    listener
+        .add_tcp_ao_key(&TcpAoKey::new(&addr, "hmac(sha1)", b"other", 101, 201))
+        .unwrap();
+    let keys = listener.tcp_ao_keys().unwrap();
+    assert_eq!(keys.len(), 2);
+    let got = keys.iter().find(|key| key.send_id() == 100).unwrap();
+    assert_eq!(got.recv_id(), 200);
+    assert_eq!(got.algorithm(), "hmac(sha1)");
+    assert_eq!(got.key(), b"secret");
+    assert!(format!("{got:?}").contains("<6 bytes>"));
+    assert_eq!(got.prefix_len(), 32);
+    listener.listen(1).unwrap();
+
+    let connect = |key: &[u8]| {
+        let client = Socket::new(Domain::IPV4, Type::STREAM, None).unwrap();
+        let key = TcpAoKey::new(&addr, "hmac(sha1)", key, 200, 100).with_current();
+        client.add_tcp_ao_key(&key).unwrap();
+        client
+            .connect_timeout(&addr, Duration::from_millis(200))
+            .map(|()| client)
+    };
+
+    let client = connect(b"secret").unwrap();
+    let (server, _) = listener.accept().unwrap();
+    client.send(DATA).unwrap();
+    let mut buf = [MaybeUninit::new(0); DATA.len()];
+    assert_eq!(server.recv(&mut buf).unwrap(), DATA.len());
+    let info = client.tcp_ao_info().unwrap();
+    assert_eq!(info.current_key(), Some(200));
+    assert!(info.packets_good() > 0);
+    client
+        .set_tcp_ao_info(&TcpAoInfo::new().with_ao_required(true))
+        .unwrap();
+    assert!(client.tcp_ao_info().unwrap().is_ao_required());
+
+    let err = connect(b"wrong").err().unwrap();
+    assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+
+    listener
+        .remove_tcp_ao_key(&TcpAoKey::new(&addr, "", &[], 101, 201))
+        .unwrap();
+    assert_eq!(listener.tcp_ao_keys().unwrap().len(), 1);
+}
+
+#[test]
+#[cfg(all(feature = "all", target_os = "linux"))]
+fn zerocopy() {
+    let listener = Socket::new(Domain::IPV4, Type::STREAM, None).unwrap();
+    listener.bind(&any_ipv4()).unwrap();
+    listener.listen(1).unwrap();
+    let client = Socket::new(Domain::IPV4, Type::STREAM, None).unwrap();
+    client.connect(&listener.local_addr().unwrap()).unwrap();
+    let (server, _) = listener.accept().unwrap();
+
+    assert!(!client.zerocopy().unwrap());
+    client.set_zerocopy(true).unwrap();
+    assert!(client.zerocopy().unwrap());
+
+    let data = vec![1; 64 * 1024];
+    for _ in 0..2 {
+        assert_eq!(client.send_zerocopy(&data).unwrap(), data.len());
+    }
+    let mut buf = vec![MaybeUninit::new(0); 2 * data.len()];
+    let mut received = 0;
+    while received < buf.len() {
+        received += server.recv(&mut buf[received..]).unwrap();
+    }
+
+    // Completions are reported once the data is acknowledged.
+    let mut completed = Vec::new();
+    for _ in 0..100 {
+        match client.recv_zerocopy_completion() {
+            Ok(completion) => {
+                // The kernel copies data sent over the loopback device.
+                assert!(completion.copied());
+                completed.extend((0..2).filter(|id| completion.contains(*id)));
+                if completed.len() == 2 {
+                    break;
+                }
+            }
+            Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => {
+                thread::sleep(Duration::from_millis(10))
+            }
+            Err(err) => panic!("unexpected error: {err}"),
+        }
+    }
+    assert_eq!(completed, [0, 1]);
+
+    // Unconnected UDP socket.
+    let server = Socket::new(Domain::IPV4, Type::DGRAM, None).unwrap();
+    server.bind(&any_ipv4()).unwrap();
+    let client = Socket::new(Domain::IPV4, Type::DGRAM, None).unwrap();
+    client.bind(&any_ipv4()).unwrap();
+    client.set_zerocopy(true).unwrap();
+    let addr = server.local_addr().unwrap();
+    assert_eq!(client.send_to_zerocopy(DATA, &addr).unwrap(), DATA.len());
+    let mut buf = [MaybeUninit::new(0); DATA.len()];
+    assert_eq!(server.recv(&mut buf).unwrap(), DATA.len());
+    assert_eq!(unsafe { assume_init(&buf) }, DATA);
+    let mut completion = None;
+    for _ in 0..100 {
+        match client.recv_zerocopy_completion() {
+            Ok(got) => {
+                completion = Some(got);
+                break;
+            }
+            Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => {
+                thread::sleep(Duration::from_millis(10))
+            }
+            Err(err) => panic!("unexpected error: {err}"),
+        }
+    }
+    assert!(completion.expect("no completion received").contains(0));
+}
+
+#[test]
+#[cfg(all(feature = "all", target_os = "linux"))]
+fn recv_error_queue() {
+    // Get an address on which nothing is listening.
+    let closed = Socket::new(Domain::IPV4, Type::DGRAM, None).unwrap();
+    closed.bind(&any_ipv4()).unwrap();
+    let closed_addr = closed.local_addr().unwrap();
+    drop(closed);
+
+    let socket = Socket::new(Domain::IPV4, Type::DGRAM, None).unwrap();
+    socket.bind(&any_ipv4()).unwrap();
+    assert!(!socket.recv_err_v4().unwrap());
+    socket.set_recv_err_v4(true).unwrap();
+    assert!(socket.recv_err_v4().unwrap());
+
+    socket.send_to(DATA, &closed_addr).unwrap();
+
+    let mut buf = [MaybeUninit::new(0); DATA.len() + 10];
+    let mut res = None;
+    for _ in 0..100 {
+        match socket.recv_error_queue(&mut buf) {
+            Ok(got) => {
+                res = Some(got);
+                break;
+            }
+            Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => {
+                thread::sleep(Duration::from_millis(10))
+            }
+            Err(err) => panic!("unexpected error: {err}"),
+        }
+    }
+    let (n, err, addr) = res.expect("no ICMP error received");
+    assert_eq!(unsafe { assume_init(&buf[..n]) }, DATA);
+    assert_eq!(addr, Some(closed_addr));
+    assert_eq!(err.origin(), socket2::ErrorOrigin::Icmp);
+    assert_eq!(err.errno(), libc::ECONNREFUSED);
+    assert_eq!(err.error().kind(), io::ErrorKind::ConnectionRefused);
+    // ICMP destination unreachable, port unreachable.
+    assert_eq!(err.r#type(), 3);
+    assert_eq!(err.code(), 3);
+    let offender = err.offender().unwrap().as_socket_ipv4().unwrap();
+    assert_eq!(*offender.ip(), Ipv4Addr::LOCALHOST);
+
+    let err = socket.recv_error_queue(&mut buf).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+}
+
+#[test]
+#[cfg(all(feature = "all", target_os = "linux"))]
+fn path_mtu() {
+    use socket2::{ControlMessage, PmtuDiscovery};
+
+    let lo = SockAddr::from(SocketAddr::from((Ipv4Addr::LOCALHOST, 9)));
+    let socket = Socket::new(Domain::IPV4, Type::DGRAM, None).unwrap();
+    // Only valid for connected sockets.
+    assert_eq!(
+        socket.mtu_v4().unwrap_err().raw_os_error(),
+        Some(libc::ENOTCONN)
+    );
+    socket.connect(&lo).unwrap();
+    let mtu = socket.mtu_v4().unwrap();
+    assert!(mtu >= 1280);
+    socket.set_mtu_discover_v4(PmtuDiscovery::Do).unwrap();
+    let err = socket.send(&vec![0; mtu as usize]).unwrap_err();
+    assert_eq!(err.raw_os_error(), Some(libc::EMSGSIZE));
+
+    let socket = Socket::new(Domain::IPV6, Type::DGRAM, None).unwrap();
+    socket
+        .bind(&SocketAddr::from((Ipv6Addr::LOCALHOST, 0)).into())
+        .unwrap();
+    let addr = socket.local_addr().unwrap();
+    socket.connect(&addr).unwrap();
+    let mtu = socket.mtu_v6().unwrap();
+    assert!(mtu >= 1280);
+    socket.set_dontfrag_v6(true).unwrap();
+    socket.set_recv_path_mtu_v6(true).unwrap();
+    let err = socket.send(&vec![0; mtu as usize]).unwrap_err();
+    assert_eq!(err.raw_os_error(), Some(libc::EMSGSIZE));
+
+    // The next receive returns the path MTU.
+    let mut data = [MaybeUninit::new(0); 16];
+    let mut bufs = [MaybeUninitSlice::new(&mut data)];
+    let mut control = [MaybeUninit::uninit(); 64];
+    let mut msg = socket2::MsgHdrMut::new()
+        .with_buffers(&mut bufs)
+        .with_control(&mut control);
+    assert_eq!(socket.recvmsg(&mut msg, 0).unwrap(), 0);
+    let (destination, got) = msg
+        .control_messages()
+        .find_map(|msg| match msg {
+            ControlMessage::PathMtuV6 { destination, mtu } => Some((destination, mtu)),
+            _ => None,
+        })
+        .unwrap();
+    assert_eq!(got, mtu);
+    assert_eq!(*destination.ip(), Ipv6Addr::LOCALHOST);
+}
+
+#[test]
+#[cfg(all(feature = "all", target_os = "linux"))]
+fn recv_from_with_dst() {
+    use std::net::IpAddr;
+
+    use socket2::TimestampingFlags;
+
+    let lo = unsafe { libc::if_nametoindex(b"lo\0".as_ptr().cast()) };
+
+    // Bound to the unspecified address, replies must use the destination of
+    // the request as source address.
+    let server = Socket::new(Domain::IPV4, Type::DGRAM, None).unwrap();
+    server
+        .bind(&SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0)).into())
+        .unwrap();
+    server.set_recv_pktinfo_v4(true).unwrap();
+    let port = server.local_addr().unwrap().as_socket().unwrap().port();
+    let destination = Ipv4Addr::new(127, 0, 0, 2);
+
+    let client = Socket::new(Domain::IPV4, Type::DGRAM, None).unwrap();
+    client.bind(&any_ipv4()).unwrap();
+    client
+        .send_to(DATA, &SocketAddr::from((destination, port)).into())
+        .unwrap();
+
+    let mut buf = [MaybeUninit::new(0); DATA.len()];
+    let (n, flags, addr, pktinfo) = server.recv_from_with_dst(&mut buf).unwrap();
+    assert_eq!(n, DATA.len());
+    assert!(!flags.is_control_truncated());
+    assert_eq!(addr, client.local_addr().unwrap());
+    let pktinfo = pktinfo.unwrap();
+    assert_eq!(pktinfo.destination(), IpAddr::V4(destination));
+    assert_eq!(pktinfo.local(), IpAddr::V4(destination));
+    assert_eq!(pktinfo.interface(), lo);
+
+    server
+        .send_to_from(DATA, &addr, pktinfo.local(), pktinfo.interface())
+        .unwrap();
+    let (n, from) = client.recv_from(&mut buf).unwrap();
+    assert_eq!(n, DATA.len());
+    assert_eq!(
+        from.as_socket().unwrap(),
+        SocketAddr::from((destination, port))
+    );
+
+    // Without the option there is no packet information.
+    server.set_recv_pktinfo_v4(false).unwrap();
+    client
+        .send_to(DATA, &SocketAddr::from((Ipv4Addr::LOCALHOST, port)).into())
+        .unwrap();
+    let (_, _, _, pktinfo) = server.recv_from_with_dst(&mut buf).unwrap();
+    assert_eq!(pktinfo, None);
+
+    // Too many control messages to fit in the buffer.
+    server.set_recv_pktinfo_v4(true).unwrap();
+    server.set_recv_tos_v4(true).unwrap();
+    server.set_timestamp_ns(true).unwrap();
+    server
+        .set_timestamping(TimestampingFlags::RX_SOFTWARE | TimestampingFlags::SOFTWARE)
+        .unwrap();
+    client
+        .send_to(DATA, &SocketAddr::from((Ipv4Addr::LOCALHOST, port)).into())
+        .unwrap();
+    let (n, flags, addr, _) = server.recv_from_with_dst(&mut buf).unwrap();
+    assert_eq!(n, DATA.len());
+    assert!(flags.is_control_truncated());
+    assert_eq!(addr, client.local_addr().unwrap());
+
+    let server = Socket::new(Domain::IPV6, Type::DGRAM, None).unwrap();
+    server
+        .bind(&SocketAddr::from((Ipv6Addr::UNSPECIFIED, 0)).into())
+        .unwrap();
+    server.set_recv_pktinfo_v6(true).unwrap();
+    let port = server.local_addr().unwrap().as_socket().unwrap().port();
+    let client = Socket::new(Domain::IPV6, Type::DGRAM, None).unwrap();
+    client
+        .send_to(DATA, &SocketAddr::from((Ipv6Addr::LOCALHOST, port)).into())
+        .unwrap();
+    let (_, _, addr, pktinfo) = server.recv_from_with_dst(&mut buf).unwrap();
+    let pktinfo = pktinfo.unwrap();
+    assert_eq!(pktinfo.destination(), IpAddr::V6(Ipv6Addr::LOCALHOST));
+    assert_eq!(pktinfo.interface(), lo);
+    server
+        .send_to_from(DATA, &addr, pktinfo.local(), 0)
+        .unwrap();
+    let (_, from) = client.recv_from(&mut buf).unwrap();
+    assert_eq!(
+        from.as_socket().unwrap(),
+        SocketAddr::from((Ipv6Addr::LOCALHOST, port))
+    );
+}
+
 #[test]
 #[cfg(not(target_os = "vita"))]
 fn tcp_keepalive() {
@@ -1460,6 +3329,10 @@ test!(tcp_quickack, set_tcp_quickack(false));
 test!(tcp_thin_linear_timeouts, set_tcp_thin_linear_timeouts(true));
 #[cfg(all(feature = "all", any(target_os = "android", target_os = "linux")))]
 test!(tcp_notsent_lowat, set_tcp_notsent_lowat(16 * 1024));
+#[cfg(all(feature = "all", target_os = "linux"))]
+test!(tcp_fastopen, set_tcp_fastopen(16));
+#[cfg(all(feature = "all", target_os = "linux"))]
+test!(tcp_fastopen_connect, set_tcp_fastopen_connect(true));
 test!(linger, set_linger(Some(Duration::from_secs(10))));
 test!(
     read_timeout,
@@ -1470,6 +3343,18 @@ test!(keepalive, set_keepalive(true));
 test!(freebind_v4, set_freebind_v4(true));
 #[cfg(all(feature = "all", target_os = "linux"))]
 test!(IPv6 freebind_v6, set_freebind_v6(true));
+#[cfg(all(feature = "all", target_os = "linux"))]
+test!(IPv4 recv_pktinfo_v4, set_recv_pktinfo_v4(true));
+#[cfg(all(feature = "all", target_os = "linux"))]
+test!(IPv6 recv_pktinfo_v6, set_recv_pktinfo_v6(true));
+#[cfg(all(feature = "all", target_os = "linux"))]
+test!(IPv4 mtu_discover_v4, set_mtu_discover_v4(socket2::PmtuDiscovery::Probe));
+#[cfg(all(feature = "all", target_os = "linux"))]
+test!(IPv6 mtu_discover_v6, set_mtu_discover_v6(socket2::PmtuDiscovery::Omit));
+#[cfg(all(feature = "all", target_os = "linux"))]
+test!(IPv6 dontfrag_v6, set_dontfrag_v6(true));
+#[cfg(all(feature = "all", target_os = "linux"))]
+test!(IPv6 recv_path_mtu_v6, set_recv_path_mtu_v6(true));
 
 test!(IPv4 ttl_v4, set_ttl_v4(40));
 